@@ -1,4 +1,67 @@
-pub fn about() {
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+use crate::{
+    app_config::{self, AppConfig, EnvCredentials},
+    common::{
+        hub_helper::{get_metadata_hub, get_metadata_hub_by_account_name, GetHubError},
+        size_format::SizeFormat,
+    },
+    hub::{self, Hub},
+    version,
+};
+
+pub struct Config {
+    pub storage: bool,
+    pub all_accounts: bool,
+    /// Print the build and the credential files instead, for support requests
+    pub diagnostics: bool,
+    pub size_format: SizeFormat,
+}
+
+pub async fn about(config: Config) -> Result<(), Error> {
+    if config.diagnostics {
+        print_diagnostics();
+        return Ok(());
+    }
+
+    if !config.storage {
+        print_about();
+        return Ok(());
+    }
+
+    if config.all_accounts {
+        let accounts = app_config::list_accounts().map_err(Error::ListAccounts)?;
+        for account in accounts {
+            let hub = match get_metadata_hub_by_account_name(&account).await {
+                Ok(hub) => hub,
+                Err(source) => {
+                    return Err(Error::AccountHub {
+                        name: account,
+                        source,
+                    })
+                }
+            };
+            let quota = get_storage_quota(&hub)
+                .await
+                .map_err(|err| Error::GetStorageQuota(Box::new(err)))?;
+            print_storage_quota(&quota, Some(&account), config.size_format);
+        }
+    } else {
+        let hub = get_metadata_hub().await.map_err(Error::Hub)?;
+        let quota = get_storage_quota(&hub)
+            .await
+            .map_err(|err| Error::GetStorageQuota(Box::new(err)))?;
+        print_storage_quota(&quota, None, config.size_format);
+    }
+
+    Ok(())
+}
+
+fn print_about() {
     println!("gdrive is a command line application for interacting with Google Drive.");
     println!();
     println!(
@@ -9,3 +72,135 @@ pub fn about() {
         project."
     );
 }
+
+/// Prints the build and where the credentials of the current account live. Nothing secret is
+/// printed and failures are reported inline, the point is to describe a broken setup.
+fn print_diagnostics() {
+    version::version();
+    println!();
+
+    match EnvCredentials::load() {
+        Ok(Some(credentials)) => {
+            println!("Credentials: environment variables, the current account is ignored");
+            println!(
+                "Refresh token: {}",
+                if credentials.refresh_token.is_some() {
+                    "set"
+                } else {
+                    "not set"
+                }
+            );
+            return;
+        }
+        Ok(None) => {}
+        Err(err) => println!("Credentials: invalid environment variables ({err})"),
+    }
+
+    let app_cfg = match AppConfig::load_current_account() {
+        Ok(app_cfg) => app_cfg,
+        Err(err) => {
+            println!("Account: none ({err})");
+            return;
+        }
+    };
+
+    println!("Account: {}", app_cfg.account.name);
+    print_path("Account config", app_cfg.account_config_path());
+    print_path("Account directory", app_cfg.account_base_path());
+    if app_cfg.uses_application_default_credentials() {
+        println!("Credentials: application default credentials");
+    } else {
+        print_path("Secret", app_cfg.secret_path());
+    }
+    print_path("Tokens", app_cfg.tokens_path());
+
+    match hub::stored_token_scopes(app_cfg.tokens_path()) {
+        Ok(scopes) if scopes.is_empty() => println!("Token scopes: none"),
+        Ok(scopes) => {
+            println!("Token scopes:");
+            for scope in scopes {
+                println!("  {scope}");
+            }
+        }
+        Err(err) => println!("Token scopes: unknown ({err})"),
+    }
+}
+
+fn print_path(label: &str, path: &Path) {
+    let state = if path.exists() { "" } else { " (missing)" };
+    println!("{label}: {}{state}", path.display());
+}
+
+pub async fn get_storage_quota(
+    hub: &Hub,
+) -> Result<google_drive3::api::AboutStorageQuota, google_drive3::Error> {
+    let (_, about) = hub
+        .about()
+        .get()
+        .param("fields", "storageQuota")
+        .add_scope(hub.metadata_scope())
+        .doit()
+        .await?;
+
+    Ok(about.storage_quota.unwrap_or_default())
+}
+
+fn print_storage_quota(
+    quota: &google_drive3::api::AboutStorageQuota,
+    account: Option<&str>,
+    size_format: SizeFormat,
+) {
+    let prefix = account.map(|name| format!("{name}: ")).unwrap_or_default();
+    let format_bytes = |bytes: Option<i64>| {
+        bytes.map_or_else(
+            || String::from("unlimited"),
+            |bytes| {
+                size_format
+                    .display(u64::try_from(bytes).unwrap_or(0))
+                    .to_string()
+            },
+        )
+    };
+
+    println!("{prefix}Limit: {}", format_bytes(quota.limit));
+    println!("{prefix}Usage: {}", format_bytes(quota.usage));
+    println!(
+        "{prefix}Usage in drive: {}",
+        format_bytes(quota.usage_in_drive)
+    );
+    println!(
+        "{prefix}Usage in trash: {}",
+        format_bytes(quota.usage_in_drive_trash)
+    );
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    ListAccounts(app_config::errors::ListAccounts),
+    AccountHub { name: String, source: GetHubError },
+    GetStorageQuota(Box<google_drive3::Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::ListAccounts(_) => f.write_str("unable to list accounts"),
+            Error::AccountHub { name, source: _ } => {
+                write!(f, "unable to get drive hub for account '{name}'")
+            }
+            Error::GetStorageQuota(_) => f.write_str("unable to get storage quota"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) | Error::AccountHub { source, .. } => Some(source),
+            Error::ListAccounts(source) => Some(source),
+            Error::GetStorageQuota(source) => Some(source),
+        }
+    }
+}
@@ -2,12 +2,172 @@ use std::{
     borrow::Cow,
     error,
     fmt::{Display, Formatter},
-    io::{self, Write},
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
-use crate::{app_config, hub};
+use crate::{
+    app_config::{self, AppConfig},
+    common::{
+        bulk,
+        delegate::{Backoff, BackoffConfig},
+        prompt,
+    },
+    hub,
+};
+
+/// Directory below the gdrive one keeping the tokens of an addition which failed after the sign
+/// in, until it is resumed.
+const PENDING_DIR_NAME: &str = "pending_account";
+const TOKENS_FILE_NAME: &str = "tokens.json";
+const SECRET_FILE_NAME: &str = "secret.json";
+
+pub struct Config {
+    pub application_default_credentials: bool,
+    /// Time allowed to each attempt of getting the account info
+    pub timeout: Duration,
+    /// Finish an addition which failed after the sign in, with the tokens kept then
+    pub resume: bool,
+}
+
+pub async fn add(config: Config) -> Result<(), Error> {
+    let tmp_dir = tempfile::tempdir().map_err(Error::Tempdir)?;
+    let pending_dir = pending_dir()?;
+
+    let (tokens_path, secret) = if config.resume {
+        let secret = load_pending(&pending_dir)?;
+        (pending_dir.join(TOKENS_FILE_NAME), secret)
+    } else if config.application_default_credentials {
+        (tmp_dir.path().join(TOKENS_FILE_NAME), None)
+    } else {
+        let secret = secret_prompt().map_err(Error::Prompt)?;
+        (tmp_dir.path().join(TOKENS_FILE_NAME), Some(secret))
+    };
+
+    let auth = match &secret {
+        Some(secret) => hub::Auth::new(secret, &tokens_path).await,
+        None => hub::Auth::application_default(&tokens_path).await,
+    }
+    .map_err(Error::Auth)?;
+
+    let email = match authorize(auth, config.timeout).await {
+        Ok(email) => email,
+        Err(err @ Error::About { .. }) if !config.resume => {
+            return Err(keep_pending(
+                err,
+                &pending_dir,
+                &tokens_path,
+                secret.as_ref(),
+            ));
+        }
+        Err(err) => return Err(err),
+    };
+
+    let app_cfg = match &secret {
+        Some(secret) => app_config::add_account(&email, secret, &tokens_path),
+        None => app_config::add_adc_account(&email, &tokens_path),
+    }
+    .map_err(Error::AddAccount)?;
+
+    println!();
+    println!(
+        "Saved account credentials in {}",
+        app_cfg.base_path.display()
+    );
+    println!(
+        "Keep them safe! If someone gets access to them, they will also be able to access your\
+        Google Drive."
+    );
+
+    app_config::switch_account(&app_cfg).map_err(Error::SwitchAccount)?;
+    println!();
+    println!("Logged in as {}", app_cfg.account.name);
+
+    // A fresh addition makes an earlier failed one pointless to resume
+    match fs::remove_dir_all(&pending_dir) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => eprintln!(
+            "Warning: unable to remove '{}': {err}",
+            pending_dir.display()
+        ),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn pending_dir() -> Result<PathBuf, Error> {
+    let base_path = AppConfig::default_base_path().map_err(Error::BasePath)?;
+    Ok(base_path.join(PENDING_DIR_NAME))
+}
+
+/// Reads the secret of the pending addition, which has none when it uses Application Default
+/// Credentials.
+fn load_pending(pending_dir: &Path) -> Result<Option<app_config::Secret>, Error> {
+    if !pending_dir.join(TOKENS_FILE_NAME).exists() {
+        return Err(Error::NothingToResume);
+    }
+
+    let secret_path = pending_dir.join(SECRET_FILE_NAME);
+    match fs::read(&secret_path) {
+        Ok(content) => serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|err| Error::LoadPending(io::Error::other(err))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::LoadPending(err)),
+    }
+}
+
+/// Keeps the tokens and the secret after the sign in succeeded, so that `--resume` does not need
+/// another one. Returns `err`, telling how to resume when they were kept.
+fn keep_pending(
+    err: Error,
+    pending_dir: &Path,
+    tokens_path: &Path,
+    secret: Option<&app_config::Secret>,
+) -> Error {
+    let kept = save_pending(pending_dir, tokens_path, secret);
+    if let Err(save_err) = &kept {
+        eprintln!("Warning: unable to keep the sign in: {save_err}");
+    }
+
+    match err {
+        Error::About { source, .. } => Error::About {
+            source,
+            kept: kept.is_ok(),
+        },
+        err => err,
+    }
+}
 
-pub async fn add() -> Result<(), Error> {
+fn save_pending(
+    pending_dir: &Path,
+    tokens_path: &Path,
+    secret: Option<&app_config::Secret>,
+) -> io::Result<()> {
+    app_config::create_private_dir(pending_dir)?;
+
+    let pending_tokens_path = pending_dir.join(TOKENS_FILE_NAME);
+    fs::copy(tokens_path, &pending_tokens_path)?;
+    app_config::set_file_permissions(&pending_tokens_path)?;
+
+    let secret_path = pending_dir.join(SECRET_FILE_NAME);
+    match secret {
+        Some(secret) => {
+            fs::write(
+                &secret_path,
+                serde_json::to_vec(secret).map_err(io::Error::other)?,
+            )?;
+            app_config::set_file_permissions(&secret_path)
+        }
+        None => match fs::remove_file(secret_path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        },
+    }
+}
+
+fn secret_prompt() -> Result<app_config::Secret, io::Error> {
     println!("To add an account you need a Google Client ID and Client Secret.");
     println!(
         "Instructions for how to create credentials can be found here:\
@@ -19,15 +179,16 @@ pub async fn add() -> Result<(), Error> {
     );
     println!();
 
-    let secret = secret_prompt().map_err(Error::Prompt)?;
-
-    let tmp_dir = tempfile::tempdir().map_err(Error::Tempdir)?;
-    let tokens_path = tmp_dir.path().join("tokens.json");
+    let client_id = prompt::input("Client ID")?;
+    let client_secret = prompt::input("Client secret")?;
 
-    let auth = hub::Auth::new(&secret, &tokens_path)
-        .await
-        .map_err(Error::Auth)?;
+    Ok(app_config::Secret {
+        client_id,
+        client_secret,
+    })
+}
 
+async fn authorize(auth: hub::Auth, timeout: Duration) -> Result<String, Error> {
     // Get access tokens
     auth.token(&[
         "https://www.googleapis.com/auth/drive",
@@ -37,37 +198,54 @@ pub async fn add() -> Result<(), Error> {
     .map_err(Error::AccessToken)?;
 
     let hub = hub::Hub::new(auth).map_err(Error::HubCreation)?;
-    let (_, about) = hub
-        .about()
-        .get()
-        .param("fields", "user")
-        .doit()
+    let about = get_about(&hub, timeout)
         .await
-        .map_err(Error::About)?;
+        .map_err(|source| Error::About {
+            source: Box::new(source),
+            kept: false,
+        })?;
 
     let email = about
         .user
         .and_then(|u| u.email_address)
         .map_or(Cow::Borrowed("unknown"), Into::into);
 
-    let app_cfg =
-        app_config::add_account(&email, &secret, &tokens_path).map_err(Error::AddAccount)?;
+    Ok(email.into_owned())
+}
 
-    println!();
-    println!(
-        "Saved account credentials in {}",
-        app_cfg.base_path.display()
-    );
-    println!(
-        "Keep them safe! If someone gets access to them, they will also be able to access your\
-        Google Drive."
-    );
+/// Gets the user of the account, retrying the attempts which time out or fail transiently, since
+/// the sign in is lost if this first request fails.
+async fn get_about(
+    hub: &hub::Hub,
+    timeout: Duration,
+) -> Result<google_drive3::api::About, google_drive3::Error> {
+    let mut backoff = Backoff::new(&BackoffConfig {
+        max_retries: 5,
+        min_sleep: Duration::from_secs(1),
+        max_sleep: Duration::from_secs(30),
+    });
 
-    app_config::switch_account(&app_cfg).map_err(Error::SwitchAccount)?;
-    println!();
-    println!("Logged in as {}", app_cfg.account.name);
+    loop {
+        let request = hub.about().get().param("fields", "user").doit();
+        let err = match tokio::time::timeout(timeout, request).await {
+            Ok(Ok((_, about))) => return Ok(about),
+            Ok(Err(err)) if !bulk::is_transient(&err) => return Err(err),
+            Ok(Err(err)) => err,
+            Err(_) => google_drive3::Error::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("no response within {}s", timeout.as_secs()),
+            )),
+        };
 
-    Ok(())
+        let Some(delay) = backoff.next_delay() else {
+            return Err(err);
+        };
+        eprintln!(
+            "Unable to get the account info ({err}), retrying in {}s",
+            delay.as_secs()
+        );
+        tokio::time::sleep(delay).await;
+    }
 }
 
 #[derive(Debug)]
@@ -79,7 +257,14 @@ pub enum Error {
     AddAccount(app_config::errors::AddAccount),
     SwitchAccount(app_config::errors::SaveAccountConfig),
     AccessToken(google_drive3::oauth2::Error),
-    About(google_drive3::Error),
+    About {
+        source: Box<google_drive3::Error>,
+        /// The tokens were kept to resume the addition
+        kept: bool,
+    },
+    BasePath(app_config::errors::DefaultBasePath),
+    NothingToResume,
+    LoadPending(io::Error),
 }
 
 impl error::Error for Error {
@@ -88,11 +273,14 @@ impl error::Error for Error {
             Error::HubCreation(error)
             | Error::Prompt(error)
             | Error::Tempdir(error)
-            | Error::Auth(error) => Some(error),
+            | Error::Auth(error)
+            | Error::LoadPending(error) => Some(error),
             Error::AddAccount(error) => Some(error),
             Error::SwitchAccount(error) => Some(error),
             Error::AccessToken(error) => Some(error),
-            Error::About(error) => Some(error),
+            Error::About { source, .. } => Some(source),
+            Error::BasePath(error) => Some(error),
+            Error::NothingToResume => None,
         }
     }
 }
@@ -107,29 +295,16 @@ impl Display for Error {
             Error::AddAccount(_) => "unable to add account in the config",
             Error::SwitchAccount(_) => "unable to switch account in the config",
             Error::AccessToken(_) => "failed to get access token",
-            Error::About(_) => "failed to get user info",
+            Error::About { kept: false, .. } => "failed to get user info",
+            Error::About { kept: true, .. } => {
+                "failed to get user info. The sign in is kept, run 'gdrive account add --resume' \
+                to finish adding the account without signing in again"
+            }
+            Error::BasePath(_) => "unable to find the gdrive directory",
+            Error::NothingToResume => "no account addition to resume",
+            Error::LoadPending(_) => "unable to load the account addition to resume",
         };
 
         f.write_str(s)
     }
 }
-
-fn secret_prompt() -> Result<app_config::Secret, io::Error> {
-    let client_id = prompt_input("Client ID")?;
-    let client_secret = prompt_input("Client secret")?;
-
-    Ok(app_config::Secret {
-        client_id,
-        client_secret,
-    })
-}
-
-fn prompt_input(msg: &str) -> Result<String, io::Error> {
-    print!("{msg}: ");
-    let _ = io::stdout().flush();
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-
-    Ok(input.trim().to_string())
-}
@@ -2,24 +2,35 @@ use std::{
     borrow::Cow,
     error,
     fmt::{Display, Formatter, Write},
+    io,
     ops::Not,
-    path::Path,
+    path::PathBuf,
 };
 
 use error_trace::ErrorTrace;
 
 use crate::{
     app_config::{self, set_file_permissions, AppConfig},
-    common::account_archive,
+    common::{
+        account_archive,
+        transfer::{Destination, ExistingFileAction, Resolution},
+    },
 };
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub account_name: String,
+    /// A path to a directory gets the archive with the default name
+    pub destination: Destination,
+    pub existing_file_action: ExistingFileAction,
 }
 
-pub fn export(config: &Config) -> Result<(), Error> {
-    let Config { account_name } = config;
+pub async fn export(config: &Config) -> Result<(), Error> {
+    let Config {
+        account_name,
+        destination,
+        existing_file_action,
+    } = config;
     let accounts = app_config::list_accounts().map_err(Error::ListAccounts)?;
     if accounts.contains(account_name).not() {
         return Err(Error::AccountNotFound);
@@ -29,17 +40,41 @@ pub fn export(config: &Config) -> Result<(), Error> {
     let account_path = app_cfg.account_base_path();
 
     let archive_name = format!("gdrive_export-{}.tar", normalize_name(account_name));
-    let archive_path = Path::new(&archive_name);
-    account_archive::create(account_path, archive_path).map_err(Error::CreateArchive)?;
+    let archive_path = match destination {
+        Destination::CurrentDir => PathBuf::from(&archive_name),
+        Destination::Path(path) if path.is_dir() => path.join(&archive_name),
+        Destination::Path(path) => path.clone(),
+        Destination::Stdout => {
+            let stdout = io::stdout().lock();
+            account_archive::write(account_path, stdout).map_err(Error::CreateArchive)?;
+            eprintln!("Exported account '{account_name}' to stdout");
+            return Ok(());
+        }
+    };
+
+    // Archives have no checksum to compare, an existing file is either kept or replaced
+    match existing_file_action.resolve(&archive_path, None).await {
+        Resolution::Write => {}
+        Resolution::Keep => {
+            println!("Skipping {}, it already exists", archive_path.display());
+            return Ok(());
+        }
+        Resolution::Conflict => return Err(Error::FileExists(archive_path)),
+    }
+
+    account_archive::create(account_path, &archive_path, true).map_err(Error::CreateArchive)?;
 
-    if let Err(err) = set_file_permissions(archive_path) {
+    if let Err(err) = set_file_permissions(&archive_path) {
         eprintln!(
             "Warning: Failed to set permissions on archive: {}",
             err.trace()
         );
     }
 
-    println!("Exported account '{account_name}' to {archive_name}");
+    println!(
+        "Exported account '{account_name}' to {}",
+        archive_path.display()
+    );
 
     Ok(())
 }
@@ -50,6 +85,7 @@ pub enum Error {
     InitAccount(app_config::errors::InitAccount),
     AccountNotFound,
     CreateArchive(account_archive::errors::Create),
+    FileExists(PathBuf),
 }
 
 impl error::Error for Error {
@@ -57,7 +93,7 @@ impl error::Error for Error {
         match self {
             Error::ListAccounts(error) => Some(error),
             Error::InitAccount(error) => Some(error),
-            Error::AccountNotFound => None,
+            Error::AccountNotFound | Error::FileExists(_) => None,
             Error::CreateArchive(error) => Some(error),
         }
     }
@@ -70,6 +106,11 @@ impl Display for Error {
             Error::InitAccount(_) => f.write_str("unable to initialize account in config"),
             Error::AccountNotFound => f.write_str("account not found"),
             Error::CreateArchive(_) => f.write_str("unable to create account archive"),
+            Error::FileExists(path) => write!(
+                f,
+                "file '{}' already exists, use --overwrite to overwrite it",
+                path.display()
+            ),
         }
     }
 }
@@ -1,30 +1,67 @@
 use std::{
     error,
     fmt::{Display, Formatter},
-    path::PathBuf,
+    io::{self, Read},
+    path::{Path, PathBuf},
 };
 
+use google_drive3::hyper::{self, body::HttpBody, header, http::uri::Scheme, StatusCode, Uri};
+
 use crate::{
     app_config::{self, AppConfig},
     common::account_archive,
+    hub,
 };
 
+/// Maximum number of redirects followed while fetching an archive from a URL.
+const MAX_REDIRECTS: usize = 10;
+
+/// Archives from stdin or a URL are read in memory, an account archive only holds a few small
+/// JSON files.
+const MAX_ARCHIVE_SIZE: usize = 64 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub archive_path: PathBuf,
+    pub source: Source,
+}
+
+/// Where the archive is read from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Source {
+    Path(PathBuf),
+    Stdin,
+    Url(String),
 }
 
-pub fn import(config: &Config) -> Result<(), Error> {
-    let account_name =
-        account_archive::get_account_name(&config.archive_path).map_err(Error::ReadAccountName)?;
+impl Source {
+    /// The source given on the command line, where a path of `-` is stdin. The URL takes
+    /// precedence over the path.
+    pub fn new(path: Option<PathBuf>, url: Option<String>) -> Result<Source, Error> {
+        match (path, url) {
+            (_, Some(url)) => Ok(Source::Url(url)),
+            (Some(path), None) if path.as_os_str() == "-" => Ok(Source::Stdin),
+            (Some(path), None) => Ok(Source::Path(path)),
+            (None, None) => Err(Error::MissingSource),
+        }
+    }
+}
+
+pub async fn import(config: &Config) -> Result<(), Error> {
+    let archive = match &config.source {
+        Source::Path(path) => Archive::File(path),
+        Source::Stdin => Archive::Memory(read_archive(io::stdin().lock())?),
+        Source::Url(url) => Archive::Memory(fetch_archive(url).await?),
+    };
+
+    let account_name = archive.account_name().map_err(Error::ReadAccountName)?;
 
     let accounts = app_config::list_accounts().map_err(Error::ListAccounts)?;
     if accounts.contains(&account_name) {
-        return Err(Error::AccountExists(account_name.to_string()));
+        return Err(Error::AccountExists(account_name.clone()));
     }
 
     let config_base_path = AppConfig::default_base_path().map_err(Error::DefaultBasePath)?;
-    account_archive::unpack(&config.archive_path, &config_base_path).map_err(Error::Unpack)?;
+    archive.unpack(&config_base_path).map_err(Error::Unpack)?;
 
     println!("Imported account '{account_name}'");
 
@@ -37,8 +74,115 @@ pub fn import(config: &Config) -> Result<(), Error> {
     Ok(())
 }
 
+enum Archive<'a> {
+    File(&'a Path),
+    /// Archives from stdin or a URL are kept in memory, since they are read twice
+    Memory(Vec<u8>),
+}
+
+impl Archive<'_> {
+    fn account_name(&self) -> Result<String, account_archive::errors::GetAccountName> {
+        match self {
+            Archive::File(path) => account_archive::get_account_name(path),
+            Archive::Memory(archive) => account_archive::read_account_name(archive.as_slice()),
+        }
+    }
+
+    fn unpack(&self, dst_path: &Path) -> Result<(), account_archive::errors::Unpack> {
+        match self {
+            Archive::File(path) => account_archive::unpack(path, dst_path),
+            Archive::Memory(archive) => account_archive::unpack_from(archive.as_slice(), dst_path),
+        }
+    }
+}
+
+/// Reads the archive from `reader`, refusing archives larger than [`MAX_ARCHIVE_SIZE`].
+fn read_archive(reader: impl Read) -> Result<Vec<u8>, Error> {
+    let mut archive = Vec::new();
+    reader
+        .take(MAX_ARCHIVE_SIZE as u64 + 1)
+        .read_to_end(&mut archive)
+        .map_err(Error::ReadStdin)?;
+
+    if archive.len() > MAX_ARCHIVE_SIZE {
+        return Err(Error::ArchiveTooLarge);
+    }
+    Ok(archive)
+}
+
+/// Downloads the archive at `url`, following redirects. The archive holds the credentials of the
+/// account, it is only downloaded over HTTPS.
+async fn fetch_archive(url: &str) -> Result<Vec<u8>, Error> {
+    let client = hub::http_client().map_err(Error::HttpClient)?;
+    let mut uri: Uri = url.parse().map_err(|_| Error::InvalidUrl(url.to_owned()))?;
+
+    for _ in 0..=MAX_REDIRECTS {
+        require_https(&uri)?;
+        let response = client.get(uri.clone()).await.map_err(Error::Fetch)?;
+        let status = response.status();
+
+        if status.is_redirection() {
+            let location = response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .ok_or(Error::FetchStatus(status))?;
+            uri = resolve_redirect(&uri, location)
+                .ok_or_else(|| Error::InvalidUrl(location.to_owned()))?;
+            continue;
+        }
+
+        if !status.is_success() {
+            return Err(Error::FetchStatus(status));
+        }
+
+        let mut body = response.into_body();
+        let mut archive = Vec::new();
+        while let Some(chunk) = body.data().await {
+            archive.extend_from_slice(&chunk.map_err(Error::Fetch)?);
+            if archive.len() > MAX_ARCHIVE_SIZE {
+                return Err(Error::ArchiveTooLarge);
+            }
+        }
+        return Ok(archive);
+    }
+
+    Err(Error::TooManyRedirects)
+}
+
+/// Redirects are checked as well, a redirect to plain HTTP would send the credentials in clear.
+fn require_https(uri: &Uri) -> Result<(), Error> {
+    if uri.scheme() == Some(&Scheme::HTTPS) {
+        Ok(())
+    } else {
+        Err(Error::InsecureUrl(uri.to_string()))
+    }
+}
+
+/// Resolves the `Location` of a redirect, which can be relative to the requested URI.
+fn resolve_redirect(uri: &Uri, location: &str) -> Option<Uri> {
+    let location: Uri = location.parse().ok()?;
+    if location.scheme().is_some() {
+        return Some(location);
+    }
+
+    let mut parts = location.into_parts();
+    parts.scheme = uri.scheme().cloned();
+    parts.authority = uri.authority().cloned();
+    Uri::from_parts(parts).ok()
+}
+
 #[derive(Debug)]
 pub enum Error {
+    MissingSource,
+    ReadStdin(io::Error),
+    InvalidUrl(String),
+    InsecureUrl(String),
+    HttpClient(io::Error),
+    Fetch(hyper::Error),
+    FetchStatus(StatusCode),
+    TooManyRedirects,
+    ArchiveTooLarge,
     ReadAccountName(account_archive::errors::GetAccountName),
     ListAccounts(app_config::errors::ListAccounts),
     AccountExists(String),
@@ -51,7 +195,15 @@ pub enum Error {
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
-            Error::AccountExists(_) => None,
+            Error::AccountExists(_)
+            | Error::MissingSource
+            | Error::InvalidUrl(_)
+            | Error::InsecureUrl(_)
+            | Error::FetchStatus(_)
+            | Error::TooManyRedirects
+            | Error::ArchiveTooLarge => None,
+            Error::ReadStdin(error) | Error::HttpClient(error) => Some(error),
+            Error::Fetch(error) => Some(error),
             Error::ReadAccountName(error) => Some(error),
             Error::Unpack(error) => Some(error),
             Error::ListAccounts(error) => Some(error),
@@ -65,6 +217,29 @@ impl error::Error for Error {
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            Error::MissingSource => f.write_str("either a path or a URL is required"),
+            Error::ReadStdin(_) => f.write_str("unable to read the archive from stdin"),
+            Error::InvalidUrl(url) => write!(f, "'{url}' is not a valid URL"),
+            Error::InsecureUrl(url) => write!(
+                f,
+                "refusing to download the archive from '{url}', only https URLs are allowed"
+            ),
+            Error::HttpClient(_) => f.write_str("unable to create the HTTP client"),
+            Error::Fetch(_) => f.write_str("unable to download the archive"),
+            Error::FetchStatus(status) => {
+                write!(
+                    f,
+                    "unable to download the archive, the server returned {status}"
+                )
+            }
+            Error::TooManyRedirects => {
+                f.write_str("unable to download the archive, too many redirects")
+            }
+            Error::ArchiveTooLarge => write!(
+                f,
+                "the archive is larger than {} KiB, it is not an account archive",
+                MAX_ARCHIVE_SIZE / 1024
+            ),
             Error::ReadAccountName(_) => f.write_str("unable to read the account name"),
             Error::ListAccounts(_) => f.write_str("unable to list accounts"),
             Error::AccountExists(name) => write!(f, "Account '{name}' already exists"),
@@ -75,3 +250,62 @@ impl Display for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io, path::PathBuf};
+
+    use google_drive3::hyper::Uri;
+
+    use super::{read_archive, require_https, resolve_redirect, Error, Source, MAX_ARCHIVE_SIZE};
+
+    #[test]
+    fn source_from_args() {
+        assert_eq!(
+            Source::new(Some(PathBuf::from("-")), None).unwrap(),
+            Source::Stdin
+        );
+        assert_eq!(
+            Source::new(Some(PathBuf::from("a.tar")), None).unwrap(),
+            Source::Path(PathBuf::from("a.tar"))
+        );
+        assert_eq!(
+            Source::new(None, Some(String::from("https://example.com/a.tar"))).unwrap(),
+            Source::Url(String::from("https://example.com/a.tar"))
+        );
+        assert!(matches!(Source::new(None, None), Err(Error::MissingSource)));
+    }
+
+    #[test]
+    fn archive_size_is_capped() {
+        let archive = vec![0; MAX_ARCHIVE_SIZE];
+        assert_eq!(read_archive(archive.as_slice()).unwrap(), archive);
+
+        let reader = io::repeat(0);
+        assert!(matches!(read_archive(reader), Err(Error::ArchiveTooLarge)));
+    }
+
+    #[test]
+    fn only_https_urls() {
+        let uri: Uri = "https://example.com/archive.tar".parse().unwrap();
+        assert!(require_https(&uri).is_ok());
+
+        // Also when redirected from an https URL
+        let uri = resolve_redirect(&uri, "http://example.com/archive.tar").unwrap();
+        assert!(matches!(require_https(&uri), Err(Error::InsecureUrl(_))));
+    }
+
+    #[test]
+    fn resolve_redirects() {
+        let uri: Uri = "https://example.com/a/archive.tar".parse().unwrap();
+
+        assert_eq!(
+            resolve_redirect(&uri, "https://cdn.example.com/archive.tar"),
+            Some("https://cdn.example.com/archive.tar".parse().unwrap())
+        );
+        assert_eq!(
+            resolve_redirect(&uri, "/b/archive.tar?sig=1"),
+            Some("https://example.com/b/archive.tar?sig=1".parse().unwrap())
+        );
+    }
+}
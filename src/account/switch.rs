@@ -1,34 +1,90 @@
 use std::{
     error,
     fmt::{Display, Formatter},
+    io::{self, IsTerminal},
     ops::Not,
 };
 
-use crate::app_config::{self, AppConfig};
+use crate::{
+    app_config::{self, AppConfig},
+    common::prompt,
+};
 
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub account_name: String,
+    /// Pick the account from a numbered list when unset
+    pub account_name: Option<String>,
+    /// Show the list even when stdin is not a terminal, reading the choice from it
+    pub interactive: bool,
 }
 
 pub fn switch(config: &Config) -> Result<(), Error> {
     let accounts = app_config::list_accounts().map_err(Error::ListAccounts)?;
 
-    if accounts.contains(&config.account_name).not() {
+    let account_name = match &config.account_name {
+        Some(account_name) => account_name.clone(),
+        None => pick_account(&accounts, config.interactive)?,
+    };
+
+    if accounts.contains(&account_name).not() {
         return Err(Error::AccountNotFound);
     }
 
-    let app_cfg = AppConfig::init_account(&config.account_name).map_err(Error::InitAccount)?;
+    let app_cfg = AppConfig::init_account(&account_name).map_err(Error::InitAccount)?;
     app_config::switch_account(&app_cfg).map_err(Error::SwitchAccount)?;
-    println!("Switched to account '{}'", &config.account_name);
+    println!("Switched to account '{account_name}'");
 
     Ok(())
 }
 
+/// Lists the accounts, marking the current one, and asks for the number or the name of one.
+/// Unless `forced`, stdin must be a terminal.
+fn pick_account(accounts: &[String], forced: bool) -> Result<String, Error> {
+    if accounts.is_empty() {
+        return Err(Error::NoAccounts);
+    }
+    if !forced && !io::stdin().is_terminal() {
+        return Err(Error::NotInteractive);
+    }
+
+    let current = AppConfig::load_current_account()
+        .ok()
+        .map(|app_cfg| app_cfg.account.name);
+    for (index, account) in accounts.iter().enumerate() {
+        let marker = if current.as_ref() == Some(account) {
+            "*"
+        } else {
+            " "
+        };
+        println!("{marker} {}) {account}", index + 1);
+    }
+
+    let answer = prompt::input("Account").map_err(Error::Prompt)?;
+    parse_choice(&answer, accounts)
+        .map(str::to_owned)
+        .ok_or(Error::InvalidChoice(answer))
+}
+
+/// The account chosen by its position in the list, starting from 1, or by its name.
+fn parse_choice<'a>(answer: &str, accounts: &'a [String]) -> Option<&'a str> {
+    let by_number = answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| accounts.get(number.checked_sub(1)?));
+
+    by_number
+        .or_else(|| accounts.iter().find(|account| *account == answer))
+        .map(String::as_str)
+}
+
 #[derive(Debug)]
 pub enum Error {
     ListAccounts(app_config::errors::ListAccounts),
     AccountNotFound,
+    NoAccounts,
+    NotInteractive,
+    Prompt(io::Error),
+    InvalidChoice(String),
     InitAccount(app_config::errors::InitAccount),
     SwitchAccount(app_config::errors::SaveAccountConfig),
 }
@@ -37,7 +93,11 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::ListAccounts(source) => Some(source),
-            Error::AccountNotFound => None,
+            Error::AccountNotFound
+            | Error::NoAccounts
+            | Error::NotInteractive
+            | Error::InvalidChoice(_) => None,
+            Error::Prompt(source) => Some(source),
             Error::InitAccount(source) => Some(source),
             Error::SwitchAccount(source) => Some(source),
         }
@@ -46,13 +106,36 @@ impl error::Error for Error {
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            Error::ListAccounts(_) => "unable to list accounts",
-            Error::AccountNotFound => "account not found",
-            Error::InitAccount(_) => "unable to initialize the account",
-            Error::SwitchAccount(_) => "unable to switch to the account",
-        };
+        match self {
+            Error::ListAccounts(_) => f.write_str("unable to list accounts"),
+            Error::AccountNotFound => f.write_str("account not found"),
+            Error::NoAccounts => {
+                f.write_str("no accounts found; use `gdrive account add` to add an account")
+            }
+            Error::NotInteractive => f.write_str(
+                "no account name given and stdin is not a terminal, use --interactive to pick one anyway",
+            ),
+            Error::Prompt(_) => f.write_str("failed to get input from user"),
+            Error::InvalidChoice(answer) => write!(f, "'{answer}' is not one of the accounts"),
+            Error::InitAccount(_) => f.write_str("unable to initialize the account"),
+            Error::SwitchAccount(_) => f.write_str("unable to switch to the account"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_choice;
+
+    #[test]
+    fn choose_account() {
+        let accounts = [String::from("personal"), String::from("work")];
 
-        f.write_str(s)
+        assert_eq!(parse_choice("1", &accounts), Some("personal"));
+        assert_eq!(parse_choice("2", &accounts), Some("work"));
+        assert_eq!(parse_choice("work", &accounts), Some("work"));
+        assert_eq!(parse_choice("0", &accounts), None);
+        assert_eq!(parse_choice("3", &accounts), None);
+        assert_eq!(parse_choice("", &accounts), None);
     }
 }
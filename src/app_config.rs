@@ -1,7 +1,7 @@
 pub mod errors;
 
 use std::{
-    fs, io,
+    env, fs, io,
     ops::Not,
     path::{Path, PathBuf},
     sync::OnceLock,
@@ -14,6 +14,11 @@ const BASE_PATH_DIR_NAME: &str = "gdrive3";
 const ACCOUNT_CONFIG_NAME: &str = "account.json";
 const SECRET_CONFIG_NAME: &str = "secret.json";
 const TOKENS_CONFIG_NAME: &str = "tokens.json";
+const ADC_MARKER_NAME: &str = "adc";
+
+const CLIENT_ID_ENV: &str = "GDRIVE_SECRET_CLIENT_ID";
+const CLIENT_SECRET_ENV: &str = "GDRIVE_SECRET_CLIENT_SECRET";
+const REFRESH_TOKEN_ENV: &str = "GDRIVE_SECRET_REFRESH_TOKEN";
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
@@ -23,6 +28,7 @@ pub struct AppConfig {
     account_base_path: OnceLock<PathBuf>,
     secret_path: OnceLock<PathBuf>,
     tokens_path: OnceLock<PathBuf>,
+    adc_marker_path: OnceLock<PathBuf>,
 }
 
 pub fn add_account(
@@ -34,10 +40,32 @@ pub fn add_account(
     config
         .save_secret(secret)
         .map_err(errors::AddAccount::SaveSecret)?;
-    fs::copy(tokens_path, config.tokens_path()).map_err(errors::AddAccount::CopyTokens)?;
+    copy_tokens(tokens_path, &config)?;
     Ok(config)
 }
 
+/// Adds an account authenticated with Application Default Credentials, which has no secret.
+pub fn add_adc_account(
+    account_name: &str,
+    tokens_path: &Path,
+) -> Result<AppConfig, errors::AddAccount> {
+    let config = AppConfig::init_account(account_name).map_err(errors::AddAccount::InitAccount)?;
+    fs::write(config.adc_marker_path(), "").map_err(errors::AddAccount::SaveAdcMarker)?;
+    copy_tokens(tokens_path, &config)?;
+    Ok(config)
+}
+
+/// Copies the tokens of a new account, readable by the current user only.
+fn copy_tokens(tokens_path: &Path, config: &AppConfig) -> Result<(), errors::AddAccount> {
+    fs::copy(tokens_path, config.tokens_path()).map_err(errors::AddAccount::CopyTokens)?;
+
+    if let Err(err) = set_file_permissions(config.tokens_path()) {
+        eprintln!("Warning: Failed to set file permissions on tokens file: {err}");
+    }
+
+    Ok(())
+}
+
 pub fn switch_account(config: &AppConfig) -> Result<(), errors::SaveAccountConfig> {
     config.save_account_config()
 }
@@ -83,6 +111,7 @@ impl AppConfig {
             account_base_path: OnceLock::new(),
             secret_path: OnceLock::new(),
             tokens_path: OnceLock::new(),
+            adc_marker_path: OnceLock::new(),
         }
     }
 
@@ -241,6 +270,19 @@ impl AppConfig {
             .get_or_init(|| self.account_base_path().join(TOKENS_CONFIG_NAME))
     }
 
+    #[must_use]
+    pub fn adc_marker_path(&self) -> &Path {
+        self.adc_marker_path
+            .get_or_init(|| self.account_base_path().join(ADC_MARKER_NAME))
+    }
+
+    /// Whether the account authenticates with Application Default Credentials instead of a
+    /// client secret.
+    #[must_use]
+    pub fn uses_application_default_credentials(&self) -> bool {
+        self.adc_marker_path().exists()
+    }
+
     pub fn default_base_path() -> Result<PathBuf, errors::DefaultBasePath> {
         let home_path = home::home_dir().ok_or(errors::DefaultBasePath)?;
         let base_path = home_path
@@ -282,6 +324,67 @@ pub struct Secret {
     pub client_secret: String,
 }
 
+/// Credentials given through the `GDRIVE_SECRET_*` environment variables, meant for ephemeral
+/// environments like CI where no account is configured. They are never written to disk.
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    pub secret: Secret,
+    /// Without a refresh token the usual authorization flow is started
+    pub refresh_token: Option<String>,
+}
+
+impl EnvCredentials {
+    /// Loads the credentials from the environment, `None` when neither the client id nor the
+    /// client secret are set.
+    pub fn load() -> Result<Option<EnvCredentials>, errors::LoadEnvCredentials> {
+        let client_id = env_var(CLIENT_ID_ENV)?;
+        let client_secret = env_var(CLIENT_SECRET_ENV)?;
+
+        let (client_id, client_secret) = match (client_id, client_secret) {
+            (Some(client_id), Some(client_secret)) => (client_id, client_secret),
+            (None, None) => return Ok(None),
+            (Some(_), None) => return Err(errors::LoadEnvCredentials::Missing(CLIENT_SECRET_ENV)),
+            (None, Some(_)) => return Err(errors::LoadEnvCredentials::Missing(CLIENT_ID_ENV)),
+        };
+
+        Ok(Some(EnvCredentials {
+            secret: Secret {
+                client_id,
+                client_secret,
+            },
+            refresh_token: env_var(REFRESH_TOKEN_ENV)?,
+        }))
+    }
+}
+
+fn env_var(name: &'static str) -> Result<Option<String>, errors::LoadEnvCredentials> {
+    match env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(errors::LoadEnvCredentials::NotUnicode(name)),
+    }
+}
+
+/// Creates the directory and its missing parents, readable only by the current user on Unix.
+pub fn create_private_dir(path: &Path) -> Result<(), io::Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(path)?;
+        // The directory may have been created before with the default mode
+        fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    }
+
+    #[cfg(not(unix))]
+    fs::create_dir_all(path)?;
+
+    Ok(())
+}
+
 pub fn set_file_permissions(path: &Path) -> Result<(), io::Error> {
     #[cfg(unix)]
     {
@@ -289,5 +392,44 @@ pub fn set_file_permissions(path: &Path) -> Result<(), io::Error> {
         fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
     }
 
+    #[cfg(windows)]
+    {
+        // Drop the entries inherited from the parent directory, leaving only the current user
+        let Some(user) = current_user_sid().or_else(|| env::var("USERNAME").ok()) else {
+            eprintln!(
+                "Warning: Unable to find the current user, the permissions of '{}' are unchanged",
+                path.display()
+            );
+            return Ok(());
+        };
+        let status = std::process::Command::new("icacls")
+            .arg(path)
+            .args(["/inheritance:r", "/grant:r"])
+            .arg(format!("{user}:F"))
+            .stdout(std::process::Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("icacls failed with {status}")));
+        }
+    }
+
     Ok(())
 }
+
+/// The SID of the current user as given to icacls, i.e. `*S-1-5-21-...`. Unlike `USERNAME`, it is
+/// known to services and scheduled tasks as well.
+#[cfg(windows)]
+fn current_user_sid() -> Option<String> {
+    let output = std::process::Command::new("whoami")
+        .args(["/user", "/fo", "csv", "/nh"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // The output is a single row like "domain\user","S-1-5-21-..."
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let sid = stdout.trim().rsplit(',').next()?.trim_matches('"');
+    sid.starts_with("S-").then(|| format!("*{sid}"))
+}
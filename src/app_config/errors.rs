@@ -103,6 +103,7 @@ impl Error for SaveSecret {
 pub enum AddAccount {
     InitAccount(InitAccount),
     SaveSecret(SaveSecret),
+    SaveAdcMarker(io::Error),
     CopyTokens(io::Error),
 }
 
@@ -111,6 +112,7 @@ impl Display for AddAccount {
         let s = match self {
             AddAccount::InitAccount(_) => "unable to initialize account",
             AddAccount::SaveSecret(_) => "unable to save secret",
+            AddAccount::SaveAdcMarker(_) => "unable to save application default credentials marker",
             AddAccount::CopyTokens(_) => "unable to save tokens to file",
         };
         f.write_str(s)
@@ -122,7 +124,7 @@ impl Error for AddAccount {
         match self {
             AddAccount::InitAccount(source) => Some(source),
             AddAccount::SaveSecret(source) => Some(source),
-            AddAccount::CopyTokens(source) => Some(source),
+            AddAccount::SaveAdcMarker(source) | AddAccount::CopyTokens(source) => Some(source),
         }
     }
 }
@@ -331,3 +333,24 @@ impl Error for LoadSecret {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadEnvCredentials {
+    Missing(&'static str),
+    NotUnicode(&'static str),
+}
+
+impl Display for LoadEnvCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadEnvCredentials::Missing(name) => {
+                write!(f, "environment variable {name} is not set")
+            }
+            LoadEnvCredentials::NotUnicode(name) => {
+                write!(f, "environment variable {name} is not valid unicode")
+            }
+        }
+    }
+}
+
+impl Error for LoadEnvCredentials {}
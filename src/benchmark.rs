@@ -0,0 +1,226 @@
+use std::{
+    borrow::Cow,
+    error,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{self, Seek, Write},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bytesize::ByteSize;
+use futures::StreamExt;
+use google_drive3::hyper;
+
+use crate::{
+    common::{
+        delegate::{BackoffConfig, ChunkSize, UploadDelegate, UploadDelegateConfig},
+        file_info::FileInfo,
+        hub_helper::{get_hub, GetHubError},
+    },
+    files,
+    hub::Hub,
+};
+
+const LATENCY_SAMPLES: u32 = 5;
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+pub struct Config {
+    pub size: u64,
+    pub chunk_size: ChunkSize,
+}
+
+pub async fn benchmark(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig {
+        chunk_size: config.chunk_size,
+        backoff_config: BackoffConfig {
+            max_retries: 100_000,
+            min_sleep: Duration::from_secs(1),
+            max_sleep: Duration::from_secs(60),
+        },
+        print_chunk_errors: false,
+        print_chunk_info: false,
+    };
+
+    let file = random_file(config.size).map_err(Error::CreateFile)?;
+
+    println!(
+        "Uploading {} with chunks of {}",
+        ByteSize::b(config.size).display().si(),
+        ByteSize::b(delegate_config.chunk_size.in_bytes())
+            .display()
+            .si()
+    );
+
+    let mut delegate = UploadDelegate::new(&delegate_config);
+    let start = Instant::now();
+    let drive_file = files::upload::upload_file_with_delegate(
+        &hub,
+        file,
+        None,
+        FileInfo {
+            name: Cow::Owned(benchmark_file_name()),
+            mime_type: Cow::Borrowed(&mime::APPLICATION_OCTET_STREAM),
+            parents: None,
+            size: config.size,
+            app_properties: None,
+            description: None,
+            starred: false,
+            content_hints: None,
+        },
+        &mut delegate,
+    )
+    .await
+    .map_err(|err| Error::Upload(Box::new(err)))?;
+    let upload_time = start.elapsed();
+
+    println!(
+        "Upload: {} in {upload_time:.1?} ({}/s), {} chunks, {} retries",
+        ByteSize::b(config.size).display().si(),
+        throughput(config.size, upload_time).display().si(),
+        delegate.chunk_count(),
+        delegate.retry_count(),
+    );
+
+    let file_id = drive_file.id.unwrap_or_default();
+    let result = measure_download_and_latency(&hub, &file_id, config.size).await;
+
+    // The file is always deleted, even when the measurements fail
+    hub.files()
+        .delete(&file_id)
+        .supports_all_drives(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .doit()
+        .await
+        .map_err(|err| Error::Delete(Box::new(err)))?;
+
+    result
+}
+
+async fn measure_download_and_latency(hub: &Hub, file_id: &str, size: u64) -> Result<(), Error> {
+    let start = Instant::now();
+    let mut body = files::download::download_file(hub, file_id)
+        .await
+        .map_err(|err| Error::Download(Box::new(err)))?;
+    let first_byte_time = start.elapsed();
+
+    let mut downloaded: u64 = 0;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(Error::ReadChunk)?;
+        downloaded += chunk.len() as u64;
+    }
+    let download_time = start.elapsed();
+
+    if downloaded != size {
+        return Err(Error::SizeMismatch {
+            expected: size,
+            actual: downloaded,
+        });
+    }
+
+    println!(
+        "Download: {} in {download_time:.1?} ({}/s), first byte after {first_byte_time:.0?}",
+        ByteSize::b(downloaded).display().si(),
+        throughput(downloaded, download_time).display().si(),
+    );
+
+    let mut latencies = Vec::new();
+    for _ in 0..LATENCY_SAMPLES {
+        let start = Instant::now();
+        files::info::get_file(hub, file_id)
+            .await
+            .map_err(|err| Error::GetFile(Box::new(err)))?;
+        latencies.push(start.elapsed());
+    }
+
+    let min = latencies.iter().min().copied().unwrap_or_default();
+    let avg = latencies.iter().sum::<Duration>() / LATENCY_SAMPLES;
+    println!("Latency: min {min:.0?}, avg {avg:.0?} over {LATENCY_SAMPLES} metadata requests");
+
+    Ok(())
+}
+
+/// Creates an anonymous temporary file filled with pseudo random, hence incompressible, bytes.
+fn random_file(size: u64) -> io::Result<File> {
+    let mut file = tempfile::tempfile()?;
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+        | 1;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut remaining = size;
+
+    while remaining > 0 {
+        for bytes in buffer.chunks_exact_mut(8) {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            bytes.copy_from_slice(&state.to_le_bytes());
+        }
+
+        let len = usize::try_from(remaining).map_or(BUFFER_SIZE, |len| len.min(BUFFER_SIZE));
+        file.write_all(&buffer[..len])?;
+        remaining -= len as u64;
+    }
+
+    file.rewind()?;
+    Ok(file)
+}
+
+fn benchmark_file_name() -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    format!("gdrive-benchmark-{timestamp}")
+}
+
+fn throughput(bytes: u64, elapsed: Duration) -> ByteSize {
+    let per_second = u128::from(bytes) * 1000 / elapsed.as_millis().max(1);
+    ByteSize::b(u64::try_from(per_second).unwrap_or(u64::MAX))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    CreateFile(io::Error),
+    Upload(Box<google_drive3::Error>),
+    Download(Box<google_drive3::Error>),
+    ReadChunk(hyper::Error),
+    SizeMismatch { expected: u64, actual: u64 },
+    GetFile(Box<google_drive3::Error>),
+    Delete(Box<google_drive3::Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::CreateFile(_) => f.write_str("unable to create benchmark file"),
+            Error::Upload(_) => f.write_str("unable to upload benchmark file"),
+            Error::Download(_) => f.write_str("unable to download benchmark file"),
+            Error::ReadChunk(_) => f.write_str("unable to read chunk of bytes"),
+            Error::SizeMismatch { expected, actual } => write!(
+                f,
+                "downloaded {actual} bytes, but {expected} bytes were uploaded"
+            ),
+            Error::GetFile(_) => f.write_str("unable to get benchmark file info"),
+            Error::Delete(_) => f.write_str("unable to delete benchmark file"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::CreateFile(source) => Some(source),
+            Error::Upload(source)
+            | Error::Download(source)
+            | Error::GetFile(source)
+            | Error::Delete(source) => Some(source),
+            Error::ReadChunk(source) => Some(source),
+            Error::SizeMismatch { .. } => None,
+        }
+    }
+}
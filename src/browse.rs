@@ -0,0 +1,621 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    future::Future,
+    io,
+    path::PathBuf,
+};
+
+use error_trace::ErrorTrace;
+use ratatui::{
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+        execute,
+        terminal::{self, EnterAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    common::{
+        delegate::ChunkSize,
+        drive_file, drive_path,
+        file_filter::FileFilter,
+        file_id::FileId,
+        hook::OnComplete,
+        hub_helper::{get_hub, GetHubError},
+        local_name::SanitizeNames,
+        permission,
+        size_format::SizeFormat,
+        transfer::{Destination, ExistingFileAction},
+    },
+    files::{
+        self,
+        download::OnCollision,
+        info::{DateFormat, DisplayBytes, DisplayConfig},
+        list::{ListFilesConfig, ListQuery, ListSortOrder},
+    },
+    hub::Hub,
+    permissions,
+};
+
+const ROOT_FOLDER_NAME: &str = "My Drive";
+const HELP: &str =
+    "↑/↓ move  enter open  backspace back  i info  d download  u upload  x delete  s share  r refresh  q quit";
+
+pub struct Config {
+    pub folder_id: Option<FileId>,
+    pub max_files: usize,
+}
+
+pub async fn browse(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let (root, drive_id) = if let Some(folder_id) = &config.folder_id {
+        let file = files::info::get_file(&hub, folder_id)
+            .await
+            .map_err(|err| Error::GetFile(Box::new(err)))?;
+        if !drive_file::is_directory(&file) {
+            return Err(Error::NotADirectory(folder_id.to_string()));
+        }
+        let folder = Folder::new(folder_id.to_string(), file.name.unwrap_or_default());
+        (folder, file.drive_id)
+    } else {
+        let folder = Folder::new(
+            drive_path::MY_DRIVE_ROOT_ID.to_owned(),
+            ROOT_FOLDER_NAME.to_owned(),
+        );
+        (folder, None)
+    };
+
+    let mut browser = Browser {
+        hub,
+        drive_id,
+        max_files: config.max_files,
+        folders: vec![root],
+        details: None,
+        mode: Mode::Normal,
+        status: None,
+    };
+    browser.reload().await;
+
+    let mut terminal = ratatui::try_init().map_err(Error::Terminal)?;
+    let result = browser.run(&mut terminal).await;
+    ratatui::try_restore().map_err(Error::Terminal)?;
+
+    result
+}
+
+struct Folder {
+    id: String,
+    name: String,
+    files: Vec<google_drive3::api::File>,
+    state: ListState,
+}
+
+impl Folder {
+    fn new(id: String, name: String) -> Self {
+        Self {
+            id,
+            name,
+            files: vec![],
+            state: ListState::default(),
+        }
+    }
+
+    fn selected(&self) -> Option<&google_drive3::api::File> {
+        self.state
+            .selected()
+            .and_then(|index| self.files.get(index))
+    }
+}
+
+enum Mode {
+    Normal,
+    Input { action: InputAction, value: String },
+    ConfirmDelete,
+}
+
+#[derive(Clone, Copy)]
+enum InputAction {
+    Upload,
+    Share,
+}
+
+impl InputAction {
+    fn prompt(self) -> &'static str {
+        match self {
+            InputAction::Upload => "Upload path: ",
+            InputAction::Share => "Share with email: ",
+        }
+    }
+}
+
+enum Action {
+    Quit,
+    Open,
+    Back,
+    Info,
+    Refresh,
+    Download,
+    Upload(PathBuf),
+    Delete,
+    Share(String),
+}
+
+struct Browser {
+    hub: Hub,
+    drive_id: Option<String>,
+    max_files: usize,
+    folders: Vec<Folder>,
+    details: Option<google_drive3::api::File>,
+    mode: Mode,
+    status: Option<String>,
+}
+
+impl Browser {
+    async fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(Error::Terminal)?;
+
+            let Event::Key(key) = event::read().map_err(Error::Terminal)? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let Some(action) = self.handle_key(key) else {
+                continue;
+            };
+            self.status = None;
+
+            match action {
+                Action::Quit => return Ok(()),
+                Action::Open => self.open().await,
+                Action::Back => {
+                    if self.folders.len() > 1 {
+                        self.folders.pop();
+                        self.details = None;
+                    }
+                }
+                Action::Info => self.load_details().await,
+                Action::Refresh => self.reload().await,
+                Action::Download => self.download(terminal).await?,
+                Action::Upload(path) => self.upload(terminal, path).await?,
+                Action::Delete => self.delete(terminal).await?,
+                Action::Share(email) => self.share(terminal, email).await?,
+            }
+        }
+    }
+
+    fn current(&self) -> &Folder {
+        self.folders
+            .last()
+            .expect("root folder should always exist")
+    }
+
+    fn current_mut(&mut self) -> &mut Folder {
+        self.folders
+            .last_mut()
+            .expect("root folder should always exist")
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match &mut self.mode {
+            Mode::Normal => self.handle_normal_key(key),
+
+            Mode::Input { action, value } => match key.code {
+                KeyCode::Enter => {
+                    let value = value.trim().to_owned();
+                    let action = *action;
+                    self.mode = Mode::Normal;
+                    if value.is_empty() {
+                        return None;
+                    }
+                    match action {
+                        InputAction::Upload => Some(Action::Upload(PathBuf::from(value))),
+                        InputAction::Share => Some(Action::Share(value)),
+                    }
+                }
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    None
+                }
+                KeyCode::Backspace => {
+                    value.pop();
+                    None
+                }
+                KeyCode::Char(c) => {
+                    value.push(c);
+                    None
+                }
+                _ => None,
+            },
+
+            Mode::ConfirmDelete => {
+                self.mode = Mode::Normal;
+                matches!(key.code, KeyCode::Char('y' | 'Y')).then_some(Action::Delete)
+            }
+        }
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.details = None;
+                self.current_mut().state.select_previous();
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.details = None;
+                self.current_mut().state.select_next();
+                None
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => Some(Action::Open),
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => Some(Action::Back),
+            KeyCode::Char('i') => Some(Action::Info),
+            KeyCode::Char('r') => Some(Action::Refresh),
+            KeyCode::Char('d') => Some(Action::Download),
+            KeyCode::Char('u') => {
+                self.mode = Mode::Input {
+                    action: InputAction::Upload,
+                    value: String::new(),
+                };
+                None
+            }
+            KeyCode::Char('s') if self.current().selected().is_some() => {
+                self.mode = Mode::Input {
+                    action: InputAction::Share,
+                    value: String::new(),
+                };
+                None
+            }
+            KeyCode::Char('x') | KeyCode::Delete if self.current().selected().is_some() => {
+                self.mode = Mode::ConfirmDelete;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    async fn reload(&mut self) {
+        let folder_id = self.current().id.clone();
+        let query = ListQuery::FilesInFolder { folder_id };
+        let result = files::list::list_files(
+            &self.hub,
+            ListFilesConfig {
+                query: &query,
+                order_by: &ListSortOrder::default(),
+                max_files: self.max_files,
+                extra_fields: &[],
+                drive_id: self.drive_id.as_deref(),
+                corpora: None,
+            },
+        )
+        .await;
+
+        self.details = None;
+        match result {
+            Ok(files) => {
+                let folder = self.current_mut();
+                let selected = folder.state.selected().unwrap_or(0);
+                folder.state.select(if files.is_empty() {
+                    None
+                } else {
+                    Some(selected.min(files.len() - 1))
+                });
+                folder.files = files;
+            }
+            Err(err) => self.status = Some(err.to_string()),
+        }
+    }
+
+    async fn open(&mut self) {
+        let Some(file) = self.current().selected() else {
+            return;
+        };
+
+        if drive_file::is_directory(file) {
+            let folder = Folder::new(
+                file.id.clone().unwrap_or_default(),
+                file.name.clone().unwrap_or_default(),
+            );
+            self.folders.push(folder);
+            self.reload().await;
+        } else {
+            self.load_details().await;
+        }
+    }
+
+    async fn load_details(&mut self) {
+        let Some(file_id) = self.current().selected().and_then(|file| file.id.clone()) else {
+            return;
+        };
+
+        match files::info::get_file(&self.hub, &file_id).await {
+            Ok(file) => self.details = Some(file),
+            Err(err) => self.status = Some(format!("unable to get file: {err}")),
+        }
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.current().selected().and_then(|file| file.id.clone())
+    }
+
+    async fn download(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        let Some(file_id) = self.selected_id() else {
+            return Ok(());
+        };
+
+        suspended(
+            terminal,
+            files::download(files::download::Config {
+                file_id: FileId::from_drive(file_id),
+                existing_file_action: ExistingFileAction::Abort,
+                follow_shortcuts: true,
+                download_directories: true,
+                destination: Destination::CurrentDir,
+                auto_decompress: false,
+                filter: FileFilter::default(),
+                include_docs: false,
+                manifest: None,
+                file_timeout: None,
+                cache_ttl: None,
+                failure_manifest: None,
+                verify_checksum: true,
+                join: false,
+                size_format: SizeFormat::default(),
+                ranges: 1,
+                export_format: None,
+                name: None,
+                on_complete: OnComplete::resolve(None),
+                sanitize_names: SanitizeNames::default(),
+                on_collision: OnCollision::default(),
+            }),
+        )
+        .await
+    }
+
+    async fn upload(&mut self, terminal: &mut DefaultTerminal, path: PathBuf) -> Result<(), Error> {
+        let parent = self.current().id.clone();
+
+        suspended(
+            terminal,
+            Box::pin(files::upload(files::upload::Config {
+                file_path: Some(path),
+                mime_type: None,
+                parents: Some(vec![FileId::from_drive(parent)]),
+                chunk_size: ChunkSize::default(),
+                print_chunk_errors: false,
+                print_chunk_info: false,
+                upload_directories: true,
+                print_only_id: false,
+                gzip: false,
+                continue_on_error: false,
+                failure_manifest: None,
+                retry_failed: None,
+                from_archive: None,
+                verify: false,
+                check_quota: false,
+                filter: FileFilter::default(),
+                max_depth: None,
+                one_file_system: false,
+                split: None,
+                size_format: SizeFormat::default(),
+                app_properties: None,
+                name: None,
+                description: None,
+                starred: false,
+                thumbnail: None,
+                drive_name: None,
+                parent_name: None,
+                parent_path: None,
+                on_complete: OnComplete::resolve(None),
+                staged: None,
+            })),
+        )
+        .await?;
+
+        self.reload().await;
+        Ok(())
+    }
+
+    async fn delete(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        let Some(file_id) = self.selected_id() else {
+            return Ok(());
+        };
+
+        suspended(
+            terminal,
+            files::delete(files::delete::Config {
+                file_id: FileId::from_drive(file_id),
+                delete_directories: true,
+                // Already confirmed in the browser
+                assume_yes: true,
+                trash: false,
+            }),
+        )
+        .await?;
+
+        self.reload().await;
+        Ok(())
+    }
+
+    async fn share(&mut self, terminal: &mut DefaultTerminal, email: String) -> Result<(), Error> {
+        let Some(file_id) = self.selected_id() else {
+            return Ok(());
+        };
+
+        suspended(
+            terminal,
+            permissions::share(permissions::share::Config {
+                file_id: FileId::from_drive(file_id),
+                role: permission::Role::Reader,
+                type_: permission::Type::User,
+                discoverable: false,
+                email: Some(email),
+                domain: None,
+                domain_admin: false,
+                expiration: None,
+            }),
+        )
+        .await
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, details_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(main_area);
+
+        let path = self
+            .folders
+            .iter()
+            .map(|folder| folder.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" / ");
+
+        let details = self
+            .details
+            .as_ref()
+            .or_else(|| self.current().selected())
+            .map(detail_lines)
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(details)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title("Details")),
+            details_area,
+        );
+
+        let status = match &self.mode {
+            Mode::Normal => self.status.clone().unwrap_or_else(|| HELP.to_owned()),
+            Mode::Input { action, value } => format!("{}{value}", action.prompt()),
+            Mode::ConfirmDelete => String::from("Delete selected file? (y/N)"),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+
+        let folder = self.current_mut();
+        let items = folder
+            .files
+            .iter()
+            .map(|file| {
+                let name = file.name.as_deref().unwrap_or_default();
+                if drive_file::is_directory(file) {
+                    ListItem::new(format!("{name}/"))
+                } else {
+                    ListItem::new(name)
+                }
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(path))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut folder.state);
+    }
+}
+
+fn detail_lines(file: &google_drive3::api::File) -> Vec<Line<'static>> {
+    let display_config = DisplayConfig::default();
+    let fields = [
+        ("Id", file.id.clone()),
+        ("Name", file.name.clone()),
+        ("Mime", file.mime_type.clone()),
+        (
+            "Size",
+            file.size.map(|bytes| {
+                DisplayBytes {
+                    bytes: u64::try_from(bytes).unwrap_or(0),
+                    config: &display_config,
+                }
+                .to_string()
+            }),
+        ),
+        (
+            "Created",
+            file.created_time
+                .map(|time| files::info::format_date_time(time, &DateFormat::default())),
+        ),
+        (
+            "Modified",
+            file.modified_time
+                .map(|time| files::info::format_date_time(time, &DateFormat::default())),
+        ),
+        ("MD5", file.md5_checksum.clone()),
+        ("SHA256", file.sha256_checksum.clone()),
+        (
+            "Shared",
+            file.shared
+                .map(|shared| files::info::format_bool(shared).to_owned()),
+        ),
+        ("ViewUrl", file.web_view_link.clone()),
+    ];
+
+    fields
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| Line::from(format!("{name}: {value}"))))
+        .collect()
+}
+
+/// Leaves the terminal UI while `action` runs, so that its regular output stays readable, and
+/// waits for the user before going back.
+async fn suspended<F, E>(terminal: &mut DefaultTerminal, action: F) -> Result<(), Error>
+where
+    F: Future<Output = Result<(), E>>,
+    E: error::Error + 'static,
+{
+    ratatui::try_restore().map_err(Error::Terminal)?;
+
+    if let Err(err) = action.await {
+        eprintln!("{}", err.trace());
+    }
+
+    println!();
+    println!("Press enter to return to the browser");
+    io::stdin()
+        .read_line(&mut String::new())
+        .map_err(Error::Terminal)?;
+
+    terminal::enable_raw_mode().map_err(Error::Terminal)?;
+    execute!(io::stdout(), EnterAlternateScreen).map_err(Error::Terminal)?;
+    terminal.clear().map_err(Error::Terminal)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    GetFile(Box<google_drive3::Error>),
+    NotADirectory(String),
+    Terminal(io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFile(_) => f.write_str("unable to get folder"),
+            Error::NotADirectory(id) => write!(f, "file '{id}' is not a directory"),
+            Error::Terminal(_) => f.write_str("unable to control the terminal"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source) => Some(source),
+            Error::NotADirectory(_) => None,
+            Error::Terminal(source) => Some(source),
+        }
+    }
+}
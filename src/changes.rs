@@ -0,0 +1,3 @@
+pub mod watch;
+
+pub use watch::watch;
@@ -0,0 +1,94 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+};
+
+use crate::common::{
+    channel::{self, Action},
+    file_id::FileId,
+    hub_helper::{get_hub, GetHubError},
+};
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Watch the changes of this shared drive instead of the ones of the user
+    pub drive_id: Option<FileId>,
+    pub action: Action,
+}
+
+/// Opens a channel notified of the changes from now on, or closes it.
+pub async fn watch(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let channel_config = match &config.action {
+        Action::Start(channel_config) => channel_config,
+        Action::Stop {
+            channel_id,
+            resource_id,
+        } => {
+            channel::stop(&hub, channel_id, resource_id)
+                .await
+                .map_err(Error::Stop)?;
+            println!("Stopped channel {channel_id}");
+            return Ok(());
+        }
+    };
+
+    let mut start_call = hub
+        .changes()
+        .get_start_page_token()
+        .supports_all_drives(true);
+    if let Some(drive_id) = &config.drive_id {
+        start_call = start_call.drive_id(drive_id);
+    }
+    let (_, start) = start_call.doit().await.map_err(Error::StartPageToken)?;
+    let page_token = start.start_page_token.ok_or(Error::MissingPageToken)?;
+
+    let mut watch_call = hub
+        .changes()
+        .watch(channel_config.channel(), &page_token)
+        .supports_all_drives(true)
+        .include_items_from_all_drives(true);
+    if let Some(drive_id) = &config.drive_id {
+        watch_call = watch_call.drive_id(drive_id);
+    }
+    let (_, channel) = watch_call.doit().await.map_err(Error::Watch)?;
+
+    println!("Watching changes from page token {page_token}");
+    channel::print_channel(&channel);
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    StartPageToken(google_drive3::Error),
+    MissingPageToken,
+    Watch(google_drive3::Error),
+    Stop(google_drive3::Error),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::StartPageToken(source) | Error::Watch(source) | Error::Stop(source) => {
+                Some(source)
+            }
+            Error::MissingPageToken => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::StartPageToken(_) => f.write_str("unable to get the start page token"),
+            Error::MissingPageToken => f.write_str("the start page token is missing"),
+            Error::Watch(_) => f.write_str("unable to watch the changes"),
+            Error::Stop(_) => f.write_str("unable to stop the channel"),
+        }
+    }
+}
@@ -2,26 +2,40 @@ pub mod errors;
 
 use std::{
     fs::File,
+    io::{Read, Write},
     ops::Not,
     path::{Path, PathBuf},
 };
 
 /// Creates an archive of the given source directory.
 ///
+/// An existing file at `archive_path` is replaced only when `overwrite` is set.
+///
 /// # Panics
 ///
 /// The function panics if `src_path` terminates with a `..`.
-pub fn create(src_path: &Path, archive_path: &Path) -> Result<(), errors::Create> {
-    if src_path.exists().not() {
-        return Err(errors::Create::SrcPathDoesNotExist);
-    }
+pub fn create(src_path: &Path, archive_path: &Path, overwrite: bool) -> Result<(), errors::Create> {
+    check_src_path(src_path)?;
 
-    if src_path.is_dir().not() {
-        return Err(errors::Create::SrcPathNotDirectory);
+    let archive_file = if overwrite {
+        File::create(archive_path)
+    } else {
+        File::create_new(archive_path)
     }
+    .map_err(errors::Create::CreateArchive)?;
 
-    let archive_file = File::create_new(archive_path).map_err(errors::Create::CreateArchive)?;
-    let mut builder = tar::Builder::new(archive_file);
+    write(src_path, archive_file)
+}
+
+/// Writes an archive of the given source directory to `writer`.
+///
+/// # Panics
+///
+/// The function panics if `src_path` terminates with a `..`.
+pub fn write<W: Write>(src_path: &Path, writer: W) -> Result<(), errors::Create> {
+    check_src_path(src_path)?;
+
+    let mut builder = tar::Builder::new(writer);
 
     let src_dir_name = src_path
         .file_name()
@@ -39,23 +53,45 @@ pub fn create(src_path: &Path, archive_path: &Path) -> Result<(), errors::Create
     Ok(())
 }
 
+fn check_src_path(src_path: &Path) -> Result<(), errors::Create> {
+    if src_path.exists().not() {
+        return Err(errors::Create::SrcPathDoesNotExist);
+    }
+
+    if src_path.is_dir().not() {
+        return Err(errors::Create::SrcPathNotDirectory);
+    }
+
+    Ok(())
+}
+
 pub fn unpack(archive_path: &Path, dst_path: &Path) -> Result<(), errors::Unpack> {
     if archive_path.exists().not() {
         return Err(errors::Unpack::ArchivePathDoesNotExist);
     }
 
+    let archive_file = File::open(archive_path).map_err(errors::Unpack::Open)?;
+    unpack_from(archive_file, dst_path)
+}
+
+/// Unpacks an archive read from `reader` into `dst_path`.
+pub fn unpack_from<R: Read>(reader: R, dst_path: &Path) -> Result<(), errors::Unpack> {
     if dst_path.exists().not() {
         return Err(errors::Unpack::DstDoesNotExist);
     }
 
-    let archive_file = File::open(archive_path).map_err(errors::Unpack::Open)?;
-    let mut archive = tar::Archive::new(archive_file);
+    let mut archive = tar::Archive::new(reader);
     archive.unpack(dst_path).map_err(errors::Unpack::Unpack)
 }
 
 pub fn get_account_name(archive_path: &Path) -> Result<String, errors::GetAccountName> {
     let archive_file = File::open(archive_path).map_err(errors::GetAccountName::Open)?;
-    let mut archive = tar::Archive::new(archive_file);
+    read_account_name(archive_file)
+}
+
+/// Gets the name of the account of an archive read from `reader`.
+pub fn read_account_name<R: Read>(reader: R) -> Result<String, errors::GetAccountName> {
+    let mut archive = tar::Archive::new(reader);
     let entries = archive
         .entries()
         .map_err(errors::GetAccountName::ReadEntries)?;
@@ -0,0 +1,92 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// A `key=value` pair stored in the `appProperties` of a file, which are only visible to the
+/// application that set them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppProperty {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for AppProperty {
+    type Err = InvalidAppProperty;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s.split_once('=').ok_or(InvalidAppProperty)?;
+        if key.is_empty() {
+            return Err(InvalidAppProperty);
+        }
+
+        Ok(AppProperty {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Collects the properties into the map expected by the API, `None` if there are none so that
+/// the field is not sent at all. A key given more than once keeps its last value.
+#[must_use]
+pub fn to_map(properties: &[AppProperty]) -> Option<HashMap<String, String>> {
+    if properties.is_empty() {
+        return None;
+    }
+
+    Some(
+        properties
+            .iter()
+            .map(|property| (property.key.clone(), property.value.clone()))
+            .collect(),
+    )
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidAppProperty;
+
+impl Display for InvalidAppProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("property must be in the form key=value, with a non-empty key")
+    }
+}
+
+impl Error for InvalidAppProperty {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_app_property() {
+        assert_eq!(
+            "project=apollo".parse(),
+            Ok(AppProperty {
+                key: "project".to_string(),
+                value: "apollo".to_string(),
+            })
+        );
+        assert_eq!(
+            "query=a=b".parse(),
+            Ok(AppProperty {
+                key: "query".to_string(),
+                value: "a=b".to_string(),
+            })
+        );
+        assert_eq!(
+            "empty=".parse(),
+            Ok(AppProperty {
+                key: "empty".to_string(),
+                value: String::new(),
+            })
+        );
+        assert_eq!("=value".parse::<AppProperty>(), Err(InvalidAppProperty));
+        assert_eq!(
+            "no-separator".parse::<AppProperty>(),
+            Err(InvalidAppProperty)
+        );
+    }
+}
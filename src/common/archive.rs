@@ -0,0 +1,362 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+};
+
+/// Entries up to this size are decompressed in memory, bigger ones are spooled to an anonymous
+/// temporary file.
+const MAX_IN_MEMORY_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A tar or zip archive whose entries can be read one by one, without unpacking the whole
+/// archive.
+///
+/// Plain tar entries and stored zip entries are read straight from the archive file. Compressed
+/// zip entries need to be decompressed first, because uploads need to seek through the content.
+pub struct Archive {
+    path: PathBuf,
+    zip: Option<zip::ZipArchive<File>>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Relative path of the entry inside the archive
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+    source: EntrySource,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum EntrySource {
+    Slice { offset: u64 },
+    Zip { index: usize },
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            Some("tar") => Self::open_tar(path),
+            Some("zip") => Self::open_zip(path),
+            _ => Err(Error::UnsupportedFormat(path.to_owned())),
+        }
+    }
+
+    fn open_tar(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path).map_err(Error::Open)?;
+        let mut archive = tar::Archive::new(file);
+        let mut entries = Vec::new();
+
+        for entry in archive.entries_with_seek().map_err(Error::ReadEntries)? {
+            let entry = entry.map_err(Error::ReadEntries)?;
+            let entry_type = entry.header().entry_type();
+            let is_dir = entry_type.is_dir();
+            if !is_dir && !entry_type.is_file() {
+                // Links, devices and other special entries cannot be represented on drive
+                continue;
+            }
+
+            let entry_path = entry.path().map_err(Error::ReadEntries)?;
+            let entry_path = relative_path(&entry_path)
+                .ok_or_else(|| Error::InvalidEntryPath(entry_path.into_owned()))?;
+            if entry_path.as_os_str().is_empty() {
+                // The directory the archive was made from, i.e. `./` of `tar cf x.tar .`
+                continue;
+            }
+
+            entries.push(Entry {
+                path: entry_path,
+                is_dir,
+                size: entry.size(),
+                source: EntrySource::Slice {
+                    offset: entry.raw_file_position(),
+                },
+            });
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            zip: None,
+            entries,
+        })
+    }
+
+    fn open_zip(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path).map_err(Error::Open)?;
+        let mut archive = zip::ZipArchive::new(file).map_err(Error::Zip)?;
+        let mut entries = Vec::with_capacity(archive.len());
+
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index).map_err(Error::Zip)?;
+            let entry_path = file
+                .enclosed_name()
+                .and_then(|path| relative_path(&path))
+                .ok_or_else(|| Error::InvalidEntryPath(PathBuf::from(file.name())))?;
+            if entry_path.as_os_str().is_empty() {
+                continue;
+            }
+
+            let source = if file.compression() == zip::CompressionMethod::Stored {
+                EntrySource::Slice {
+                    offset: file.data_start(),
+                }
+            } else {
+                EntrySource::Zip { index }
+            };
+
+            entries.push(Entry {
+                path: entry_path,
+                is_dir: file.is_dir(),
+                size: file.size(),
+                source,
+            });
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            zip: Some(archive),
+            entries,
+        })
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Opens a seekable reader over the content of a file entry.
+    pub fn reader(&mut self, entry: &Entry) -> Result<EntryReader, Error> {
+        match entry.source {
+            EntrySource::Slice { offset } => {
+                let file = File::open(&self.path).map_err(Error::Open)?;
+                let slice = FileSlice::new(file, offset, entry.size).map_err(Error::ReadEntry)?;
+                Ok(EntryReader::Slice(slice))
+            }
+
+            EntrySource::Zip { index } => {
+                let archive = self
+                    .zip
+                    .as_mut()
+                    .expect("zip entries should only come from zip archives");
+                let mut file = archive.by_index(index).map_err(Error::Zip)?;
+
+                if entry.size <= MAX_IN_MEMORY_ENTRY_SIZE {
+                    let mut content = Vec::with_capacity(usize::try_from(entry.size).unwrap_or(0));
+                    file.read_to_end(&mut content).map_err(Error::ReadEntry)?;
+                    Ok(EntryReader::Memory(Cursor::new(content)))
+                } else {
+                    let mut spooled = tempfile::tempfile().map_err(Error::ReadEntry)?;
+                    io::copy(&mut file, &mut spooled).map_err(Error::ReadEntry)?;
+                    spooled.rewind().map_err(Error::ReadEntry)?;
+                    Ok(EntryReader::Spooled(spooled))
+                }
+            }
+        }
+    }
+}
+
+/// Returns the path without any `./`, or `None` if it could escape the destination. The path is
+/// empty for the entries of the archived directory itself, i.e. `.` or `./`.
+fn relative_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => relative.push(name),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(relative)
+}
+
+pub enum EntryReader {
+    Slice(FileSlice),
+    Memory(Cursor<Vec<u8>>),
+    Spooled(File),
+}
+
+impl Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            EntryReader::Slice(reader) => reader.read(buf),
+            EntryReader::Memory(reader) => reader.read(buf),
+            EntryReader::Spooled(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for EntryReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            EntryReader::Slice(reader) => reader.seek(pos),
+            EntryReader::Memory(reader) => reader.seek(pos),
+            EntryReader::Spooled(reader) => reader.seek(pos),
+        }
+    }
+}
+
+/// A reader over the `len` bytes of a file starting at `start`.
+pub struct FileSlice {
+    file: File,
+    start: u64,
+    len: u64,
+    position: u64,
+}
+
+impl FileSlice {
+    pub fn new(mut file: File, start: u64, len: u64) -> io::Result<Self> {
+        file.seek(SeekFrom::Start(start))?;
+        Ok(Self {
+            file,
+            start,
+            len,
+            position: 0,
+        })
+    }
+}
+
+impl Read for FileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.position);
+        let max = usize::try_from(remaining)
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+        let count = self.file.read(&mut buf[..max])?;
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for FileSlice {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        let position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        self.file.seek(SeekFrom::Start(self.start + position))?;
+        self.position = position;
+        Ok(position)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    UnsupportedFormat(PathBuf),
+    Open(io::Error),
+    ReadEntries(io::Error),
+    ReadEntry(io::Error),
+    Zip(zip::result::ZipError),
+    InvalidEntryPath(PathBuf),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnsupportedFormat(path) => write!(
+                f,
+                "unsupported archive '{}', only .tar and .zip archives are supported",
+                path.display()
+            ),
+            Error::Open(_) => f.write_str("unable to open archive"),
+            Error::ReadEntries(_) => f.write_str("unable to read archive entries"),
+            Error::ReadEntry(_) => f.write_str("unable to read archive entry"),
+            Error::Zip(_) => f.write_str("unable to read zip archive"),
+            Error::InvalidEntryPath(path) => {
+                write!(f, "archive entry '{}' has an invalid path", path.display())
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::UnsupportedFormat(_) | Error::InvalidEntryPath(_) => None,
+            Error::Open(source) | Error::ReadEntries(source) | Error::ReadEntry(source) => {
+                Some(source)
+            }
+            Error::Zip(source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+    };
+
+    use super::{relative_path, Archive, FileSlice};
+
+    #[test]
+    fn relative_path_rejects_escaping_paths() {
+        assert_eq!(
+            relative_path(Path::new("./a/b.txt")),
+            Some(PathBuf::from("a/b.txt"))
+        );
+        assert_eq!(relative_path(Path::new("../a")), None);
+        assert_eq!(relative_path(Path::new("/etc/passwd")), None);
+        assert_eq!(relative_path(Path::new("./")), Some(PathBuf::new()));
+        assert_eq!(relative_path(Path::new(".")), Some(PathBuf::new()));
+    }
+
+    #[test]
+    fn tar_of_current_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("x.tar");
+        let mut builder = tar::Builder::new(std::fs::File::create(&path).unwrap());
+        for (name, content) in [("./", &b""[..]), ("./a.txt", b"abc")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(if content.is_empty() {
+                tar::EntryType::Directory
+            } else {
+                tar::EntryType::Regular
+            });
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, content).unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+
+        let archive = Archive::open(&path).unwrap();
+        let paths: Vec<_> = archive.entries().iter().map(|entry| &entry.path).collect();
+        assert_eq!(paths, [Path::new("a.txt")]);
+    }
+
+    #[test]
+    fn file_slice_read_and_seek() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"0123456789").unwrap();
+
+        let mut slice = FileSlice::new(file, 2, 5).unwrap();
+        let mut content = String::new();
+        slice.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "23456");
+
+        assert_eq!(slice.seek(SeekFrom::End(0)).unwrap(), 5);
+        slice.seek(SeekFrom::Start(3)).unwrap();
+        content.clear();
+        slice.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "56");
+    }
+}
@@ -0,0 +1,315 @@
+//! Execution of many independent Drive operations, i.e. deleting, moving or renaming a list of
+//! files, with bounded concurrency, a shared rate limit and retries of transient failures.
+//!
+//! Every command working on many items reports through the same [`Summary`]: what succeeded, what
+//! was skipped and why, and what failed with the reason Drive gave.
+
+use std::{
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use futures::stream::{self, StreamExt};
+use tokio::{
+    sync::Mutex,
+    time::{self, Instant},
+};
+
+use crate::common::{
+    delegate::{Backoff, BackoffConfig},
+    error_report,
+};
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of operations running at the same time
+    pub concurrency: usize,
+    /// Minimum time between the start of two requests, shared by all the operations
+    pub min_interval: Duration,
+    pub backoff_config: BackoffConfig,
+    /// Print a line for each completed operation
+    pub print_progress: bool,
+    /// Start no more operations after the first failure, the remaining ones are skipped. The
+    /// running ones are completed
+    pub fail_fast: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            concurrency: 4,
+            min_interval: Duration::from_millis(100),
+            backoff_config: BackoffConfig {
+                max_retries: 5,
+                min_sleep: Duration::from_secs(1),
+                max_sleep: Duration::from_secs(30),
+            },
+            print_progress: true,
+            fail_fast: false,
+        }
+    }
+}
+
+pub struct Operation<T> {
+    /// Human readable description, i.e. "rename 'a' to 'b'"
+    pub description: String,
+    pub input: T,
+}
+
+/// What became of an operation that did not fail.
+pub enum Outcome<R> {
+    Done(R),
+    /// Nothing had to be done, for the given reason
+    Skipped(String),
+}
+
+pub struct Skipped<T> {
+    pub operation: Operation<T>,
+    pub reason: String,
+}
+
+pub struct Failure<T> {
+    pub operation: Operation<T>,
+    pub error: google_drive3::Error,
+}
+
+pub struct Summary<T, R> {
+    pub succeeded: Vec<(Operation<T>, R)>,
+    pub skipped: Vec<Skipped<T>>,
+    pub failed: Vec<Failure<T>>,
+}
+
+impl<T, R> Summary<T, R> {
+    fn new() -> Self {
+        Summary {
+            succeeded: Vec::new(),
+            skipped: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.succeeded.len() + self.skipped.len() + self.failed.len()
+    }
+
+    /// Prints the counts, then each failure with its reason.
+    pub fn print(&self) {
+        println!(
+            "{} operations succeeded, {} skipped, {} failed",
+            self.succeeded.len(),
+            self.skipped.len(),
+            self.failed.len()
+        );
+
+        for failure in &self.failed {
+            println!(
+                "Failed to {}: {}",
+                failure.operation.description,
+                error_report::describe(&failure.error)
+            );
+        }
+    }
+}
+
+/// Runs `execute` on the input of every operation.
+///
+/// Requests failing with a network error, a server error or because of rate limiting are retried
+/// with an exponential backoff, all the other errors are collected in the summary.
+pub async fn run<T, R, F, Fut>(
+    config: &Config,
+    operations: impl IntoIterator<Item = Operation<T>>,
+    execute: F,
+) -> Summary<T, R>
+where
+    F: Fn(&T) -> Fut,
+    Fut: Future<Output = Result<Outcome<R>, google_drive3::Error>>,
+{
+    let operations: Vec<_> = operations.into_iter().collect();
+    let total = operations.len();
+    let rate_limiter = RateLimiter::new(config.min_interval);
+    let stopped = AtomicBool::new(false);
+
+    let mut results = stream::iter(operations)
+        .map(|operation| {
+            let rate_limiter = &rate_limiter;
+            let execute = &execute;
+            let stopped = &stopped;
+            async move {
+                // Operations are only started when polled, after the failure for the last ones
+                if stopped.load(Ordering::Relaxed) {
+                    let skipped = Outcome::Skipped(String::from("an earlier operation failed"));
+                    return (operation, Ok(skipped));
+                }
+                let result =
+                    run_with_retries(config, rate_limiter, || execute(&operation.input)).await;
+                (operation, result)
+            }
+        })
+        .buffer_unordered(config.concurrency.max(1));
+
+    let mut summary = Summary::new();
+
+    while let Some((operation, result)) = results.next().await {
+        let done = summary.len() + 1;
+        match result {
+            Ok(Outcome::Done(value)) => {
+                if config.print_progress {
+                    println!("[{done}/{total}] {}", operation.description);
+                }
+                summary.succeeded.push((operation, value));
+            }
+            Ok(Outcome::Skipped(reason)) => {
+                if config.print_progress {
+                    println!(
+                        "[{done}/{total}] Skipped {}: {reason}",
+                        operation.description
+                    );
+                }
+                summary.skipped.push(Skipped { operation, reason });
+            }
+            Err(error) => {
+                eprintln!(
+                    "[{done}/{total}] Failed to {}: {}",
+                    operation.description,
+                    error_report::trace(&error)
+                );
+                if config.fail_fast {
+                    stopped.store(true, Ordering::Relaxed);
+                }
+                summary.failed.push(Failure { operation, error });
+            }
+        }
+    }
+
+    summary
+}
+
+async fn run_with_retries<R, F, Fut>(
+    config: &Config,
+    rate_limiter: &RateLimiter,
+    execute: F,
+) -> Result<R, google_drive3::Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<R, google_drive3::Error>>,
+{
+    let mut backoff = Backoff::new(&config.backoff_config);
+
+    loop {
+        rate_limiter.wait().await;
+
+        match execute().await {
+            Err(err) if is_transient(&err) => match backoff.next_delay() {
+                Some(delay) => {
+                    tracing::warn!(error = %err, ?delay, "transient failure, retrying");
+                    time::sleep(delay).await;
+                }
+                None => return Err(err),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Whether a request failed for a reason that may go away by itself: a network error, a server
+/// error or rate limiting.
+pub(crate) fn is_transient(err: &google_drive3::Error) -> bool {
+    if matches!(err, google_drive3::Error::HttpError(_)) {
+        return true;
+    }
+
+    if matches!(
+        error_report::api_reason(err),
+        Some("rateLimitExceeded" | "userRateLimitExceeded")
+    ) {
+        return true;
+    }
+
+    error_report::http_status(err).is_some_and(|status| status == 429 || status >= 500)
+}
+
+/// Spaces the start of the requests, across all the concurrent operations.
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(interval: Duration) -> Self {
+        RateLimiter {
+            interval,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn wait(&self) {
+        let mut next = self.next.lock().await;
+        time::sleep_until(*next).await;
+        *next = Instant::now() + self.interval;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use google_drive3::hyper;
+
+    use super::{is_transient, run, Config, Operation, Outcome};
+
+    fn failure(status: u16) -> google_drive3::Error {
+        google_drive3::Error::Failure(
+            hyper::Response::builder()
+                .status(status)
+                .body(hyper::Body::empty())
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn stop_after_failure() {
+        let config = Config {
+            concurrency: 1,
+            min_interval: Duration::ZERO,
+            print_progress: false,
+            fail_fast: true,
+            ..Config::default()
+        };
+        let operations = ["ok", "skip", "fail", "ok"].map(|input| Operation {
+            description: format!("run {input}"),
+            input,
+        });
+
+        let summary = run(&config, operations, |&input| async move {
+            match input {
+                "ok" => Ok(Outcome::Done(())),
+                "skip" => Ok(Outcome::Skipped(String::from("nothing to do"))),
+                _ => Err(failure(404)),
+            }
+        })
+        .await;
+
+        assert_eq!(summary.succeeded.len(), 1);
+        assert_eq!(summary.failed.len(), 1);
+        let reasons: Vec<_> = summary.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(reasons, ["nothing to do", "an earlier operation failed"]);
+    }
+
+    #[test]
+    fn transient_errors() {
+        assert!(is_transient(&failure(503)));
+        assert!(is_transient(&failure(429)));
+        assert!(!is_transient(&failure(404)));
+
+        let rate_limited = google_drive3::Error::BadRequest(serde_json::json!({
+            "error": {"code": 403, "errors": [{"reason": "userRateLimitExceeded"}]}
+        }));
+        assert!(is_transient(&rate_limited));
+
+        let forbidden = google_drive3::Error::BadRequest(serde_json::json!({
+            "error": {"code": 403, "errors": [{"reason": "insufficientFilePermissions"}]}
+        }));
+        assert!(!is_transient(&forbidden));
+    }
+}
@@ -0,0 +1,123 @@
+//! Notification channels, through which Drive pushes a request to a webhook whenever a watched
+//! file, or any file of the account, changes.
+
+use std::{
+    process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use google_drive3::api::Channel;
+
+use crate::hub::Hub;
+
+/// Whether to open a channel or to close an existing one.
+#[derive(Debug, Clone)]
+pub enum Action {
+    Start(Config),
+    /// Close the channel, both ids are printed when it is opened
+    Stop {
+        channel_id: String,
+        resource_id: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// HTTPS URL receiving the notifications
+    pub webhook: String,
+    /// Id of the channel, generated when unset
+    pub channel_id: Option<String>,
+    /// Sent back with every notification, i.e. to check that it comes from this channel
+    pub token: Option<String>,
+    /// How long the channel stays open, Drive picks a default and caps it when unset
+    pub ttl: Option<Duration>,
+}
+
+impl Config {
+    /// The channel to send to the API, notifying `webhook`.
+    #[must_use]
+    pub fn channel(&self) -> Channel {
+        let expiration = self.ttl.and_then(|ttl| {
+            let expiration = SystemTime::now().checked_add(ttl)?;
+            let millis = expiration.duration_since(UNIX_EPOCH).ok()?.as_millis();
+            i64::try_from(millis).ok()
+        });
+
+        Channel {
+            id: Some(self.channel_id.clone().unwrap_or_else(generate_channel_id)),
+            type_: Some(String::from("web_hook")),
+            address: Some(self.webhook.clone()),
+            token: self.token.clone(),
+            expiration,
+            ..Channel::default()
+        }
+    }
+}
+
+/// Prints the ids needed to stop the channel later on, and when it expires.
+pub fn print_channel(channel: &Channel) {
+    if let Some(id) = &channel.id {
+        println!("Channel id: {id}");
+    }
+    if let Some(resource_id) = &channel.resource_id {
+        println!("Resource id: {resource_id}");
+    }
+    if let Some(expiration) = channel.expiration {
+        println!("Expiration: {expiration} (ms since the epoch)");
+    }
+}
+
+pub async fn stop(
+    hub: &Hub,
+    channel_id: &str,
+    resource_id: &str,
+) -> Result<(), google_drive3::Error> {
+    let channel = Channel {
+        id: Some(channel_id.to_owned()),
+        resource_id: Some(resource_id.to_owned()),
+        ..Channel::default()
+    };
+
+    hub.channels().stop(channel).doit().await?;
+    Ok(())
+}
+
+/// Channel ids only need to be unique among the open channels of the project.
+fn generate_channel_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_nanos());
+    format!("gdrive-{nanos}-{}", process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Config;
+
+    #[test]
+    fn web_hook_channel() {
+        let config = Config {
+            webhook: String::from("https://example.com/hook"),
+            channel_id: None,
+            token: Some(String::from("secret")),
+            ttl: Some(Duration::from_secs(3600)),
+        };
+
+        let channel = config.channel();
+        assert_eq!(channel.type_.as_deref(), Some("web_hook"));
+        assert_eq!(channel.address.as_deref(), Some("https://example.com/hook"));
+        assert!(channel.id.is_some_and(|id| id.starts_with("gdrive-")));
+        assert!(channel.expiration.is_some());
+
+        let channel = Config {
+            channel_id: Some(String::from("my-channel")),
+            ttl: None,
+            ..config
+        }
+        .channel();
+        assert_eq!(channel.id.as_deref(), Some("my-channel"));
+        assert_eq!(channel.expiration, None);
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, Read, Write},
+    pin::Pin,
+    task::{self, Poll},
+};
+
+use pin_project_lite::pin_project;
+use sha2::Digest;
+use tokio::io::AsyncWrite;
+
+/// A content checksum, as computed by Drive or locally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksum {
+    Sha256([u8; 32]),
+    Md5([u8; 16]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha256,
+    Md5,
+}
+
+impl Checksum {
+    /// Returns the strongest checksum Drive reported for the file, preferring sha256 over md5.
+    ///
+    /// The `sha256Checksum` and `md5Checksum` fields must be requested for this to work.
+    #[must_use]
+    pub fn from_drive_file(file: &google_drive3::api::File) -> Option<Self> {
+        file.sha256_checksum
+            .as_deref()
+            .and_then(parse_hex)
+            .map(Checksum::Sha256)
+            .or_else(|| {
+                file.md5_checksum
+                    .as_deref()
+                    .and_then(parse_hex)
+                    .map(Checksum::Md5)
+            })
+    }
+
+    #[must_use]
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            Checksum::Sha256(_) => Algorithm::Sha256,
+            Checksum::Md5(_) => Algorithm::Md5,
+        }
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Checksum::Sha256(bytes) => bytes,
+            Checksum::Md5(bytes) => bytes,
+        }
+    }
+}
+
+impl Display for Checksum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.as_bytes()
+            .iter()
+            .try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::Sha256 => f.write_str("sha256"),
+            Algorithm::Md5 => f.write_str("md5"),
+        }
+    }
+}
+
+/// Incremental computation of a checksum with the given algorithm.
+pub enum Hasher {
+    Sha256(sha2::Sha256),
+    Md5(md5::Context),
+}
+
+impl Hasher {
+    #[must_use]
+    pub fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            Algorithm::Md5 => Hasher::Md5(md5::Context::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Md5(context) => context.consume(data),
+        }
+    }
+
+    #[must_use]
+    pub fn finalize(self) -> Checksum {
+        match self {
+            Hasher::Sha256(hasher) => Checksum::Sha256(hasher.finalize().into()),
+            Hasher::Md5(context) => Checksum::Md5(context.compute().0),
+        }
+    }
+}
+
+/// Size of the buffer used to read the data to hash. Big enough to keep the number of read
+/// syscalls low on large files.
+const BUFFER_SIZE: usize = 256 * 1024;
+
+pub fn compute<R: Read>(algorithm: Algorithm, reader: R) -> io::Result<Checksum> {
+    let mut hasher = Hasher::new(algorithm);
+    read_chunks(reader, |chunk| hasher.update(chunk))?;
+    Ok(hasher.finalize())
+}
+
+/// Reads `reader` to the end in a single pass, returning the number of bytes read along with
+/// their checksum when an algorithm is given.
+pub fn compute_with_size<R: Read>(
+    algorithm: Option<Algorithm>,
+    reader: R,
+) -> io::Result<(Option<Checksum>, u64)> {
+    let mut hasher = algorithm.map(Hasher::new);
+    let size = read_chunks(reader, |chunk| {
+        if let Some(hasher) = &mut hasher {
+            hasher.update(chunk);
+        }
+    })?;
+    Ok((hasher.map(Hasher::finalize), size))
+}
+
+/// Passes the content of `reader` to `on_chunk`, returning its size.
+fn read_chunks<R: Read>(mut reader: R, mut on_chunk: impl FnMut(&[u8])) -> io::Result<u64> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut size = 0;
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(count) => {
+                on_chunk(&buffer[..count]);
+                size += count as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(size)
+}
+
+pin_project! {
+    /// A writer computing the checksum of everything written through it.
+    pub struct ChecksumWriter<T> {
+        #[pin]
+        writer: T,
+        hasher: Hasher,
+    }
+}
+
+impl<T> ChecksumWriter<T> {
+    pub fn new(writer: T, algorithm: Algorithm) -> Self {
+        Self {
+            writer,
+            hasher: Hasher::new(algorithm),
+        }
+    }
+
+    pub fn checksum(self) -> Checksum {
+        self.hasher.finalize()
+    }
+}
+
+impl<T: Write> Write for ChecksumWriter<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let byte_count = self.writer.write(buf)?;
+        self.hasher.update(&buf[..byte_count]);
+        Ok(byte_count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for ChecksumWriter<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        this.writer.poll_write(cx, buf).map(|result| {
+            result.inspect(|&written_bytes| this.hasher.update(&buf[..written_bytes]))
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().writer.poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        self.project().writer.poll_shutdown(cx)
+    }
+}
+
+/// Parses an hex encoded digest of exactly `N` bytes.
+pub(crate) fn parse_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 {
+        return None;
+    }
+
+    let (chunks, _) = s.as_bytes().as_chunks::<2>();
+    let mut bytes = [0; N];
+    chunks
+        .iter()
+        .map(|bytes| {
+            let s = std::str::from_utf8(bytes).ok()?;
+            u8::from_str_radix(s, 16).ok()
+        })
+        .zip(&mut bytes)
+        .try_for_each(|(byte, out)| {
+            *out = byte?;
+            Some(())
+        })?;
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compute, compute_with_size, parse_hex, Algorithm, Checksum};
+
+    #[test]
+    fn parse_hex_valid() {
+        assert_eq!(
+            parse_hex::<16>("123456789abcdef01fedcba098765432").unwrap(),
+            [
+                0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x1f, 0xed, 0xcb, 0xa0, 0x98, 0x76,
+                0x54, 0x32,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_hex_invalid() {
+        assert!(parse_hex::<16>("123456789abcdef01f3dcba09876542").is_none());
+        assert!(parse_hex::<16>("123456789abcdef01f3dcba09876543").is_none());
+        assert!(parse_hex::<16>("123456789abcdef01f3dcba0987654321").is_none());
+        assert!(parse_hex::<16>("123456789abcdef01f3dcba09876543210").is_none());
+        assert!(parse_hex::<16>("g23456789abcdef01f3dcba098765432").is_none());
+    }
+
+    #[test]
+    fn prefer_sha256_over_md5() {
+        let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let mut file = google_drive3::api::File {
+            md5_checksum: Some(String::from("5d41402abc4b2a76b9719d911017c592")),
+            sha256_checksum: Some(String::from(sha256)),
+            ..Default::default()
+        };
+
+        let checksum = Checksum::from_drive_file(&file).unwrap();
+        assert_eq!(checksum.algorithm(), Algorithm::Sha256);
+        assert_eq!(checksum.to_string(), sha256);
+        assert_eq!(compute(Algorithm::Sha256, &b"hello"[..]).unwrap(), checksum);
+
+        file.sha256_checksum = None;
+        let checksum = Checksum::from_drive_file(&file).unwrap();
+        assert_eq!(checksum.algorithm(), Algorithm::Md5);
+        assert_eq!(compute(Algorithm::Md5, &b"hello"[..]).unwrap(), checksum);
+        assert_eq!(
+            compute_with_size(Some(Algorithm::Md5), &b"hello"[..]).unwrap(),
+            (Some(checksum), 5)
+        );
+        assert_eq!(compute_with_size(None, &b"hello"[..]).unwrap(), (None, 5));
+    }
+}
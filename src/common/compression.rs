@@ -0,0 +1,246 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use flate2::{read::GzEncoder, Compression};
+
+use crate::common::drive_file::{MIME_TYPE_GZIP, MIME_TYPE_X_GZIP};
+
+pub const GZIP_EXTENSION: &str = "gz";
+
+/// A reader that gzip-compresses `R` on the fly while still supporting `Seek`.
+///
+/// The compressed length is computed upfront with an extra compression pass, because the
+/// resumable upload protocol needs to know the content length before sending any chunk. Seeking
+/// is lazy: the position is only materialized on the next read, restarting the encoder from the
+/// beginning of the source when seeking backwards. This works because the gzip header produced by
+/// the encoder is deterministic, so every pass yields exactly the same bytes.
+pub struct GzipReader<R> {
+    encoder: Option<GzEncoder<R>>,
+    encoder_position: u64,
+    position: u64,
+    compressed_len: u64,
+}
+
+impl<R: Read + Seek> GzipReader<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        inner.rewind()?;
+        let mut encoder = GzEncoder::new(inner, Compression::default());
+        let compressed_len = io::copy(&mut encoder, &mut io::sink())?;
+
+        let mut inner = encoder.into_inner();
+        inner.rewind()?;
+
+        Ok(Self {
+            encoder: Some(GzEncoder::new(inner, Compression::default())),
+            encoder_position: 0,
+            position: 0,
+            compressed_len,
+        })
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        // The encoder is only replaced once the source is rewound, a failing seek leaves it usable
+        self.encoder
+            .as_mut()
+            .expect("encoder should always be present")
+            .get_mut()
+            .rewind()?;
+        let encoder = self
+            .encoder
+            .take()
+            .expect("encoder should always be present");
+        self.encoder = Some(GzEncoder::new(encoder.into_inner(), Compression::default()));
+        self.encoder_position = 0;
+        Ok(())
+    }
+
+    fn sync_position(&mut self) -> io::Result<()> {
+        if self.position < self.encoder_position {
+            self.restart()?;
+        }
+
+        let to_skip = self.position - self.encoder_position;
+        if to_skip > 0 {
+            let encoder = self
+                .encoder
+                .as_mut()
+                .expect("encoder should always be present");
+            let skipped = io::copy(&mut encoder.take(to_skip), &mut io::sink())?;
+            self.encoder_position += skipped;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> GzipReader<R> {
+    #[must_use]
+    pub fn compressed_len(&self) -> u64 {
+        self.compressed_len
+    }
+}
+
+impl<R: Read + Seek> Read for GzipReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.sync_position()?;
+        if self.position != self.encoder_position {
+            // The source ended before reaching the requested position
+            return Ok(0);
+        }
+
+        let encoder = self
+            .encoder
+            .as_mut()
+            .expect("encoder should always be present");
+        let count = encoder.read(buf)?;
+        self.encoder_position += count as u64;
+        self.position = self.encoder_position;
+        Ok(count)
+    }
+}
+
+impl<R: Read + Seek> Seek for GzipReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.compressed_len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[must_use]
+pub fn gzip_file_name(name: &str) -> String {
+    format!("{name}.{GZIP_EXTENSION}")
+}
+
+/// Returns the name without the `.gz` extension, if the file looks like a gzip archive.
+#[must_use]
+pub fn strip_gzip_extension(name: &str) -> Option<&str> {
+    name.strip_suffix(GZIP_EXTENSION)
+        .and_then(|name| name.strip_suffix('.'))
+        .filter(|name| !name.is_empty())
+}
+
+#[must_use]
+pub fn is_gzip(file: &google_drive3::api::File) -> bool {
+    let has_gzip_mime = matches!(
+        file.mime_type.as_deref(),
+        Some(MIME_TYPE_GZIP | MIME_TYPE_X_GZIP)
+    );
+    let has_gzip_name = file
+        .name
+        .as_deref()
+        .is_some_and(|name| strip_gzip_extension(name).is_some());
+
+    has_gzip_mime || has_gzip_name
+}
+
+#[must_use]
+pub fn decompressed_file_name(name: &str) -> &str {
+    strip_gzip_extension(name).unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+    use flate2::read::GzDecoder;
+
+    use super::{strip_gzip_extension, GzipReader};
+
+    fn sample_data() -> Vec<u8> {
+        (0..100_000u32)
+            .flat_map(|n| (n % 251).to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn gzip_reader_roundtrip() {
+        let data = sample_data();
+        let mut reader = GzipReader::new(Cursor::new(data.clone())).unwrap();
+
+        let mut compressed = Vec::new();
+        reader.read_to_end(&mut compressed).unwrap();
+        assert_eq!(compressed.len() as u64, reader.compressed_len());
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn gzip_reader_seek() {
+        let mut reader = GzipReader::new(Cursor::new(sample_data())).unwrap();
+        let mut compressed = Vec::new();
+        reader.read_to_end(&mut compressed).unwrap();
+
+        assert_eq!(
+            reader.seek(SeekFrom::End(0)).unwrap(),
+            reader.compressed_len()
+        );
+        reader.seek(SeekFrom::Start(10)).unwrap();
+        let mut chunk = [0; 32];
+        reader.read_exact(&mut chunk).unwrap();
+        assert_eq!(chunk, compressed[10..42]);
+
+        reader.seek(SeekFrom::Current(-2)).unwrap();
+        reader.read_exact(&mut chunk).unwrap();
+        assert_eq!(chunk, compressed[40..72]);
+    }
+
+    /// A source whose seeks fail after the first `ok_seeks` ones.
+    struct FailingSeek {
+        inner: Cursor<Vec<u8>>,
+        ok_seeks: usize,
+    }
+
+    impl Read for FailingSeek {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for FailingSeek {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            if self.ok_seeks == 0 {
+                return Err(io::Error::other("seek failed"));
+            }
+            self.ok_seeks -= 1;
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn gzip_reader_failing_rewind() {
+        let source = FailingSeek {
+            inner: Cursor::new(sample_data()),
+            ok_seeks: 2,
+        };
+        let mut reader = GzipReader::new(source).unwrap();
+        let mut chunk = [0; 32];
+        reader.read_exact(&mut chunk).unwrap();
+
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        assert!(reader.read(&mut chunk).is_err());
+        // Still an error instead of a panic
+        assert!(reader.read(&mut chunk).is_err());
+    }
+
+    #[test]
+    fn strip_gzip_extension_names() {
+        assert_eq!(strip_gzip_extension("backup.tar.gz"), Some("backup.tar"));
+        assert_eq!(strip_gzip_extension("backup.gz"), Some("backup"));
+        assert_eq!(strip_gzip_extension("backupgz"), None);
+        assert_eq!(strip_gzip_extension(".gz"), None);
+    }
+}
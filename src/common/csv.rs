@@ -0,0 +1,115 @@
+//! Reading of the csv files given as input, the counterpart of the csv output of
+//! [`table`](super::table). Fields can be quoted with `"`, which allows separators, newlines and
+//! doubled quotes in them.
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+};
+
+/// A record and the line it starts on, starting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// Splits `content` into records, skipping the empty lines.
+pub fn parse(content: &str) -> Result<Vec<Record>, UnterminatedQuote> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                line += 1;
+                end_record(&mut records, &mut fields, &mut field, record_line);
+                record_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(UnterminatedQuote { line: record_line });
+    }
+    end_record(&mut records, &mut fields, &mut field, record_line);
+
+    Ok(records)
+}
+
+fn end_record(
+    records: &mut Vec<Record>,
+    fields: &mut Vec<String>,
+    field: &mut String,
+    line: usize,
+) {
+    fields.push(std::mem::take(field));
+    let fields = std::mem::take(fields);
+    if fields.iter().any(|field| !field.is_empty()) {
+        records.push(Record { line, fields });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnterminatedQuote {
+    /// Line of the record with the unterminated quote
+    pub line: usize,
+}
+
+impl Display for UnterminatedQuote {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "unterminated quote in the record on line {}", self.line)
+    }
+}
+
+impl error::Error for UnterminatedQuote {}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Record, UnterminatedQuote};
+
+    #[test]
+    fn parse_quoted_fields() {
+        let records = parse("id,email\r\n\n1a,\"a@example.com\"\n\"2, \"\"b\"\"\nc\",,\n").unwrap();
+
+        assert_eq!(
+            records,
+            [
+                Record {
+                    line: 1,
+                    fields: vec!["id".into(), "email".into()],
+                },
+                Record {
+                    line: 3,
+                    fields: vec!["1a".into(), "a@example.com".into()],
+                },
+                Record {
+                    line: 4,
+                    fields: vec!["2, \"b\"\nc".into(), String::new(), String::new()],
+                },
+            ]
+        );
+        assert_eq!(parse("a,\"b\n"), Err(UnterminatedQuote { line: 1 }));
+    }
+}
@@ -8,6 +8,8 @@ use bytesize::ByteSize;
 use error_trace::ErrorTrace;
 use google_drive3::hyper::{self, http};
 
+use crate::hub;
+
 #[derive(Debug, Clone, Default)]
 pub struct UploadDelegateConfig {
     pub chunk_size: ChunkSize,
@@ -21,6 +23,10 @@ pub struct UploadDelegate<'a> {
     backoff: Backoff,
     resumable_upload_url: Option<String>,
     previous_chunk: Option<google_drive3::client::ContentRange>,
+    chunk_count: u32,
+    retry_count: u32,
+    /// The request was already retried with a refreshed access token
+    token_refreshed: bool,
 }
 
 impl<'a> UploadDelegate<'a> {
@@ -33,9 +39,24 @@ impl<'a> UploadDelegate<'a> {
             backoff,
             resumable_upload_url: None,
             previous_chunk: None,
+            chunk_count: 0,
+            retry_count: 0,
+            token_refreshed: false,
         }
     }
 
+    /// Number of distinct chunks sent by a resumable upload.
+    #[must_use]
+    pub fn chunk_count(&self) -> u32 {
+        self.chunk_count
+    }
+
+    /// Number of failed attempts that have been retried.
+    #[must_use]
+    pub fn retry_count(&self) -> u32 {
+        self.retry_count
+    }
+
     fn print_chunk_info(&self, chunk: &google_drive3::client::ContentRange) {
         if self.config.print_chunk_info {
             if let Some(range) = &chunk.range {
@@ -65,12 +86,38 @@ impl<'a> UploadDelegate<'a> {
 }
 
 impl google_drive3::client::Delegate for UploadDelegate<'_> {
+    fn begin(&mut self, info: google_drive3::client::MethodInfo) {
+        tracing::debug!(method = info.id, http_method = %info.http_method, "request started");
+    }
+
+    fn finished(&mut self, is_success: bool) {
+        tracing::debug!(
+            is_success,
+            chunks = self.chunk_count,
+            retries = self.retry_count,
+            "request finished"
+        );
+    }
+
     fn chunk_size(&mut self) -> u64 {
         self.config.chunk_size.in_bytes()
     }
 
     fn cancel_chunk_upload(&mut self, chunk: &google_drive3::client::ContentRange) -> bool {
         self.print_chunk_info(chunk);
+        let is_retry = Some(chunk) == self.previous_chunk.as_ref();
+        if let Some(range) = &chunk.range {
+            tracing::debug!(
+                first = range.first,
+                last = range.last,
+                total = chunk.total_length,
+                is_retry,
+                "uploading chunk"
+            );
+        }
+        if !is_retry {
+            self.chunk_count += 1;
+        }
         self.previous_chunk = Some(chunk.clone());
 
         false
@@ -85,9 +132,11 @@ impl google_drive3::client::Delegate for UploadDelegate<'_> {
     }
 
     fn http_error(&mut self, err: &hyper::Error) -> google_drive3::client::Retry {
+        tracing::warn!(error = %err.trace(), retries = self.retry_count, "request failed");
         if self.config.print_chunk_errors {
             eprintln!("Warning: Failed attempt to upload chunk: {}", err.trace());
         }
+        self.retry_count += 1;
         self.backoff.retry()
     }
 
@@ -97,8 +146,22 @@ impl google_drive3::client::Delegate for UploadDelegate<'_> {
         _err: Option<serde_json::Value>,
     ) -> google_drive3::client::Retry {
         let status = res.status();
-
-        if should_retry(status) {
+        tracing::warn!(
+            status = status.as_u16(),
+            retries = self.retry_count,
+            "request failed"
+        );
+
+        // Only the requests made before the chunks fetch a token on each attempt, the chunks are
+        // sent to the session url without one and a refreshed token would not change anything
+        let sending_chunks = self.previous_chunk.is_some();
+        if status == http::StatusCode::UNAUTHORIZED && !self.token_refreshed && !sending_chunks {
+            // The token expired while in use, the next attempt gets a new one
+            hub::reject_token();
+            self.token_refreshed = true;
+            self.retry_count += 1;
+            google_drive3::client::Retry::After(Duration::ZERO)
+        } else if should_retry(status) {
             if self.config.print_chunk_errors {
                 eprintln!(
                     "Warning: Failed attempt to upload chunk. Status code: {}, body: {:?}",
@@ -106,6 +169,7 @@ impl google_drive3::client::Delegate for UploadDelegate<'_> {
                     res.body()
                 );
             }
+            self.retry_count += 1;
             self.backoff.retry()
         } else {
             google_drive3::client::Retry::Abort
@@ -152,55 +216,45 @@ impl Backoff {
         }
     }
 
-    fn retry(&mut self) -> google_drive3::client::Retry {
+    /// Time to wait before the next attempt, `None` once the retries are exhausted.
+    pub fn next_delay(&mut self) -> Option<Duration> {
         self.attempts += 1;
-        self.backoff.next(self.attempts).map_or(
+        self.backoff.next(self.attempts)
+    }
+
+    fn retry(&mut self) -> google_drive3::client::Retry {
+        self.next_delay().map_or(
             google_drive3::client::Retry::Abort,
             google_drive3::client::Retry::After,
         )
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub enum ChunkSize {
-    Approx1,
-    Approx2,
-    Approx4,
-    Approx8,
-    Approx16,
-    #[default]
-    Approx32,
-    Approx64,
-    Approx128,
-    Approx256,
-    Approx512,
-    Approx1024,
-    Approx2048,
-    Approx4096,
-    Approx8192,
-}
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+
+/// The size of the chunks of resumable uploads. Drive requires a multiple of 256 KiB.
+///
+/// Parsed from a number with an optional unit, `K`, `M` or `G`, all powers of 1024: `48M`,
+/// `512K`, `1G`. A bare number is a count of MiB, as accepted by earlier versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSize(u64);
 
 impl ChunkSize {
+    /// The granularity of chunk sizes required by the API.
+    pub const MULTIPLE: u64 = 256 * KIB;
+    pub const MAX: u64 = 8192 * MIB;
+
     #[must_use]
     pub fn in_bytes(&self) -> u64 {
-        let exponent = match self {
-            ChunkSize::Approx1 => 20,
-            ChunkSize::Approx2 => 21,
-            ChunkSize::Approx4 => 22,
-            ChunkSize::Approx8 => 23,
-            ChunkSize::Approx16 => 24,
-            ChunkSize::Approx32 => 25,
-            ChunkSize::Approx64 => 26,
-            ChunkSize::Approx128 => 27,
-            ChunkSize::Approx256 => 28,
-            ChunkSize::Approx512 => 29,
-            ChunkSize::Approx1024 => 30,
-            ChunkSize::Approx2048 => 31,
-            ChunkSize::Approx4096 => 32,
-            ChunkSize::Approx8192 => 33,
-        };
+        self.0
+    }
+}
 
-        u64::pow(2, exponent)
+impl Default for ChunkSize {
+    fn default() -> Self {
+        ChunkSize(32 * MIB)
     }
 }
 
@@ -208,56 +262,133 @@ impl FromStr for ChunkSize {
     type Err = InvalidChunkSize;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "1" => Ok(ChunkSize::Approx1),
-            "2" => Ok(ChunkSize::Approx2),
-            "4" => Ok(ChunkSize::Approx4),
-            "8" => Ok(ChunkSize::Approx8),
-            "16" => Ok(ChunkSize::Approx16),
-            "32" => Ok(ChunkSize::Approx32),
-            "64" => Ok(ChunkSize::Approx64),
-            "128" => Ok(ChunkSize::Approx128),
-            "256" => Ok(ChunkSize::Approx256),
-            "512" => Ok(ChunkSize::Approx512),
-            "1024" => Ok(ChunkSize::Approx1024),
-            "2048" => Ok(ChunkSize::Approx2048),
-            "4096" => Ok(ChunkSize::Approx4096),
-            "8192" => Ok(ChunkSize::Approx8192),
-            _ => Err(InvalidChunkSize),
+        let s = s.trim();
+        let digits_len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(digits_len);
+        let number: u64 = number.parse().map_err(|_| InvalidChunkSize::Format)?;
+
+        let unit_size = match unit.trim_start().to_ascii_lowercase().as_str() {
+            "" | "m" | "mb" | "mib" => MIB,
+            "k" | "kb" | "kib" => KIB,
+            "g" | "gb" | "gib" => GIB,
+            _ => return Err(InvalidChunkSize::Format),
+        };
+
+        let bytes = number
+            .checked_mul(unit_size)
+            .ok_or(InvalidChunkSize::OutOfRange)?;
+        if bytes == 0 || bytes > ChunkSize::MAX {
+            return Err(InvalidChunkSize::OutOfRange);
         }
+        if !bytes.is_multiple_of(ChunkSize::MULTIPLE) {
+            return Err(InvalidChunkSize::NotMultiple);
+        }
+
+        Ok(ChunkSize(bytes))
     }
 }
 
 impl Display for ChunkSize {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            ChunkSize::Approx1 => "1",
-            ChunkSize::Approx2 => "2",
-            ChunkSize::Approx4 => "4",
-            ChunkSize::Approx8 => "8",
-            ChunkSize::Approx16 => "16",
-            ChunkSize::Approx32 => "32",
-            ChunkSize::Approx64 => "64",
-            ChunkSize::Approx128 => "128",
-            ChunkSize::Approx256 => "256",
-            ChunkSize::Approx512 => "512",
-            ChunkSize::Approx1024 => "1024",
-            ChunkSize::Approx2048 => "2048",
-            ChunkSize::Approx4096 => "4096",
-            ChunkSize::Approx8192 => "8192",
-        };
-
-        f.write_str(s)
+        if self.0.is_multiple_of(MIB) {
+            write!(f, "{}M", self.0 / MIB)
+        } else {
+            write!(f, "{}K", self.0 / KIB)
+        }
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct InvalidChunkSize;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidChunkSize {
+    Format,
+    OutOfRange,
+    NotMultiple,
+}
 
 impl Display for InvalidChunkSize {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("not a valid chunk size, must be a power of 2 between 1 and 8192")
+        match self {
+            InvalidChunkSize::Format => {
+                f.write_str("expected a number of MiB, or a number followed by K, M or G")
+            }
+            InvalidChunkSize::OutOfRange => {
+                f.write_str("the chunk size must be between 256K and 8192M")
+            }
+            InvalidChunkSize::NotMultiple => {
+                f.write_str("the chunk size must be a multiple of 256K")
+            }
+        }
     }
 }
 
 impl std::error::Error for InvalidChunkSize {}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::{
+        client::{Chunk, ContentRange, Delegate, Retry},
+        hyper::{self, http},
+    };
+
+    use super::{ChunkSize, InvalidChunkSize, UploadDelegate, UploadDelegateConfig, KIB, MIB};
+
+    fn response(status: http::StatusCode) -> http::Response<hyper::Body> {
+        http::Response::builder()
+            .status(status)
+            .body(hyper::Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn unauthorized_is_retried_once_with_a_new_token() {
+        let config = UploadDelegateConfig::default();
+        let mut delegate = UploadDelegate::new(&config);
+
+        let first = delegate.http_failure(&response(http::StatusCode::UNAUTHORIZED), None);
+        let second = delegate.http_failure(&response(http::StatusCode::UNAUTHORIZED), None);
+
+        assert!(matches!(first, Retry::After(_)));
+        assert!(matches!(second, Retry::Abort));
+        assert_eq!(delegate.retry_count(), 1);
+    }
+
+    #[test]
+    fn unauthorized_chunk_is_not_retried() {
+        let config = UploadDelegateConfig::default();
+        let mut delegate = UploadDelegate::new(&config);
+
+        delegate.cancel_chunk_upload(&ContentRange {
+            range: Some(Chunk {
+                first: 0,
+                last: 262_143,
+            }),
+            total_length: 1_048_576,
+        });
+        let retry = delegate.http_failure(&response(http::StatusCode::UNAUTHORIZED), None);
+
+        assert!(matches!(retry, Retry::Abort));
+        assert_eq!(delegate.retry_count(), 0);
+    }
+
+    #[test]
+    fn parse_chunk_size() {
+        let bytes = |s: &str| s.parse::<ChunkSize>().map(|size| size.in_bytes());
+
+        assert_eq!(bytes("32"), Ok(32 * MIB));
+        assert_eq!(bytes("2048"), Ok(2048 * MIB));
+        assert_eq!(bytes("48M"), Ok(48 * MIB));
+        assert_eq!(bytes("768KiB"), Ok(768 * KIB));
+        assert_eq!(bytes("1 g"), Ok(1024 * MIB));
+        assert_eq!(bytes("100K"), Err(InvalidChunkSize::NotMultiple));
+        assert_eq!(bytes("0"), Err(InvalidChunkSize::OutOfRange));
+        assert_eq!(bytes("16G"), Err(InvalidChunkSize::OutOfRange));
+        assert_eq!(bytes("1.5M"), Err(InvalidChunkSize::Format));
+        assert_eq!(bytes("M"), Err(InvalidChunkSize::Format));
+
+        assert_eq!(ChunkSize::default().to_string(), "32M");
+        assert_eq!(
+            bytes(&"768K".parse::<ChunkSize>().unwrap().to_string()),
+            Ok(768 * KIB)
+        );
+    }
+}
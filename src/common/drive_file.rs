@@ -1,4 +1,4 @@
-use std::{fmt, path::Path, sync::LazyLock};
+use std::{error, fmt, path::Path, str::FromStr, sync::LazyLock};
 
 use mime::Mime;
 
@@ -74,6 +74,8 @@ pub const MIME_TYPE_PPTX: &str =
 pub const MIME_TYPE_ODP: &str = "application/vnd.oasis.opendocument.presentation";
 pub const MIME_TYPE_EPUB: &str = "application/epub+zip";
 pub const MIME_TYPE_TXT: &str = "text/plain";
+pub const MIME_TYPE_GZIP: &str = "application/gzip";
+pub const MIME_TYPE_X_GZIP: &str = "application/x-gzip";
 
 create_mime_from_str!(
     MIME_TYPE_DOC => MIME_TYPE_DOC_MIME: "microsoft doc",
@@ -96,6 +98,7 @@ create_mime_from_str!(
     MIME_TYPE_ODP => MIME_TYPE_ODP_MIME: "opendocument presentation",
     MIME_TYPE_EPUB => MIME_TYPE_EPUB_MIME: "epub document",
     MIME_TYPE_TXT => MIME_TYPE_TXT_MIME: "plain text",
+    MIME_TYPE_GZIP => MIME_TYPE_GZIP_MIME: "gzip archive",
 );
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -329,31 +332,7 @@ impl fmt::Display for FileExtension {
 impl FileExtension {
     #[must_use]
     pub fn from_path(path: &Path) -> Option<FileExtension> {
-        let extension = path.extension()?.to_str()?;
-
-        match extension {
-            EXTENSION_DOC => Some(FileExtension::Doc),
-            EXTENSION_DOCX => Some(FileExtension::Docx),
-            EXTENSION_ODT => Some(FileExtension::Odt),
-            EXTENSION_JPG => Some(FileExtension::Jpg),
-            EXTENSION_JPEG => Some(FileExtension::Jpeg),
-            EXTENSION_GIF => Some(FileExtension::Gif),
-            EXTENSION_PNG => Some(FileExtension::Png),
-            EXTENSION_RTF => Some(FileExtension::Rtf),
-            EXTENSION_PDF => Some(FileExtension::Pdf),
-            EXTENSION_HTML => Some(FileExtension::Html),
-            EXTENSION_XLS => Some(FileExtension::Xls),
-            EXTENSION_XLSX => Some(FileExtension::Xlsx),
-            EXTENSION_CSV => Some(FileExtension::Csv),
-            EXTENSION_TSV => Some(FileExtension::Tsv),
-            EXTENSION_ODS => Some(FileExtension::Ods),
-            EXTENSION_PPT => Some(FileExtension::Ppt),
-            EXTENSION_PPTX => Some(FileExtension::Pptx),
-            EXTENSION_ODP => Some(FileExtension::Odp),
-            EXTENSION_EPUB => Some(FileExtension::Epub),
-            EXTENSION_TXT => Some(FileExtension::Txt),
-            _ => None,
-        }
+        path.extension()?.to_str()?.parse().ok()
     }
 
     #[must_use]
@@ -383,6 +362,47 @@ impl FileExtension {
     }
 }
 
+impl FromStr for FileExtension {
+    type Err = InvalidFileExtension;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            EXTENSION_DOC => Ok(FileExtension::Doc),
+            EXTENSION_DOCX => Ok(FileExtension::Docx),
+            EXTENSION_ODT => Ok(FileExtension::Odt),
+            EXTENSION_JPG => Ok(FileExtension::Jpg),
+            EXTENSION_JPEG => Ok(FileExtension::Jpeg),
+            EXTENSION_GIF => Ok(FileExtension::Gif),
+            EXTENSION_PNG => Ok(FileExtension::Png),
+            EXTENSION_RTF => Ok(FileExtension::Rtf),
+            EXTENSION_PDF => Ok(FileExtension::Pdf),
+            EXTENSION_HTML => Ok(FileExtension::Html),
+            EXTENSION_XLS => Ok(FileExtension::Xls),
+            EXTENSION_XLSX => Ok(FileExtension::Xlsx),
+            EXTENSION_CSV => Ok(FileExtension::Csv),
+            EXTENSION_TSV => Ok(FileExtension::Tsv),
+            EXTENSION_ODS => Ok(FileExtension::Ods),
+            EXTENSION_PPT => Ok(FileExtension::Ppt),
+            EXTENSION_PPTX => Ok(FileExtension::Pptx),
+            EXTENSION_ODP => Ok(FileExtension::Odp),
+            EXTENSION_EPUB => Ok(FileExtension::Epub),
+            EXTENSION_TXT => Ok(FileExtension::Txt),
+            _ => Err(InvalidFileExtension),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidFileExtension;
+
+impl fmt::Display for InvalidFileExtension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("not a supported file extension, i.e. pdf, docx or csv")
+    }
+}
+
+impl error::Error for InvalidFileExtension {}
+
 #[must_use]
 pub fn is_directory(file: &google_drive3::api::File) -> bool {
     file.mime_type.as_deref() == Some(MIME_TYPE_DRIVE_FOLDER)
@@ -393,6 +413,15 @@ pub fn is_binary(file: &google_drive3::api::File) -> bool {
     file.md5_checksum.is_some()
 }
 
+/// Whether the file is a Google Docs, Sheets, Slides or another native format, which has neither
+/// a checksum nor the size of the content it was converted from.
+#[must_use]
+pub fn is_google_apps(file: &google_drive3::api::File) -> bool {
+    file.mime_type
+        .as_deref()
+        .is_some_and(|mime| mime.starts_with("application/vnd.google-apps."))
+}
+
 #[must_use]
 pub fn is_shortcut(file: &google_drive3::api::File) -> bool {
     file.mime_type.as_deref() == Some(MIME_TYPE_DRIVE_SHORTCUT)
@@ -0,0 +1,205 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+};
+
+use crate::{
+    common::{delegate::UploadDelegateConfig, drive_file::MIME_TYPE_DRIVE_FOLDER, file_id::FileId},
+    files::{
+        list::{self, ListFilesConfig, ListQuery, ListSortOrder, QueryBuilder},
+        mkdir,
+    },
+    hub::Hub,
+};
+
+pub const MY_DRIVE_ROOT_ID: &str = "root";
+
+/// Resolves a slash separated folder path, like `projects/2024`, to the id of the last folder.
+///
+/// The path is resolved starting from the root of the given shared drive, or from the root of My
+/// Drive when `drive_id` is `None`. Empty segments are ignored, therefore an empty path resolves to
+/// the root.
+pub async fn resolve_folder_path(
+    hub: &Hub,
+    drive_id: Option<&str>,
+    path: &str,
+) -> Result<String, Error> {
+    let mut folder_id = drive_id.unwrap_or(MY_DRIVE_ROOT_ID).to_owned();
+    let mut resolved_segments = Vec::new();
+
+    for segment in path_segments(path) {
+        resolved_segments.push(segment);
+
+        folder_id = find_child_folder(hub, drive_id, &folder_id, segment)
+            .await
+            .map_err(|err| match err {
+                Error::NotFound(_) => Error::NotFound(resolved_segments.join("/")),
+                Error::Ambiguous(_) => Error::Ambiguous(resolved_segments.join("/")),
+                err => err,
+            })?;
+    }
+
+    Ok(folder_id)
+}
+
+/// Finds the id of the folder named `name` directly below the folder `parent_id`. The errors
+/// report `name` alone, not the whole path.
+pub async fn find_child_folder(
+    hub: &Hub,
+    drive_id: Option<&str>,
+    parent_id: &str,
+    name: &str,
+) -> Result<String, Error> {
+    let query = child_folder_query(parent_id, name);
+    let folders = list::list_files(
+        hub,
+        ListFilesConfig {
+            query: &query,
+            order_by: &ListSortOrder::default(),
+            max_files: 2,
+            extra_fields: &[],
+            drive_id,
+            corpora: None,
+        },
+    )
+    .await
+    .map_err(|err| Error::ListFiles(Box::new(err)))?;
+
+    match folders.as_slice() {
+        [folder] => Ok(folder.id.clone().unwrap_or_default()),
+        [] => Err(Error::NotFound(name.to_owned())),
+        _ => Err(Error::Ambiguous(name.to_owned())),
+    }
+}
+
+/// Finds the id of the shared drive containing the folder `folder_id`, `None` for the folders of My
+/// Drive. Child folders can only be listed within their shared drive.
+pub async fn shared_drive_id(hub: &Hub, folder_id: &str) -> Result<Option<String>, Error> {
+    if folder_id == MY_DRIVE_ROOT_ID {
+        return Ok(None);
+    }
+
+    let (_, folder) = hub
+        .files()
+        .get(folder_id)
+        .param("fields", "driveId")
+        .supports_all_drives(true)
+        .add_scope(hub.metadata_scope())
+        .doit()
+        .await
+        .map_err(|err| Error::GetFolder(folder_id.to_owned(), Box::new(err)))?;
+
+    Ok(folder.drive_id)
+}
+
+/// A folder given by its name below another folder, i.e. the folder of the day below the folder
+/// of the backups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildFolder {
+    pub name: String,
+    pub within: FileId,
+    /// Create the folder when there is none with this name
+    pub create_missing: bool,
+}
+
+impl ChildFolder {
+    /// Finds the id of the folder, creating it if allowed. The returned flag tells whether the
+    /// folder was created.
+    pub async fn resolve(&self, hub: &Hub) -> Result<(String, bool), Error> {
+        let within = self.within.as_str();
+        let drive_id = shared_drive_id(hub, within).await?;
+        match find_child_folder(hub, drive_id.as_deref(), within, &self.name).await {
+            Ok(id) => Ok((id, false)),
+            Err(Error::NotFound(_)) if self.create_missing => {
+                let folder = mkdir::create_directory(
+                    hub,
+                    &mkdir::Config {
+                        name: self.name.clone(),
+                        parents: Some(vec![self.within.clone()]),
+                        ..mkdir::Config::default()
+                    },
+                    &UploadDelegateConfig::default(),
+                )
+                .await
+                .map_err(|err| Error::CreateFolder(self.name.clone(), Box::new(err)))?;
+                Ok((folder.id.unwrap_or_default(), true))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Query of the folders named `name` below `parent_id`. Names are escaped, since apostrophes are
+/// common in folder names, i.e. "John's photos".
+fn child_folder_query(parent_id: &str, name: &str) -> ListQuery {
+    ListQuery::from(
+        QueryBuilder::new()
+            .in_parent(parent_id)
+            .name_equals(name)
+            .mime_type(MIME_TYPE_DRIVE_FOLDER)
+            .trashed(false),
+    )
+}
+
+pub(crate) fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ListFiles(Box<list::Error>),
+    NotFound(String),
+    Ambiguous(String),
+    CreateFolder(String, Box<google_drive3::Error>),
+    GetFolder(String, Box<google_drive3::Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ListFiles(_) => f.write_str("unable to list folders"),
+            Error::NotFound(path) => write!(f, "folder '{path}' does not exist"),
+            Error::Ambiguous(path) => {
+                write!(
+                    f,
+                    "path '{path}' is ambiguous, multiple folders have this name"
+                )
+            }
+            Error::CreateFolder(name, _) => write!(f, "unable to create folder '{name}'"),
+            Error::GetFolder(id, _) => write!(f, "unable to get folder '{id}'"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::ListFiles(source) => Some(source),
+            Error::CreateFolder(_, source) | Error::GetFolder(_, source) => Some(source),
+            Error::NotFound(_) | Error::Ambiguous(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{child_folder_query, path_segments};
+
+    #[test]
+    fn path_segments_skip_empty() {
+        assert_eq!(
+            path_segments("/projects//2024/").collect::<Vec<_>>(),
+            ["projects", "2024"]
+        );
+        assert_eq!(path_segments("").count(), 0);
+    }
+
+    #[test]
+    fn child_folder_query_escapes_name() {
+        assert_eq!(
+            child_folder_query("1a2B", "John's \\ photos").to_string(),
+            "'1a2B' in parents and name = 'John\\'s \\\\ photos' \
+            and mimeType = 'application/vnd.google-apps.folder' and trashed = false"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! The endpoints of the Google APIs. They can be changed through the environment, i.e. to go
+//! through an API gateway or to run against a mock server.
+
+use std::env;
+
+pub const AUTH_URI_ENV: &str = "GDRIVE_AUTH_URI";
+pub const TOKEN_URI_ENV: &str = "GDRIVE_TOKEN_URI";
+pub const API_ROOT_URL_ENV: &str = "GDRIVE_API_ROOT_URL";
+pub const SHEETS_API_URL_ENV: &str = "GDRIVE_SHEETS_API_URL";
+
+const DEFAULT_AUTH_URI: &str = "https://accounts.google.com/o/oauth2/auth";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const DEFAULT_API_ROOT_URL: &str = "https://www.googleapis.com/";
+const DEFAULT_SHEETS_API_URL: &str = "https://sheets.googleapis.com/v4/";
+
+/// Where the user is sent to authorize gdrive.
+#[must_use]
+pub fn auth_uri() -> String {
+    env_or(AUTH_URI_ENV, DEFAULT_AUTH_URI)
+}
+
+/// Where authorization codes and refresh tokens are exchanged for access tokens.
+#[must_use]
+pub fn token_uri() -> String {
+    env_or(TOKEN_URI_ENV, DEFAULT_TOKEN_URI)
+}
+
+/// The root of the Google APIs, ending with a slash.
+#[must_use]
+pub fn api_root_url() -> String {
+    with_trailing_slash(env_or(API_ROOT_URL_ENV, DEFAULT_API_ROOT_URL))
+}
+
+/// The base of the Drive API calls, ending with a slash.
+#[must_use]
+pub fn drive_api_url() -> String {
+    format!("{}drive/v3/", api_root_url())
+}
+
+/// The base of the Drive API uploads, ending with a slash.
+#[must_use]
+pub fn upload_api_url() -> String {
+    format!("{}upload/drive/v3/", api_root_url())
+}
+
+/// The base of the Sheets API calls, ending with a slash. The Sheets API has its own host, it does
+/// not follow the root of the Google APIs.
+#[must_use]
+pub fn sheets_api_url() -> String {
+    with_trailing_slash(env_or(SHEETS_API_URL_ENV, DEFAULT_SHEETS_API_URL))
+}
+
+/// Unset and empty variables fall back to the default.
+fn env_or(name: &str, default: &str) -> String {
+    env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| default.to_owned())
+}
+
+fn with_trailing_slash(mut url: String) -> String {
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::with_trailing_slash;
+
+    #[test]
+    fn trailing_slash() {
+        assert_eq!(
+            with_trailing_slash(String::from("http://localhost:8080")),
+            "http://localhost:8080/"
+        );
+        assert_eq!(
+            with_trailing_slash(String::from("https://gateway.example.com/google/")),
+            "https://gateway.example.com/google/"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::{error::Error, iter};
+
+use clap::ValueEnum;
+use error_trace::ErrorTraceFormatter;
+use serde::Serialize;
+
+use crate::common::hub_helper::GetHubError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// Human readable error trace
+    #[default]
+    Text,
+    /// A single JSON object per failure
+    Json,
+}
+
+/// Machine readable description of a failure, printed with `--errors json`.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub message: String,
+    /// Messages of the error and all its sources, outermost first
+    pub chain: Vec<String>,
+    pub category: Category,
+    pub retryable: bool,
+    pub http_status: Option<u16>,
+    /// The reason Drive gives for a rejected request, i.e. `storageQuotaExceeded`
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// The Drive API answered with an error
+    Api,
+    /// The Drive API could not be reached
+    Network,
+    /// Authentication with Google failed
+    Auth,
+    /// The local configuration or accounts could not be loaded
+    Config,
+    /// A local file operation failed
+    Io,
+    Other,
+}
+
+impl ErrorReport {
+    #[must_use]
+    pub fn new(err: &(dyn Error + 'static)) -> Self {
+        let chain: Vec<_> = sources(err).map(ToString::to_string).collect();
+        let drive_error = sources(err).find_map(as_drive_error);
+        let http_status = drive_error.and_then(http_status);
+        let reason = drive_error.and_then(api_reason).map(str::to_owned);
+
+        let category = match drive_error {
+            Some(google_drive3::Error::HttpError(_)) => Category::Network,
+            Some(google_drive3::Error::MissingAPIKey | google_drive3::Error::MissingToken(_)) => {
+                Category::Auth
+            }
+            Some(google_drive3::Error::Io(_)) => Category::Io,
+            Some(_) => Category::Api,
+            None => local_category(err),
+        };
+
+        let retryable = category == Category::Network
+            || http_status.is_some_and(|status| matches!(status, 408 | 429 | 500..=599));
+
+        Self {
+            message: err.to_string(),
+            chain,
+            category,
+            retryable,
+            http_status,
+            reason,
+        }
+    }
+}
+
+/// Like [`ErrorTrace::trace`](error_trace::ErrorTrace::trace), with the reason and the message Drive gives for a rejected
+/// request, if any, appended to the first line.
+#[must_use]
+pub fn trace<'e>(err: &'e (dyn Error + 'static)) -> ErrorTraceFormatter<'static, 'e, 'static> {
+    ErrorTraceFormatter::new(describe(err), err.source())
+}
+
+/// The message of the error, followed by the reason and the message Drive gives for a rejected
+/// request, if any.
+#[must_use]
+pub fn describe(err: &(dyn Error + 'static)) -> String {
+    let Some(drive_error) = sources(err).find_map(as_drive_error) else {
+        return err.to_string();
+    };
+    let Some(reason) = api_reason(drive_error) else {
+        return err.to_string();
+    };
+
+    let detail = match api_message(drive_error) {
+        Some(message) => format!("{message} ({reason})"),
+        None => reason.to_owned(),
+    };
+    // The raw response printed by the Drive error itself is still in the trace
+    if as_drive_error(err).is_some() {
+        detail
+    } else {
+        format!("{err}: {detail}")
+    }
+}
+
+pub fn print(err: &(dyn Error + 'static), format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => eprintln!("{}", trace(err)),
+        ErrorFormat::Json => match serde_json::to_string(&ErrorReport::new(err)) {
+            Ok(json) => eprintln!("{json}"),
+            Err(_) => eprintln!("{}", trace(err)),
+        },
+    }
+}
+
+fn local_category(err: &(dyn Error + 'static)) -> Category {
+    let hub_error = sources(err).find_map(<dyn Error>::downcast_ref::<GetHubError>);
+
+    match hub_error {
+        Some(GetHubError::Auth(_) | GetHubError::Hub(_)) => Category::Auth,
+        Some(_) => Category::Config,
+        None if sources(err).any(<dyn Error>::is::<std::io::Error>) => Category::Io,
+        None => Category::Other,
+    }
+}
+
+fn sources<'a>(err: &'a (dyn Error + 'static)) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+    iter::successors(Some(err), |&err| err.source())
+}
+
+// Drive errors are often boxed to keep the error enums small
+fn as_drive_error<'a>(err: &'a (dyn Error + 'static)) -> Option<&'a google_drive3::Error> {
+    err.downcast_ref::<google_drive3::Error>().or_else(|| {
+        err.downcast_ref::<Box<google_drive3::Error>>()
+            .map(AsRef::as_ref)
+    })
+}
+
+pub fn http_status(err: &google_drive3::Error) -> Option<u16> {
+    match err {
+        google_drive3::Error::Failure(response) => Some(response.status().as_u16()),
+        google_drive3::Error::BadRequest(value) => value
+            .pointer("/error/code")
+            .and_then(serde_json::Value::as_u64)
+            .and_then(|code| u16::try_from(code).ok()),
+        _ => None,
+    }
+}
+
+/// The reason of the first error of a rejected request, i.e. `cannotAddParent`.
+#[must_use]
+pub fn api_reason(err: &google_drive3::Error) -> Option<&str> {
+    match err {
+        google_drive3::Error::BadRequest(value) => value
+            .pointer("/error/errors/0/reason")
+            .and_then(serde_json::Value::as_str),
+        _ => None,
+    }
+}
+
+fn api_message(err: &google_drive3::Error) -> Option<&str> {
+    match err {
+        google_drive3::Error::BadRequest(value) => value
+            .pointer("/error/message")
+            .and_then(serde_json::Value::as_str),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::hyper;
+
+    use super::{describe, Category, ErrorReport};
+    use crate::files;
+
+    #[test]
+    fn report_api_failure() {
+        let response = hyper::Response::builder()
+            .status(503)
+            .body(hyper::Body::empty())
+            .unwrap();
+        let err = files::info::Error::GetFile(google_drive3::Error::Failure(response));
+
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.category, Category::Api);
+        assert_eq!(report.http_status, Some(503));
+        assert!(report.retryable);
+        assert_eq!(report.chain.len(), 2);
+    }
+
+    #[test]
+    fn report_bad_request() {
+        let value = serde_json::json!({ "error": { "code": 404, "message": "File not found" } });
+        let err = files::info::Error::GetFile(google_drive3::Error::BadRequest(value));
+
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.category, Category::Api);
+        assert_eq!(report.http_status, Some(404));
+        assert!(!report.retryable);
+        assert_eq!(report.reason, None);
+        assert_eq!(describe(&err), "unable to get file");
+    }
+
+    #[test]
+    fn report_api_reason() {
+        let value = serde_json::json!({
+            "error": {
+                "code": 403,
+                "message": "The user's Drive storage quota has been exceeded.",
+                "errors": [{ "reason": "storageQuotaExceeded" }],
+            }
+        });
+        let err = files::info::Error::GetFile(google_drive3::Error::BadRequest(value));
+
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.reason.as_deref(), Some("storageQuotaExceeded"));
+        assert_eq!(
+            describe(&err),
+            "unable to get file: The user's Drive storage quota has been exceeded. \
+            (storageQuotaExceeded)"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Machine readable list of the files and directories a recursive transfer failed on. Uploads
+//! produce it with `--continue-on-error` and read it back with `--retry-failed`, recursive
+//! downloads produce it with `--file-timeout`.
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error_report;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FailureManifest {
+    pub failures: Vec<Failure>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Failure {
+    pub kind: FailureKind,
+    /// Id of the file on Drive, known for downloads only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub path: PathBuf,
+    /// Parents of the file on Drive, known for uploads only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parents: Option<Vec<String>>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureKind {
+    File,
+    Directory,
+}
+
+impl FailureManifest {
+    pub fn read(path: &Path) -> Result<FailureManifest, Error> {
+        let content =
+            fs::read_to_string(path).map_err(|err| Error::Read(path.to_path_buf(), err))?;
+        serde_json::from_str(&content).map_err(|err| Error::Parse(path.to_path_buf(), err))
+    }
+
+    /// Prints the error and adds the failure, the returned entry can be completed with the id or
+    /// the parents.
+    pub fn add(
+        &mut self,
+        kind: FailureKind,
+        path: PathBuf,
+        error: &(dyn error::Error + 'static),
+    ) -> &mut Failure {
+        let error = error_report::trace(error).to_string();
+        eprintln!("{error}");
+        self.failures.push(Failure {
+            kind,
+            id: None,
+            path,
+            parents: None,
+            error,
+        });
+        self.failures.last_mut().unwrap()
+    }
+
+    /// Writes the manifest to `path`, or prints it, when anything failed. Returns the number of
+    /// failures.
+    pub fn finish(&self, path: Option<&Path>) -> Result<usize, Error> {
+        if self.failures.is_empty() {
+            return Ok(0);
+        }
+
+        let json = serde_json::to_string_pretty(self).map_err(Error::Serialize)?;
+        match path {
+            Some(path) => {
+                fs::write(path, json).map_err(|err| Error::Write(path.to_owned(), err))?;
+            }
+            None => println!("{json}"),
+        }
+
+        Ok(self.failures.len())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Serialize(serde_json::Error),
+    Write(PathBuf, io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read(path, _) => {
+                write!(f, "unable to read failure manifest '{}'", path.display())
+            }
+            Error::Parse(path, _) => {
+                write!(f, "unable to parse failure manifest '{}'", path.display())
+            }
+            Error::Serialize(_) => f.write_str("unable to serialize failure manifest"),
+            Error::Write(path, _) => {
+                write!(f, "unable to write failure manifest '{}'", path.display())
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Read(_, source) | Error::Write(_, source) => Some(source),
+            Error::Parse(_, source) | Error::Serialize(source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, path::PathBuf};
+
+    use super::{FailureKind, FailureManifest};
+
+    #[test]
+    fn uploads_and_downloads_share_the_format() {
+        let mut manifest = FailureManifest::default();
+        let error = io::Error::other("timed out");
+        manifest.add(FailureKind::Directory, PathBuf::from("a"), &error);
+        manifest
+            .add(FailureKind::File, PathBuf::from("a/b.txt"), &error)
+            .parents = Some(vec![String::from("parent")]);
+        manifest
+            .add(FailureKind::File, PathBuf::from("c.txt"), &error)
+            .id = Some(String::from("1a2B"));
+
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"failures": [
+                {"kind": "directory", "path": "a", "error": "timed out"},
+                {"kind": "file", "path": "a/b.txt", "parents": ["parent"], "error": "timed out"},
+                {"kind": "file", "id": "1a2B", "path": "c.txt", "error": "timed out"},
+            ]})
+        );
+
+        // Manifests written before the id was added are still read
+        let manifest: FailureManifest = serde_json::from_str(
+            r#"{"failures": [{"kind": "file", "path": "d", "parents": null, "error": "e"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.failures[0].kind, FailureKind::File);
+        assert!(manifest.failures[0].parents.is_none());
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use google_drive3::chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc};
+
+/// Size and age limits for the files of a recursive transfer.
+///
+/// Folders are never filtered, therefore they are transferred even when all their files are
+/// skipped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileFilter {
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub newer_than: Option<TimeBound>,
+    pub older_than: Option<TimeBound>,
+}
+
+impl FileFilter {
+    /// Returns whether a file with the given size and modification time should be transferred.
+    ///
+    /// Files without a modification time are skipped when any age limit is set.
+    #[must_use]
+    pub fn matches(&self, size: u64, modified_time: Option<DateTime<Utc>>) -> bool {
+        let size_matches = self.min_size.is_none_or(|min_size| size >= min_size)
+            && self.max_size.is_none_or(|max_size| size <= max_size);
+
+        let age_matches = match modified_time {
+            Some(time) => {
+                self.newer_than.is_none_or(|bound| time > bound.0)
+                    && self.older_than.is_none_or(|bound| time < bound.0)
+            }
+            None => self.newer_than.is_none() && self.older_than.is_none(),
+        };
+
+        size_matches && age_matches
+    }
+}
+
+/// A point in time, given either as an age relative to now (`30m`, `12h`, `7d`, `2w`), as a date
+/// (`2024-01-31`, local midnight) or as an RFC 3339 timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeBound(pub DateTime<Utc>);
+
+impl FromStr for TimeBound {
+    type Err = InvalidTimeBound;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_age(s)
+            .map(|age| Utc::now() - age)
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .and_then(|time| time.and_local_timezone(Local).earliest())
+                    .map(|time| time.with_timezone(&Utc))
+            })
+            .or_else(|| {
+                DateTime::parse_from_rfc3339(s)
+                    .ok()
+                    .map(|time| time.with_timezone(&Utc))
+            })
+            .map(TimeBound)
+            .ok_or(InvalidTimeBound)
+    }
+}
+
+/// Parses an age like `30m`, `12h` or `7d`, the unit being one of `s`, `m`, `h`, `d` and `w`.
+pub(crate) fn parse_age(s: &str) -> Option<TimeDelta> {
+    let unit = s.chars().last()?;
+    let count: i64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+
+    match unit {
+        's' => TimeDelta::try_seconds(count),
+        'm' => TimeDelta::try_minutes(count),
+        'h' => TimeDelta::try_hours(count),
+        'd' => TimeDelta::try_days(count),
+        'w' => TimeDelta::try_weeks(count),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTimeBound;
+
+impl Display for InvalidTimeBound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "not a valid time, use an age like 12h, 7d or 2w, a date like 2024-01-31 or an RFC \
+            3339 timestamp",
+        )
+    }
+}
+
+impl error::Error for InvalidTimeBound {}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::chrono::{DateTime, TimeDelta, Utc};
+
+    use super::{parse_age, FileFilter, TimeBound};
+
+    #[test]
+    fn parse_ages() {
+        assert_eq!(parse_age("30m"), TimeDelta::try_minutes(30));
+        assert_eq!(parse_age("7d"), TimeDelta::try_days(7));
+        assert_eq!(parse_age("2w"), TimeDelta::try_weeks(2));
+        assert_eq!(parse_age("7"), None);
+        assert_eq!(parse_age("d"), None);
+        assert_eq!(parse_age("5y"), None);
+    }
+
+    #[test]
+    fn parse_time_bounds() {
+        assert_eq!(
+            "2024-01-31T10:00:00Z".parse(),
+            Ok(TimeBound(
+                DateTime::parse_from_rfc3339("2024-01-31T10:00:00Z")
+                    .unwrap()
+                    .to_utc()
+            ))
+        );
+        assert!("2024-01-31".parse::<TimeBound>().is_ok());
+        assert!("yesterday".parse::<TimeBound>().is_err());
+    }
+
+    #[test]
+    fn filter_matches() {
+        let now = Utc::now();
+        let filter = FileFilter {
+            min_size: Some(10),
+            max_size: Some(100),
+            newer_than: Some(TimeBound(now - TimeDelta::days(7))),
+            older_than: None,
+        };
+
+        assert!(filter.matches(10, Some(now)));
+        assert!(!filter.matches(9, Some(now)));
+        assert!(!filter.matches(101, Some(now)));
+        assert!(!filter.matches(50, Some(now - TimeDelta::days(8))));
+        assert!(!filter.matches(50, None));
+        assert!(FileFilter::default().matches(0, None));
+    }
+}
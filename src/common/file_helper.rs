@@ -1,4 +1,5 @@
 use std::{
+    env,
     error::Error,
     fmt::{self, Display},
     fs,
@@ -6,60 +7,173 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use mktemp::Temp;
+use crate::common::tempfiles::TempFile;
 
-pub fn stdin_to_file() -> Result<Temp, StdinToFileError> {
-    let tmp_file = Temp::new_file().map_err(StdinToFileError::NewTempFile)?;
-    let mut file = fs::File::create(&tmp_file).map_err(StdinToFileError::CreateTempFile)?;
-    io::copy(&mut io::stdin(), &mut file).map_err(StdinToFileError::CopyStdin)?;
+/// Whether `path` can only be read once and has no size, like pipes, character devices and
+/// sockets. Uploads need the size beforehand and seek to resume, such content has to be staged.
+pub fn is_stream(path: &Path) -> io::Result<bool> {
+    let file_type = fs::metadata(path)?.file_type();
+    Ok(!file_type.is_file() && !file_type.is_dir() && !is_block_device(&file_type))
+}
+
+/// Block devices are seekable, their size is found by seeking to the end.
+#[must_use]
+pub fn is_block_device(file_type: &fs::FileType) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        file_type.is_block_device()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = file_type;
+        false
+    }
+}
+
+/// Environment variable with the maximum number of bytes staged from stdin or a stream, 0 lifts
+/// the limit.
+pub const MAX_STAGED_SIZE_ENV: &str = "GDRIVE_MAX_STAGED_SIZE";
+
+/// Streams like `/dev/zero` never end, staging stops there instead of filling the disk.
+const DEFAULT_MAX_STAGED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+/// Copies the content of a stream, see [`is_stream`], or of stdin when there is no path, to a
+/// temporary file. Fails when the content is larger than [`max_staged_size`].
+pub fn stage(path: Option<&Path>) -> Result<TempFile, StageError> {
+    let max_size = max_staged_size()?;
+    match path {
+        Some(path) => {
+            let stream = fs::File::open(path).map_err(StageError::Open)?;
+            stage_reader(stream, max_size)
+        }
+        None => stage_reader(io::stdin(), max_size),
+    }
+}
+
+/// The maximum size of staged content, from [`MAX_STAGED_SIZE_ENV`]. `None` when unlimited.
+fn max_staged_size() -> Result<Option<u64>, StageError> {
+    parse_max_staged_size(env::var(MAX_STAGED_SIZE_ENV).ok())
+}
+
+fn parse_max_staged_size(value: Option<String>) -> Result<Option<u64>, StageError> {
+    let Some(value) = value.filter(|value| !value.trim().is_empty()) else {
+        return Ok(Some(DEFAULT_MAX_STAGED_SIZE));
+    };
+    match value.trim().parse() {
+        Ok(0) => Ok(None),
+        Ok(max_size) => Ok(Some(max_size)),
+        Err(_) => Err(StageError::InvalidMaxSize(value)),
+    }
+}
+
+fn stage_reader(mut reader: impl Read, max_size: Option<u64>) -> Result<TempFile, StageError> {
+    let tmp_file = TempFile::new_in_temp_dir().map_err(StageError::NewTempFile)?;
+    let mut file = fs::File::create(&tmp_file).map_err(StageError::CreateTempFile)?;
+    match max_size {
+        Some(max_size) => {
+            // One byte more than allowed tells a content of exactly `max_size` from a larger one
+            let copied = io::copy(&mut (&mut reader).take(max_size + 1), &mut file)
+                .map_err(StageError::Copy)?;
+            if copied > max_size {
+                return Err(StageError::TooLarge(max_size));
+            }
+        }
+        None => {
+            io::copy(&mut reader, &mut file).map_err(StageError::Copy)?;
+        }
+    }
     Ok(tmp_file)
 }
 
 #[derive(Debug)]
-pub enum StdinToFileError {
+pub enum StageError {
+    InvalidMaxSize(String),
+    Open(io::Error),
     NewTempFile(io::Error),
     CreateTempFile(io::Error),
-    CopyStdin(io::Error),
+    Copy(io::Error),
+    TooLarge(u64),
 }
 
-impl Display for StdinToFileError {
+impl Display for StageError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            StdinToFileError::NewTempFile(_) => "unable to create a new temporary file",
-            StdinToFileError::CreateTempFile(_) => {
-                "unable to open and truncate the new temporary file"
+        match self {
+            StageError::InvalidMaxSize(value) => write!(
+                f,
+                "{MAX_STAGED_SIZE_ENV} must be a whole number of bytes, '{value}' is not"
+            ),
+            StageError::Open(_) => f.write_str("unable to open the stream"),
+            StageError::NewTempFile(_) => f.write_str("unable to create a new temporary file"),
+            StageError::CreateTempFile(_) => {
+                f.write_str("unable to open and truncate the new temporary file")
             }
-            StdinToFileError::CopyStdin(_) => "unable to copy stdin to temporary file",
-        };
-
-        f.write_str(s)
+            StageError::Copy(_) => f.write_str("unable to copy the content to the temporary file"),
+            StageError::TooLarge(max_size) => write!(
+                f,
+                "the content is larger than {max_size} bytes, set {MAX_STAGED_SIZE_ENV} to raise \
+                 the limit or to 0 to remove it"
+            ),
+        }
     }
 }
 
-impl Error for StdinToFileError {
+impl Error for StageError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            StdinToFileError::NewTempFile(source)
-            | StdinToFileError::CreateTempFile(source)
-            | StdinToFileError::CopyStdin(source) => Some(source),
+            StageError::Open(source)
+            | StageError::NewTempFile(source)
+            | StageError::CreateTempFile(source)
+            | StageError::Copy(source) => Some(source),
+            StageError::InvalidMaxSize(_) | StageError::TooLarge(_) => None,
         }
     }
 }
 
+/// Opens the file to upload, reading stdin when there is no path. Stdin and streams are staged in
+/// a temporary file first.
 pub fn open_file(path: &Option<PathBuf>) -> Result<File<'_>, OpenFileError> {
     let (file, kind) = if let Some(path) = path {
-        let file = fs::File::open(path).map_err(|source| OpenFileError::Open {
+        let is_stream = is_stream(path).map_err(|source| OpenFileError::Open {
             path: path.clone(),
             source,
         })?;
-        (file, FileKind::File(path))
+        if is_stream {
+            let tmp_file = stage(Some(path)).map_err(|source| OpenFileError::Stage {
+                path: Some(path.clone()),
+                source,
+            })?;
+            let file = match fs::File::open(&tmp_file) {
+                Ok(file) => file,
+                Err(source) => {
+                    return Err(OpenFileError::Open {
+                        path: tmp_file.to_path_buf(),
+                        source,
+                    })
+                }
+            };
+            (
+                file,
+                FileKind::Staged {
+                    _temp: tmp_file,
+                    original: path,
+                },
+            )
+        } else {
+            let file = fs::File::open(path).map_err(|source| OpenFileError::Open {
+                path: path.clone(),
+                source,
+            })?;
+            (file, FileKind::File(path))
+        }
     } else {
-        let tmp_file = stdin_to_file().map_err(OpenFileError::StdinToFile)?;
+        let tmp_file = stage(None).map_err(|source| OpenFileError::Stage { path: None, source })?;
         let file = match fs::File::open(&tmp_file) {
             Ok(file) => file,
             Err(source) => {
                 return Err(OpenFileError::Open {
-                    path: tmp_file.release(),
+                    path: tmp_file.to_path_buf(),
                     source,
                 })
             }
@@ -79,8 +193,14 @@ pub struct File<'a> {
 
 #[derive(Debug)]
 enum FileKind<'a> {
-    Temp(Temp),
+    Temp(TempFile),
     File(&'a Path),
+    /// The content of a stream copied to a temporary file, named after the original path. The
+    /// temporary file is deleted when dropped
+    Staged {
+        _temp: TempFile,
+        original: &'a Path,
+    },
 }
 
 impl File<'_> {
@@ -92,8 +212,8 @@ impl File<'_> {
     #[must_use]
     pub fn into_path_buf(self) -> PathBuf {
         match self.kind {
-            FileKind::Temp(temp) => temp.release(),
-            FileKind::File(path) => path.to_path_buf(),
+            FileKind::Temp(temp) => temp.keep(),
+            FileKind::File(path) | FileKind::Staged { original: path, .. } => path.to_path_buf(),
         }
     }
 
@@ -153,15 +273,22 @@ impl FileKind<'_> {
     fn path(&self) -> &Path {
         match self {
             FileKind::Temp(temp) => temp,
-            FileKind::File(path) => path,
+            FileKind::File(path) | FileKind::Staged { original: path, .. } => path,
         }
     }
 }
 
 #[derive(Debug)]
 pub enum OpenFileError {
-    Open { path: PathBuf, source: io::Error },
-    StdinToFile(StdinToFileError),
+    Open {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// Staging of the stream at `path`, or of stdin when `None`
+    Stage {
+        path: Option<PathBuf>,
+        source: StageError,
+    },
 }
 
 impl Display for OpenFileError {
@@ -170,7 +297,20 @@ impl Display for OpenFileError {
             OpenFileError::Open { path, source: _ } => {
                 write!(f, "unable to open path '{}'", path.display())
             }
-            OpenFileError::StdinToFile(_) => f.write_str("unable to create a file from stdin"),
+            OpenFileError::Stage {
+                path: Some(path),
+                source: _,
+            } => {
+                write!(
+                    f,
+                    "unable to stage '{}' in a temporary file",
+                    path.display()
+                )
+            }
+            OpenFileError::Stage {
+                path: None,
+                source: _,
+            } => f.write_str("unable to create a file from stdin"),
         }
     }
 }
@@ -179,7 +319,61 @@ impl Error for OpenFileError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             OpenFileError::Open { source, .. } => Some(source),
-            OpenFileError::StdinToFile(source) => Some(source),
+            OpenFileError::Stage { source, .. } => Some(source),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_stream, parse_max_staged_size, stage_reader, StageError};
+
+    #[test]
+    fn regular_files_are_not_streams() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(!is_stream(file.path()).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn character_devices_are_staged() {
+        use std::{fs, path::Path};
+
+        use super::stage;
+
+        let path = Path::new("/dev/null");
+        assert!(is_stream(path).unwrap());
+
+        let staged = stage(Some(path)).unwrap();
+        assert_eq!(fs::metadata(&staged).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn staging_stops_at_max_size() {
+        use std::{fs, io};
+
+        let staged = stage_reader(&[0; 8][..], Some(8)).unwrap();
+        assert_eq!(fs::metadata(&staged).unwrap().len(), 8);
+
+        // A never ending stream
+        let err = stage_reader(io::repeat(0), Some(8)).unwrap_err();
+        assert!(matches!(err, StageError::TooLarge(8)));
+    }
+
+    #[test]
+    fn max_staged_size_from_var() {
+        assert!(parse_max_staged_size(None).unwrap().is_some());
+        assert_eq!(
+            parse_max_staged_size(Some(String::from(" 1024 "))).unwrap(),
+            Some(1024)
+        );
+        assert_eq!(
+            parse_max_staged_size(Some(String::from("0"))).unwrap(),
+            None
+        );
+        assert!(matches!(
+            parse_max_staged_size(Some(String::from("1G"))),
+            Err(StageError::InvalidMaxSize(_))
+        ));
+    }
+}
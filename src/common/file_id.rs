@@ -0,0 +1,124 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    ops::Deref,
+    str::FromStr,
+};
+
+/// The id of a file or folder. Ids parsed from the command line, through [`FromStr`], are checked
+/// to only contain the characters Drive uses, catching i.e. a pasted link before any request is
+/// made. There is deliberately no `From<String>`, clap would prefer it to the validating parser.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(String);
+
+impl FileId {
+    /// An id returned by Drive, taken as it is.
+    #[must_use]
+    pub fn from_drive(id: String) -> Self {
+        FileId(id)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for FileId {
+    type Err = InvalidFileId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(InvalidFileId::Empty);
+        }
+
+        if let Some(c) = s.chars().find(|&c| !is_id_char(c)) {
+            return Err(InvalidFileId::Char(c));
+        }
+
+        Ok(FileId(s.to_string()))
+    }
+}
+
+/// The ids as sent to the API, i.e. as the parents of a new file.
+#[must_use]
+pub fn to_strings(ids: &[FileId]) -> Vec<String> {
+    ids.iter().map(ToString::to_string).collect()
+}
+
+/// The ids returned by the API.
+#[must_use]
+pub fn from_strings(ids: Vec<String>) -> Vec<FileId> {
+    ids.into_iter().map(FileId::from_drive).collect()
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+impl From<FileId> for String {
+    fn from(id: FileId) -> Self {
+        id.0
+    }
+}
+
+impl Deref for FileId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for FileId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidFileId {
+    Empty,
+    Char(char),
+}
+
+impl Display for InvalidFileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidFileId::Empty => f.write_str("the id is empty"),
+            InvalidFileId::Char(c) => write!(
+                f,
+                "'{c}' is not allowed in an id, expected letters, digits, '-' and '_'"
+            ),
+        }
+    }
+}
+
+impl Error for InvalidFileId {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_file_id() {
+        let id: FileId = "1a2B-c_D3".parse().unwrap();
+        assert_eq!(id.as_str(), "1a2B-c_D3");
+        assert_eq!(id.to_string(), "1a2B-c_D3");
+        assert_eq!(String::from(id), "1a2B-c_D3");
+
+        assert_eq!("root".parse::<FileId>().unwrap().as_str(), "root");
+        assert_eq!("".parse::<FileId>(), Err(InvalidFileId::Empty));
+        assert_eq!(
+            "https://drive.google.com/file/d/abc".parse::<FileId>(),
+            Err(InvalidFileId::Char(':'))
+        );
+        assert_eq!("abc ".parse::<FileId>(), Err(InvalidFileId::Char(' ')));
+    }
+}
@@ -1,22 +1,39 @@
 use std::{
     borrow::Cow,
+    collections::HashMap,
     error,
     fmt::{Display, Formatter},
     fs,
+    io::{Seek, SeekFrom},
     path::Path,
 };
 
+use google_drive3::api::FileContentHints;
+
+use super::file_helper;
+
 pub struct FileInfo<'a> {
     pub name: Cow<'a, str>,
     pub mime_type: Cow<'a, mime::Mime>,
     pub parents: Option<Vec<String>>,
     pub size: u64,
+    /// Properties private to this application, set on the created file
+    pub app_properties: Option<HashMap<String, String>>,
+    /// Description of the created file
+    pub description: Option<String>,
+    /// Whether the created file is starred
+    pub starred: bool,
+    /// Hints set on the file, i.e. a custom thumbnail
+    pub content_hints: Option<FileContentHints>,
 }
 
 pub struct Config<'a> {
     pub file_path: &'a Path,
     pub mime_type: Option<&'a mime::Mime>,
     pub parents: Option<Vec<String>>,
+    pub app_properties: Option<HashMap<String, String>>,
+    pub description: Option<String>,
+    pub starred: bool,
 }
 
 impl<'a> FileInfo<'a> {
@@ -27,7 +44,7 @@ impl<'a> FileInfo<'a> {
             .map(|s| s.to_string_lossy())
             .ok_or(FromFileError)?;
 
-        let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let file_size = content_len(file);
 
         let mime_type = config.mime_type.map_or_else(
             || {
@@ -43,10 +60,29 @@ impl<'a> FileInfo<'a> {
             mime_type,
             parents: config.parents,
             size: file_size,
+            app_properties: config.app_properties,
+            description: config.description,
+            starred: config.starred,
+            content_hints: None,
         })
     }
 }
 
+/// The size of the content of `file`, which for block devices is not the one of the metadata.
+fn content_len(file: &fs::File) -> u64 {
+    let Ok(metadata) = file.metadata() else {
+        return 0;
+    };
+    if !file_helper::is_block_device(&metadata.file_type()) {
+        return metadata.len();
+    }
+
+    let mut file = file;
+    let len = file.seek(SeekFrom::End(0)).unwrap_or(0);
+    let _ = file.rewind();
+    len
+}
+
 #[derive(Debug)]
 pub struct FromFileError;
 
@@ -2,34 +2,135 @@ pub mod errors;
 
 use std::{
     borrow::Cow,
-    fs,
+    fs, io,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use async_recursion::async_recursion;
+use google_drive3::chrono::{DateTime, Utc};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 
 use super::{FileLike, FileTreeLike, FolderInfoLike, FolderLike};
-use crate::common::{file_info::FileInfo, file_tree_like, id_gen::IdGen};
+use crate::common::{
+    file_filter::FileFilter, file_info::FileInfo, file_tree_like, id_gen::IdGen, local_name,
+};
+
+/// Name of the file listing the paths to skip with the gitignore syntax. It applies to the
+/// directory holding it and below, the files of subdirectories take precedence, like for git.
+pub const IGNORE_FILE_NAME: &str = ".gdriveignore";
 
 #[derive(Debug, Clone)]
 pub struct FileTree {
     pub root: Folder,
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Config {
+    pub filter: FileFilter,
+    /// How many levels of directories below the root to walk, all of them when `None`
+    pub max_depth: Option<usize>,
+    /// Skip the directories on another file system than the root, i.e. mounted network shares.
+    /// Only supported on Unix
+    pub one_file_system: bool,
+}
+
 impl FileTree {
-    pub async fn from_path(path: &Path, ids: &mut IdGen<'_>) -> Result<FileTree, errors::FileTree> {
+    /// Walks the directory at `path`, skipping the files not matching the filter of `config`,
+    /// the directories beyond its limits and the paths matched by the [`IGNORE_FILE_NAME`] files
+    /// of the walked directories.
+    pub async fn from_path(
+        path: &Path,
+        ids: &mut IdGen<'_>,
+        config: &Config,
+    ) -> Result<FileTree, errors::FileTree> {
         let canonical_path = path
             .canonicalize()
+            .map(local_name::long_path)
             .map_err(errors::FileTree::Canonicalize)?;
 
-        let root = Folder::from_path(&canonical_path, None, ids)
+        let walk = Walk {
+            config,
+            root_device: if config.one_file_system {
+                device_id(&canonical_path).map_err(errors::FileTree::Canonicalize)?
+            } else {
+                None
+            },
+        };
+        let root = Folder::from_path(&canonical_path, None, 0, &[], ids, &walk)
             .await
             .map_err(errors::FileTree::Folder)?;
         Ok(FileTree { root })
     }
 }
 
+/// The settings of a walk, shared by all its directories.
+struct Walk<'a> {
+    config: &'a Config,
+    root_device: Option<u64>,
+}
+
+impl Walk<'_> {
+    /// Why the directory at `path`, child of a directory `depth` levels below the root, is not
+    /// walked, if it is not.
+    fn skipped_directory(&self, path: &Path, depth: usize) -> io::Result<Option<&'static str>> {
+        if self
+            .config
+            .max_depth
+            .is_some_and(|max_depth| depth >= max_depth)
+        {
+            return Ok(Some("it is deeper than the maximum depth"));
+        }
+
+        if let Some(root_device) = self.root_device {
+            if device_id(path)? != Some(root_device) {
+                return Ok(Some("it is on another file system"));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> io::Result<Option<u64>> {
+    use std::os::unix::fs::MetadataExt;
+
+    fs::metadata(path).map(|metadata| Some(metadata.dev()))
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> io::Result<Option<u64>> {
+    Ok(None)
+}
+
+/// Loads the [`IGNORE_FILE_NAME`] file of the directory `dir`, if any.
+pub(crate) fn load_ignore_file(dir: &Path) -> Result<Option<Gitignore>, ignore::Error> {
+    let ignore_path = dir.join(IGNORE_FILE_NAME);
+    if !ignore_path.is_file() {
+        return Ok(None);
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    // The ignore file itself is skipped, unless the file negates it with `!/.gdriveignore`
+    builder.add_line(None, &format!("/{IGNORE_FILE_NAME}"))?;
+    if let Some(err) = builder.add(&ignore_path) {
+        return Err(err);
+    }
+    builder.build().map(Some)
+}
+
+/// Whether `path` is ignored by the ignore files of its ancestors, from the root down. The
+/// deepest file matching the path decides, so that it can re-include what a parent ignores.
+fn is_ignored(ignores: &[Arc<Gitignore>], path: &Path, is_dir: bool) -> bool {
+    ignores
+        .iter()
+        .rev()
+        .map(|ignore| ignore.matched(path, is_dir))
+        .find(|matched| !matched.is_none())
+        .is_some_and(|matched| matched.is_ignore())
+}
+
 impl FileTreeLike for FileTree {
     type Folder = Folder;
 
@@ -56,10 +157,13 @@ pub struct Folder {
 
 impl Folder {
     #[async_recursion]
-    pub async fn from_path(
+    async fn from_path(
         path: &Path,
         parent: Option<&'async_recursion Folder>,
+        depth: usize,
+        ignores: &[Arc<Gitignore>],
         ids: &mut IdGen<'_>,
+        walk: &Walk<'_>,
     ) -> Result<Folder, errors::Folder> {
         use errors::Folder as E;
 
@@ -80,6 +184,12 @@ impl Folder {
             children: Vec::new(),
         };
 
+        let mut ignores = ignores.to_vec();
+        if let Some(ignore) = load_ignore_file(path).map_err(E::IgnoreFile)? {
+            ignores.push(Arc::new(ignore));
+        }
+
+        tracing::debug!(path = %path.display(), "walking directory");
         let entries = fs::read_dir(path).map_err(E::ReadDir)?;
         let mut children = Vec::new();
 
@@ -87,21 +197,42 @@ impl Folder {
             let entry = e.map_err(E::ReadDirEntry)?;
             let path = entry.path();
 
+            if is_ignored(&ignores, &path, path.is_dir()) {
+                continue;
+            }
+
             if path.is_dir() {
-                let folder = match Folder::from_path(&path, Some(&folder), ids).await {
-                    Ok(folder) => folder,
-                    Err(source) => {
-                        return Err(E::Nested {
-                            path,
-                            source: Box::new(source),
-                        });
-                    }
-                };
+                if let Some(reason) = walk
+                    .skipped_directory(&path, depth)
+                    .map_err(E::ReadDirEntry)?
+                {
+                    eprintln!("Skipping directory '{}', {reason}", path.display());
+                    continue;
+                }
+
+                let folder =
+                    match Folder::from_path(&path, Some(&folder), depth + 1, &ignores, ids, walk)
+                        .await
+                    {
+                        Ok(folder) => folder,
+                        Err(source) => {
+                            return Err(E::Nested {
+                                path,
+                                source: Box::new(source),
+                            });
+                        }
+                    };
                 let node = Node::Folder(folder);
                 children.push(node);
             } else if path.is_symlink() {
                 return Err(E::IsSymlink(path));
             } else if path.is_file() {
+                let metadata = entry.metadata().map_err(E::ReadDirEntry)?;
+                let modified_time = metadata.modified().ok().map(DateTime::<Utc>::from);
+                if !walk.config.filter.matches(metadata.len(), modified_time) {
+                    continue;
+                }
+
                 let file = match File::from_path(&path, &folder, ids).await {
                     Ok(file) => file,
                     Err(source) => {
@@ -183,7 +314,7 @@ impl File {
             .ok_or(E::InvalidPath)?;
 
         let os_file = fs::File::open(path).map_err(E::OpenFile)?;
-        let size = os_file.metadata().map(|m| m.len()).unwrap_or(0);
+        let size = os_file.metadata().map_or(0, |m| m.len());
         let mime_type = mime_guess::from_path(path)
             .first()
             .unwrap_or(mime::APPLICATION_OCTET_STREAM);
@@ -213,6 +344,10 @@ impl File {
             size: self.size,
             mime_type: Cow::Borrowed(&self.mime_type),
             parents,
+            app_properties: None,
+            description: None,
+            starred: false,
+            content_hints: None,
         }
     }
 }
@@ -239,12 +374,16 @@ fn get_relative_path<'a>(path: &'a Path, folder_info: &FolderInfo) -> &'a Path {
 #[cfg(test)]
 mod tests {
     use std::{
+        fs,
         path::{Path, PathBuf},
         sync::Arc,
     };
 
-    use super::{File, Folder, Node};
-    use crate::common::{drive_file::MIME_TYPE_CSV_MIME, file_tree::FolderInfo, FolderLike};
+    use super::{load_ignore_file, Config, File, FileTree, Folder, Node, Walk, IGNORE_FILE_NAME};
+    use crate::common::{
+        drive_file::MIME_TYPE_CSV_MIME, file_tree::FolderInfo, id_gen::IdGen, FileTreeLike,
+        FolderLike,
+    };
 
     #[test]
     fn folder_folders_recursive() {
@@ -349,4 +488,95 @@ mod tests {
         assert_eq!(weak_folder_a.strong_count(), 0);
         assert_eq!(weak_folder_b.strong_count(), 0);
     }
+
+    #[test]
+    fn ignore_file_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        assert!(load_ignore_file(&root).unwrap().is_none());
+
+        fs::write(root.join(IGNORE_FILE_NAME), "*.log\ntarget/\n!keep.log\n").unwrap();
+        let ignore = load_ignore_file(&root).unwrap().unwrap();
+
+        assert!(ignore.matched(root.join("a.log"), false).is_ignore());
+        assert!(ignore.matched(root.join("sub/b.log"), false).is_ignore());
+        assert!(ignore.matched(root.join("target"), true).is_ignore());
+        assert!(!ignore.matched(root.join("target"), false).is_ignore());
+        assert!(!ignore.matched(root.join("keep.log"), false).is_ignore());
+        assert!(!ignore.matched(root.join("a.txt"), false).is_ignore());
+        assert!(ignore
+            .matched(root.join(IGNORE_FILE_NAME), false)
+            .is_ignore());
+        assert!(!ignore
+            .matched(root.join("sub").join(IGNORE_FILE_NAME), false)
+            .is_ignore());
+
+        fs::write(
+            root.join(IGNORE_FILE_NAME),
+            format!("!/{IGNORE_FILE_NAME}\n"),
+        )
+        .unwrap();
+        let ignore = load_ignore_file(&root).unwrap().unwrap();
+        assert!(!ignore
+            .matched(root.join(IGNORE_FILE_NAME), false)
+            .is_ignore());
+    }
+
+    #[tokio::test]
+    async fn nested_ignore_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("sub/build")).unwrap();
+        fs::write(root.join("a.log"), "").unwrap();
+        fs::write(root.join("sub/b.log"), "").unwrap();
+        fs::write(root.join("sub/c.txt"), "").unwrap();
+        fs::write(root.join("sub/build/out.o"), "").unwrap();
+        fs::write(root.join(IGNORE_FILE_NAME), "*.log\n").unwrap();
+        // Re-includes what the root ignores, below `sub` only
+        fs::write(root.join("sub").join(IGNORE_FILE_NAME), "!*.log\nbuild/\n").unwrap();
+
+        let tree = FileTree::from_path(root, &mut IdGen::offline(), &Config::default())
+            .await
+            .unwrap();
+        let mut paths: Vec<_> = tree
+            .folders()
+            .iter()
+            .flat_map(|folder| folder.files())
+            .map(|file| {
+                file.path
+                    .strip_prefix(&tree.root.info.path)
+                    .unwrap()
+                    .to_path_buf()
+            })
+            .collect();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            [
+                Path::new("sub").join("b.log"),
+                Path::new("sub").join("c.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_directories_beyond_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            max_depth: Some(1),
+            one_file_system: true,
+            ..Config::default()
+        };
+        let walk = Walk {
+            config: &config,
+            root_device: super::device_id(dir.path()).unwrap(),
+        };
+
+        assert_eq!(walk.skipped_directory(dir.path(), 0).unwrap(), None);
+        assert_eq!(
+            walk.skipped_directory(dir.path(), 1).unwrap(),
+            Some("it is deeper than the maximum depth")
+        );
+    }
 }
@@ -39,6 +39,7 @@ pub enum Folder {
     GenerateId(id_gen::NextError),
     ReadDir(io::Error),
     ReadDirEntry(io::Error),
+    IgnoreFile(ignore::Error),
     Nested { path: PathBuf, source: Box<Folder> },
     IsSymlink(PathBuf),
     File { path: PathBuf, source: File },
@@ -52,6 +53,7 @@ impl Display for Folder {
             Folder::GenerateId(_) => f.write_str("unable to generate google drive id"),
             Folder::ReadDir(_) => f.write_str("unable to read directory content"),
             Folder::ReadDirEntry(_) => f.write_str("unable to read entry from directory"),
+            Folder::IgnoreFile(_) => f.write_str("unable to load the ignore file"),
             Folder::Nested { path, source: _ } => {
                 write!(f, "cannot evaluate child directory '{}", path.display())
             }
@@ -78,6 +80,7 @@ impl Error for Folder {
             Folder::InvalidPath | Folder::IsSymlink(_) | Folder::UnknownFileType(_) => None,
             Folder::GenerateId(source) => Some(source),
             Folder::ReadDir(source) | Folder::ReadDirEntry(source) => Some(source),
+            Folder::IgnoreFile(source) => Some(source),
             Folder::Nested { source, .. } => Some(source),
             Folder::File { source, .. } => Some(source),
         }
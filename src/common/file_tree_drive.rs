@@ -1,12 +1,24 @@
+mod cache;
 pub mod errors;
 
-use std::{iter, ops::Not, path::PathBuf, sync::Arc};
+use std::{iter, ops::Not, path::PathBuf, sync::Arc, time::Duration};
 
 use async_recursion::async_recursion;
+use error_trace::ErrorTrace;
+use google_drive3::chrono::{DateTime, Utc};
 
-use super::{file_tree_like, parse_md5_digest, FileLike, FileTreeLike, FolderInfoLike, FolderLike};
+pub use self::cache::ListingCache;
+
+use super::{
+    checksum::Checksum, file_tree_like, FileLike, FileTreeLike, FolderInfoLike, FolderLike,
+};
 use crate::{
-    common::{drive_file, file_tree_drive::errors::FileIdentifier},
+    common::{
+        drive_file,
+        file_filter::FileFilter,
+        file_tree_drive::errors::FileIdentifier,
+        local_name::{self, SanitizeNames},
+    },
     files::list::{self, ListQuery, ListSortOrder},
     hub::Hub,
 };
@@ -16,14 +28,38 @@ pub struct FileTreeDrive {
     pub root: Folder,
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Config {
+    pub filter: FileFilter,
+    /// Add Google Docs files to the tree, marked as not downloadable. They are skipped otherwise
+    pub include_docs: bool,
+    /// Reuse the folder listings of a previous walk of the same tree for this long, as long as
+    /// nothing was modified since. Listings are not cached when unset
+    pub cache_ttl: Option<Duration>,
+}
+
 impl FileTreeDrive {
+    /// Lists the folder `file` recursively, skipping the files not matching the filter.
     pub async fn from_file(
         hub: &Hub,
         file: google_drive3::api::File,
+        config: &Config,
     ) -> Result<FileTreeDrive, errors::FileTreeDrive> {
-        let root = Folder::from_file(hub, file, None)
+        let mut cache = match config.cache_ttl {
+            Some(ttl) => ListingCache::open(hub, &file, ttl).await,
+            None => ListingCache::disabled(),
+        };
+
+        let root = Folder::from_file(hub, file, None, config, &mut cache)
             .await
             .map_err(errors::FileTreeDrive)?;
+
+        if let Err(err) = cache.save() {
+            eprintln!(
+                "Warning: Failed to save the folder listing cache: {}",
+                err.trace()
+            );
+        }
         Ok(FileTreeDrive { root })
     }
 }
@@ -36,7 +72,7 @@ impl FileTreeLike for FileTreeDrive {
     }
 }
 
-type Node = file_tree_like::Node<Folder>;
+pub(crate) type Node = file_tree_like::Node<Folder>;
 
 #[derive(Debug, Clone)]
 pub struct Folder {
@@ -49,6 +85,8 @@ pub struct FolderInfo {
     pub name: String,
     pub parent: Option<Arc<Self>>,
     pub drive_id: String,
+    /// Id of the shared drive containing the folder, if any
+    pub shared_drive_id: Option<String>,
 }
 
 impl Folder {
@@ -57,6 +95,8 @@ impl Folder {
         hub: &Hub,
         file: google_drive3::api::File,
         parent: Option<&'async_recursion Arc<FolderInfo>>,
+        config: &Config,
+        cache: &mut ListingCache,
     ) -> Result<Folder, errors::Folder> {
         if drive_file::is_directory(&file).not() {
             return Err(errors::Folder::NotDirectory);
@@ -64,41 +104,70 @@ impl Folder {
 
         let name = file.name.ok_or(errors::Folder::MissingFileName)?;
         let file_id = file.id.ok_or(errors::Folder::MissingFileId)?;
+        // Only the root file comes with its drive id, children inherit it
+        let shared_drive_id = parent.map_or(file.drive_id, |parent| parent.shared_drive_id.clone());
 
         let mut folder = Folder {
             info: Arc::new(FolderInfo {
                 name,
                 parent: parent.map(Arc::clone),
                 drive_id: file_id.clone(),
+                shared_drive_id,
             }),
             children: Vec::new(),
         };
 
-        let files = list::list_files(
-            hub,
-            list::ListFilesConfig {
-                query: &ListQuery::FilesInFolder { folder_id: file_id },
-                order_by: &ListSortOrder::default(),
-                max_files: usize::MAX,
-            },
-        )
-        .await
-        .map_err(errors::Folder::ListFiles)?;
+        let files = if let Some(files) = cache.get(&file_id) {
+            tracing::debug!(folder_id = file_id, "using cached listing");
+            files
+        } else {
+            tracing::debug!(folder_id = file_id, "listing directory");
+            let files = list::list_files(
+                hub,
+                list::ListFilesConfig {
+                    query: &ListQuery::FilesInFolder {
+                        folder_id: file_id.clone(),
+                    },
+                    order_by: &ListSortOrder::default(),
+                    max_files: usize::MAX,
+                    extra_fields: &["modifiedTime"],
+                    drive_id: folder.info.shared_drive_id.as_deref(),
+                    corpora: None,
+                },
+            )
+            .await
+            .map_err(errors::Folder::ListFiles)?;
+            cache.insert(&file_id, &files);
+            files
+        };
 
         let mut children = Vec::new();
 
         for file in files {
             if drive_file::is_directory(&file) {
-                let folder = Folder::from_file(hub, file, Some(&folder.info)).await?;
+                let folder =
+                    Folder::from_file(hub, file, Some(&folder.info), config, cache).await?;
                 let node = Node::Folder(folder);
                 children.push(node);
             } else if drive_file::is_binary(&file) {
+                let size = file.size.and_then(|size| u64::try_from(size).ok());
+                if !config.filter.matches(size.unwrap_or(0), file.modified_time) {
+                    continue;
+                }
+
                 let f = File::from_file(file, &folder)
                     .map_err(|(source, identifier)| errors::Folder::File { identifier, source })?;
                 let node = Node::File(f);
                 children.push(node);
-            } else {
-                // Skip documents
+            } else if config.include_docs {
+                // Documents take no storage, their size is not reported
+                if !config.filter.matches(0, file.modified_time) {
+                    continue;
+                }
+
+                let f = File::from_document(file, &folder)
+                    .map_err(|(source, identifier)| errors::Folder::File { identifier, source })?;
+                children.push(Node::File(f));
             }
         }
 
@@ -134,6 +203,18 @@ impl FolderInfo {
         path.join(&self.name)
     }
 
+    /// Like [`FolderInfo::relative_path`], with the names sanitized to be valid local names.
+    #[must_use]
+    pub fn local_path(&self, policy: SanitizeNames) -> PathBuf {
+        let mut path = PathBuf::new();
+
+        for folder in self.ancestors() {
+            path.push(local_name::sanitize(&folder.name, policy).as_ref());
+        }
+
+        path.join(local_name::sanitize(&self.name, policy).as_ref())
+    }
+
     fn ancestors(&self) -> Vec<Arc<FolderInfo>> {
         let mut folders = iter::successors(self.parent.as_ref(), |folder_info| {
             folder_info.parent.as_ref()
@@ -162,7 +243,11 @@ pub struct File {
     pub size: u64,
     pub parent: Arc<FolderInfo>,
     pub drive_id: String,
-    pub md5: Option<md5::Digest>,
+    pub checksum: Option<Checksum>,
+    pub md5_checksum: Option<String>,
+    pub modified_time: Option<DateTime<Utc>>,
+    /// Google Docs files can only be exported
+    pub downloadable: bool,
 }
 
 impl File {
@@ -170,6 +255,7 @@ impl File {
         file: google_drive3::api::File,
         parent: &Folder,
     ) -> Result<File, (errors::File, FileIdentifier)> {
+        let checksum = Checksum::from_drive_file(&file);
         let name = file
             .name
             .ok_or((errors::File::MissingFileName, FileIdentifier::None))?;
@@ -188,23 +274,57 @@ impl File {
         let Some(file_id) = file.id else {
             return Err((errors::File::MissingFileId, FileIdentifier::Name(name)));
         };
-        let md5 = file.md5_checksum.as_deref().and_then(parse_md5_digest);
 
         let file = File {
             name,
             size,
             parent: Arc::clone(&parent.info),
             drive_id: file_id,
-            md5,
+            checksum,
+            md5_checksum: file.md5_checksum,
+            modified_time: file.modified_time,
+            downloadable: true,
         };
 
         Ok(file)
     }
 
+    /// Creates the entry of a Google Docs file, which has neither a size nor a checksum.
+    pub fn from_document(
+        file: google_drive3::api::File,
+        parent: &Folder,
+    ) -> Result<File, (errors::File, FileIdentifier)> {
+        let name = file
+            .name
+            .ok_or((errors::File::MissingFileName, FileIdentifier::None))?;
+        let Some(file_id) = file.id else {
+            return Err((errors::File::MissingFileId, FileIdentifier::Name(name)));
+        };
+
+        Ok(File {
+            name,
+            size: 0,
+            parent: Arc::clone(&parent.info),
+            drive_id: file_id,
+            checksum: None,
+            md5_checksum: None,
+            modified_time: file.modified_time,
+            downloadable: false,
+        })
+    }
+
     #[must_use]
     pub fn relative_path(&self) -> PathBuf {
         self.parent.relative_path().join(&self.name)
     }
+
+    /// Like [`File::relative_path`], with the names sanitized to be valid local names.
+    #[must_use]
+    pub fn local_path(&self, policy: SanitizeNames) -> PathBuf {
+        self.parent
+            .local_path(policy)
+            .join(local_name::sanitize(&self.name, policy).as_ref())
+    }
 }
 
 impl FileLike for File {
@@ -229,21 +349,25 @@ mod tests {
             name: "a".to_string(),
             parent: None,
             drive_id: "a".to_string(),
+            shared_drive_id: None,
         });
         let folder_b = Arc::new(FolderInfo {
             name: "b".to_string(),
             parent: Some(Arc::clone(&folder_a)),
             drive_id: "b".to_string(),
+            shared_drive_id: None,
         });
         let folder_c = Arc::new(FolderInfo {
             name: "c".to_string(),
             parent: Some(Arc::clone(&folder_b)),
             drive_id: "c".to_string(),
+            shared_drive_id: None,
         });
         let folder_d = Arc::new(FolderInfo {
             name: "d".to_string(),
             parent: Some(Arc::clone(&folder_c)),
             drive_id: "d".to_string(),
+            shared_drive_id: None,
         });
 
         let ancestors = folder_d.ancestors();
@@ -0,0 +1,288 @@
+//! Listings of the folders walked by [`FileTreeDrive`](super::FileTreeDrive), kept on disk so
+//! that walking the same tree again, i.e. comparing it then downloading it, costs a single
+//! request.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use google_drive3::chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_config::{AppConfig, EnvCredentials},
+    hub::Hub,
+};
+
+const CACHE_DIR_NAME: &str = "tree_cache";
+
+/// Caches of the trees not walked for this long are removed when another one is opened.
+const MAX_CACHE_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The listings of the folders below a root folder. A disabled cache never returns anything and
+/// is never written.
+pub struct ListingCache {
+    path: Option<PathBuf>,
+    entry: Entry,
+    changed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Modified time of the root folder when it was listed
+    root_modified_time: Option<DateTime<Utc>>,
+    /// Page token of the changes of the drive taken before listing, any later change drops the
+    /// listings
+    #[serde(default)]
+    changes_token: Option<String>,
+    saved_at: DateTime<Utc>,
+    /// Children of each folder, keyed by folder id
+    listings: HashMap<String, Vec<google_drive3::api::File>>,
+}
+
+impl ListingCache {
+    #[must_use]
+    pub fn disabled() -> ListingCache {
+        ListingCache {
+            path: None,
+            entry: Entry::new(None, None),
+            changed: false,
+        }
+    }
+
+    /// Opens the cache of the tree below `root` for the current account. The cached listings are
+    /// dropped when they are older than `ttl`, when the modified time of the root changed, or when
+    /// the changes of the drive report anything since they were listed, including moved, trashed
+    /// and deleted files. The cache is disabled for credentials given through the environment,
+    /// which have no account.
+    pub async fn open(hub: &Hub, root: &google_drive3::api::File, ttl: Duration) -> ListingCache {
+        let Some(path) = cache_path(root) else {
+            return ListingCache::disabled();
+        };
+        if let Some(dir) = path.parent() {
+            prune(dir, MAX_CACHE_AGE);
+        }
+
+        let cached = load_entry(&path).filter(|entry| entry.is_fresh(root.modified_time, ttl));
+        let entry = match cached {
+            Some(entry) if !changed_since(hub, root, entry.changes_token.as_deref()).await => entry,
+            _ => Entry::new(root.modified_time, changes_token(hub, root).await),
+        };
+        tracing::debug!(
+            path = %path.display(),
+            folders = entry.listings.len(),
+            "opened folder listing cache"
+        );
+
+        ListingCache {
+            path: Some(path),
+            entry,
+            changed: false,
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, folder_id: &str) -> Option<Vec<google_drive3::api::File>> {
+        self.entry.listings.get(folder_id).cloned()
+    }
+
+    pub fn insert(&mut self, folder_id: &str, files: &[google_drive3::api::File]) {
+        if self.path.is_some() {
+            self.entry.insert(folder_id, files);
+            self.changed = true;
+        }
+    }
+
+    /// Writes the listings back, if any was added.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.changed {
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_vec(&self.entry).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+impl Entry {
+    fn new(root_modified_time: Option<DateTime<Utc>>, changes_token: Option<String>) -> Entry {
+        Entry {
+            root_modified_time,
+            changes_token,
+            saved_at: Utc::now(),
+            listings: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, folder_id: &str, files: &[google_drive3::api::File]) {
+        self.listings.insert(folder_id.to_owned(), files.to_vec());
+    }
+
+    fn is_fresh(&self, root_modified_time: Option<DateTime<Utc>>, ttl: Duration) -> bool {
+        let age = (Utc::now() - self.saved_at).to_std().unwrap_or_default();
+        self.root_modified_time == root_modified_time && age < ttl
+    }
+}
+
+fn cache_path(root: &google_drive3::api::File) -> Option<PathBuf> {
+    if !matches!(EnvCredentials::load(), Ok(None)) {
+        return None;
+    }
+
+    let app_cfg = AppConfig::load_current_account().ok()?;
+    let root_id = root.id.as_deref()?;
+    Some(
+        app_cfg
+            .account_base_path()
+            .join(CACHE_DIR_NAME)
+            .join(format!("{root_id}.json")),
+    )
+}
+
+/// The current page token of the changes of the drive of `root`. Without it, the listings are
+/// dropped the next time.
+async fn changes_token(hub: &Hub, root: &google_drive3::api::File) -> Option<String> {
+    let mut call = hub
+        .changes()
+        .get_start_page_token()
+        .supports_all_drives(true)
+        .add_scope(hub.metadata_scope());
+    if let Some(drive_id) = &root.drive_id {
+        call = call.drive_id(drive_id);
+    }
+
+    match call.doit().await {
+        Ok((_, start)) => start.start_page_token,
+        Err(err) => {
+            tracing::debug!(error = %err, "unable to get the changes page token");
+            None
+        }
+    }
+}
+
+/// Whether anything changed on the drive of `root` since `token`, which Drive tells with a single
+/// request. Changes deeper in the tree do not change the modified time of the root, and deleted
+/// files have no modified time to look for. Any doubt, i.e. a failed request, counts as a change.
+async fn changed_since(hub: &Hub, root: &google_drive3::api::File, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+
+    let mut call = hub
+        .changes()
+        .list(token)
+        .page_size(1)
+        .include_removed(true)
+        .supports_all_drives(true)
+        .include_items_from_all_drives(true)
+        .add_scope(hub.metadata_scope());
+    if let Some(drive_id) = &root.drive_id {
+        call = call.drive_id(drive_id);
+    }
+
+    match call.doit().await {
+        Ok((_, changes)) => {
+            changes.changes.is_some_and(|changes| !changes.is_empty())
+                || changes.next_page_token.is_some()
+        }
+        Err(err) => {
+            tracing::debug!(error = %err, "unable to check the cached listings, dropping them");
+            true
+        }
+    }
+}
+
+/// Removes the cache files not written for `max_age`. Failures only leave them around.
+fn prune(dir: &Path, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let now = SystemTime::now();
+    for entry in entries.filter_map(Result::ok) {
+        let is_old = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= max_age);
+        if is_old {
+            tracing::debug!(path = %entry.path().display(), "removing old folder listing cache");
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// A missing or unreadable cache is the same as an empty one.
+fn load_entry(path: &Path) -> Option<Entry> {
+    let content = fs::read(path).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    use google_drive3::chrono::{TimeZone, Utc};
+
+    use super::{prune, Entry};
+
+    #[test]
+    fn stale_entries() {
+        let modified = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).single();
+        let ttl = Duration::from_secs(600);
+        let mut entry = Entry::new(modified, Some(String::from("42")));
+
+        assert!(entry.is_fresh(modified, ttl));
+        assert!(!entry.is_fresh(None, ttl));
+        assert!(!entry.is_fresh(Utc.with_ymd_and_hms(2024, 5, 2, 0, 0, 0).single(), ttl));
+
+        entry.saved_at -= google_drive3::chrono::Duration::seconds(601);
+        assert!(!entry.is_fresh(modified, ttl));
+    }
+
+    #[test]
+    fn entries_without_changes_token() {
+        // Written before the changes were checked, they are dropped on the next walk
+        let entry: Entry = serde_json::from_str(
+            r#"{"root_modified_time": null, "newest_modified_time": null,
+            "saved_at": "2024-05-01T12:00:00Z", "listings": {}}"#,
+        )
+        .unwrap();
+
+        assert!(entry.changes_token.is_none());
+    }
+
+    #[test]
+    fn prune_old_caches() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.json");
+        let recent = dir.path().join("recent.json");
+        fs::write(&old, "{}").unwrap();
+        fs::write(&recent, "{}").unwrap();
+        let week_ago = SystemTime::now() - Duration::from_secs(8 * 24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(week_ago)
+            .unwrap();
+
+        prune(dir.path(), Duration::from_secs(7 * 24 * 60 * 60));
+
+        assert!(!old.exists());
+        assert!(recent.exists());
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+};
+
+use crate::{files, hub::Hub};
+
+/// Checks that `file` is still at the `expected` version.
+///
+/// Drive v3 does not honor `If-Match` on files, but it increments the `version` field on every
+/// change, which gives the same optimistic concurrency guarantees when checked right before
+/// modifying a file.
+pub fn check(file: &google_drive3::api::File, expected: i64) -> Result<(), Error> {
+    if file.version == Some(expected) {
+        Ok(())
+    } else {
+        Err(Error::Conflict {
+            expected,
+            actual: file.version,
+        })
+    }
+}
+
+/// Fetches the current version of the file and [checks](check) it against `expected`.
+pub async fn ensure(hub: &Hub, file_id: &str, expected: i64) -> Result<(), Error> {
+    let file = files::info::get_file(hub, file_id)
+        .await
+        .map_err(|err| Error::GetFile(Box::new(err)))?;
+    check(&file, expected)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    GetFile(Box<google_drive3::Error>),
+    Conflict { expected: i64, actual: Option<i64> },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::GetFile(_) => f.write_str("unable to get current file version"),
+            Error::Conflict {
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "file has been modified concurrently, expected version {expected} but found \
+                {actual}"
+            ),
+            Error::Conflict {
+                expected,
+                actual: None,
+            } => write!(
+                f,
+                "file has been modified concurrently, expected version {expected} but the file \
+                has no version"
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::GetFile(source) => Some(source),
+            Error::Conflict { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Error};
+
+    #[test]
+    fn check_version() {
+        let file = google_drive3::api::File {
+            version: Some(42),
+            ..Default::default()
+        };
+
+        assert!(check(&file, 42).is_ok());
+        assert!(matches!(
+            check(&file, 41),
+            Err(Error::Conflict {
+                expected: 41,
+                actual: Some(42)
+            })
+        ));
+    }
+}
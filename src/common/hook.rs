@@ -0,0 +1,141 @@
+use std::{env, path::Path};
+
+use tokio::process::Command;
+
+/// Environment variable giving the `--on-complete` command when it is not given on the command
+/// line, i.e. from a shell profile.
+pub const ON_COMPLETE_ENV: &str = "GDRIVE_ON_COMPLETE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    Upload,
+    Download,
+}
+
+impl Transfer {
+    fn as_str(self) -> &'static str {
+        match self {
+            Transfer::Upload => "upload",
+            Transfer::Download => "download",
+        }
+    }
+}
+
+/// A file transferred successfully, described to the hook through environment variables.
+#[derive(Debug, Clone, Copy)]
+pub struct Event<'a> {
+    pub transfer: Transfer,
+    pub file_id: &'a str,
+    /// The local file, where it was read from or written to. `None` for uploads of stdin
+    pub path: Option<&'a Path>,
+    pub md5: Option<&'a str>,
+    pub size: Option<u64>,
+}
+
+/// A shell command run after each file successfully uploaded or downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnComplete {
+    command: String,
+}
+
+impl OnComplete {
+    /// The command given on the command line, falling back to the one in [`ON_COMPLETE_ENV`].
+    #[must_use]
+    pub fn resolve(command: Option<String>) -> Option<OnComplete> {
+        command
+            .or_else(|| env::var(ON_COMPLETE_ENV).ok())
+            .filter(|command| !command.trim().is_empty())
+            .map(|command| OnComplete { command })
+    }
+
+    /// Runs the command and waits for it. The file is already transferred, therefore a failing
+    /// hook is only reported.
+    pub async fn run(&self, event: &Event<'_>) {
+        let mut command = shell_command(&self.command);
+        command.envs(env_vars(event));
+
+        match command.status().await {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                let file = event.path.map_or_else(
+                    || String::from("stdin"),
+                    |path| format!("'{}'", path.display()),
+                );
+                eprintln!("Warning: the --on-complete command exited with {status} for {file}");
+            }
+            Err(err) => eprintln!("Warning: unable to run the --on-complete command: {err}"),
+        }
+    }
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// The variables describing `event`, empty when a value is unknown.
+fn env_vars(event: &Event) -> [(&'static str, String); 5] {
+    [
+        ("GDRIVE_TRANSFER", event.transfer.as_str().to_string()),
+        ("GDRIVE_FILE_ID", event.file_id.to_string()),
+        (
+            "GDRIVE_FILE_PATH",
+            event
+                .path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+        ),
+        ("GDRIVE_FILE_MD5", event.md5.unwrap_or_default().to_string()),
+        (
+            "GDRIVE_FILE_SIZE",
+            event.size.map(|size| size.to_string()).unwrap_or_default(),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{env_vars, Event, Transfer};
+
+    #[test]
+    fn describe_event() {
+        let event = Event {
+            transfer: Transfer::Download,
+            file_id: "abc",
+            path: Some(Path::new("report.pdf")),
+            md5: None,
+            size: Some(42),
+        };
+
+        assert_eq!(
+            env_vars(&event).map(|(_, value)| value),
+            ["download", "abc", "report.pdf", "", "42"]
+        );
+
+        let stdin = Event {
+            transfer: Transfer::Upload,
+            path: None,
+            md5: Some("d41d8cd98f00b204e9800998ecf8427e"),
+            ..event
+        };
+        assert_eq!(
+            env_vars(&stdin).map(|(_, value)| value),
+            [
+                "upload",
+                "abc",
+                "",
+                "d41d8cd98f00b204e9800998ecf8427e",
+                "42"
+            ]
+        );
+    }
+}
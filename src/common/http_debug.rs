@@ -0,0 +1,391 @@
+use std::{
+    error::Error,
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use futures::{future, TryStreamExt};
+use google_drive3::hyper::{
+    self,
+    body::HttpBody,
+    client::{
+        self,
+        connect::{Connected, Connection},
+    },
+    http::uri,
+    server,
+    service::{self, Service},
+    Body, Request, Response, Uri,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf},
+    sync::Mutex,
+};
+
+use crate::common::usage_stats;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Query parameters whose value grants access on its own and must never be logged.
+const SENSITIVE_PARAMS: [&str; 3] = ["access_token", "key", "upload_id"];
+
+/// Size of the in-process pipe between the client and the inspected connection.
+const PIPE_SIZE: usize = 64 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Logs every HTTP exchange of the clients created afterwards.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// What is done with each inspected exchange.
+#[derive(Debug, Clone, Copy)]
+struct Inspection {
+    log: bool,
+    record_usage: bool,
+}
+
+/// A connector logging the request line and the response status of the exchanges on the
+/// connections it opens, when enabled, and counting them in the usage statistics when those are
+/// enabled. Headers are never inspected, therefore tokens are not logged.
+///
+/// Hyper has no hook around the requests of a client, the inspected connections are served in
+/// process instead: the client speaks HTTP/1 to a local service, which inspects each request and
+/// sends it on the actual connection, with HTTP/2 when negotiated. Connections which are not
+/// inspected are handed to the client as they are.
+#[derive(Debug, Clone)]
+pub struct DebugConnector<C> {
+    inner: C,
+    inspection: Option<Inspection>,
+}
+
+impl<C> DebugConnector<C> {
+    pub fn new(inner: C) -> Self {
+        let inspection = Inspection {
+            log: is_enabled(),
+            record_usage: usage_stats::is_enabled(),
+        };
+
+        DebugConnector {
+            inner,
+            inspection: (inspection.log || inspection.record_usage).then_some(inspection),
+        }
+    }
+}
+
+impl<C> Service<Uri> for DebugConnector<C>
+where
+    C: Service<Uri>,
+    C::Response: AsyncRead + AsyncWrite + Connection + Unpin + Send + 'static,
+    C::Error: Into<BoxError>,
+    C::Future: Send + 'static,
+{
+    type Response = DebugStream<C::Response>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let inspection = self.inspection;
+        if inspection.is_some_and(|inspection| inspection.log) {
+            tracing::debug!(host = uri.host().unwrap_or_default(), "connecting");
+        }
+        let connecting = self.inner.call(uri.clone());
+
+        Box::pin(async move {
+            let stream = connecting.await.map_err(Into::into)?;
+            match inspection {
+                Some(inspection) => {
+                    let pipe = inspect_connection(stream, uri, inspection).await?;
+                    Ok(DebugStream::Inspected(pipe))
+                }
+                None => Ok(DebugStream::Direct(stream)),
+            }
+        })
+    }
+}
+
+/// Serves the requests written to the returned pipe, sending them on `stream` after inspecting
+/// them. `origin` is the scheme and the authority the connection was opened to.
+async fn inspect_connection<T>(
+    stream: T,
+    origin: Uri,
+    inspection: Inspection,
+) -> Result<DuplexStream, hyper::Error>
+where
+    T: AsyncRead + AsyncWrite + Connection + Unpin + Send + 'static,
+{
+    let http2 = stream.connected().is_negotiated_h2();
+    let (sender, connection) = client::conn::Builder::new()
+        .http2_only(http2)
+        .handshake(stream)
+        .await?;
+    let sender = Arc::new(Mutex::new(sender));
+
+    let (pipe, local_end) = tokio::io::duplex(PIPE_SIZE);
+    let local_connection = server::conn::Http::new().http1_only(true).serve_connection(
+        local_end,
+        service::service_fn(move |request| {
+            let target = http2.then(|| origin.clone());
+            forward(Arc::clone(&sender), target, inspection, request)
+        }),
+    );
+
+    tokio::spawn(async move {
+        // Whichever side ends closes the other one, the client then knows the connection is gone
+        tokio::select! {
+            result = connection => if let Err(err) = result {
+                tracing::debug!(error = %err, "connection closed");
+            },
+            _ = local_connection => {},
+        }
+    });
+
+    Ok(pipe)
+}
+
+/// Sends `request` on the actual connection. HTTP/2 requests need the `origin` of the connection,
+/// HTTP/1 ones keep the target they were written with.
+async fn forward(
+    sender: Arc<Mutex<client::conn::SendRequest<Body>>>,
+    origin: Option<Uri>,
+    inspection: Inspection,
+    request: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    let (mut parts, body) = request.into_parts();
+    let method = parts.method.to_string();
+    let target = parts
+        .uri
+        .path_and_query()
+        .map_or("/", uri::PathAndQuery::as_str)
+        .to_owned();
+
+    let request_line = format!("{method} {}", sanitize_target(&target));
+    if inspection.log {
+        tracing::debug!(request = request_line, "request sent");
+    }
+    if inspection.record_usage {
+        usage_stats::record_request(&method, &target);
+    }
+
+    if let Some(origin) = origin {
+        let mut uri_parts = origin.into_parts();
+        uri_parts.path_and_query = parts.uri.path_and_query().cloned();
+        parts.uri = Uri::from_parts(uri_parts).unwrap_or(parts.uri);
+    }
+    let body = if inspection.record_usage {
+        count_bytes(body, usage_stats::record_bytes_sent)
+    } else {
+        body
+    };
+
+    let sent_at = Instant::now();
+    let sending = {
+        let mut sender = sender.lock().await;
+        future::poll_fn(|cx| sender.poll_ready(cx)).await?;
+        sender.send_request(Request::from_parts(parts, body))
+    };
+    let response = match sending.await {
+        Ok(response) => response,
+        Err(err) => {
+            if inspection.log {
+                tracing::debug!(request = request_line, error = %err, "request failed");
+            }
+            return Err(err);
+        }
+    };
+
+    if inspection.log {
+        tracing::debug!(
+            request = request_line,
+            status = response.status().as_u16(),
+            elapsed = ?sent_at.elapsed(),
+            "response received"
+        );
+    }
+    if !inspection.record_usage {
+        return Ok(response);
+    }
+    let (parts, body) = response.into_parts();
+    Ok(Response::from_parts(
+        parts,
+        count_bytes(body, usage_stats::record_bytes_received),
+    ))
+}
+
+/// Passes the length of each chunk of `body` to `record`. Empty bodies are kept as they are, so
+/// that they are still sent without one.
+fn count_bytes(body: Body, record: fn(usize)) -> Body {
+    if body.is_end_stream() {
+        return body;
+    }
+    Body::wrap_stream(body.inspect_ok(move |chunk| record(chunk.len())))
+}
+
+pub enum DebugStream<T> {
+    Direct(T),
+    /// Pipe to the local service inspecting the exchanges
+    Inspected(DuplexStream),
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for DebugStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            DebugStream::Direct(stream) => Pin::new(stream).poll_read(cx, buf),
+            DebugStream::Inspected(pipe) => Pin::new(pipe).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for DebugStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            DebugStream::Direct(stream) => Pin::new(stream).poll_write(cx, buf),
+            DebugStream::Inspected(pipe) => Pin::new(pipe).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            DebugStream::Direct(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            DebugStream::Inspected(pipe) => Pin::new(pipe).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            DebugStream::Direct(stream) => stream.is_write_vectored(),
+            DebugStream::Inspected(pipe) => pipe.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            DebugStream::Direct(stream) => Pin::new(stream).poll_flush(cx),
+            DebugStream::Inspected(pipe) => Pin::new(pipe).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            DebugStream::Direct(stream) => Pin::new(stream).poll_shutdown(cx),
+            DebugStream::Inspected(pipe) => Pin::new(pipe).poll_shutdown(cx),
+        }
+    }
+}
+
+impl<T: Connection> Connection for DebugStream<T> {
+    fn connected(&self) -> Connected {
+        match self {
+            DebugStream::Direct(stream) => stream.connected(),
+            // The client always speaks HTTP/1 to the local service
+            DebugStream::Inspected(_) => Connected::new(),
+        }
+    }
+}
+
+/// Replaces the values of the sensitive query parameters of a request target.
+fn sanitize_target(target: &str) -> String {
+    let Some((path, query)) = target.split_once('?') else {
+        return target.to_string();
+    };
+
+    let query = query
+        .split('&')
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if SENSITIVE_PARAMS.contains(&name) => format!("{name}=REDACTED"),
+            _ => param.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{path}?{query}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, net::SocketAddr};
+
+    use google_drive3::hyper::{
+        self, client::HttpConnector, service, Body, Method, Request, Response,
+    };
+
+    use super::{sanitize_target, DebugConnector, Inspection};
+
+    #[tokio::test]
+    async fn inspected_exchanges_reach_the_server() {
+        let server = hyper::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(
+            service::make_service_fn(|_| async {
+                Ok::<_, Infallible>(service::service_fn(|request: Request<Body>| async move {
+                    let target = request.uri().to_string();
+                    let body = hyper::body::to_bytes(request.into_body()).await?;
+                    Ok::<_, hyper::Error>(Response::new(Body::from(format!(
+                        "{target} {}",
+                        String::from_utf8_lossy(&body)
+                    ))))
+                }))
+            }),
+        );
+        let address = server.local_addr();
+        tokio::spawn(server);
+
+        let connector = DebugConnector {
+            inner: HttpConnector::new(),
+            inspection: Some(Inspection {
+                log: true,
+                record_usage: false,
+            }),
+        };
+        let client = hyper::Client::builder().build::<_, Body>(connector);
+
+        // The second request reuses the inspected connection
+        for (method, body) in [(Method::GET, ""), (Method::POST, "content")] {
+            let request = Request::builder()
+                .method(method)
+                .uri(format!("http://{address}/drive/v3/files?q=x"))
+                .body(Body::from(body))
+                .unwrap();
+            let response = client.request(request).await.unwrap();
+            let received = hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+            assert_eq!(received, format!("/drive/v3/files?q=x {body}"));
+        }
+    }
+
+    #[test]
+    fn sanitize_sensitive_params() {
+        assert_eq!(
+            sanitize_target("/upload/drive/v3/files?uploadType=resumable&upload_id=secret"),
+            "/upload/drive/v3/files?uploadType=resumable&upload_id=REDACTED"
+        );
+        assert_eq!(sanitize_target("/drive/v3/about"), "/drive/v3/about");
+    }
+}
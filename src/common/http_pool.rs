@@ -0,0 +1,140 @@
+//! Tuning of the connection pool of the HTTP client through the environment. Big recursive jobs
+//! make thousands of small requests, which are faster when they reuse the open connections instead
+//! of opening new ones.
+
+use std::{
+    env, error,
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+pub const MAX_IDLE_ENV: &str = "GDRIVE_POOL_MAX_IDLE";
+pub const IDLE_TIMEOUT_ENV: &str = "GDRIVE_POOL_IDLE_TIMEOUT";
+pub const HTTP2_KEEPALIVE_ENV: &str = "GDRIVE_HTTP2_KEEPALIVE";
+
+const DEFAULT_MAX_IDLE: usize = 32;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Maximum number of idle connections kept open to each host
+    pub max_idle_per_host: usize,
+    /// Idle connections are closed after this delay, never when `None`
+    pub idle_timeout: Option<Duration>,
+    /// Interval of the pings keeping HTTP/2 connections alive, even when idle. No pings are sent
+    /// when `None`
+    pub http2_keep_alive: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_idle_per_host: DEFAULT_MAX_IDLE,
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            http2_keep_alive: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Reads the settings given through the environment, the others keep their default. Unset and
+    /// empty variables fall back to the default.
+    pub fn from_env() -> Result<PoolConfig, InvalidValue> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<PoolConfig, InvalidValue> {
+        let default = PoolConfig::default();
+        let parse = |name: &'static str| -> Result<Option<u64>, InvalidValue> {
+            let Some(value) = var(name).filter(|value| !value.trim().is_empty()) else {
+                return Ok(None);
+            };
+            value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| InvalidValue { name, value })
+        };
+
+        let max_idle_per_host = match parse(MAX_IDLE_ENV)? {
+            Some(max_idle) => usize::try_from(max_idle).unwrap_or(usize::MAX),
+            None => default.max_idle_per_host,
+        };
+        // A zero delay never closes, or never pings, the connections
+        let idle_timeout = match parse(IDLE_TIMEOUT_ENV)? {
+            Some(secs) => seconds(secs),
+            None => default.idle_timeout,
+        };
+        let http2_keep_alive = match parse(HTTP2_KEEPALIVE_ENV)? {
+            Some(secs) => seconds(secs),
+            None => default.http2_keep_alive,
+        };
+
+        Ok(PoolConfig {
+            max_idle_per_host,
+            idle_timeout,
+            http2_keep_alive,
+        })
+    }
+}
+
+fn seconds(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidValue {
+    pub name: &'static str,
+    pub value: String,
+}
+
+impl Display for InvalidValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} must be a whole number, '{}' is not",
+            self.name, self.value
+        )
+    }
+}
+
+impl error::Error for InvalidValue {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{InvalidValue, PoolConfig, HTTP2_KEEPALIVE_ENV, IDLE_TIMEOUT_ENV, MAX_IDLE_ENV};
+
+    #[test]
+    fn pool_config_from_vars() {
+        assert_eq!(
+            PoolConfig::from_vars(|_| None).unwrap(),
+            PoolConfig::default()
+        );
+
+        let vars = |name: &str| match name {
+            MAX_IDLE_ENV => Some(String::from(" 8 ")),
+            IDLE_TIMEOUT_ENV => Some(String::from("0")),
+            HTTP2_KEEPALIVE_ENV => Some(String::from("20")),
+            _ => None,
+        };
+        assert_eq!(
+            PoolConfig::from_vars(vars).unwrap(),
+            PoolConfig {
+                max_idle_per_host: 8,
+                idle_timeout: None,
+                http2_keep_alive: Some(Duration::from_secs(20)),
+            }
+        );
+
+        let vars = |name: &str| (name == IDLE_TIMEOUT_ENV).then(|| String::from("1m"));
+        assert_eq!(
+            PoolConfig::from_vars(vars),
+            Err(InvalidValue {
+                name: IDLE_TIMEOUT_ENV,
+                value: String::from("1m"),
+            })
+        );
+    }
+}
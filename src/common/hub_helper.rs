@@ -4,17 +4,88 @@ use std::{
     io,
 };
 
+use google_drive3::api::Scope;
+
 use crate::{
-    app_config::{self, AppConfig},
-    hub::{Auth, Hub},
+    app_config::{self, AppConfig, EnvCredentials},
+    hub::{self, Auth, Hub},
 };
 
+/// Gets the hub of the current account, unless credentials are given through the environment.
 pub async fn get_hub() -> Result<Hub, GetHubError> {
+    get_current_hub(false).await
+}
+
+/// Like [`get_hub`], for the commands that only read metadata. Their calls request the read-only
+/// metadata scope when the stored token of the account was granted it.
+///
+/// This does not narrow the access of the token sent with them: `account add` gets a single token
+/// for both scopes, which is handed out for either of them.
+pub async fn get_metadata_hub() -> Result<Hub, GetHubError> {
+    get_current_hub(true).await
+}
+
+async fn get_current_hub(metadata_only: bool) -> Result<Hub, GetHubError> {
+    if let Some(credentials) = EnvCredentials::load().map_err(GetHubError::EnvCredentials)? {
+        tracing::debug!("using credentials from the environment");
+        let auth = Auth::from_env_credentials(&credentials)
+            .await
+            .map_err(GetHubError::Auth)?;
+        return Hub::new(auth).map_err(GetHubError::Hub);
+    }
+
     let app_cfg = AppConfig::load_current_account().map_err(GetHubError::LoadCurrentAccount)?;
-    let secret = app_cfg.load_secret().map_err(GetHubError::LoadSecret)?;
-    let auth = Auth::new(&secret, app_cfg.tokens_path())
-        .await
-        .map_err(GetHubError::Auth)?;
+    tracing::debug!(account = app_cfg.account.name, "using the current account");
+    let hub = get_account_hub(&app_cfg).await?;
+
+    Ok(if metadata_only {
+        hub.with_metadata_scope(metadata_scope(&app_cfg))
+    } else {
+        hub
+    })
+}
+
+pub async fn get_hub_by_account_name(account_name: &str) -> Result<Hub, GetHubError> {
+    let app_cfg = AppConfig::load_account(account_name).map_err(GetHubError::LoadAccount)?;
+    get_account_hub(&app_cfg).await
+}
+
+/// Like [`get_hub_by_account_name`], for the commands that only read metadata.
+pub async fn get_metadata_hub_by_account_name(account_name: &str) -> Result<Hub, GetHubError> {
+    let app_cfg = AppConfig::load_account(account_name).map_err(GetHubError::LoadAccount)?;
+    let hub = get_account_hub(&app_cfg).await?;
+    Ok(hub.with_metadata_scope(metadata_scope(&app_cfg)))
+}
+
+/// The read-only metadata scope if a stored token of the account covers it. Requesting a scope
+/// no stored token covers would start the authorization flow again, instead full access is kept.
+fn metadata_scope(app_cfg: &AppConfig) -> Scope {
+    // The tokens of application default credentials are requested for the scopes of each call,
+    // they never cover a scope in advance
+    if app_cfg.uses_application_default_credentials() {
+        return Scope::Full;
+    }
+
+    let granted = hub::stored_token_scopes(app_cfg.tokens_path()).is_ok_and(|scopes| {
+        scopes
+            .iter()
+            .any(|scope| scope == Scope::MetadataReadonly.as_ref())
+    });
+    if granted {
+        Scope::MetadataReadonly
+    } else {
+        Scope::Full
+    }
+}
+
+pub async fn get_account_hub(app_cfg: &AppConfig) -> Result<Hub, GetHubError> {
+    let auth = if app_cfg.uses_application_default_credentials() {
+        Auth::application_default(app_cfg.tokens_path()).await
+    } else {
+        let secret = app_cfg.load_secret().map_err(GetHubError::LoadSecret)?;
+        Auth::new(&secret, app_cfg.tokens_path()).await
+    }
+    .map_err(GetHubError::Auth)?;
 
     let hub = Hub::new(auth).map_err(GetHubError::Hub)?;
 
@@ -24,7 +95,9 @@ pub async fn get_hub() -> Result<Hub, GetHubError> {
 #[derive(Debug)]
 pub enum GetHubError {
     LoadCurrentAccount(app_config::errors::LoadCurrentAccount),
+    LoadAccount(app_config::errors::LoadAccount),
     LoadSecret(app_config::errors::LoadSecret),
+    EnvCredentials(app_config::errors::LoadEnvCredentials),
     Auth(io::Error),
     Hub(io::Error),
 }
@@ -33,7 +106,9 @@ impl Error for GetHubError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             GetHubError::LoadCurrentAccount(source) => Some(source),
+            GetHubError::LoadAccount(source) => Some(source),
             GetHubError::LoadSecret(source) => Some(source),
+            GetHubError::EnvCredentials(source) => Some(source),
             GetHubError::Hub(source) | GetHubError::Auth(source) => Some(source),
         }
     }
@@ -43,7 +118,9 @@ impl Display for GetHubError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let s = match self {
             GetHubError::LoadCurrentAccount(_) => "unable to load current account",
+            GetHubError::LoadAccount(_) => "unable to load account",
             GetHubError::LoadSecret(_) => "unable to load secret",
+            GetHubError::EnvCredentials(_) => "unable to load credentials from the environment",
             GetHubError::Auth(_) => "unable to authenticate",
             GetHubError::Hub(_) => "unable to create Google Drive hub",
         };
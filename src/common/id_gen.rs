@@ -6,26 +6,43 @@ use std::{
 use crate::{common::delegate::UploadDelegateConfig, files::generate_ids, hub::Hub};
 
 pub struct IdGen<'a> {
-    hub: &'a Hub,
-    delegate_config: &'a UploadDelegateConfig,
+    /// Generates the ids through Drive, local ids are given out when `None`
+    drive: Option<(&'a Hub, &'a UploadDelegateConfig)>,
     ids: Vec<String>,
+    generated: usize,
 }
 
 impl<'a> IdGen<'a> {
     #[must_use]
     pub fn new(hub: &'a Hub, delegate_config: &'a UploadDelegateConfig) -> Self {
         Self {
-            hub,
-            delegate_config,
+            drive: Some((hub, delegate_config)),
             ids: Vec::new(),
+            generated: 0,
+        }
+    }
+
+    /// Ids for walks of local trees which are never uploaded, i.e. to compare them with Drive.
+    /// They are unique but are not valid Drive ids.
+    #[must_use]
+    pub fn offline() -> IdGen<'static> {
+        IdGen {
+            drive: None,
+            ids: Vec::new(),
+            generated: 0,
         }
     }
 
     pub async fn next(&mut self) -> Result<String, NextError> {
+        let Some((hub, delegate_config)) = self.drive else {
+            self.generated += 1;
+            return Ok(format!("local-{}", self.generated));
+        };
+
         if let Some(id) = self.ids.pop() {
             Ok(id)
         } else {
-            self.ids = generate_ids::generate_ids(self.hub, 1000, self.delegate_config)
+            self.ids = generate_ids::generate_ids(hub, 1000, delegate_config)
                 .await
                 .map_err(|err| NextError::GenerateIds(Box::new(err)))?;
             let id = self.ids.pop().ok_or(NextError::OutOfIds)?;
@@ -0,0 +1,122 @@
+use std::{borrow::Cow, path::PathBuf};
+
+use clap::ValueEnum;
+
+/// Names Windows reserves for devices, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// When the names of Drive files are changed to be valid local file names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SanitizeNames {
+    /// Only on Windows
+    #[default]
+    Auto,
+    /// On every platform, i.e. to copy the files to a Windows machine later on
+    Always,
+    /// Keep the names as they are on Drive
+    Never,
+}
+
+impl SanitizeNames {
+    fn is_enabled(self) -> bool {
+        match self {
+            SanitizeNames::Auto => cfg!(windows),
+            SanitizeNames::Always => true,
+            SanitizeNames::Never => false,
+        }
+    }
+}
+
+/// Turns the name of a Drive file into a name Windows accepts: forbidden characters and trailing
+/// dots and spaces, which Windows drops, are replaced by `_`, and reserved device names like `CON`
+/// or `nul.txt` get a `_` appended to their stem. Valid names are returned as they are.
+#[must_use]
+pub fn sanitize(name: &str, policy: SanitizeNames) -> Cow<'_, str> {
+    if !policy.is_enabled() {
+        return Cow::Borrowed(name);
+    }
+    if name.is_empty() {
+        return Cow::Borrowed("_");
+    }
+
+    let trimmed_len = name.trim_end_matches(['.', ' ']).len();
+    let mut sanitized: String = name
+        .char_indices()
+        .map(|(index, c)| {
+            if is_forbidden_char(c) || index >= trimmed_len {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let stem_len = sanitized.find('.').unwrap_or(sanitized.len());
+    let stem = sanitized[..stem_len].trim_end();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        sanitized.insert(stem_len, '_');
+    }
+
+    if sanitized == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(sanitized)
+    }
+}
+
+fn is_forbidden_char(c: char) -> bool {
+    c.is_ascii_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+}
+
+/// Prefixes an absolute path with `\\?\` on Windows, lifting the limit of 260 characters for the
+/// paths below it. Other paths are returned as they are.
+#[cfg(windows)]
+#[must_use]
+pub fn long_path(path: PathBuf) -> PathBuf {
+    let Some(path_str) = path.to_str() else {
+        return path;
+    };
+    if !path.is_absolute() || path_str.starts_with(r"\\?\") {
+        return path;
+    }
+
+    // Verbatim paths are not normalized, they only accept backslashes
+    let path_str = path_str.replace('/', r"\");
+    match path_str.strip_prefix(r"\\") {
+        Some(share) => PathBuf::from(format!(r"\\?\UNC\{share}")),
+        None => PathBuf::from(format!(r"\\?\{path_str}")),
+    }
+}
+
+/// Prefixes an absolute path with `\\?\` on Windows, elsewhere paths have no such limit.
+#[cfg(not(windows))]
+#[must_use]
+pub fn long_path(path: PathBuf) -> PathBuf {
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sanitize, SanitizeNames};
+
+    #[test]
+    fn sanitize_windows_names() {
+        let sanitize = |name| sanitize(name, SanitizeNames::Always).into_owned();
+
+        assert_eq!(sanitize("report.pdf"), "report.pdf");
+        assert_eq!(sanitize("a/b: c?.txt"), "a_b_ c_.txt");
+        assert_eq!(sanitize("notes. "), "notes__");
+        assert_eq!(sanitize(".."), "__");
+        assert_eq!(sanitize("CON"), "CON_");
+        assert_eq!(sanitize("nul.tar.gz"), "nul_.tar.gz");
+        assert_eq!(sanitize("console.log"), "console.log");
+        assert_eq!(sanitize(""), "_");
+        assert_eq!(super::sanitize("CON", SanitizeNames::Never), "CON");
+    }
+}
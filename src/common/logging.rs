@@ -0,0 +1,122 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    fs::OpenOptions,
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use clap::ValueEnum;
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+};
+
+/// Only the events of gdrive are logged, the ones of the libraries are too noisy.
+const LOG_TARGET: &str = "gdrive";
+
+/// Modules logging HTTP exchanges and retries, enabled at debug level by `--debug-http`.
+const HTTP_TARGETS: [&str; 4] = [
+    "gdrive::common::http_debug",
+    "gdrive::common::delegate",
+    "gdrive::common::bulk",
+    "gdrive::files::update::stream",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Enables logging, which is off unless a level, a file or `debug_http` is given.
+///
+/// Logs are written to stderr as text, or appended to `file` as one JSON object per line. When
+/// only the file is given, the level defaults to info. With `debug_http`, the HTTP exchanges and
+/// retries are logged at debug level whatever the level of the other events.
+pub fn init(
+    level: Option<LogLevel>,
+    file: Option<&Path>,
+    debug_http: bool,
+) -> Result<(), InitError> {
+    let level = level.unwrap_or(if file.is_some() {
+        LogLevel::Info
+    } else {
+        LogLevel::Off
+    });
+    if level == LogLevel::Off && !debug_http {
+        return Ok(());
+    }
+
+    let mut targets = Targets::new().with_target(LOG_TARGET, LevelFilter::from(level));
+    if debug_http {
+        for target in HTTP_TARGETS {
+            targets = targets.with_target(target, LevelFilter::from(level).max(LevelFilter::DEBUG));
+        }
+    }
+    let registry = tracing_subscriber::registry().with(targets);
+
+    match file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|source| InitError {
+                    path: path.to_owned(),
+                    source,
+                })?;
+
+            registry
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .json()
+                        .with_ansi(false)
+                        .with_writer(Mutex::new(file)),
+                )
+                .init();
+        }
+        None => registry
+            .with(tracing_subscriber::fmt::layer().with_writer(io::stderr))
+            .init(),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct InitError {
+    path: PathBuf,
+    source: io::Error,
+}
+
+impl Display for InitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "unable to open log file '{}'", self.path.display())
+    }
+}
+
+impl error::Error for InitError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
@@ -0,0 +1,149 @@
+//! Names with date tokens expanded client-side, so that periodic jobs get a new name or folder on
+//! each run: `backups/{date}`, `dump-{datetime}` or `{date:%Y/%m}`. Other braces are kept as they
+//! are, i.e. `notes {draft}`, and a token is written literally with doubled braces: `{{date}}`.
+//! Doubled braces are only collapsed in names with a token, escaped or not, so that `a}}b` stays
+//! the same.
+
+use std::{
+    error,
+    fmt::{self, Display, Formatter, Write},
+    sync::OnceLock,
+};
+
+use google_drive3::chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local,
+};
+
+/// Expands the tokens with the time of the first expansion of the run, so that all the names of a
+/// run refer to the same instant, even around midnight.
+pub fn expand(template: &str) -> Result<String, InvalidTemplate> {
+    static NOW: OnceLock<DateTime<Local>> = OnceLock::new();
+    expand_at(template, NOW.get_or_init(Local::now))
+}
+
+/// Replaces `{date}`, `{time}` and `{datetime}` with the local time `now`. A format given after a
+/// colon, i.e. `{date:%d.%m.%Y}`, replaces the default one of the token. Unknown tokens and
+/// unmatched braces are kept, so that names which are not templates stay the same.
+pub fn expand_at(template: &str, now: &DateTime<Local>) -> Result<String, InvalidTemplate> {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    let mut has_token = false;
+
+    while let Some(index) = rest.find(['{', '}']) {
+        expanded.push_str(&rest[..index]);
+        let brace = &rest[index..=index];
+        rest = &rest[index + 1..];
+
+        if let Some(after) = rest.strip_prefix(brace) {
+            has_token |= parse_token(brace, after).is_some();
+            expanded.push_str(brace);
+            rest = after;
+            continue;
+        }
+
+        let Some((token, format)) = parse_token(brace, rest) else {
+            // Not a token, the brace is part of the name
+            expanded.push_str(brace);
+            continue;
+        };
+        rest = &rest[token.len() + 1..];
+        has_token = true;
+
+        if format.is_empty() || StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(InvalidTemplate {
+                format: format.to_string(),
+            });
+        }
+        // Checked above, formatting cannot fail
+        let _ = write!(expanded, "{}", now.format(format));
+    }
+    expanded.push_str(rest);
+
+    if has_token {
+        Ok(expanded)
+    } else {
+        Ok(template.to_owned())
+    }
+}
+
+/// Parses the token following the opening `brace`, up to the closing one. Returns the token and
+/// its format, or `None` when there is no known token.
+fn parse_token<'a>(brace: &str, rest: &'a str) -> Option<(&'a str, &'a str)> {
+    if brace != "{" {
+        return None;
+    }
+    let token = &rest[..rest.find('}')?];
+
+    let (name, format) = match token.split_once(':') {
+        Some((name, format)) => (name, Some(format)),
+        None => (token, None),
+    };
+    let default_format = match name {
+        "date" => "%Y-%m-%d",
+        "time" => "%H-%M-%S",
+        "datetime" => "%Y-%m-%d_%H-%M-%S",
+        _ => return None,
+    };
+    Some((token, format.unwrap_or(default_format)))
+}
+
+/// A date token of a name with an invalid format, i.e. `{date:%Q}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTemplate {
+    pub format: String,
+}
+
+impl Display for InvalidTemplate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a valid strftime format", self.format)
+    }
+}
+
+impl error::Error for InvalidTemplate {}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::chrono::{Local, TimeZone};
+
+    use super::{expand_at, InvalidTemplate};
+
+    #[test]
+    fn expand_date_tokens() {
+        let now = Local.with_ymd_and_hms(2024, 5, 1, 2, 3, 4).unwrap();
+        let expand = |template| expand_at(template, &now);
+
+        assert_eq!(expand("backups/{date}").unwrap(), "backups/2024-05-01");
+        assert_eq!(
+            expand("dump-{datetime}.sql").unwrap(),
+            "dump-2024-05-01_02-03-04.sql"
+        );
+        assert_eq!(expand("{date:%Y/%m}-{time}").unwrap(), "2024/05-02-03-04");
+        assert_eq!(expand("{{date}} }}").unwrap(), "{date} }");
+        assert_eq!(expand("report.pdf").unwrap(), "report.pdf");
+
+        // Names which are not templates are kept
+        assert_eq!(expand("notes {draft}").unwrap(), "notes {draft}");
+        assert_eq!(expand("a}b").unwrap(), "a}b");
+        assert_eq!(expand("{date").unwrap(), "{date");
+        assert_eq!(expand("{a{date}}").unwrap(), "{a2024-05-01}");
+
+        // Doubled braces are only collapsed along with a token
+        assert_eq!(expand("a}}b {{draft}}").unwrap(), "a}}b {{draft}}");
+        assert_eq!(expand("{{time}}").unwrap(), "{time}");
+        assert_eq!(expand("{date} }}").unwrap(), "2024-05-01 }");
+
+        assert_eq!(
+            expand("{date:%Q}"),
+            Err(InvalidTemplate {
+                format: "%Q".to_string()
+            })
+        );
+        assert_eq!(
+            expand("{date:}"),
+            Err(InvalidTemplate {
+                format: String::new()
+            })
+        );
+    }
+}
@@ -4,6 +4,33 @@ use std::{
     str::FromStr,
 };
 
+use crate::common::error_report;
+
+/// Whether a change on `file` was denied because the file is in a shared drive, whose permissions
+/// and files only its organizers manage, unless acting as a domain administrator.
+#[must_use]
+pub fn denied_on_shared_drive(
+    err: &google_drive3::Error,
+    file: &google_drive3::api::File,
+    domain_admin: bool,
+) -> bool {
+    !domain_admin && file.drive_id.is_some() && error_report::http_status(err) == Some(403)
+}
+
+/// Whether a domain administrator was refused to read a file, which happens for the files of the
+/// shared drives they manage without being a member. Drive answers 404 instead of 403 for some of
+/// them.
+#[must_use]
+pub fn denied_to_domain_admin(err: &google_drive3::Error) -> bool {
+    matches!(error_report::http_status(err), Some(403 | 404))
+}
+
+/// Whether the permission makes its grantee the owner of the file, which cannot be revoked.
+#[must_use]
+pub fn is_owner(permission: &google_drive3::api::Permission) -> bool {
+    permission.role == Some(Role::Owner.to_string())
+}
+
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
 pub enum Role {
     Owner,
@@ -150,3 +177,52 @@ impl Display for InvalidType {
 }
 
 impl Error for InvalidType {}
+
+#[cfg(test)]
+mod tests {
+    use super::{denied_on_shared_drive, denied_to_domain_admin, is_owner};
+
+    fn error(code: u16) -> google_drive3::Error {
+        google_drive3::Error::BadRequest(serde_json::json!({ "error": { "code": code } }))
+    }
+
+    #[test]
+    fn shared_drive_denials() {
+        let shared = google_drive3::api::File {
+            drive_id: Some(String::from("0AB")),
+            ..google_drive3::api::File::default()
+        };
+        let owned = google_drive3::api::File::default();
+
+        assert!(denied_on_shared_drive(&error(403), &shared, false));
+        // Domain administrators are told the actual error
+        assert!(!denied_on_shared_drive(&error(403), &shared, true));
+        assert!(!denied_on_shared_drive(&error(404), &shared, false));
+        assert!(!denied_on_shared_drive(&error(403), &owned, false));
+    }
+
+    #[test]
+    fn domain_admin_denials() {
+        assert!(denied_to_domain_admin(&error(403)));
+        assert!(denied_to_domain_admin(&error(404)));
+        assert!(!denied_to_domain_admin(&error(401)));
+        assert!(!denied_to_domain_admin(&error(500)));
+        assert!(!denied_to_domain_admin(&google_drive3::Error::Cancelled));
+    }
+
+    #[test]
+    fn owner_permissions() {
+        let owner = google_drive3::api::Permission {
+            role: Some(String::from("owner")),
+            ..google_drive3::api::Permission::default()
+        };
+        let writer = google_drive3::api::Permission {
+            role: Some(String::from("writer")),
+            ..google_drive3::api::Permission::default()
+        };
+
+        assert!(is_owner(&owner));
+        assert!(!is_owner(&writer));
+        assert!(!is_owner(&google_drive3::api::Permission::default()));
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    io::{self, IsTerminal, Write},
+};
+
+/// Prints `message` and reads a line from stdin, without the surrounding whitespace.
+pub fn input(message: &str) -> Result<String, io::Error> {
+    print!("{message}: ");
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim().to_string())
+}
+
+/// Asks the user to confirm a destructive operation.
+///
+/// The question is skipped when `assume_yes` is set. When stdin is not a terminal nobody can
+/// answer, therefore the operation is refused instead of waiting forever.
+pub fn confirm(question: &str, assume_yes: bool) -> Result<(), ConfirmError> {
+    if assume_yes {
+        return Ok(());
+    }
+
+    if !io::stdin().is_terminal() {
+        return Err(ConfirmError::NotInteractive);
+    }
+
+    let answer = input(&format!("{question} [y/N]")).map_err(ConfirmError::Prompt)?;
+    if is_yes(&answer) {
+        Ok(())
+    } else {
+        Err(ConfirmError::Declined)
+    }
+}
+
+fn is_yes(answer: &str) -> bool {
+    answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes")
+}
+
+#[derive(Debug)]
+pub enum ConfirmError {
+    Prompt(io::Error),
+    NotInteractive,
+    Declined,
+}
+
+impl Display for ConfirmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfirmError::Prompt(_) => f.write_str("failed to get input from user"),
+            ConfirmError::NotInteractive => {
+                f.write_str("confirmation required, use --yes to run non-interactively")
+            }
+            ConfirmError::Declined => f.write_str("operation cancelled"),
+        }
+    }
+}
+
+impl error::Error for ConfirmError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ConfirmError::Prompt(source) => Some(source),
+            ConfirmError::NotInteractive | ConfirmError::Declined => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_yes;
+
+    #[test]
+    fn yes_answers() {
+        assert!(is_yes("y"));
+        assert!(is_yes("Yes"));
+        assert!(!is_yes(""));
+        assert!(!is_yes("n"));
+        assert!(!is_yes("yeah"));
+    }
+}
@@ -0,0 +1,55 @@
+use std::fmt::{self, Display, Formatter};
+
+use bytesize::ByteSize;
+use clap::ValueEnum;
+
+/// How file sizes are displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SizeFormat {
+    /// Raw number of bytes, i.e. 1500000
+    Bytes,
+    /// Powers of 1000, i.e. 1.5 MB
+    #[default]
+    Si,
+    /// Powers of 1024, i.e. 1.4 MiB
+    Iec,
+}
+
+impl SizeFormat {
+    #[must_use]
+    pub fn display(self, bytes: u64) -> DisplaySize {
+        DisplaySize {
+            bytes,
+            format: self,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DisplaySize {
+    bytes: u64,
+    format: SizeFormat,
+}
+
+impl Display for DisplaySize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let size = ByteSize::b(self.bytes);
+        match self.format {
+            SizeFormat::Bytes => write!(f, "{}", self.bytes),
+            SizeFormat::Si => write!(f, "{}", size.display().si()),
+            SizeFormat::Iec => write!(f, "{}", size.display().iec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SizeFormat;
+
+    #[test]
+    fn display_sizes() {
+        assert_eq!(SizeFormat::Bytes.display(1_500_000).to_string(), "1500000");
+        assert_eq!(SizeFormat::Si.display(1_500_000).to_string(), "1.5 MB");
+        assert_eq!(SizeFormat::Iec.display(1_500_000).to_string(), "1.4 MiB");
+    }
+}
@@ -0,0 +1,45 @@
+//! Naming of the parts of a file uploaded with `--split`, i.e. `disk.img.part00`.
+
+const PART_SEPARATOR: &str = ".part";
+
+/// Suffix appended to the name of the folder containing the parts of a file.
+pub const PARTS_FOLDER_SUFFIX: &str = ".parts";
+
+/// Name of the part `index` of a file split in `count` parts. Indices are zero padded, so that
+/// the parts are sorted correctly by name.
+#[must_use]
+pub fn part_name(name: &str, index: u64, count: u64) -> String {
+    let width = count.saturating_sub(1).to_string().len().max(2);
+    format!("{name}{PART_SEPARATOR}{index:0width$}")
+}
+
+/// Splits a part name into the name of the original file and the index of the part.
+#[must_use]
+pub fn parse_part_name(part_name: &str) -> Option<(&str, u64)> {
+    let (name, index) = part_name.rsplit_once(PART_SEPARATOR)?;
+    if name.is_empty() || index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((name, index.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_part_name, part_name};
+
+    #[test]
+    fn part_names_are_padded() {
+        assert_eq!(part_name("disk.img", 3, 10), "disk.img.part03");
+        assert_eq!(part_name("disk.img", 7, 250), "disk.img.part007");
+    }
+
+    #[test]
+    fn parse_part_names() {
+        assert_eq!(parse_part_name("disk.img.part03"), Some(("disk.img", 3)));
+        assert_eq!(parse_part_name("disk.img.part"), None);
+        assert_eq!(parse_part_name("disk.img.partx1"), None);
+        assert_eq!(parse_part_name(".part01"), None);
+        assert_eq!(parse_part_name("disk.img"), None);
+    }
+}
@@ -1,6 +1,9 @@
 use std::{
+    borrow::Cow,
+    error,
     fmt::{self, Display},
     io::{self, Write},
+    str::FromStr,
 };
 
 use tabwriter::TabWriter;
@@ -13,9 +16,66 @@ pub struct Table<H: Display, V: Display, const COLUMNS: usize> {
 #[derive(Debug, Clone)]
 pub struct DisplayConfig<'a> {
     pub skip_header: bool,
+    /// Column separator, only used by [`OutputFormat::Table`]
     pub separator: &'a str,
+    pub format: OutputFormat,
 }
 
+/// How tables are rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Aligned columns, meant to be read by humans
+    #[default]
+    Table,
+    /// Comma separated values, quoted as described by RFC 4180
+    Csv,
+    /// Tab separated values, with tabs, newlines and backslashes escaped as `\t`, `\n` and `\\`
+    Tsv,
+}
+
+impl OutputFormat {
+    const ALL: [&'static str; 3] = ["table", "csv", "tsv"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = InvalidOutputFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(InvalidOutputFormat),
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidOutputFormat;
+
+impl Display for InvalidOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "output format is invalid, valid formats are: {}",
+            OutputFormat::ALL.join(", ")
+        )
+    }
+}
+
+impl error::Error for InvalidOutputFormat {}
+
 impl DisplayConfig<'_> {
     fn display_row<'a, T>(&'a self, value: &'a [T]) -> DisplayRow<'a, T>
     where
@@ -30,6 +90,7 @@ impl Default for DisplayConfig<'static> {
         Self {
             skip_header: false,
             separator: "\t",
+            format: OutputFormat::Table,
         }
     }
 }
@@ -38,6 +99,33 @@ pub fn write<W: Write, H: Display, V: Display, const COLUMNS: usize>(
     writer: W,
     table: Table<H, V, COLUMNS>,
     config: &DisplayConfig,
+) -> Result<(), io::Error> {
+    match config.format {
+        OutputFormat::Table => write_aligned(writer, table, config),
+        OutputFormat::Csv => write_escaped(writer, table, config, ',', escape_csv),
+        OutputFormat::Tsv => write_escaped(writer, table, config, '\t', escape_tsv),
+    }
+}
+
+/// Writes one value per record, without header nor escaping, terminated by a newline or by a NUL
+/// byte when `null` is set, like `find -print0`.
+pub fn write_records<W: Write, V: Display>(
+    mut writer: W,
+    values: impl IntoIterator<Item = V>,
+    null: bool,
+) -> Result<(), io::Error> {
+    let terminator = if null { '\0' } else { '\n' };
+    for value in values {
+        write!(writer, "{value}{terminator}")?;
+    }
+
+    writer.flush()
+}
+
+fn write_aligned<W: Write, H: Display, V: Display, const COLUMNS: usize>(
+    writer: W,
+    table: Table<H, V, COLUMNS>,
+    config: &DisplayConfig,
 ) -> Result<(), io::Error> {
     let mut tw = TabWriter::new(writer).padding(3);
 
@@ -52,6 +140,61 @@ pub fn write<W: Write, H: Display, V: Display, const COLUMNS: usize>(
     tw.flush()
 }
 
+fn write_escaped<W: Write, H: Display, V: Display, const COLUMNS: usize>(
+    mut writer: W,
+    table: Table<H, V, COLUMNS>,
+    config: &DisplayConfig,
+    separator: char,
+    escape: fn(&str) -> Cow<'_, str>,
+) -> Result<(), io::Error> {
+    fn write_row<W: Write, T: Display>(
+        writer: &mut W,
+        row: &[T],
+        separator: char,
+        escape: fn(&str) -> Cow<'_, str>,
+    ) -> Result<(), io::Error> {
+        for (index, column) in row.iter().enumerate() {
+            if index > 0 {
+                write!(writer, "{separator}")?;
+            }
+            write!(writer, "{}", escape(&column.to_string()))?;
+        }
+        writeln!(writer)
+    }
+
+    if !config.skip_header {
+        write_row(&mut writer, &table.header, separator, escape)?;
+    }
+
+    for value in table.values {
+        write_row(&mut writer, &value, separator, escape)?;
+    }
+
+    writer.flush()
+}
+
+fn escape_csv(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+fn escape_tsv(value: &str) -> Cow<'_, str> {
+    if value.contains(['\t', '\n', '\r', '\\']) {
+        Cow::Owned(
+            value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct DisplayRow<'a, T>(&'a DisplayConfig<'a>, &'a [T]);
 
@@ -70,3 +213,58 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{write, write_records, DisplayConfig, OutputFormat, Table};
+
+    fn render(format: OutputFormat) -> String {
+        let table = Table {
+            header: ["Id", "Name"],
+            values: vec![
+                ["1", "plain"],
+                ["2", "with, comma"],
+                ["3", "say \"hi\""],
+                ["4", "tab\tand\nnewline\\"],
+            ],
+        };
+        let mut output = Vec::new();
+        write(
+            &mut output,
+            table,
+            &DisplayConfig {
+                format,
+                ..DisplayConfig::default()
+            },
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn csv_quotes_special_characters() {
+        assert_eq!(
+            render(OutputFormat::Csv),
+            "Id,Name\n1,plain\n2,\"with, comma\"\n3,\"say \"\"hi\"\"\"\n4,\"tab\tand\nnewline\\\"\n"
+        );
+    }
+
+    #[test]
+    fn records_are_terminated() {
+        let mut output = Vec::new();
+        write_records(&mut output, ["a", "b\nc"], true).unwrap();
+        assert_eq!(output, b"a\0b\nc\0");
+
+        let mut output = Vec::new();
+        write_records(&mut output, ["a", "b"], false).unwrap();
+        assert_eq!(output, b"a\nb\n");
+    }
+
+    #[test]
+    fn tsv_escapes_special_characters() {
+        assert_eq!(
+            render(OutputFormat::Tsv),
+            "Id\tName\n1\tplain\n2\twith, comma\n3\tsay \"hi\"\n4\ttab\\tand\\nnewline\\\\\n"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! The temporary files written during a transfer, i.e. the `.incomplete` file of a download or
+//! the copy of stdin made before an upload. Each file is removed when its [`TempFile`] is dropped
+//! without being kept, which covers the error paths, and the files still registered are removed
+//! when the process is interrupted, see [`remove_on_signal`].
+
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+    sync::{Mutex, MutexGuard},
+};
+
+const INCOMPLETE_SUFFIX: &str = ".incomplete";
+
+/// Exit code of a process interrupted by SIGINT, 128 + the signal number.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+/// Exit code of a process stopped by SIGTERM.
+#[cfg(unix)]
+const TERMINATED_EXIT_CODE: i32 = 143;
+
+static REGISTRY: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// A registered temporary file, removed on drop unless [kept](TempFile::keep).
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Registers `path`, which does not need to exist yet.
+    #[must_use]
+    pub fn register(path: PathBuf) -> TempFile {
+        registry().insert(path.clone());
+        TempFile { path }
+    }
+
+    /// Registers the `.incomplete` file a download to `path` is written to before being renamed.
+    /// The suffix is appended to the whole name, so that `report.pdf` and `report.docx` don't
+    /// share one.
+    #[must_use]
+    pub fn incomplete(path: &Path) -> TempFile {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(INCOMPLETE_SUFFIX);
+        TempFile::register(path.with_file_name(name))
+    }
+
+    /// Creates an empty file with a unique name in the temporary directory of the system.
+    pub fn new_in_temp_dir() -> io::Result<TempFile> {
+        let path = mktemp::Temp::new_file()?.release();
+        Ok(TempFile::register(path))
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stops tracking the file, which is left where it is. Files renamed to their final path
+    /// are kept as well, so that nothing is removed in their place.
+    #[must_use]
+    pub fn keep(self) -> PathBuf {
+        registry().remove(&self.path);
+        let path = self.path.clone();
+        std::mem::forget(self);
+        path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        registry().remove(&self.path);
+        remove_file(&self.path);
+    }
+}
+
+impl Deref for TempFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Removes the registered files when the process receives SIGINT or SIGTERM, then exits with the
+/// code of the signal. Without a handler, the process would be killed with its files left behind.
+pub fn remove_on_signal() {
+    tokio::spawn(async {
+        let exit_code = wait_for_signal().await;
+        remove_all();
+        process::exit(exit_code);
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> i32 {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+        return wait_for_ctrl_c().await;
+    };
+    tokio::select! {
+        code = wait_for_ctrl_c() => code,
+        _ = terminate.recv() => TERMINATED_EXIT_CODE,
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> i32 {
+    wait_for_ctrl_c().await
+}
+
+async fn wait_for_ctrl_c() -> i32 {
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        tracing::warn!(error = %err, "unable to listen for ctrl-c, temporary files are kept");
+        // Never resolve, a failing listener must not remove files still being written
+        std::future::pending::<()>().await;
+    }
+    INTERRUPTED_EXIT_CODE
+}
+
+/// Removes all the registered files.
+fn remove_all() {
+    let paths = std::mem::take(&mut *registry());
+    for path in &paths {
+        remove_file(path);
+    }
+}
+
+fn remove_file(path: &Path) {
+    match fs::remove_file(path) {
+        Ok(()) => tracing::debug!(path = %path.display(), "removed temporary file"),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => {
+            tracing::warn!(path = %path.display(), error = %err, "unable to remove temporary file");
+        }
+    }
+}
+
+/// A panic while holding the lock leaves the set as it was, it is still usable.
+fn registry() -> MutexGuard<'static, BTreeSet<PathBuf>> {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{registry, TempFile};
+
+    #[test]
+    fn remove_unless_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let dropped = TempFile::incomplete(&dir.path().join("report.pdf"));
+        fs::write(&dropped, "partial").unwrap();
+        assert_eq!(dropped.path(), dir.path().join("report.pdf.incomplete"));
+        let other = TempFile::incomplete(&dir.path().join("report.docx"));
+        assert_ne!(other.path(), dropped.path());
+        assert!(registry().contains(dropped.path()));
+
+        let dropped_path = dropped.to_path_buf();
+        drop(dropped);
+        assert!(!dropped_path.exists());
+        assert!(!registry().contains(&dropped_path));
+
+        let kept = TempFile::register(dir.path().join("kept.bin"));
+        fs::write(&kept, "content").unwrap();
+        let kept_path = kept.keep();
+        assert!(kept_path.exists());
+        assert!(!registry().contains(&kept_path));
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use error_trace::ErrorTrace;
+
+use crate::common::checksum::{self, Checksum};
+
+/// Where a transferred file is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    CurrentDir,
+    Path(PathBuf),
+    Stdout,
+}
+
+/// What to do when the local file a transfer writes to already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExistingFileAction {
+    /// Fail without writing anything
+    #[default]
+    Abort,
+    /// Replace the local file
+    Overwrite,
+    /// Keep the local file and go on with the next one
+    Skip,
+    /// Replace the local file unless its checksum matches the one of the Drive file. Files
+    /// without a checksum, i.e. exported documents, are always replaced
+    OverwriteIfDifferent,
+}
+
+/// The outcome of [`ExistingFileAction::resolve`] for a given local file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// There is no local file, or it has to be replaced
+    Write,
+    /// The local file is kept as it is
+    Keep,
+    /// The local file exists and must not be touched, the transfer fails
+    Conflict,
+}
+
+impl ExistingFileAction {
+    /// Decides whether the file at `path` can be written, `checksum` being the one Drive provides
+    /// for the transferred content.
+    pub async fn resolve(self, path: &Path, checksum: Option<&Checksum>) -> Resolution {
+        if !path.exists() {
+            return Resolution::Write;
+        }
+
+        match self {
+            ExistingFileAction::Abort => Resolution::Conflict,
+            ExistingFileAction::Overwrite => Resolution::Write,
+            ExistingFileAction::Skip => Resolution::Keep,
+            ExistingFileAction::OverwriteIfDifferent => match checksum {
+                Some(checksum) if local_file_matches(path, checksum).await => Resolution::Keep,
+                _ => Resolution::Write,
+            },
+        }
+    }
+}
+
+/// Whether the content of the local file has the expected checksum. Files that cannot be read
+/// are reported and considered different.
+pub async fn local_file_matches(path: &Path, expected: &Checksum) -> bool {
+    match compute_checksum_from_path(path, expected.algorithm()).await {
+        Ok(checksum) => checksum == *expected,
+        Err(err) => {
+            eprintln!(
+                "Warning: Error while computing {} of '{}': {}",
+                expected.algorithm(),
+                path.display(),
+                err.trace(),
+            );
+            false
+        }
+    }
+}
+
+/// Hashes the file on the blocking pool.
+pub async fn compute_checksum_from_path(
+    path: &Path,
+    algorithm: checksum::Algorithm,
+) -> Result<Checksum, io::Error> {
+    let path = path.to_owned();
+
+    // Hashing is CPU bound, run it on the blocking pool so that files are hashed in parallel
+    tokio::task::spawn_blocking(move || {
+        let input = std::fs::File::open(path)?;
+        checksum::compute(algorithm, input)
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn resolve_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.txt");
+        let same = checksum::compute(checksum::Algorithm::Md5, Cursor::new("content")).unwrap();
+        let other = checksum::compute(checksum::Algorithm::Md5, Cursor::new("other")).unwrap();
+
+        let missing = ExistingFileAction::Abort.resolve(&path, None).await;
+        assert_eq!(missing, Resolution::Write);
+
+        std::fs::write(&path, "content").unwrap();
+        let resolve = |action: ExistingFileAction, checksum| {
+            let path = path.clone();
+            async move { action.resolve(&path, checksum).await }
+        };
+        assert_eq!(
+            resolve(ExistingFileAction::Abort, None).await,
+            Resolution::Conflict
+        );
+        assert_eq!(
+            resolve(ExistingFileAction::Overwrite, None).await,
+            Resolution::Write
+        );
+        assert_eq!(
+            resolve(ExistingFileAction::Skip, None).await,
+            Resolution::Keep
+        );
+        assert_eq!(
+            resolve(ExistingFileAction::OverwriteIfDifferent, Some(&same)).await,
+            Resolution::Keep
+        );
+        assert_eq!(
+            resolve(ExistingFileAction::OverwriteIfDifferent, Some(&other)).await,
+            Resolution::Write
+        );
+        assert_eq!(
+            resolve(ExistingFileAction::OverwriteIfDifferent, None).await,
+            Resolution::Write
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Opt-in statistics of the requests sent to the Drive API, kept on disk to see which commands
+//! consume the quota. Only the method and the path of each request are recorded, with the ids
+//! replaced by `{id}`, along with the number of bytes sent and received. Nothing leaves the
+//! machine.
+
+use std::{
+    collections::BTreeMap,
+    error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use google_drive3::chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::{errors::DefaultBasePath, AppConfig};
+
+const STATS_FILE_NAME: &str = "usage_stats.json";
+
+/// Giving up on the lock after this delay, a concurrent run holds it for a few milliseconds only.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(20);
+/// A lock older than this was left by a run killed while holding it.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+
+/// Path segments following a collection which are methods rather than ids.
+const COLLECTION_METHODS: [&str; 2] = ["generateIds", "trash"];
+const COLLECTIONS: [&str; 8] = [
+    "files",
+    "permissions",
+    "revisions",
+    "comments",
+    "replies",
+    "drives",
+    "teamdrives",
+    "apps",
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REQUESTS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+static BYTES_SENT: AtomicU64 = AtomicU64::new(0);
+static BYTES_RECEIVED: AtomicU64 = AtomicU64::new(0);
+
+/// The recorded statistics, by day.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageStats {
+    pub enabled: bool,
+    pub days: BTreeMap<NaiveDate, DayStats>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayStats {
+    /// Runs of gdrive which sent at least one request
+    pub runs: u64,
+    /// Number of requests by endpoint, i.e. `GET files/{id}`
+    pub requests: BTreeMap<String, u64>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl UsageStats {
+    pub fn path() -> Result<PathBuf, Error> {
+        let base_path = AppConfig::default_base_path().map_err(Error::BasePath)?;
+        Ok(base_path.join(STATS_FILE_NAME))
+    }
+
+    /// Loads the statistics, which are empty and disabled when never saved.
+    pub fn load() -> Result<UsageStats, Error> {
+        let path = UsageStats::path()?;
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(UsageStats::default()),
+            Err(err) => return Err(Error::Read(path, err)),
+        };
+        serde_json::from_slice(&content).map_err(|err| Error::Parse(path, err))
+    }
+
+    /// Applies `change` to the saved statistics, holding a lock so that concurrent runs don't
+    /// overwrite each other's changes. Returns the statistics as saved.
+    pub fn update(change: impl FnOnce(&mut UsageStats)) -> Result<UsageStats, Error> {
+        let path = UsageStats::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| Error::Write(path.clone(), err))?;
+        }
+
+        let _lock = StatsLock::acquire(&path)?;
+        let mut stats = UsageStats::load()?;
+        change(&mut stats);
+        stats.save(&path)?;
+        Ok(stats)
+    }
+
+    /// Writes to a temporary file renamed over the statistics, an interrupted run never leaves
+    /// them truncated.
+    fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|err| Error::Write(path.to_path_buf(), io::Error::other(err)))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)
+            .and_then(|()| fs::rename(&tmp_path, path))
+            .map_err(|err| Error::Write(path.to_path_buf(), err))
+    }
+
+    fn add_run(&mut self, day: NaiveDate, run: &DayStats) {
+        self.days.entry(day).or_default().add(run);
+    }
+
+    /// Sums the statistics of the days from `first_day` on.
+    #[must_use]
+    pub fn total_since(&self, first_day: NaiveDate) -> DayStats {
+        let mut total = DayStats::default();
+        for stats in self.days.range(first_day..).map(|(_, stats)| stats) {
+            total.add(stats);
+        }
+        total
+    }
+}
+
+impl DayStats {
+    #[must_use]
+    pub fn request_count(&self) -> u64 {
+        self.requests.values().sum()
+    }
+
+    fn add(&mut self, other: &DayStats) {
+        self.runs += other.runs;
+        for (endpoint, count) in &other.requests {
+            *self.requests.entry(endpoint.clone()).or_default() += count;
+        }
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+    }
+}
+
+/// A lock file next to the statistics, removed when dropped.
+struct StatsLock {
+    path: PathBuf,
+}
+
+impl StatsLock {
+    fn acquire(stats_path: &Path) -> Result<StatsLock, Error> {
+        let path = stats_path.with_extension("json.lock");
+        let started = Instant::now();
+
+        loop {
+            let created = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path);
+            match created {
+                Ok(_) => return Ok(StatsLock { path }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        // Another run may remove it first, which is just as good
+                        let _ = fs::remove_file(&path);
+                    } else if started.elapsed() >= LOCK_TIMEOUT {
+                        return Err(Error::Lock(path, err));
+                    } else {
+                        thread::sleep(LOCK_RETRY_DELAY);
+                    }
+                }
+                Err(err) => return Err(Error::Lock(path, err)),
+            }
+        }
+    }
+}
+
+impl Drop for StatsLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn is_stale(lock_path: &Path) -> bool {
+    fs::metadata(lock_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= STALE_LOCK_AGE)
+}
+
+/// Records the requests of this run if the statistics are enabled, to be called before the
+/// first client is created. The statistics stay disabled when they cannot be loaded.
+pub fn init() -> Result<(), Error> {
+    let enabled = UsageStats::load()?.enabled;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Counts a request given its method and its target, i.e. `/drive/v3/files?q=...`.
+pub fn record_request(method: &str, target: &str) {
+    let endpoint = endpoint(method, target);
+    let mut requests = REQUESTS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    *requests.entry(endpoint).or_default() += 1;
+}
+
+pub fn record_bytes_sent(bytes: usize) {
+    BYTES_SENT.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+pub fn record_bytes_received(bytes: usize) {
+    BYTES_RECEIVED.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+/// Adds the requests of this run to the statistics of today, when enabled and if any request was
+/// sent.
+pub fn save_run() -> Result<(), Error> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    let requests = std::mem::take(
+        &mut *REQUESTS
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner),
+    );
+    if requests.is_empty() {
+        return Ok(());
+    }
+
+    let run = DayStats {
+        runs: 1,
+        requests,
+        bytes_sent: BYTES_SENT.swap(0, Ordering::Relaxed),
+        bytes_received: BYTES_RECEIVED.swap(0, Ordering::Relaxed),
+    };
+    UsageStats::update(|stats| {
+        // Statistics disabled meanwhile, i.e. by this very run
+        if stats.enabled {
+            stats.add_run(Local::now().date_naive(), &run);
+        }
+    })?;
+    Ok(())
+}
+
+/// The method and the path of a request, without the API prefix nor the query, and with the ids
+/// replaced by `{id}`: `GET files/{id}/permissions`.
+fn endpoint(method: &str, target: &str) -> String {
+    let path = target.split_once('?').map_or(target, |(path, _)| path);
+    let (prefix, path) = if let Some(path) = path.strip_prefix("/upload/drive/v3/") {
+        ("upload/", path)
+    } else if let Some(path) = path.strip_prefix("/drive/v3/") {
+        ("", path)
+    } else {
+        ("", path.trim_start_matches('/'))
+    };
+
+    let mut after_collection = false;
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let is_id = after_collection && !COLLECTION_METHODS.contains(&segment);
+            after_collection = !after_collection && COLLECTIONS.contains(&segment);
+            if is_id {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect();
+
+    format!("{method} {prefix}{}", segments.join("/"))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    BasePath(DefaultBasePath),
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Write(PathBuf, io::Error),
+    Lock(PathBuf, io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BasePath(_) => f.write_str("unable to find the directory of the statistics"),
+            Error::Read(path, _) => write!(f, "unable to read '{}'", path.display()),
+            Error::Parse(path, _) => write!(f, "'{}' is not valid statistics", path.display()),
+            Error::Write(path, _) => write!(f, "unable to write '{}'", path.display()),
+            Error::Lock(path, _) => write!(f, "unable to lock '{}'", path.display()),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::BasePath(source) => Some(source),
+            Error::Read(_, source) | Error::Write(_, source) | Error::Lock(_, source) => {
+                Some(source)
+            }
+            Error::Parse(_, source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::chrono::NaiveDate;
+
+    use super::{endpoint, DayStats, StatsLock, UsageStats};
+
+    #[test]
+    fn anonymized_endpoints() {
+        assert_eq!(
+            endpoint("GET", "/drive/v3/files?q=x&pageSize=100"),
+            "GET files"
+        );
+        assert_eq!(
+            endpoint("GET", "/drive/v3/files/1a2B?alt=media"),
+            "GET files/{id}"
+        );
+        assert_eq!(
+            endpoint("DELETE", "/drive/v3/files/1a2B/permissions/0987"),
+            "DELETE files/{id}/permissions/{id}"
+        );
+        assert_eq!(
+            endpoint("POST", "/drive/v3/files/1a2B/copy"),
+            "POST files/{id}/copy"
+        );
+        assert_eq!(
+            endpoint("GET", "/drive/v3/files/generateIds"),
+            "GET files/generateIds"
+        );
+        assert_eq!(
+            endpoint("GET", "/drive/v3/changes/startPageToken"),
+            "GET changes/startPageToken"
+        );
+        assert_eq!(
+            endpoint("PUT", "/upload/drive/v3/files/1a2B?upload_id=x"),
+            "PUT upload/files/{id}"
+        );
+    }
+
+    #[test]
+    fn lock_is_released_when_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let stats_path = dir.path().join("usage_stats.json");
+        let lock_path = dir.path().join("usage_stats.json.lock");
+
+        let lock = StatsLock::acquire(&stats_path).unwrap();
+        assert!(lock_path.exists());
+        drop(lock);
+        assert!(!lock_path.exists());
+
+        // Taken again once released
+        let _lock = StatsLock::acquire(&stats_path).unwrap();
+    }
+
+    #[test]
+    fn sum_days() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 5, d).unwrap();
+        let run = DayStats {
+            runs: 1,
+            requests: [("GET files".to_string(), 3)].into(),
+            bytes_sent: 10,
+            bytes_received: 100,
+        };
+        let mut stats = UsageStats::default();
+        stats.add_run(day(1), &run);
+        stats.add_run(day(2), &run);
+        stats.add_run(day(2), &run);
+
+        let total = stats.total_since(day(2));
+        assert_eq!(total.runs, 2);
+        assert_eq!(total.requests["GET files"], 6);
+        assert_eq!(total.bytes_received, 200);
+
+        let json = serde_json::to_string(&stats).unwrap();
+        assert_eq!(serde_json::from_str::<UsageStats>(&json).unwrap(), stats);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod list;
+pub mod members;
 
 pub use list::list;
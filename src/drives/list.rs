@@ -9,9 +9,15 @@ use crate::{
     hub::Hub,
 };
 
+/// Number of drives requested per page, the maximum allowed by the API.
+const PAGE_SIZE: i32 = 100;
+
 pub struct Config {
     pub skip_header: bool,
     pub field_separator: String,
+    pub output: table::OutputFormat,
+    /// Print the drives as a JSON array instead of a table
+    pub json: bool,
 }
 
 pub async fn list(config: Config) -> Result<(), Error> {
@@ -22,11 +28,28 @@ pub async fn list(config: Config) -> Result<(), Error> {
         .await
         .map_err(Error::ListDrives)?;
 
-    print_drives_table(&config, drives);
+    if config.json {
+        print_drives_json(&drives);
+    } else {
+        print_drives_table(&config, drives);
+    }
 
     Ok(())
 }
 
+fn print_drives_json(drives: &[google_drive3::api::Drive]) {
+    let json: Vec<_> = drives
+        .iter()
+        .map(|drive| {
+            serde_json::json!({
+                "id": drive.id,
+                "name": drive.name,
+            })
+        })
+        .collect();
+    println!("{:#}", serde_json::Value::Array(json));
+}
+
 fn print_drives_table(config: &Config, drives: Vec<google_drive3::api::Drive>) {
     let mut values: Vec<[String; 2]> = vec![];
 
@@ -45,6 +68,7 @@ fn print_drives_table(config: &Config, drives: Vec<google_drive3::api::Drive>) {
         &table::DisplayConfig {
             skip_header: config.skip_header,
             separator: &config.field_separator,
+            format: config.output,
         },
     );
 }
@@ -53,17 +77,80 @@ pub async fn list_drives(
     hub: &Hub,
     delegate_config: &UploadDelegateConfig,
 ) -> Result<Vec<google_drive3::api::Drive>, google_drive3::Error> {
-    let mut delegate = UploadDelegate::new(delegate_config);
+    let mut drives = vec![];
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut delegate = UploadDelegate::new(delegate_config);
+        let mut req = hub.drives().list().page_size(PAGE_SIZE);
+        if let Some(token) = &next_page_token {
+            req = req.page_token(token);
+        }
+
+        let (_, drives_list) = req
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .doit()
+            .await?;
+
+        drives.extend(drives_list.drives.unwrap_or_default());
+        next_page_token = drives_list.next_page_token;
+        if next_page_token.is_none() {
+            return Ok(drives);
+        }
+    }
+}
+
+/// Returns the id of the shared drive named exactly `name`.
+pub async fn find_drive_id(hub: &Hub, name: &str) -> Result<String, FindDriveError> {
+    let drives = list_drives(hub, &UploadDelegateConfig::default())
+        .await
+        .map_err(|err| FindDriveError::ListDrives(Box::new(err)))?;
+
+    let mut ids: Vec<String> = drives
+        .into_iter()
+        .filter(|drive| drive.name.as_deref() == Some(name))
+        .filter_map(|drive| drive.id)
+        .collect();
+
+    match ids.len() {
+        0 => Err(FindDriveError::NotFound(name.to_string())),
+        1 => Ok(ids.remove(0)),
+        _ => Err(FindDriveError::Ambiguous {
+            name: name.to_string(),
+            ids,
+        }),
+    }
+}
 
-    let (_, drives_list) = hub
-        .drives()
-        .list()
-        .add_scope(google_drive3::api::Scope::Full)
-        .delegate(&mut delegate)
-        .doit()
-        .await?;
+#[derive(Debug)]
+pub enum FindDriveError {
+    ListDrives(Box<google_drive3::Error>),
+    NotFound(String),
+    Ambiguous { name: String, ids: Vec<String> },
+}
 
-    Ok(drives_list.drives.unwrap_or_default())
+impl error::Error for FindDriveError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FindDriveError::ListDrives(source) => Some(source),
+            FindDriveError::NotFound(_) | FindDriveError::Ambiguous { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for FindDriveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FindDriveError::ListDrives(_) => f.write_str("failed to list drives"),
+            FindDriveError::NotFound(name) => write!(f, "no shared drive is named '{name}'"),
+            FindDriveError::Ambiguous { name, ids } => write!(
+                f,
+                "several shared drives are named '{name}', use one of their ids instead: {}",
+                ids.join(", ")
+            ),
+        }
+    }
 }
 
 #[derive(Debug)]
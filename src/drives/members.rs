@@ -0,0 +1,7 @@
+pub mod add;
+pub mod list;
+pub mod remove;
+
+pub use add::add;
+pub use list::list;
+pub use remove::remove;
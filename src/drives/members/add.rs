@@ -0,0 +1,90 @@
+use std::{error, fmt};
+
+use crate::{
+    common::{
+        delegate::UploadDelegateConfig,
+        file_id::FileId,
+        hub_helper::{get_hub, GetHubError},
+        permission,
+    },
+    permissions,
+};
+
+pub struct Config {
+    pub drive_id: String,
+    pub email: String,
+    pub role: permission::Role,
+    /// The email belongs to a group instead of a single user
+    pub group: bool,
+    /// Add the member as a domain administrator, who does not need to be a member
+    pub domain_admin: bool,
+}
+
+pub async fn add(config: Config) -> Result<(), Error> {
+    // Shared drives are owned by the organization, nobody can become their owner
+    if config.role == permission::Role::Owner {
+        return Err(Error::InvalidRole(config.role));
+    }
+
+    let hub = get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let type_ = if config.group {
+        permission::Type::Group
+    } else {
+        permission::Type::User
+    };
+
+    println!(
+        "Adding {} '{}' as {} of drive '{}'",
+        type_, config.email, config.role, config.drive_id
+    );
+
+    permissions::share::create_permission(
+        &hub,
+        &delegate_config,
+        &permissions::share::Config {
+            file_id: FileId::from_drive(config.drive_id),
+            role: config.role,
+            type_,
+            discoverable: false,
+            email: Some(config.email),
+            domain: None,
+            domain_admin: config.domain_admin,
+            expiration: None,
+        },
+    )
+    .await
+    .map_err(|err| Error::AddMember(Box::new(err)))?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    InvalidRole(permission::Role),
+    AddMember(Box<google_drive3::Error>),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::InvalidRole(_) => None,
+            Error::AddMember(source) => Some(source),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::InvalidRole(role) => {
+                write!(f, "role '{role}' cannot be granted on a shared drive")
+            }
+            Error::AddMember(_) => f.write_str("unable to add drive member"),
+        }
+    }
+}
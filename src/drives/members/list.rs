@@ -0,0 +1,136 @@
+use std::{error, fmt, io};
+
+use crate::{
+    common::{
+        delegate::{UploadDelegate, UploadDelegateConfig},
+        hub_helper::{get_hub, GetHubError},
+        table::{self, Table},
+    },
+    hub::Hub,
+};
+
+const MAX_PAGE_SIZE: i32 = 100;
+
+pub struct Config {
+    pub drive_id: String,
+    /// List the members as a domain administrator, who does not need to be a member
+    pub domain_admin: bool,
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub output: table::OutputFormat,
+}
+
+pub async fn list(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let members = list_members(
+        &hub,
+        &delegate_config,
+        &config.drive_id,
+        config.domain_admin,
+    )
+    .await
+    .map_err(Error::ListMembers)?;
+
+    print_members_table(&config, members);
+
+    Ok(())
+}
+
+fn print_members_table(config: &Config, members: Vec<google_drive3::api::Permission>) {
+    let mut values: Vec<[String; 5]> = vec![];
+
+    for member in members {
+        values.push([
+            member.id.unwrap_or_default(),
+            member.type_.unwrap_or_default(),
+            member.role.unwrap_or_default(),
+            member.email_address.unwrap_or_default(),
+            member.display_name.unwrap_or_default(),
+        ]);
+    }
+
+    let table = Table {
+        header: ["Id", "Type", "Role", "Email", "Name"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: &config.field_separator,
+            format: config.output,
+        },
+    );
+}
+
+/// Lists the members of a shared drive, which are the permissions of the drive itself.
+pub async fn list_members(
+    hub: &Hub,
+    delegate_config: &UploadDelegateConfig,
+    drive_id: &str,
+    domain_admin: bool,
+) -> Result<Vec<google_drive3::api::Permission>, google_drive3::Error> {
+    let mut members = vec![];
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut delegate = UploadDelegate::new(delegate_config);
+        let mut req = hub.permissions().list(drive_id);
+
+        if let Some(token) = next_page_token {
+            req = req.page_token(&token);
+        }
+
+        let (_, permission_list) = req
+            .page_size(MAX_PAGE_SIZE)
+            .param(
+                "fields",
+                "permissions(id,role,type,domain,emailAddress,displayName),nextPageToken",
+            )
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .supports_all_drives(true)
+            .use_domain_admin_access(domain_admin)
+            .doit()
+            .await?;
+
+        members.extend(permission_list.permissions.unwrap_or_default());
+
+        next_page_token = permission_list.next_page_token;
+        if next_page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(members)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    ListMembers(google_drive3::Error),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::ListMembers(source) => Some(source),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Error::Hub(_) => "unable to get drive hub",
+            Error::ListMembers(_) => "unable to list drive members",
+        };
+
+        f.write_str(s)
+    }
+}
@@ -0,0 +1,99 @@
+use std::{error, fmt};
+
+use crate::{
+    common::{
+        delegate::UploadDelegateConfig,
+        hub_helper::{get_hub, GetHubError},
+    },
+    drives::members,
+    permissions,
+};
+
+pub struct Config {
+    pub drive_id: String,
+    pub member: Member,
+    /// Remove the member as a domain administrator, who does not need to be a member
+    pub domain_admin: bool,
+}
+
+/// How the member to remove is identified.
+pub enum Member {
+    Email(String),
+    PermissionId(String),
+}
+
+pub async fn remove(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let permission_id = match config.member {
+        Member::PermissionId(id) => id,
+        Member::Email(email) => {
+            let members = members::list::list_members(
+                &hub,
+                &delegate_config,
+                &config.drive_id,
+                config.domain_admin,
+            )
+            .await
+            .map_err(|err| Error::ListMembers(Box::new(err)))?;
+
+            members
+                .into_iter()
+                .find(|member| {
+                    member
+                        .email_address
+                        .as_deref()
+                        .is_some_and(|address| address.eq_ignore_ascii_case(&email))
+                })
+                .and_then(|member| member.id)
+                .ok_or(Error::NoSuchMember(email))?
+        }
+    };
+
+    println!(
+        "Removing member with permission id '{permission_id}' from drive '{}'",
+        config.drive_id
+    );
+
+    permissions::revoke::delete_permission(
+        &hub,
+        &delegate_config,
+        &config.drive_id,
+        &permission_id,
+        config.domain_admin,
+    )
+    .await
+    .map_err(|err| Error::RemoveMember(Box::new(err)))?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    ListMembers(Box<google_drive3::Error>),
+    NoSuchMember(String),
+    RemoveMember(Box<google_drive3::Error>),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::ListMembers(source) | Error::RemoveMember(source) => Some(source),
+            Error::NoSuchMember(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::ListMembers(_) => f.write_str("unable to list drive members"),
+            Error::NoSuchMember(email) => write!(f, "'{email}' is not a member of the drive"),
+            Error::RemoveMember(_) => f.write_str("unable to remove drive member"),
+        }
+    }
+}
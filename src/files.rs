@@ -1,27 +1,47 @@
 pub mod copy;
 pub mod delete;
+pub mod diff;
+pub mod diff_local;
 pub mod download;
 pub mod export;
+pub mod find;
 pub mod generate_ids;
 pub mod import;
 pub mod info;
 pub mod list;
 pub mod mkdir;
 pub mod mv;
+pub mod open;
+pub mod publish;
+pub mod remove_from_shared;
 pub mod rename;
+pub mod rename_batch;
+pub mod tail;
+pub mod thumbnail;
 pub mod update;
 pub mod upload;
+pub mod watch_remote;
 
 pub use copy::copy;
 pub use delete::delete;
+pub use diff::diff;
+pub use diff_local::diff_local;
 pub use download::download;
 pub use export::export;
+pub use find::find;
 pub use generate_ids::generate_ids;
 pub use import::import;
 pub use info::info;
 pub use list::list;
 pub use mkdir::mkdir;
 pub use mv::mv;
+pub use open::open;
+pub use publish::publish;
+pub use remove_from_shared::remove_from_shared;
 pub use rename::rename;
+pub use rename_batch::rename_batch;
+pub use tail::tail;
+pub use thumbnail::thumbnail;
 pub use update::update;
 pub use upload::upload;
+pub use watch_remote::watch_remote;
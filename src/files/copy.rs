@@ -8,16 +8,27 @@ use crate::{
     common::{
         delegate::{UploadDelegate, UploadDelegateConfig},
         drive_file,
+        file_id::FileId,
         hub_helper::{get_hub, GetHubError},
     },
-    files::{self, info::DisplayConfig},
+    files::{
+        self,
+        info::DisplayConfig,
+        list::{self, ListFilesConfig, ListQuery, ListSortOrder, QueryBuilder},
+    },
     hub::Hub,
 };
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_id: String,
-    pub to_folder_id: String,
+    pub file_id: FileId,
+    pub to_folder_id: FileId,
+    /// Name of the copy, defaults to the name of the source file
+    pub name: Option<String>,
+    /// Give the copy the modified time of the source instead of the time of the copy
+    pub keep_modified_time: bool,
+    /// Print only the id of the copy
+    pub print_only_id: bool,
 }
 
 pub async fn copy(config: Config) -> Result<(), Error> {
@@ -40,22 +51,65 @@ pub async fn copy(config: Config) -> Result<(), Error> {
         return Err(Error::DestinationNotADirectory);
     }
 
-    println!(
-        "Copying '{}' to '{}'",
-        file.name.unwrap_or_default(),
-        to_parent.name.unwrap_or_default()
-    );
+    let source_name = file.name.clone().unwrap_or_default();
+    let to_parent_name = to_parent.name.unwrap_or_default();
+    let new_name = config.name.as_deref().unwrap_or(&source_name);
+
+    if name_exists_in_folder(
+        &hub,
+        new_name,
+        &config.to_folder_id,
+        to_parent.drive_id.as_deref(),
+    )
+    .await?
+    {
+        eprintln!(
+            "Warning: a file named '{new_name}' already exists in '{to_parent_name}', use --name to \
+            give the copy a different name"
+        );
+    }
+
+    if !config.print_only_id {
+        println!("Copying '{source_name}' to '{to_parent_name}'");
+    }
 
     let copy_config = CopyConfig {
-        file_id: config.file_id,
-        to_folder_id: config.to_folder_id,
+        file_id: config.file_id.to_string(),
+        to_folder_id: config.to_folder_id.to_string(),
+        name: config.name,
     };
 
-    let new_file = copy_file(&hub, &delegate_config, &copy_config)
+    let mut new_file = copy_file(&hub, &delegate_config, &copy_config)
         .await
         .map_err(|err| Error::Copy(Box::new(err)))?;
 
-    files::info::print_file_info(&new_file, &DisplayConfig::default());
+    // Binary files get a checksum as soon as they are copied, documents never have one
+    if let (Some(expected), Some(actual)) = (&file.md5_checksum, &new_file.md5_checksum) {
+        if expected != actual {
+            return Err(Error::ChecksumMismatch {
+                file_id: new_file.id.unwrap_or_default(),
+                expected: expected.clone(),
+                actual: actual.clone(),
+            });
+        }
+    }
+
+    if config.keep_modified_time {
+        new_file = files::update::restore_modified_time(
+            &hub,
+            &delegate_config,
+            &file,
+            new_file.id.as_deref().unwrap_or_default(),
+        )
+        .await
+        .map_err(|err| Error::RestoreModifiedTime(Box::new(err)))?;
+    }
+
+    if config.print_only_id {
+        print!("{}", new_file.id.unwrap_or_default());
+    } else {
+        files::info::print_file_info(&new_file, &DisplayConfig::default());
+    }
 
     Ok(())
 }
@@ -63,6 +117,7 @@ pub async fn copy(config: Config) -> Result<(), Error> {
 pub struct CopyConfig {
     pub file_id: String,
     pub to_folder_id: String,
+    pub name: Option<String>,
 }
 
 pub async fn copy_file(
@@ -73,6 +128,7 @@ pub async fn copy_file(
     let mut delegate = UploadDelegate::new(delegate_config);
 
     let file = google_drive3::api::File {
+        name: config.name.clone(),
         parents: Some(vec![config.to_folder_id.clone()]),
         ..google_drive3::api::File::default()
     };
@@ -80,7 +136,7 @@ pub async fn copy_file(
     let (_, file) = hub
         .files()
         .copy(file, &config.file_id)
-        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
+        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,sha256Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true)
@@ -89,6 +145,35 @@ pub async fn copy_file(
     Ok(file)
 }
 
+async fn name_exists_in_folder(
+    hub: &Hub,
+    name: &str,
+    folder_id: &str,
+    drive_id: Option<&str>,
+) -> Result<bool, Error> {
+    let query = ListQuery::from(
+        QueryBuilder::new()
+            .in_parent(folder_id)
+            .name_equals(name)
+            .trashed(false),
+    );
+    let files = list::list_files(
+        hub,
+        ListFilesConfig {
+            query: &query,
+            order_by: &ListSortOrder::default(),
+            max_files: 1,
+            extra_fields: &[],
+            drive_id,
+            corpora: None,
+        },
+    )
+    .await
+    .map_err(|err| Error::ListDestination(Box::new(err)))?;
+
+    Ok(!files.is_empty())
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
@@ -97,6 +182,13 @@ pub enum Error {
     DestinationNotADirectory,
     SourceIsADirectory,
     Copy(Box<google_drive3::Error>),
+    ListDestination(Box<list::Error>),
+    RestoreModifiedTime(Box<google_drive3::Error>),
+    ChecksumMismatch {
+        file_id: String,
+        expected: String,
+        actual: String,
+    },
 }
 
 impl Display for Error {
@@ -108,6 +200,18 @@ impl Display for Error {
             Error::GetDestinationFolder(_) => "unable to get destination folder",
             Error::DestinationNotADirectory => "destination is not a directory",
             Error::Copy(_) => "unable to perform the actual copy",
+            Error::ListDestination(_) => "unable to list the files of the destination folder",
+            Error::RestoreModifiedTime(_) => "file copied, but unable to restore its modified time",
+            Error::ChecksumMismatch {
+                file_id,
+                expected,
+                actual,
+            } => {
+                return write!(
+                    f,
+                    "checksum of the copy {file_id} is {actual}, but the source has {expected}"
+                );
+            }
         };
 
         f.write_str(s)
@@ -118,10 +222,14 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::Hub(source) => Some(source),
-            Error::GetFile(source) | Error::GetDestinationFolder(source) | Error::Copy(source) => {
-                Some(source)
-            }
-            Error::DestinationNotADirectory | Error::SourceIsADirectory => None,
+            Error::GetFile(source)
+            | Error::GetDestinationFolder(source)
+            | Error::Copy(source)
+            | Error::RestoreModifiedTime(source) => Some(source),
+            Error::ListDestination(source) => Some(source),
+            Error::DestinationNotADirectory
+            | Error::SourceIsADirectory
+            | Error::ChecksumMismatch { .. } => None,
         }
     }
 }
@@ -6,15 +6,24 @@ use std::{
 use crate::{
     common::{
         drive_file,
+        file_id::FileId,
         file_tree_drive::errors::FileIdentifier,
         hub_helper::{get_hub, GetHubError},
+        permission,
+        prompt::{self, ConfirmError},
     },
     files,
+    hub::Hub,
 };
 
 pub struct Config {
-    pub file_id: String,
+    pub file_id: FileId,
     pub delete_directories: bool,
+    /// Don't ask for confirmation before deleting a directory
+    pub assume_yes: bool,
+    /// Move the file to the trash instead of deleting it permanently, which is all that members
+    /// of a shared drive below the organizer role are allowed to do
+    pub trash: bool,
 }
 
 pub async fn delete(config: Config) -> Result<(), Error> {
@@ -24,8 +33,28 @@ pub async fn delete(config: Config) -> Result<(), Error> {
         .await
         .map_err(|err| Error::GetFile(Box::new(err)))?;
 
-    if drive_file::is_directory(&file) && !config.delete_directories {
-        return Err(Error::IsDirectory(FileIdentifier::from(file)));
+    let verb = if config.trash { "Trash" } else { "Delete" };
+    if drive_file::is_directory(&file) {
+        if !config.delete_directories {
+            return Err(Error::IsDirectory(FileIdentifier::from(file)));
+        }
+
+        prompt::confirm(
+            &format!(
+                "{verb} directory '{}' and all its content?",
+                file.name.as_deref().unwrap_or_default()
+            ),
+            config.assume_yes,
+        )
+        .map_err(Error::Confirm)?;
+    }
+
+    if config.trash {
+        trash_file(&hub, &config.file_id)
+            .await
+            .map_err(|err| Error::TrashFile(Box::new(err)))?;
+        println!("Moved '{}' to the trash", file.name.unwrap_or_default());
+        return Ok(());
     }
 
     hub.files()
@@ -34,19 +63,50 @@ pub async fn delete(config: Config) -> Result<(), Error> {
         .add_scope(google_drive3::api::Scope::Full)
         .doit()
         .await
-        .map_err(|err| Error::DeleteFile(Box::new(err)))?;
+        .map_err(|err| delete_error(err, &file))?;
 
     println!("Deleted '{}'", file.name.unwrap_or_default());
 
     Ok(())
 }
 
+/// Tells apart the files of shared drives the account is not allowed to delete, since trashing
+/// them may still be allowed.
+fn delete_error(err: google_drive3::Error, file: &google_drive3::api::File) -> Error {
+    if permission::denied_on_shared_drive(&err, file, false) {
+        Error::DeleteSharedDriveFile(Box::new(err))
+    } else {
+        Error::DeleteFile(Box::new(err))
+    }
+}
+
+/// Moves the file to the trash, from where it can be restored.
+pub async fn trash_file(hub: &Hub, file_id: &str) -> Result<(), google_drive3::Error> {
+    let file = google_drive3::api::File {
+        trashed: Some(true),
+        ..google_drive3::api::File::default()
+    };
+
+    hub.files()
+        .update(file, file_id)
+        .param("fields", "id")
+        .add_scope(google_drive3::api::Scope::Full)
+        .supports_all_drives(true)
+        .doit_without_upload()
+        .await?;
+
+    Ok(())
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
     GetFile(Box<google_drive3::Error>),
     DeleteFile(Box<google_drive3::Error>),
+    DeleteSharedDriveFile(Box<google_drive3::Error>),
+    TrashFile(Box<google_drive3::Error>),
     IsDirectory(FileIdentifier),
+    Confirm(ConfirmError),
 }
 
 impl Display for Error {
@@ -55,11 +115,17 @@ impl Display for Error {
             Error::Hub(_) => f.write_str("unable to get drive hub"),
             Error::GetFile(_) => f.write_str("unable to get file to delete"),
             Error::DeleteFile(_) => f.write_str("unable to delete file"),
+            Error::DeleteSharedDriveFile(_) => f.write_str(
+                "unable to delete file, deleting files of a shared drive permanently requires \
+                the organizer role, use --trash to move it to the trash instead",
+            ),
+            Error::TrashFile(_) => f.write_str("unable to move file to the trash"),
             Error::IsDirectory(identifier) => write!(
                 f,
                 "file{} is a directory, use --recursive to delete directories",
                 identifier.display(),
             ),
+            Error::Confirm(_) => f.write_str("directory not deleted"),
         }
     }
 }
@@ -68,8 +134,40 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::Hub(source) => Some(source),
-            Error::GetFile(source) | Error::DeleteFile(source) => Some(source),
+            Error::GetFile(source)
+            | Error::DeleteFile(source)
+            | Error::DeleteSharedDriveFile(source)
+            | Error::TrashFile(source) => Some(source),
             Error::IsDirectory(_) => None,
+            Error::Confirm(source) => Some(source),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{delete_error, Error};
+
+    fn forbidden() -> google_drive3::Error {
+        let value = serde_json::json!({ "error": { "code": 403, "message": "Forbidden" } });
+        google_drive3::Error::BadRequest(value)
+    }
+
+    #[test]
+    fn shared_drive_delete_denied() {
+        let shared = google_drive3::api::File {
+            drive_id: Some(String::from("0AB")),
+            ..google_drive3::api::File::default()
+        };
+        let owned = google_drive3::api::File::default();
+
+        assert!(matches!(
+            delete_error(forbidden(), &shared),
+            Error::DeleteSharedDriveFile(_)
+        ));
+        assert!(matches!(
+            delete_error(forbidden(), &owned),
+            Error::DeleteFile(_)
+        ));
+    }
+}
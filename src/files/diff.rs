@@ -0,0 +1,383 @@
+use std::{
+    collections::BTreeMap,
+    error,
+    fmt::{Display, Formatter},
+    io,
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::{
+    common::{
+        file_id::FileId,
+        file_tree_drive::{self, FileTreeDrive},
+        hub_helper::{get_hub, GetHubError},
+        size_format::SizeFormat,
+        table::{self, Table},
+        FileTreeLike, FolderLike,
+    },
+    files,
+    hub::Hub,
+};
+
+/// How the two sides of a comparison are named in the output.
+pub(crate) struct Sides {
+    pub header: [&'static str; 4],
+    pub only_a: &'static str,
+    pub only_b: &'static str,
+    pub key_a: &'static str,
+    pub key_b: &'static str,
+}
+
+const FOLDERS: Sides = Sides {
+    header: ["Status", "Path", "A", "B"],
+    only_a: "only-a",
+    only_b: "only-b",
+    key_a: "a",
+    key_b: "b",
+};
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub folder_a: FileId,
+    pub folder_b: FileId,
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub output: table::OutputFormat,
+    /// Print the differences as a JSON array instead of a table
+    pub json: bool,
+    pub size_format: SizeFormat,
+    /// Reuse the folder listings of a previous walk of the same folder for this long
+    pub cache_ttl: Option<Duration>,
+}
+
+pub async fn diff(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let files_a = list_tree(&hub, &config.folder_a, config.cache_ttl).await?;
+    let files_b = list_tree(&hub, &config.folder_b, config.cache_ttl).await?;
+
+    let differences = compare(&files_a, &files_b);
+    print_differences(&differences, &FOLDERS, &config.output_config());
+
+    eprintln!(
+        "Compared {} and {} files, found {} differences",
+        files_a.len(),
+        files_b.len(),
+        differences.len()
+    );
+
+    Ok(())
+}
+
+impl Config {
+    fn output_config(&self) -> OutputConfig<'_> {
+        OutputConfig {
+            skip_header: self.skip_header,
+            field_separator: &self.field_separator,
+            output: self.output,
+            json: self.json,
+            size_format: self.size_format,
+        }
+    }
+}
+
+pub(crate) struct OutputConfig<'a> {
+    pub skip_header: bool,
+    pub field_separator: &'a str,
+    pub output: table::OutputFormat,
+    pub json: bool,
+    pub size_format: SizeFormat,
+}
+
+/// Lists the files below a folder, keyed by their path relative to it. With a `cache_ttl`, the
+/// listings of a previous walk are reused.
+pub(crate) async fn list_tree(
+    hub: &Hub,
+    folder_id: &str,
+    cache_ttl: Option<Duration>,
+) -> Result<BTreeMap<PathBuf, Entry>, Error> {
+    let file = files::info::get_file(hub, folder_id)
+        .await
+        .map_err(|err| Error::GetFolder(folder_id.to_string(), Box::new(err)))?;
+
+    let tree_config = file_tree_drive::Config {
+        cache_ttl,
+        ..file_tree_drive::Config::default()
+    };
+    let tree = FileTreeDrive::from_file(hub, file, &tree_config)
+        .await
+        .map_err(|err| Error::CreateFileTree(folder_id.to_string(), Box::new(err)))?;
+
+    let root_path = tree.root().info.relative_path();
+    let mut entries = BTreeMap::new();
+    for folder in tree.folders() {
+        for file in folder.files() {
+            let path = file.relative_path();
+            let path = path
+                .strip_prefix(&root_path)
+                .map(PathBuf::from)
+                .unwrap_or(path);
+            entries.insert(path, Entry::from(file));
+        }
+    }
+
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    pub id: EntryId,
+    pub size: u64,
+    pub md5: Option<String>,
+}
+
+/// Identifies a compared file, only displayed in the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EntryId {
+    Drive(String),
+    /// Absolute path of a local file
+    Local(PathBuf),
+}
+
+impl Display for EntryId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntryId::Drive(id) => f.write_str(id),
+            EntryId::Local(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl From<file_tree_drive::File> for Entry {
+    fn from(file: file_tree_drive::File) -> Self {
+        Entry {
+            id: EntryId::Drive(file.drive_id),
+            size: file.size,
+            md5: file.md5_checksum,
+        }
+    }
+}
+
+impl Entry {
+    /// Files differ when their sizes do, or when both have a checksum and they do not match.
+    fn differs_from(&self, other: &Entry) -> bool {
+        if self.size != other.size {
+            return true;
+        }
+
+        match (&self.md5, &other.md5) {
+            (Some(a), Some(b)) => a != b,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Status {
+    OnlyInA,
+    OnlyInB,
+    Changed,
+}
+
+impl Sides {
+    fn label(&self, status: Status) -> &'static str {
+        match status {
+            Status::OnlyInA => self.only_a,
+            Status::OnlyInB => self.only_b,
+            Status::Changed => "changed",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Difference<'a> {
+    status: Status,
+    path: &'a PathBuf,
+    a: Option<&'a Entry>,
+    b: Option<&'a Entry>,
+}
+
+/// Returns the files missing on either side or differing, sorted by path.
+pub(crate) fn compare<'a>(
+    files_a: &'a BTreeMap<PathBuf, Entry>,
+    files_b: &'a BTreeMap<PathBuf, Entry>,
+) -> Vec<Difference<'a>> {
+    let mut differences = vec![];
+
+    for (path, a) in files_a {
+        match files_b.get(path) {
+            None => differences.push(Difference {
+                status: Status::OnlyInA,
+                path,
+                a: Some(a),
+                b: None,
+            }),
+            Some(b) if a.differs_from(b) => differences.push(Difference {
+                status: Status::Changed,
+                path,
+                a: Some(a),
+                b: Some(b),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for (path, b) in files_b {
+        if !files_a.contains_key(path) {
+            differences.push(Difference {
+                status: Status::OnlyInB,
+                path,
+                a: None,
+                b: Some(b),
+            });
+        }
+    }
+
+    differences.sort_by(|x, y| x.path.cmp(y.path));
+    differences
+}
+
+pub(crate) fn print_differences(differences: &[Difference], sides: &Sides, config: &OutputConfig) {
+    if config.json {
+        print_json(differences, sides);
+    } else {
+        print_table(differences, sides, config);
+    }
+}
+
+fn print_table(differences: &[Difference], sides: &Sides, config: &OutputConfig) {
+    let describe = |entry: Option<&Entry>| {
+        entry.map_or_else(String::new, |entry| {
+            format!(
+                "{} {}",
+                config.size_format.display(entry.size),
+                entry.md5.as_deref().unwrap_or("-")
+            )
+        })
+    };
+
+    let values: Vec<[String; 4]> = differences
+        .iter()
+        .map(|difference| {
+            [
+                sides.label(difference.status).to_string(),
+                difference.path.display().to_string(),
+                describe(difference.a),
+                describe(difference.b),
+            ]
+        })
+        .collect();
+
+    let table = Table {
+        header: sides.header,
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: config.field_separator,
+            format: config.output,
+        },
+    );
+}
+
+fn print_json(differences: &[Difference], sides: &Sides) {
+    let describe = |entry: Option<&Entry>| {
+        entry.map(|entry| {
+            serde_json::json!({
+                "id": entry.id.to_string(),
+                "size": entry.size,
+                "md5Checksum": entry.md5,
+            })
+        })
+    };
+
+    let json: Vec<_> = differences
+        .iter()
+        .map(|difference| {
+            let mut json = serde_json::json!({
+                "status": sides.label(difference.status),
+                "path": difference.path,
+            });
+            json[sides.key_a] = describe(difference.a).into();
+            json[sides.key_b] = describe(difference.b).into();
+            json
+        })
+        .collect();
+    println!("{:#}", serde_json::Value::Array(json));
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    GetFolder(String, Box<google_drive3::Error>),
+    CreateFileTree(String, Box<file_tree_drive::errors::FileTreeDrive>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFolder(id, _) => write!(f, "unable to get folder '{id}'"),
+            Error::CreateFileTree(id, _) => write!(f, "unable to list the files of '{id}'"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFolder(_, source) => Some(source),
+            Error::CreateFileTree(_, source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, size: u64, md5: Option<&str>) -> Entry {
+        Entry {
+            id: EntryId::Drive(id.to_string()),
+            size,
+            md5: md5.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn compare_trees() {
+        let files_a = BTreeMap::from([
+            (PathBuf::from("same"), entry("a1", 3, Some("x"))),
+            (PathBuf::from("resized"), entry("a2", 3, None)),
+            (PathBuf::from("edited"), entry("a3", 3, Some("x"))),
+            (PathBuf::from("dir/only-a"), entry("a4", 1, None)),
+        ]);
+        let files_b = BTreeMap::from([
+            (PathBuf::from("same"), entry("b1", 3, Some("x"))),
+            (PathBuf::from("resized"), entry("b2", 4, None)),
+            (PathBuf::from("edited"), entry("b3", 3, Some("y"))),
+            (PathBuf::from("dir/only-b"), entry("b4", 1, None)),
+        ]);
+
+        let differences = compare(&files_a, &files_b)
+            .into_iter()
+            .map(|difference| (difference.status, difference.path.to_str().unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            differences,
+            [
+                (Status::OnlyInA, "dir/only-a"),
+                (Status::OnlyInB, "dir/only-b"),
+                (Status::Changed, "edited"),
+                (Status::Changed, "resized"),
+            ]
+        );
+    }
+}
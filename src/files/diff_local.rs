@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeMap,
+    error,
+    fmt::{Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+
+use crate::{
+    common::{
+        checksum::Algorithm,
+        file_id::FileId,
+        file_tree::{self, FileTree},
+        hub_helper::{get_hub, GetHubError},
+        id_gen::IdGen,
+        size_format::SizeFormat,
+        table, transfer, FileTreeLike, FolderLike,
+    },
+    files::diff::{self, Entry, EntryId, OutputConfig, Sides},
+};
+
+const MAX_CONCURRENT_HASHES: usize = 4;
+
+const LOCAL_DRIVE: Sides = Sides {
+    header: ["Status", "Path", "Local", "Drive"],
+    only_a: "missing",
+    only_b: "extra",
+    key_a: "local",
+    key_b: "drive",
+};
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub local_path: PathBuf,
+    pub folder_id: FileId,
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub output: table::OutputFormat,
+    /// Print the differences as a JSON array instead of a table
+    pub json: bool,
+    pub size_format: SizeFormat,
+    /// Reuse the folder listings of a previous walk of the Drive folder for this long
+    pub cache_ttl: Option<Duration>,
+}
+
+pub async fn diff_local(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let local_root = config
+        .local_path
+        .canonicalize()
+        .map_err(|err| Error::Canonicalize(config.local_path.clone(), err))?;
+    if !local_root.is_dir() {
+        return Err(Error::NotADirectory(config.local_path));
+    }
+
+    let mut local_files = list_local_tree(&local_root).await?;
+    let drive_files = diff::list_tree(&hub, &config.folder_id, config.cache_ttl)
+        .await
+        .map_err(|err| Error::ListDrive(Box::new(err)))?;
+
+    hash_candidates(&mut local_files, &drive_files).await?;
+
+    let differences = diff::compare(&local_files, &drive_files);
+    diff::print_differences(
+        &differences,
+        &LOCAL_DRIVE,
+        &OutputConfig {
+            skip_header: config.skip_header,
+            field_separator: &config.field_separator,
+            output: config.output,
+            json: config.json,
+            size_format: config.size_format,
+        },
+    );
+
+    eprintln!(
+        "Compared {} local and {} Drive files, found {} differences",
+        local_files.len(),
+        drive_files.len(),
+        differences.len()
+    );
+
+    Ok(())
+}
+
+/// Lists the regular files below `root`, keyed by their path relative to it. Paths matched by the
+/// ignore files are skipped, like `upload --recursive` does.
+async fn list_local_tree(root: &Path) -> Result<BTreeMap<PathBuf, Entry>, Error> {
+    let tree = FileTree::from_path(root, &mut IdGen::offline(), &file_tree::Config::default())
+        .await
+        .map_err(Error::FileTree)?;
+
+    let root_path = &tree.root.info.path;
+    let entries = tree
+        .folders()
+        .iter()
+        .flat_map(|folder| folder.files())
+        .map(|file| {
+            let relative_path = file
+                .path
+                .strip_prefix(root_path)
+                .unwrap_or(&file.path)
+                .to_path_buf();
+            let entry = Entry {
+                id: EntryId::Local(file.path),
+                size: file.size,
+                md5: None,
+            };
+            (relative_path, entry)
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Computes the md5 of the local files whose Drive counterpart has the same size and a checksum,
+/// the only ones for which the content needs to be compared.
+async fn hash_candidates(
+    local_files: &mut BTreeMap<PathBuf, Entry>,
+    drive_files: &BTreeMap<PathBuf, Entry>,
+) -> Result<(), Error> {
+    let candidates = local_files
+        .iter_mut()
+        .filter(|(path, local)| {
+            drive_files
+                .get(*path)
+                .is_some_and(|drive| drive.size == local.size && drive.md5.is_some())
+        })
+        .map(|(_, local)| local);
+
+    stream::iter(candidates)
+        .map(|local| async move {
+            let EntryId::Local(path) = &local.id else {
+                return Ok(());
+            };
+            let md5 = transfer::compute_checksum_from_path(path, Algorithm::Md5)
+                .await
+                .map(|checksum| checksum.to_string())
+                .map_err(|err| Error::Hash(path.clone(), err))?;
+            local.md5 = Some(md5);
+            Ok::<_, Error>(())
+        })
+        .buffer_unordered(MAX_CONCURRENT_HASHES)
+        .try_collect::<()>()
+        .await
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    Canonicalize(PathBuf, io::Error),
+    NotADirectory(PathBuf),
+    FileTree(file_tree::errors::FileTree),
+    ListDrive(Box<diff::Error>),
+    Hash(PathBuf, io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::Canonicalize(path, _) => {
+                write!(f, "unable to canonicalize '{}'", path.display())
+            }
+            Error::NotADirectory(path) => write!(f, "'{}' is not a directory", path.display()),
+            Error::FileTree(_) => f.write_str("unable to walk the local directory"),
+            Error::ListDrive(_) => f.write_str("unable to list the Drive folder"),
+            Error::Hash(path, _) => write!(f, "unable to compute the md5 of '{}'", path.display()),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::Canonicalize(_, source) | Error::Hash(_, source) => Some(source),
+            Error::NotADirectory(_) => None,
+            Error::FileTree(source) => Some(source),
+            Error::ListDrive(source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::list_local_tree;
+    use crate::common::file_tree::IGNORE_FILE_NAME;
+
+    #[tokio::test]
+    async fn list_local_tree_skips_ignored_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("sub/build")).unwrap();
+        fs::write(root.join("a.txt"), "abc").unwrap();
+        fs::write(root.join("sub/b.txt"), "b").unwrap();
+        fs::write(root.join("sub/build/out.o"), "").unwrap();
+        fs::write(root.join(IGNORE_FILE_NAME), "build/\n").unwrap();
+
+        let files = list_local_tree(root).await.unwrap();
+        let paths = files
+            .keys()
+            .map(|path| path.to_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            ["a.txt", "sub/b.txt"]
+                .into_iter()
+                .map(|path| path.replace('/', std::path::MAIN_SEPARATOR_STR))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(files[std::path::Path::new("a.txt")].size, 3);
+    }
+}
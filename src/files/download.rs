@@ -1,36 +1,98 @@
+pub(crate) mod collision;
 pub(crate) mod errors;
+pub(crate) mod ranges;
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use std::path::{Path, PathBuf};
-
+use async_compression::tokio::write::GzipDecoder;
 use async_recursion::async_recursion;
-use bytesize::ByteSize;
-use error_trace::ErrorTrace;
-use futures::stream::StreamExt;
-use google_drive3::hyper;
-use md5::Digest;
+use futures::stream::{self, StreamExt};
+use google_drive3::{chrono::SecondsFormat, hyper};
+use serde::Serialize;
 use tokio::{
     fs::{self, File},
-    io::{self, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+    io::{self, AsyncWrite, AsyncWriteExt},
 };
 
+pub use collision::OnCollision;
+
 use crate::{
     common::{
-        drive_file,
+        checksum::{self, Checksum, ChecksumWriter},
+        compression,
+        drive_file::{self, DocType, FileExtension},
+        failure_manifest::{FailureKind, FailureManifest},
+        file_filter::FileFilter,
+        file_id::FileId,
         file_tree_drive::{self, errors::FileIdentifier, FileTreeDrive},
+        hook::{self, OnComplete},
         hub_helper::get_hub,
-        md5_writer::Md5Writer,
-        parse_md5_digest, FileTreeLike, FolderLike,
+        local_name::{self, SanitizeNames},
+        size_format::SizeFormat,
+        split_file,
+        tempfiles::TempFile,
+        transfer::{self, Destination, ExistingFileAction, Resolution},
+        FileTreeLike, FolderLike,
+    },
+    files::{
+        self,
+        list::{self, ListFilesConfig, ListQuery, ListSortOrder},
     },
-    files,
     hub::Hub,
 };
 
+/// Maximum number of local files hashed at the same time while looking for files that do not
+/// need to be downloaded again.
+const MAX_CONCURRENT_HASHES: usize = 8;
+
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "they are orthogonal one each other"
+)]
 pub struct Config {
-    pub file_id: String,
+    pub file_id: FileId,
     pub existing_file_action: ExistingFileAction,
     pub follow_shortcuts: bool,
     pub download_directories: bool,
     pub destination: Destination,
+    pub auto_decompress: bool,
+    pub filter: FileFilter,
+    /// Count and list the Google Docs files of recursive downloads, which are skipped
+    pub include_docs: bool,
+    /// Write a JSON manifest of the downloaded files here, only for recursive downloads
+    pub manifest: Option<PathBuf>,
+    /// Give up on a file of a recursive download that receives no data for this long, listing
+    /// it in the failure manifest and going on with the next one
+    pub file_timeout: Option<Duration>,
+    /// Write the files given up because of `file_timeout` here instead of printing them
+    pub failure_manifest: Option<PathBuf>,
+    /// Reuse the folder listings of a previous recursive download or diff of the same folder for
+    /// this long
+    pub cache_ttl: Option<Duration>,
+    /// Check the downloaded content against the checksum provided by Drive
+    pub verify_checksum: bool,
+    /// The file is a directory containing the parts of a file uploaded with `--split`, which
+    /// are concatenated back into the original file
+    pub join: bool,
+    pub size_format: SizeFormat,
+    /// Download regular files as this many byte ranges fetched concurrently
+    pub ranges: usize,
+    /// Format Google Docs files are exported to, instead of the default one of their type
+    pub export_format: Option<FileExtension>,
+    /// Save a single file under this name instead of its Drive name
+    pub name: Option<String>,
+    /// Run after each downloaded file, not when writing to stdout
+    pub on_complete: Option<OnComplete>,
+    /// When the Drive names are changed into valid local names, not for `name`
+    pub sanitize_names: SanitizeNames,
+    /// How files of a recursive download with the same local path are told apart
+    pub on_collision: OnCollision,
 }
 
 impl Config {
@@ -43,7 +105,7 @@ impl Config {
                 let canonical_current_path = current_path
                     .canonicalize()
                     .map_err(|err| E::CanonicalizeDestinationPath(current_path.clone(), err))?;
-                Ok(canonical_current_path)
+                Ok(local_name::long_path(canonical_current_path))
             }
 
             Destination::Path(path) => {
@@ -53,6 +115,7 @@ impl Config {
                     Err(E::DestinationPathNotADirectory(path.clone()))
                 } else {
                     path.canonicalize()
+                        .map(local_name::long_path)
                         .map_err(|err| E::CanonicalizeDestinationPath(path.clone(), err))
                 }
             }
@@ -62,17 +125,40 @@ impl Config {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum Destination {
-    CurrentDir,
-    Path(PathBuf),
-    Stdout,
+/// How the content of a downloaded file was checked against Drive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verification {
+    Verified,
+    /// Drive has no checksum for the file, i.e. files created by Google Workspace
+    NoChecksum,
+    Disabled,
+}
+
+impl Verification {
+    fn new(checksum: Option<&Checksum>, config: &Config) -> Self {
+        if !config.verify_checksum {
+            Verification::Disabled
+        } else if checksum.is_some() {
+            Verification::Verified
+        } else {
+            Verification::NoChecksum
+        }
+    }
+
+    /// The checksum the downloaded content must match, if it has to be verified.
+    fn expected(self, checksum: Option<&Checksum>) -> Option<&Checksum> {
+        checksum.filter(|_| self == Verification::Verified)
+    }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum ExistingFileAction {
-    Abort,
-    Overwrite,
+impl Display for Verification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Verification::Verified => f.write_str("checksum verified"),
+            Verification::NoChecksum => f.write_str("not verified, no checksum available"),
+            Verification::Disabled => f.write_str("not verified"),
+        }
+    }
 }
 
 #[async_recursion]
@@ -85,7 +171,9 @@ pub async fn download(config: Config) -> Result<(), errors::Download> {
         .await
         .map_err(|err| E::GetFile(Box::new(err)))?;
 
-    err_if_file_exists(&file, &config)?;
+    if resolve_existing_file(&file, &config).await? == Resolution::Keep {
+        return Ok(());
+    }
 
     if drive_file::is_shortcut(&file) {
         if !config.follow_shortcuts {
@@ -103,13 +191,21 @@ pub async fn download(config: Config) -> Result<(), errors::Download> {
         let file_id = target_file_id
             .ok_or_else(|| E::MissingShortcutTarget(FileIdentifier::new(name, id)))?;
 
-        download(Config { file_id, ..config }).await?;
+        download(Config {
+            file_id: FileId::from_drive(file_id),
+            ..config
+        })
+        .await?;
+    } else if drive_file::is_directory(&file) && config.join {
+        download_joined(&hub, &file, &config).await?;
     } else if drive_file::is_directory(&file) {
         if !config.download_directories {
             return Err(E::IsDirectory(FileIdentifier::from(file)));
         }
 
         download_directory(&hub, file, &config).await?;
+    } else if let Some((doc_type, extension)) = export_format(&file, &config)? {
+        download_exported(&hub, &file, doc_type, extension, &config).await?;
     } else {
         download_regular(&hub, &file, &config).await?;
     }
@@ -124,34 +220,132 @@ async fn download_regular(
 ) -> Result<(), errors::Download> {
     use errors::Download as E;
 
-    let body = download_file(hub, &config.file_id)
-        .await
-        .map_err(|err| E::DownloadFile(Box::new(err)))?;
+    let decompress = should_decompress(file, config);
 
     if config.destination == Destination::Stdout {
-        save_body_to_stdout(body).await?;
+        let body = download_file(hub, &config.file_id)
+            .await
+            .map_err(|err| E::DownloadFile(Box::new(err)))?;
+
+        if decompress {
+            save_gzip_body_to_stdout(body).await?;
+        } else {
+            save_body_to_stdout(body).await?;
+        }
     } else {
-        let file_name = file
-            .name
-            .as_deref()
-            .ok_or_else(|| E::MissingFileName(FileIdentifier::from(file)))?;
+        let file_name = local_file_name(file, config)?;
         let mut abs_file_path = config.canonical_destination_root()?;
-        abs_file_path.push(file_name);
+        abs_file_path.push(file_name.as_ref());
+
+        let checksum = Checksum::from_drive_file(file);
+        let verification = Verification::new(checksum.as_ref(), config);
+        let expected_checksum = verification.expected(checksum.as_ref());
+        let ranged_size = file
+            .size
+            .and_then(|size| u64::try_from(size).ok())
+            .filter(|&size| config.ranges > 1 && !decompress && size > ranges::MIN_RANGE_SIZE);
+
+        let result = if let Some(size) = ranged_size {
+            println!("Downloading {file_name} in up to {} ranges", config.ranges);
+            ranges::save_ranges_to_file(
+                hub,
+                &config.file_id,
+                size,
+                config.ranges,
+                &abs_file_path,
+                expected_checksum,
+            )
+            .await
+        } else {
+            let body = download_file(hub, &config.file_id)
+                .await
+                .map_err(|err| E::DownloadFile(Box::new(err)))?;
 
-        println!("Downloading {file_name}");
-        let md5_checksum = file.md5_checksum.as_deref().and_then(parse_md5_digest);
-        if let Err(source) = save_body_to_file(body, &abs_file_path, md5_checksum.as_ref()).await {
+            println!("Downloading {file_name}");
+            if decompress {
+                save_gzip_body_to_file(body, &abs_file_path, expected_checksum).await
+            } else {
+                save_body_to_file(body, &abs_file_path, expected_checksum).await
+            }
+        };
+        if let Err(source) = result {
             return Err(E::SaveBodyToFile {
                 path: abs_file_path,
                 source,
             });
         }
-        println!("Successfully downloaded {file_name}");
+        println!("Successfully downloaded {file_name} ({verification})");
+
+        run_on_complete(
+            config,
+            &config.file_id,
+            &abs_file_path,
+            file.md5_checksum.as_deref(),
+        )
+        .await;
     }
 
     Ok(())
 }
 
+/// Downloads a Google Docs file, which has no content of its own, by exporting it.
+async fn download_exported(
+    hub: &Hub,
+    file: &google_drive3::api::File,
+    doc_type: DocType,
+    extension: FileExtension,
+    config: &Config,
+) -> Result<(), errors::Download> {
+    use errors::Download as E;
+
+    let body = files::export::export_file(hub, &config.file_id, extension.get_export_mime())
+        .await
+        .map_err(|err| E::ExportFile(Box::new(err)))?;
+
+    if config.destination == Destination::Stdout {
+        save_body_to_stdout(body).await?;
+        return Ok(());
+    }
+
+    let file_name = local_file_name(file, config)?;
+    let mut abs_file_path = config.canonical_destination_root()?;
+    abs_file_path.push(file_name.as_ref());
+
+    println!("Exporting {doc_type} to {file_name}");
+    if let Err(source) = save_body_to_file(body, &abs_file_path, None).await {
+        return Err(E::SaveBodyToFile {
+            path: abs_file_path,
+            source,
+        });
+    }
+    println!("Successfully exported {file_name}");
+
+    run_on_complete(config, &config.file_id, &abs_file_path, None).await;
+
+    Ok(())
+}
+
+/// The type of a Google Docs file and the format it is exported to, `None` for other files.
+fn export_format(
+    file: &google_drive3::api::File,
+    config: &Config,
+) -> Result<Option<(DocType, FileExtension)>, errors::Download> {
+    let Some(doc_type) = file.mime_type.as_deref().and_then(DocType::from_mime_type) else {
+        return Ok(None);
+    };
+
+    let extension = config
+        .export_format
+        .unwrap_or_else(|| doc_type.default_export_type());
+    if doc_type.can_export_to(extension) {
+        Ok(Some((doc_type, extension)))
+    } else {
+        Err(errors::Download::UnsupportedExportFormat(
+            doc_type, extension,
+        ))
+    }
+}
+
 async fn download_directory(
     hub: &Hub,
     file: google_drive3::api::File,
@@ -159,7 +353,12 @@ async fn download_directory(
 ) -> Result<(), errors::Download> {
     use errors::Download as E;
 
-    let tree = FileTreeDrive::from_file(hub, file)
+    let tree_config = file_tree_drive::Config {
+        filter: config.filter,
+        include_docs: config.include_docs,
+        cache_ttl: config.cache_ttl,
+    };
+    let tree = FileTreeDrive::from_file(hub, file, &tree_config)
         .await
         .map_err(E::CreateFileTree)?;
 
@@ -169,13 +368,22 @@ async fn download_directory(
         "Found {} files in {} directories with a total size of {}",
         tree_info.file_count,
         tree_info.folder_count,
-        ByteSize::b(tree_info.total_file_size).display().si(),
+        config.size_format.display(tree_info.total_file_size),
     );
 
     let root_path = config.canonical_destination_root()?;
-
-    for folder in &tree.folders() {
-        let folder_path = folder.info.relative_path();
+    let mut manifest = DownloadManifest::default();
+    let mut failures = FailureManifest::default();
+    let folders = tree.folders();
+    let local_paths =
+        collision::assign_paths(&folders, config.sanitize_names, config.on_collision)?;
+    let identical_files = find_identical_local_files(&root_path, &folders, &local_paths).await;
+    let mut verified_count: usize = 0;
+    let mut unverified_count: usize = 0;
+    let mut skipped_docs: u64 = 0;
+
+    for folder in &folders {
+        let folder_path = folder.info.local_path(config.sanitize_names);
         let abs_folder_path = root_path.join(&folder_path);
 
         println!("Creating directory {}", folder_path.display());
@@ -184,38 +392,291 @@ async fn download_directory(
             .map_err(|err| E::CreateDirectory(abs_folder_path, err))?;
 
         for file in folder.files() {
-            let file_path = file.relative_path();
-            let abs_file_path = root_path.join(&file_path);
-
-            if local_file_is_identical(&abs_file_path, &file).await {
+            if !file.downloadable {
+                println!(
+                    "Skipping document '{}', it can only be exported",
+                    file.local_path(config.sanitize_names).display()
+                );
+                skipped_docs += 1;
+                continue;
+            }
+            let Some(file_path) = local_paths.get(&file.drive_id) else {
+                continue;
+            };
+            let abs_file_path = root_path.join(file_path);
+            let skip_existing =
+                config.existing_file_action == ExistingFileAction::Skip && abs_file_path.exists();
+            if skip_existing || identical_files.contains(&file.drive_id) {
+                manifest.add(file_path, &file);
                 continue;
             }
-
-            let body = download_file(hub, &file.drive_id)
-                .await
-                .map_err(|err| E::DownloadFile(Box::new(err)))?;
 
             println!("Downloading file '{}'", file_path.display());
-            if let Err(source) = save_body_to_file(body, &abs_file_path, file.md5.as_ref()).await {
-                return Err(E::SaveBodyToFile {
-                    path: abs_file_path,
-                    source,
-                });
+            let verification = Verification::new(file.checksum.as_ref(), config);
+            let expected_checksum = verification.expected(file.checksum.as_ref());
+            match download_tree_file(hub, &file, &abs_file_path, expected_checksum, config).await {
+                Ok(()) => manifest.add(file_path, &file),
+                Err(err @ E::FileStalled { .. }) => {
+                    failures.add(FailureKind::File, file_path.clone(), &err).id =
+                        Some(file.drive_id.clone());
+                    continue;
+                }
+                Err(err) => return Err(err),
             }
+
+            match verification {
+                Verification::Verified => verified_count += 1,
+                Verification::NoChecksum => {
+                    eprintln!("Warning: '{}' was {verification}", file_path.display());
+                    unverified_count += 1;
+                }
+                Verification::Disabled => unverified_count += 1,
+            }
+
+            run_on_complete(
+                config,
+                &file.drive_id,
+                &abs_file_path,
+                file.md5_checksum.as_deref(),
+            )
+            .await;
         }
     }
 
     println!(
         "Downloaded {} files in {} directories with a total size of {}",
-        tree_info.file_count,
+        manifest.files.len(),
         tree_info.folder_count,
-        ByteSize::b(tree_info.total_file_size).display().si()
+        config.size_format.display(tree_info.total_file_size)
+    );
+    println!("Verified {verified_count} files, {unverified_count} files not verified");
+    if skipped_docs > 0 {
+        println!("Skipped {skipped_docs} documents");
+    }
+
+    if let Some(manifest_path) = &config.manifest {
+        manifest.write(manifest_path)?;
+    }
+
+    finish_failure_manifest(config, &failures)
+}
+
+fn finish_failure_manifest(
+    config: &Config,
+    failures: &FailureManifest,
+) -> Result<(), errors::Download> {
+    use errors::Download as E;
+
+    match failures
+        .finish(config.failure_manifest.as_deref())
+        .map_err(E::FailureManifest)?
+    {
+        0 => Ok(()),
+        failures => Err(E::FailedDownloads(failures)),
+    }
+}
+
+/// Downloads a file of a recursive download. With a `file_timeout`, a file that receives no data
+/// for that long fails with [`errors::Download::FileStalled`] instead of blocking the others.
+async fn download_tree_file(
+    hub: &Hub,
+    file: &file_tree_drive::File,
+    path: &Path,
+    expected_checksum: Option<&Checksum>,
+    config: &Config,
+) -> Result<(), errors::Download> {
+    use errors::Download as E;
+
+    let stalled = |timeout| E::FileStalled {
+        path: path.to_owned(),
+        timeout,
+    };
+
+    let request = download_file(hub, &file.drive_id);
+    let body = match config.file_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, request)
+            .await
+            .map_err(|_| stalled(timeout))?,
+        None => request.await,
+    }
+    .map_err(|err| E::DownloadFile(Box::new(err)))?;
+
+    save_body_to_file_with_timeout(body, path, expected_checksum, config.file_timeout)
+        .await
+        .map_err(|source| match source {
+            errors::SaveBodyToFile::Stalled(timeout) => stalled(timeout),
+            source => E::SaveBodyToFile {
+                path: path.to_owned(),
+                source,
+            },
+        })
+}
+
+/// Downloads the parts of a file uploaded with `--split`, concatenating them into the original
+/// file.
+async fn download_joined(
+    hub: &Hub,
+    folder: &google_drive3::api::File,
+    config: &Config,
+) -> Result<(), errors::Download> {
+    use errors::Download as E;
+
+    let parts = list::list_files(
+        hub,
+        ListFilesConfig {
+            query: &ListQuery::FilesInFolder {
+                folder_id: config.file_id.to_string(),
+            },
+            order_by: &ListSortOrder::Custom(String::from("name")),
+            max_files: usize::MAX,
+            extra_fields: &[],
+            drive_id: folder.drive_id.as_deref(),
+            corpora: None,
+        },
+    )
+    .await
+    .map_err(|err| E::ListParts(Box::new(err)))?;
+
+    let mut file_name: Option<String> = None;
+    let mut indexed_parts = Vec::with_capacity(parts.len());
+    for part in parts {
+        let part_name = part.name.clone().unwrap_or_default();
+        let (name, index) = split_file::parse_part_name(&part_name)
+            .ok_or_else(|| E::InvalidPartName(part_name.clone()))?;
+
+        match &file_name {
+            Some(file_name) if file_name != name => {
+                return Err(E::MixedParts(file_name.clone(), name.to_owned()));
+            }
+            Some(_) => {}
+            None => file_name = Some(name.to_owned()),
+        }
+
+        indexed_parts.push((index, part));
+    }
+
+    let file_name = file_name.ok_or_else(|| E::NoParts(FileIdentifier::from(folder)))?;
+    indexed_parts.sort_by_key(|(index, _)| *index);
+    if let Some(missing) = (0..)
+        .zip(&indexed_parts)
+        .find_map(|(expected, (index, _))| (expected != *index).then_some(expected))
+    {
+        return Err(E::MissingPart(missing));
+    }
+
+    let file_name = config.name.clone().unwrap_or(file_name);
+    let file_path = config.canonical_destination_root()?.join(&file_name);
+    match config.existing_file_action.resolve(&file_path, None).await {
+        Resolution::Write => {}
+        Resolution::Keep => {
+            println!("Skipping '{file_name}', it already exists");
+            return Ok(());
+        }
+        Resolution::Conflict => return Err(E::JoinedFileExists(file_path)),
+    }
+
+    let save_err = |source| E::SaveBodyToFile {
+        path: file_path.clone(),
+        source,
+    };
+    let tmp_file = TempFile::incomplete(&file_path);
+    let mut file = File::create(&tmp_file)
+        .await
+        .map_err(|err| save_err(errors::SaveBodyToFile::CreateFile(err)))?;
+
+    let mut unverified_count: usize = 0;
+    for (_, part) in &indexed_parts {
+        let part_id = part.id.as_deref().unwrap_or_default();
+        let part_name = part.name.as_deref().unwrap_or_default();
+        let checksum = Checksum::from_drive_file(part);
+        let verification = Verification::new(checksum.as_ref(), config);
+        if verification != Verification::Verified {
+            unverified_count += 1;
+        }
+
+        println!("Downloading part '{part_name}'");
+        let body = download_file(hub, part_id)
+            .await
+            .map_err(|err| E::DownloadFile(Box::new(err)))?;
+        write_body(
+            body,
+            &mut file,
+            verification.expected(checksum.as_ref()),
+            None,
+        )
+        .await
+        .map_err(save_err)?;
+    }
+
+    fs::rename(&tmp_file, &file_path)
+        .await
+        .map_err(|err| save_err(errors::SaveBodyToFile::RenameFile(err)))?;
+    let _ = tmp_file.keep();
+
+    println!(
+        "Successfully joined {} parts into {file_name}, {unverified_count} parts not verified",
+        indexed_parts.len()
     );
 
+    run_on_complete(config, &config.file_id, &file_path, None).await;
+
     Ok(())
 }
 
-async fn download_file(hub: &Hub, file_id: &str) -> Result<hyper::Body, google_drive3::Error> {
+/// Runs the `--on-complete` command for a file downloaded to `path`. The size is the one of the
+/// local file, which differs from the Drive one for decompressed and exported files.
+async fn run_on_complete(config: &Config, file_id: &str, path: &Path, md5: Option<&str>) {
+    if let Some(on_complete) = &config.on_complete {
+        let size = fs::metadata(path).await.ok().map(|metadata| metadata.len());
+        let event = hook::Event {
+            transfer: hook::Transfer::Download,
+            file_id,
+            path: Some(path),
+            md5,
+            size,
+        };
+        on_complete.run(&event).await;
+    }
+}
+
+/// Maps the relative path of each file of a recursive download to its drive metadata.
+#[derive(Debug, Default, Serialize)]
+struct DownloadManifest {
+    files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+    id: String,
+    md5: Option<String>,
+    modified_time: Option<String>,
+}
+
+impl DownloadManifest {
+    fn add(&mut self, path: &Path, file: &file_tree_drive::File) {
+        self.files.insert(
+            path.to_string_lossy().into_owned(),
+            ManifestEntry {
+                id: file.drive_id.clone(),
+                md5: file.md5_checksum.clone(),
+                modified_time: file
+                    .modified_time
+                    .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            },
+        );
+    }
+
+    fn write(&self, path: &Path) -> Result<(), errors::Download> {
+        use errors::Download as E;
+
+        let json = serde_json::to_string_pretty(self).map_err(E::SerializeManifest)?;
+        std::fs::write(path, json).map_err(|err| E::WriteManifest(path.to_owned(), err))
+    }
+}
+
+#[tracing::instrument(level = "info", skip(hub))]
+pub async fn download_file(hub: &Hub, file_id: &str) -> Result<hyper::Body, google_drive3::Error> {
     let (response, _) = hub
         .files()
         .get(file_id)
@@ -230,125 +691,269 @@ async fn download_file(hub: &Hub, file_id: &str) -> Result<hyper::Body, google_d
 
 // TODO: move to common
 pub async fn save_body_to_file(
-    mut body: hyper::Body,
+    body: hyper::Body,
+    file_path: &Path,
+    expected_checksum: Option<&Checksum>,
+) -> Result<(), errors::SaveBodyToFile> {
+    save_body_to_file_with_timeout(body, file_path, expected_checksum, None).await
+}
+
+/// Like [`save_body_to_file`], but fails with [`errors::SaveBodyToFile::Stalled`] if no data is
+/// received for `stall_timeout`.
+async fn save_body_to_file_with_timeout(
+    body: hyper::Body,
+    file_path: &Path,
+    expected_checksum: Option<&Checksum>,
+    stall_timeout: Option<Duration>,
+) -> Result<(), errors::SaveBodyToFile> {
+    use errors::SaveBodyToFile as E;
+
+    // Create temporary file, removed if the download fails
+    let tmp_file = TempFile::incomplete(file_path);
+    let file = File::create(&tmp_file).await.map_err(E::CreateFile)?;
+
+    write_body(body, file, expected_checksum, stall_timeout).await?;
+
+    // Rename temporary file to final file
+    fs::rename(&tmp_file, file_path)
+        .await
+        .map_err(E::RenameFile)?;
+    let _ = tmp_file.keep();
+    Ok(())
+}
+
+/// Like [`save_body_to_file`], but the body is gunzipped before being written to disk.
+///
+/// The checksum verification is performed on the compressed body, as received from Drive.
+pub async fn save_gzip_body_to_file(
+    body: hyper::Body,
     file_path: &Path,
-    expected_md5: Option<&Digest>,
+    expected_checksum: Option<&Checksum>,
 ) -> Result<(), errors::SaveBodyToFile> {
     use errors::SaveBodyToFile as E;
 
-    // Create temporary file
-    let tmp_file_path = file_path.with_extension("incomplete");
-    let file = File::create(&tmp_file_path).await.map_err(E::CreateFile)?;
+    let tmp_file = TempFile::incomplete(file_path);
+    let file = File::create(&tmp_file).await.map_err(E::CreateFile)?;
+
+    write_body(body, GzipDecoder::new(file), expected_checksum, None).await?;
+
+    fs::rename(&tmp_file, file_path)
+        .await
+        .map_err(E::RenameFile)?;
+    let _ = tmp_file.keep();
+    Ok(())
+}
+
+async fn write_body<W>(
+    mut body: hyper::Body,
+    writer: W,
+    expected_checksum: Option<&Checksum>,
+    stall_timeout: Option<Duration>,
+) -> Result<(), errors::SaveBodyToFile>
+where
+    W: AsyncWrite + Unpin,
+{
+    use errors::SaveBodyToFile as E;
 
-    // Wrap file in writer that calculates md5
-    let mut writer = Md5Writer::new(file);
+    // Wrap writer in writer that calculates the checksum, md5 is only a placeholder when drive
+    // does not provide any checksum
+    let algorithm = expected_checksum.map_or(checksum::Algorithm::Md5, Checksum::algorithm);
+    let mut writer = ChecksumWriter::new(writer, algorithm);
 
     // Read chunks from stream and write to file
-    while let Some(chunk_result) = body.next().await {
+    loop {
+        let next = match stall_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, body.next())
+                .await
+                .map_err(|_| E::Stalled(timeout))?,
+            None => body.next().await,
+        };
+        let Some(chunk_result) = next else {
+            break;
+        };
         let chunk = chunk_result.map_err(E::ReadChunk)?;
         writer.write_all(&chunk).await.map_err(E::WriteChunk)?;
     }
-
-    // Check md5
-    let md5_digest = writer.md5();
-    if let Some(expected_md5) = expected_md5 {
-        if *expected_md5 != md5_digest {
-            return Err(E::Md5Mismatch {
-                expected: *expected_md5,
-                actual: md5_digest,
+    writer.shutdown().await.map_err(E::WriteChunk)?;
+
+    // Check checksum
+    let actual = writer.checksum();
+    if let Some(expected) = expected_checksum {
+        if *expected != actual {
+            return Err(E::ChecksumMismatch {
+                expected: *expected,
+                actual,
             });
         }
     }
 
-    // Rename temporary file to final file
-    fs::rename(&tmp_file_path, file_path)
-        .await
-        .map_err(E::RenameFile)
+    Ok(())
 }
 
 // TODO: move to common
-pub async fn save_body_to_stdout(mut body: hyper::Body) -> Result<(), errors::SaveBodyToStdout> {
-    let mut stdout = io::stdout();
+pub async fn save_body_to_stdout(body: hyper::Body) -> Result<(), errors::SaveBodyToStdout> {
+    write_body_to(body, io::stdout()).await
+}
+
+pub async fn save_gzip_body_to_stdout(body: hyper::Body) -> Result<(), errors::SaveBodyToStdout> {
+    write_body_to(body, GzipDecoder::new(io::stdout())).await
+}
 
+async fn write_body_to<W>(
+    mut body: hyper::Body,
+    mut writer: W,
+) -> Result<(), errors::SaveBodyToStdout>
+where
+    W: AsyncWrite + Unpin,
+{
     // Read chunks from stream and write to stdout
     while let Some(chunk_result) = body.next().await {
         let chunk = chunk_result.map_err(errors::SaveBodyToStdout::ReadChunk)?;
-        stdout
+        writer
             .write_all(&chunk)
             .await
             .map_err(errors::SaveBodyToStdout::WriteChunk)?;
     }
 
-    Ok(())
+    writer
+        .shutdown()
+        .await
+        .map_err(errors::SaveBodyToStdout::WriteChunk)
 }
 
-fn err_if_file_exists(
-    file: &google_drive3::api::File,
+fn should_decompress(file: &google_drive3::api::File, config: &Config) -> bool {
+    config.auto_decompress && compression::is_gzip(file)
+}
+
+fn local_file_name<'a>(
+    file: &'a google_drive3::api::File,
     config: &Config,
-) -> Result<(), errors::Download> {
+) -> Result<Cow<'a, str>, errors::Download> {
+    if let Some(name) = &config.name {
+        return Ok(Cow::Owned(name.clone()));
+    }
+
+    let name = drive_file_name(file, config)?;
+    match local_name::sanitize(&name, config.sanitize_names) {
+        Cow::Borrowed(_) => Ok(name),
+        Cow::Owned(sanitized) => Ok(Cow::Owned(sanitized)),
+    }
+}
+
+/// The name of the file on Drive, with the extension of the export format or without the one of
+/// the compression.
+fn drive_file_name<'a>(
+    file: &'a google_drive3::api::File,
+    config: &Config,
+) -> Result<Cow<'a, str>, errors::Download> {
     let file_name = file
         .name
         .as_deref()
         .ok_or_else(|| errors::Download::MissingFileName(FileIdentifier::from(file)))?;
 
+    if let Some((_, extension)) = export_format(file, config)? {
+        let has_extension = Path::new(file_name)
+            .extension()
+            .is_some_and(|current| current.eq_ignore_ascii_case(extension.to_string()));
+        if has_extension {
+            Ok(Cow::Borrowed(file_name))
+        } else {
+            Ok(Cow::Owned(format!("{file_name}.{extension}")))
+        }
+    } else if should_decompress(file, config) {
+        Ok(Cow::Borrowed(compression::decompressed_file_name(
+            file_name,
+        )))
+    } else {
+        Ok(Cow::Borrowed(file_name))
+    }
+}
+
+/// Checks the local file the download writes to, `Keep` meaning that there is nothing to
+/// download. Directories and shortcuts are only checked for conflicts, the files they lead to
+/// are checked on their own.
+async fn resolve_existing_file(
+    file: &google_drive3::api::File,
+    config: &Config,
+) -> Result<Resolution, errors::Download> {
+    let file_name = local_file_name(file, config)?;
+
     let file_path = match &config.destination {
-        Destination::CurrentDir => Some(Path::new(".").join(file_name)),
-        Destination::Path(path) => Some(path.join(file_name)),
-        Destination::Stdout => None,
+        Destination::CurrentDir => Path::new(".").join(file_name.as_ref()),
+        Destination::Path(path) => path.join(file_name.as_ref()),
+        Destination::Stdout => return Ok(Resolution::Write),
     };
 
-    match file_path {
-        Some(path) => {
-            if path.exists() && config.existing_file_action == ExistingFileAction::Abort {
-                Err(errors::Download::FileExists(FileIdentifier::from(file)))
+    let checksum = Checksum::from_drive_file(file);
+    let resolution = config
+        .existing_file_action
+        .resolve(&file_path, checksum.as_ref())
+        .await;
+    let leads_to_other_files = drive_file::is_directory(file) || drive_file::is_shortcut(file);
+
+    match resolution {
+        Resolution::Conflict => Err(errors::Download::FileExists(FileIdentifier::from(file))),
+        Resolution::Keep if !leads_to_other_files => {
+            if config.existing_file_action == ExistingFileAction::Skip {
+                println!("Skipping '{file_name}', it already exists");
             } else {
-                Ok(())
+                println!("Skipping '{file_name}', it is identical to the local file");
             }
+            Ok(Resolution::Keep)
         }
-
-        None => Ok(()),
+        Resolution::Keep | Resolution::Write => Ok(Resolution::Write),
     }
 }
 
+/// Hashes the already existing local files concurrently, returning the drive ids of the files
+/// whose local copy is identical.
+async fn find_identical_local_files(
+    root_path: &Path,
+    folders: &[&file_tree_drive::Folder],
+    local_paths: &HashMap<String, PathBuf>,
+) -> HashSet<String> {
+    let candidates: Vec<_> = folders
+        .iter()
+        .flat_map(|folder| folder.files())
+        .filter_map(|file| Some((root_path.join(local_paths.get(&file.drive_id)?), file)))
+        .filter(|(path, _)| path.exists())
+        .collect();
+
+    stream::iter(candidates)
+        .map(|(path, file)| async move {
+            local_file_is_identical(&path, &file)
+                .await
+                .then_some(file.drive_id)
+        })
+        .buffer_unordered(MAX_CONCURRENT_HASHES)
+        .filter_map(|drive_id| async move { drive_id })
+        .collect()
+        .await
+}
+
 async fn local_file_is_identical(path: &Path, file: &file_tree_drive::File) -> bool {
-    if path.exists() {
-        match compute_md5_from_path(path).await {
-            Ok(file_md5) => file.md5.as_ref().is_some_and(|md5| md5 == &file_md5),
-            Err(err) => {
-                eprintln!(
-                    "Warning: Error while computing md5 of '{}': {}",
-                    path.display(),
-                    err.trace(),
-                );
-                false
-            }
-        }
-    } else {
-        false
+    match &file.checksum {
+        Some(expected) if path.exists() => transfer::local_file_matches(path, expected).await,
+        _ => false,
     }
 }
 
-async fn compute_md5_from_path(path: &Path) -> Result<Digest, io::Error> {
-    let input = File::open(path).await?;
-    let reader = BufReader::new(input);
-    compute_md5_from_async_reader(reader).await
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-async fn compute_md5_from_async_reader<R>(mut reader: R) -> Result<Digest, io::Error>
-where
-    R: AsyncRead + Unpin,
-{
-    let mut context = md5::Context::new();
-    let mut buffer = [0; 4096];
+    #[tokio::test]
+    async fn give_up_on_stalled_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stalled.bin");
+        let (mut sender, body) = hyper::Body::channel();
+        sender.send_data("partial".into()).await.unwrap();
 
-    loop {
-        match reader.read(&mut buffer).await {
-            Ok(0) => break,
-            Ok(count) => context.consume(&buffer[..count]),
-            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
-            Err(err) => return Err(err),
-        }
-    }
+        let timeout = Duration::from_millis(20);
+        let result = save_body_to_file_with_timeout(body, &path, None, Some(timeout)).await;
 
-    Ok(context.compute())
+        assert!(matches!(result, Err(errors::SaveBodyToFile::Stalled(t)) if t == timeout));
+        assert!(!path.exists());
+        assert!(!dir.path().join("stalled.bin.incomplete").exists());
+    }
 }
@@ -0,0 +1,182 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+
+use super::errors::Download;
+use crate::common::{file_tree_drive, local_name::SanitizeNames, FolderLike};
+
+/// What to do with a file of a recursive download whose local path is already taken by another
+/// file of the same download, as Drive allows identical names in a folder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnCollision {
+    /// Append " (1)", " (2)" and so on to the name, before the extension
+    #[default]
+    Suffix,
+    /// Append the id of the file to the name, before the extension
+    Id,
+    /// Only download the first of the files, in order of id
+    Skip,
+    /// Fail before downloading anything
+    Fail,
+}
+
+/// Assigns its local path, relative to the destination, to each downloadable file of the
+/// folders. Files left out by [`OnCollision::Skip`] have no path.
+///
+/// Drive lists files with the same name in no particular order, the files are taken by id so that
+/// the same file gets the same path on each download. Files keep their own name whenever it is
+/// free, the suffixed names are given afterwards.
+pub fn assign_paths(
+    folders: &[&file_tree_drive::Folder],
+    sanitize_names: SanitizeNames,
+    on_collision: OnCollision,
+) -> Result<HashMap<String, PathBuf>, Download> {
+    // Folders with the same name are merged, but a file cannot take the path of a folder
+    let mut taken: HashSet<String> = folders
+        .iter()
+        .map(|folder| collision_key(&folder.info.local_path(sanitize_names)))
+        .collect();
+    let mut paths = HashMap::new();
+
+    let mut files: Vec<_> = folders
+        .iter()
+        .flat_map(|folder| folder.files())
+        .filter(|file| file.downloadable)
+        .map(|file| (file.local_path(sanitize_names), file))
+        .collect();
+    files.sort_by(|(_, a), (_, b)| a.drive_id.cmp(&b.drive_id));
+
+    let mut colliding = Vec::new();
+    for (path, file) in files {
+        if taken.insert(collision_key(&path)) {
+            paths.insert(file.drive_id, path);
+        } else {
+            colliding.push((path, file));
+        }
+    }
+
+    for (path, file) in colliding {
+        let path = match on_collision {
+            // Fewer paths than this are taken, one of the suffixes is always free
+            OnCollision::Suffix => (1..=taken.len() + 1)
+                .map(|index| with_suffix(&path, &index.to_string()))
+                .find(|candidate| !taken.contains(&collision_key(candidate)))
+                .unwrap_or(path),
+            OnCollision::Id => with_suffix(&path, &file.drive_id),
+            OnCollision::Skip => {
+                println!(
+                    "Skipping file '{}' with id {}, another file has the same name",
+                    path.display(),
+                    file.drive_id
+                );
+                continue;
+            }
+            OnCollision::Fail => return Err(Download::NameCollision(path)),
+        };
+        taken.insert(collision_key(&path));
+        paths.insert(file.drive_id, path);
+    }
+
+    Ok(paths)
+}
+
+/// `report.pdf` becomes `report (suffix).pdf`.
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem} ({suffix}).{}", extension.to_string_lossy()),
+        None => format!("{stem} ({suffix})"),
+    };
+    path.with_file_name(name)
+}
+
+/// Paths differing only by case are the same file on Windows and macOS.
+pub(crate) fn collision_key(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if cfg!(any(windows, target_os = "macos")) {
+        path.to_lowercase()
+    } else {
+        path.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use super::{assign_paths, with_suffix, OnCollision};
+    use crate::common::{
+        file_tree_drive::{File, Folder, FolderInfo, Node},
+        local_name::SanitizeNames,
+    };
+
+    fn folder(files: &[(&str, &str)]) -> Folder {
+        let info = Arc::new(FolderInfo {
+            name: String::from("docs"),
+            parent: None,
+            drive_id: String::from("docs"),
+            shared_drive_id: None,
+        });
+        let children = files
+            .iter()
+            .map(|(id, name)| {
+                Node::File(File {
+                    name: (*name).to_owned(),
+                    size: 0,
+                    parent: Arc::clone(&info),
+                    drive_id: (*id).to_owned(),
+                    checksum: None,
+                    md5_checksum: None,
+                    modified_time: None,
+                    downloadable: true,
+                })
+            })
+            .collect();
+        Folder { info, children }
+    }
+
+    #[test]
+    fn suffixes_do_not_depend_on_listing_order() {
+        let listings = [
+            [("c", "a.txt"), ("b", "a.txt"), ("a", "a (1).txt")],
+            [("a", "a (1).txt"), ("b", "a.txt"), ("c", "a.txt")],
+        ];
+        for files in listings {
+            let folder = folder(&files);
+            let paths =
+                assign_paths(&[&folder], SanitizeNames::Never, OnCollision::Suffix).unwrap();
+
+            // The file named "a (1).txt" keeps its name, the suffix goes to the later id
+            assert_eq!(paths["a"], PathBuf::from("docs/a (1).txt"));
+            assert_eq!(paths["b"], PathBuf::from("docs/a.txt"));
+            assert_eq!(paths["c"], PathBuf::from("docs/a (2).txt"));
+        }
+    }
+
+    #[test]
+    fn skip_keeps_the_first_id() {
+        let folder = folder(&[("b", "a.txt"), ("a", "a.txt")]);
+        let paths = assign_paths(&[&folder], SanitizeNames::Never, OnCollision::Skip).unwrap();
+
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths["a"], PathBuf::from("docs/a.txt"));
+    }
+
+    #[test]
+    fn suffix_before_extension() {
+        assert_eq!(
+            with_suffix(Path::new("docs/report.pdf"), "1"),
+            PathBuf::from("docs/report (1).pdf")
+        );
+        assert_eq!(
+            with_suffix(Path::new("README"), "1a2B"),
+            PathBuf::from("README (1a2B)")
+        );
+    }
+}
@@ -1,10 +1,14 @@
-use std::{error::Error, fmt::Display, io, path::PathBuf};
+use std::{error::Error, fmt::Display, io, ops::Range, path::PathBuf, time::Duration};
 
-use md5::Digest;
-
-use crate::common::{
-    file_tree_drive::{self, errors::FileIdentifier},
-    hub_helper::GetHubError,
+use crate::{
+    common::{
+        checksum::Checksum,
+        drive_file::{DocType, FileExtension},
+        failure_manifest,
+        file_tree_drive::{self, errors::FileIdentifier},
+        hub_helper::GetHubError,
+    },
+    files,
 };
 
 #[derive(Debug)]
@@ -34,9 +38,30 @@ pub enum Download {
         path: PathBuf,
         source: SaveBodyToFile,
     },
+    SerializeManifest(serde_json::Error),
+    WriteManifest(PathBuf, io::Error),
+    FileStalled {
+        path: PathBuf,
+        timeout: Duration,
+    },
+    FailureManifest(failure_manifest::Error),
+    FailedDownloads(usize),
+    NameCollision(PathBuf),
+    ListParts(Box<files::list::Error>),
+    NoParts(FileIdentifier),
+    InvalidPartName(String),
+    MixedParts(String, String),
+    MissingPart(u64),
+    JoinedFileExists(PathBuf),
+    ExportFile(Box<google_drive3::Error>),
+    UnsupportedExportFormat(DocType, FileExtension),
 }
 
 impl Display for Download {
+    #[expect(
+        clippy::too_many_lines,
+        reason = "Download has many variants, one message each"
+    )]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Download::Hub(_) => f.write_str("unable to get drive hub"),
@@ -89,6 +114,59 @@ impl Display for Download {
             Download::SaveBodyToFile { path, source: _ } => {
                 write!(f, "unable to save body to file '{}'", path.display())
             }
+            Download::SerializeManifest(_) => f.write_str("unable to serialize download manifest"),
+            Download::WriteManifest(path, _) => {
+                write!(f, "unable to write download manifest '{}'", path.display())
+            }
+            Download::FileStalled { path, timeout } => write!(
+                f,
+                "gave up on '{}', no data received for {} seconds",
+                path.display(),
+                timeout.as_secs()
+            ),
+            Download::FailureManifest(err) => write!(f, "{err}"),
+            Download::FailedDownloads(count) => write!(f, "{count} files failed to download"),
+            Download::NameCollision(path) => write!(
+                f,
+                "multiple files would be saved as '{}', use --on-collision to tell them apart",
+                path.display()
+            ),
+            Download::ListParts(_) => f.write_str("unable to list file parts"),
+            Download::NoParts(identifier) => {
+                write!(
+                    f,
+                    "directory{} does not contain any part",
+                    identifier.display()
+                )
+            }
+            Download::InvalidPartName(name) => write!(
+                f,
+                "'{name}' is not a file part, expected a name like 'name.part00'"
+            ),
+            Download::MixedParts(a, b) => {
+                write!(f, "directory contains the parts of both '{a}' and '{b}'")
+            }
+            Download::MissingPart(index) => write!(f, "part {index} is missing"),
+            Download::JoinedFileExists(path) => write!(
+                f,
+                "file '{}' already exists, use --overwrite to overwrite it",
+                path.display()
+            ),
+            Download::ExportFile(_) => f.write_str("unable to export file from drive"),
+            Download::UnsupportedExportFormat(doc_type, extension) => {
+                write!(
+                    f,
+                    "a {doc_type} cannot be exported to {extension}, supported formats are: "
+                )?;
+                let types = doc_type.supported_export_types();
+                for (index, ty) in types.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{ty}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -106,9 +184,23 @@ impl Error for Download {
             | Download::DestinationPathNotADirectory(_)
             | Download::MissingShortcutTarget(_)
             | Download::IsShortcut(_)
-            | Download::StdoutNotValidDestination => None,
+            | Download::StdoutNotValidDestination
+            | Download::NoParts(_)
+            | Download::InvalidPartName(_)
+            | Download::MixedParts(_, _)
+            | Download::MissingPart(_)
+            | Download::JoinedFileExists(_)
+            | Download::FileStalled { .. }
+            | Download::FailedDownloads(_)
+            | Download::NameCollision(_)
+            | Download::UnsupportedExportFormat(..) => None,
+            Download::ExportFile(source) => Some(source),
+            Download::ListParts(source) => Some(source),
             Download::CreateDirectory(_, source)
-            | Download::CanonicalizeDestinationPath(_, source) => Some(source),
+            | Download::CanonicalizeDestinationPath(_, source)
+            | Download::WriteManifest(_, source) => Some(source),
+            Download::SerializeManifest(source) => Some(source),
+            Download::FailureManifest(source) => Some(source),
             Download::CopyFile(error) | Download::RenameFile(error) => Some(error),
             Download::SaveBodyToStdout(save_body_to_stdout) => Some(save_body_to_stdout),
             Download::SaveBodyToFile { source, .. } => Some(source),
@@ -153,8 +245,15 @@ pub enum SaveBodyToFile {
     CreateFile(io::Error),
     ReadChunk(hyper::Error),
     WriteChunk(io::Error),
-    Md5Mismatch { expected: Digest, actual: Digest },
+    ChecksumMismatch {
+        expected: Checksum,
+        actual: Checksum,
+    },
     RenameFile(io::Error),
+    RequestRange(Box<google_drive3::Error>),
+    RangesNotSupported,
+    IncompleteRange(Range<u64>),
+    Stalled(Duration),
 }
 
 impl Display for SaveBodyToFile {
@@ -163,13 +262,24 @@ impl Display for SaveBodyToFile {
             SaveBodyToFile::CreateFile(_) => f.write_str("unable to create file"),
             SaveBodyToFile::ReadChunk(_) => f.write_str("unable to read chunk of bytes"),
             SaveBodyToFile::WriteChunk(_) => f.write_str("unable to write chunk of bytes"),
-            SaveBodyToFile::Md5Mismatch { expected, actual } => {
-                write!(
-                    f,
-                    "md5 mismatches (expected {expected:x}, actual is {actual:x})"
-                )
-            }
+            SaveBodyToFile::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "{} mismatches (expected {expected}, actual is {actual})",
+                expected.algorithm()
+            ),
             SaveBodyToFile::RenameFile(_) => f.write_str("unable to rename file"),
+            SaveBodyToFile::RequestRange(_) => f.write_str("unable to request a range of bytes"),
+            SaveBodyToFile::RangesNotSupported => f.write_str(
+                "drive stopped honoring the ranges of this file, retry without --ranges",
+            ),
+            SaveBodyToFile::IncompleteRange(range) => write!(
+                f,
+                "the content received for bytes {}-{} does not match the requested range",
+                range.start, range.end
+            ),
+            SaveBodyToFile::Stalled(timeout) => {
+                write!(f, "no data received for {} seconds", timeout.as_secs())
+            }
         }
     }
 }
@@ -178,7 +288,11 @@ impl Error for SaveBodyToFile {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             SaveBodyToFile::ReadChunk(source) => Some(source),
-            SaveBodyToFile::Md5Mismatch { .. } => None,
+            SaveBodyToFile::RequestRange(source) => Some(source),
+            SaveBodyToFile::ChecksumMismatch { .. }
+            | SaveBodyToFile::RangesNotSupported
+            | SaveBodyToFile::IncompleteRange(_)
+            | SaveBodyToFile::Stalled(_) => None,
             SaveBodyToFile::CreateFile(source)
             | SaveBodyToFile::WriteChunk(source)
             | SaveBodyToFile::RenameFile(source) => Some(source),
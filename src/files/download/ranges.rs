@@ -0,0 +1,239 @@
+use std::{fs, io, ops::Range, path::Path, sync::Arc};
+
+use futures::{future, StreamExt};
+use google_drive3::{client::url::Params, hyper};
+
+use super::errors::SaveBodyToFile;
+use crate::{
+    common::{
+        checksum::Checksum, endpoints, tempfiles::TempFile, transfer::compute_checksum_from_path,
+    },
+    files,
+    hub::Hub,
+};
+
+/// Ranges smaller than this are not worth a request of their own.
+pub(crate) const MIN_RANGE_SIZE: u64 = 1024 * 1024;
+
+/// Received bytes are buffered up to this size before being written to disk.
+const WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Splits `size` bytes, which must not be zero, in at most `count` contiguous ranges of about
+/// the same size, none smaller than [`MIN_RANGE_SIZE`] unless the whole file is.
+pub(crate) fn split(size: u64, count: usize) -> Vec<Range<u64>> {
+    let max_count = size.div_ceil(MIN_RANGE_SIZE).max(1);
+    let count = u64::try_from(count).unwrap_or(u64::MAX).clamp(1, max_count);
+    let range_size = size.div_ceil(count).max(1);
+
+    (0..count)
+        .map(|index| index * range_size)
+        .take_while(|&start| start < size)
+        .map(|start| start..(start + range_size).min(size))
+        .collect()
+}
+
+/// Downloads the content of a file of `size` bytes to `file_path`, fetching up to
+/// `range_count` ranges concurrently and writing each one at its offset. When the server ignores
+/// the range of the first request, its whole content is saved as a regular download instead.
+pub(crate) async fn save_ranges_to_file(
+    hub: &Hub,
+    file_id: &str,
+    size: u64,
+    range_count: usize,
+    file_path: &Path,
+    expected_checksum: Option<&Checksum>,
+) -> Result<(), SaveBodyToFile> {
+    use SaveBodyToFile as E;
+
+    let mut ranges = split(size, range_count).into_iter();
+    let Some(first_range) = ranges.next() else {
+        return Err(E::IncompleteRange(0..size));
+    };
+    let response = request_range(hub, file_id, first_range.start, Some(first_range.end))
+        .await
+        .map_err(|err| E::RequestRange(Box::new(err)))?;
+    if response.status() != hyper::StatusCode::PARTIAL_CONTENT {
+        return files::download::save_body_to_file(
+            response.into_body(),
+            file_path,
+            expected_checksum,
+        )
+        .await;
+    }
+
+    let tmp_file = TempFile::incomplete(file_path);
+    let file = tokio::fs::File::create(&tmp_file)
+        .await
+        .map_err(E::CreateFile)?;
+    file.set_len(size).await.map_err(E::WriteChunk)?;
+    let file = Arc::new(file.into_std().await);
+
+    let first = write_range(response.into_body(), first_range, Arc::clone(&file));
+    let rest = future::try_join_all(
+        ranges.map(|range| save_range(hub, file_id, range, Arc::clone(&file))),
+    );
+    future::try_join(first, rest).await?;
+    drop(file);
+
+    if let Some(expected) = expected_checksum {
+        let actual = compute_checksum_from_path(&tmp_file, expected.algorithm())
+            .await
+            .map_err(E::WriteChunk)?;
+
+        if *expected != actual {
+            return Err(E::ChecksumMismatch {
+                expected: *expected,
+                actual,
+            });
+        }
+    }
+
+    tokio::fs::rename(&tmp_file, file_path)
+        .await
+        .map_err(E::RenameFile)?;
+    let _ = tmp_file.keep();
+    Ok(())
+}
+
+async fn save_range(
+    hub: &Hub,
+    file_id: &str,
+    range: Range<u64>,
+    file: Arc<fs::File>,
+) -> Result<(), SaveBodyToFile> {
+    let body = download_range(hub, file_id, &range).await?;
+    write_range(body, range, file).await
+}
+
+/// Writes the content of `range` received in `body` at its offset.
+async fn write_range(
+    mut body: hyper::Body,
+    range: Range<u64>,
+    file: Arc<fs::File>,
+) -> Result<(), SaveBodyToFile> {
+    use SaveBodyToFile as E;
+
+    let mut offset = range.start;
+    let mut buffer = Vec::with_capacity(WRITE_BUFFER_SIZE);
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(E::ReadChunk)?;
+        buffer.extend_from_slice(&chunk);
+
+        if buffer.len() >= WRITE_BUFFER_SIZE {
+            let full_buffer = std::mem::replace(&mut buffer, Vec::with_capacity(WRITE_BUFFER_SIZE));
+            offset = write_at(&file, full_buffer, offset, &range).await?;
+        }
+    }
+    if !buffer.is_empty() {
+        offset = write_at(&file, buffer, offset, &range).await?;
+    }
+
+    if offset == range.end {
+        Ok(())
+    } else {
+        Err(E::IncompleteRange(range))
+    }
+}
+
+/// Writes `buffer` at `offset`, returning the offset following it.
+async fn write_at(
+    file: &Arc<fs::File>,
+    buffer: Vec<u8>,
+    offset: u64,
+    range: &Range<u64>,
+) -> Result<u64, SaveBodyToFile> {
+    let end = offset + buffer.len() as u64;
+    if end > range.end {
+        return Err(SaveBodyToFile::IncompleteRange(range.clone()));
+    }
+
+    let file = Arc::clone(file);
+    tokio::task::spawn_blocking(move || write_all_at(&file, &buffer, offset))
+        .await
+        .map_err(io::Error::other)
+        .and_then(|result| result)
+        .map_err(SaveBodyToFile::WriteChunk)?;
+
+    Ok(end)
+}
+
+#[cfg(unix)]
+fn write_all_at(file: &fs::File, buffer: &[u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    file.write_all_at(buffer, offset)
+}
+
+#[cfg(windows)]
+fn write_all_at(file: &fs::File, mut buffer: &[u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buffer.is_empty() {
+        let written = file.seek_write(buffer, offset)?;
+        if written == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        buffer = &buffer[written..];
+        offset += written as u64;
+    }
+    Ok(())
+}
+
+async fn download_range(
+    hub: &Hub,
+    file_id: &str,
+    range: &Range<u64>,
+) -> Result<hyper::Body, SaveBodyToFile> {
+    use SaveBodyToFile as E;
+
+    let response = request_range(hub, file_id, range.start, Some(range.end))
+        .await
+        .map_err(|err| E::RequestRange(Box::new(err)))?;
+
+    match response.status() {
+        hyper::StatusCode::PARTIAL_CONTENT => Ok(response.into_body()),
+        _ => Err(E::RangesNotSupported),
+    }
+}
+
+/// Requests the content of a file from `start` to `end`, excluded, or to the end of the file.
+/// Servers ignoring the range answer with the whole content and a status other than 206.
+pub(crate) async fn request_range(
+    hub: &Hub,
+    file_id: &str,
+    start: u64,
+    end: Option<u64>,
+) -> Result<hyper::Response<hyper::Body>, google_drive3::Error> {
+    let mut params = Params::with_capacity(2);
+    params.push("alt", "media");
+    params.push("supportsAllDrives", "true");
+    let url = params.parse_with_url(&format!("{}files/{file_id}", endpoints::drive_api_url()));
+
+    let range = match end {
+        Some(end) => format!("bytes={start}-{}", end.saturating_sub(1)),
+        None => format!("bytes={start}-"),
+    };
+    let request = hyper::Request::builder()
+        .uri(url.as_str())
+        .header(hyper::header::RANGE, range);
+    hub.authorized_get(request, google_drive3::api::Scope::Full)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split, MIN_RANGE_SIZE};
+
+    #[test]
+    fn split_ranges() {
+        let size = 10 * MIN_RANGE_SIZE + 1;
+        let ranges = split(size, 4);
+        assert_eq!(ranges.len(), 4);
+        assert_eq!(ranges[0].start, 0);
+        assert_eq!(ranges[3].end, size);
+        assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+
+        assert_eq!(split(3 * MIN_RANGE_SIZE, 8).len(), 3);
+        assert_eq!(split(10, 8), vec![0..10]);
+    }
+}
@@ -1,17 +1,22 @@
+mod sheets;
+
 use std::{
     error,
     fmt::{Display, Formatter},
+    io,
     ops::Not,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use mime::Mime;
 
 use crate::{
     common::{
+        checksum::Checksum,
         drive_file::{DocType, FileExtension},
+        file_id::FileId,
         hub_helper::{get_hub, GetHubError},
-        parse_md5_digest,
+        transfer::{ExistingFileAction, Resolution},
     },
     files,
     hub::Hub,
@@ -19,41 +24,84 @@ use crate::{
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_id: String,
-    pub file_path: PathBuf,
+    pub file_id: FileId,
+    pub output: Output,
     pub existing_file_action: ExistingFileAction,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum ExistingFileAction {
-    Abort,
-    Overwrite,
+/// What is exported, and where.
+#[derive(Clone, Debug)]
+pub enum Output {
+    /// The whole document. The extension picks the export format. Without a known extension, the
+    /// default format of the document type is used and its extension appended. Spreadsheets
+    /// exported to csv or tsv only get their first sheet
+    Document(PathBuf),
+    /// The sheet of a spreadsheet with this title or index, to a csv or tsv file
+    Sheet { sheet: String, file_path: PathBuf },
+    /// Each sheet of a spreadsheet to a csv file named after it in `destination`
+    AllSheets { destination: PathBuf },
 }
 
 pub async fn export(config: Config) -> Result<(), Error> {
     let hub = get_hub().await.map_err(Error::Hub)?;
 
-    if config.file_path.exists() && config.existing_file_action == ExistingFileAction::Abort {
-        return Err(Error::FileExists(config.file_path));
-    }
-
     let file = files::info::get_file(&hub, &config.file_id)
         .await
         .map_err(|err| Error::GetFile(Box::new(err)))?;
+    let checksum = Checksum::from_drive_file(&file);
 
     let drive_mime = file.mime_type.ok_or(Error::MissingDriveMime)?;
     let Some(doc_type) = DocType::from_mime_type(&drive_mime) else {
         return Err(Error::UnsupportedDriveMime(drive_mime));
     };
 
-    let extension = FileExtension::from_path(&config.file_path)
-        .ok_or(Error::UnsupportedExportExtension(doc_type))?;
+    let path = match &config.output {
+        Output::Document(path) => path,
+        Output::Sheet { .. } | Output::AllSheets { .. } if doc_type != DocType::Spreadsheet => {
+            return Err(Error::NotASpreadsheet(doc_type));
+        }
+        Output::Sheet { sheet, file_path } => {
+            return sheets::export_sheet(
+                &hub,
+                &config.file_id,
+                sheet,
+                file_path,
+                config.existing_file_action,
+            )
+            .await;
+        }
+        Output::AllSheets { destination } => {
+            return sheets::export_all_sheets(
+                &hub,
+                &config.file_id,
+                destination,
+                config.existing_file_action,
+            )
+            .await;
+        }
+    };
+
+    let target = ExportTarget::new(path, doc_type)?;
+    let file_path = target.path;
+    if target.is_default {
+        println!(
+            "No known extension in '{}', exporting as {}, the default for a {doc_type}",
+            path.display(),
+            target.extension
+        );
+    }
 
-    if doc_type.can_export_to(extension).not() {
-        return Err(Error::UnsupportedExportExtension(doc_type));
+    // Exported documents have no checksum, an existing file is either kept or replaced
+    match config.existing_file_action.resolve(&file_path, None).await {
+        Resolution::Write => {}
+        Resolution::Keep => {
+            println!("Skipping {}, it already exists", file_path.display());
+            return Ok(());
+        }
+        Resolution::Conflict => return Err(Error::FileExists(file_path)),
     }
 
-    let mime_type = extension.get_export_mime();
+    let mime_type = target.extension.get_export_mime();
 
     let body = export_file(&hub, &config.file_id, mime_type)
         .await
@@ -63,19 +111,53 @@ pub async fn export(config: Config) -> Result<(), Error> {
         "Exporting {} '{}' to {}",
         doc_type,
         file.name.unwrap_or_default(),
-        config.file_path.display()
+        file_path.display()
     );
 
-    let md5_checksum = file.md5_checksum.as_deref().and_then(parse_md5_digest);
-    files::download::save_body_to_file(body, &config.file_path, md5_checksum.as_ref())
+    files::download::save_body_to_file(body, &file_path, checksum.as_ref())
         .await
         .map_err(Error::SaveFile)?;
 
-    println!("Successfully exported {}", config.file_path.display());
+    println!("Successfully exported {}", file_path.display());
 
     Ok(())
 }
 
+/// Where a document is exported, and in which format.
+#[derive(Debug, PartialEq, Eq)]
+struct ExportTarget {
+    path: PathBuf,
+    extension: FileExtension,
+    /// The path had no known extension, the default export format was picked
+    is_default: bool,
+}
+
+impl ExportTarget {
+    /// Takes the format from the extension of `path`. Without a known extension, i.e. `out` or
+    /// `notes.v2`, the default format of the document type is used and its extension appended.
+    fn new(path: &Path, doc_type: DocType) -> Result<ExportTarget, Error> {
+        if let Some(extension) = FileExtension::from_path(path) {
+            if doc_type.can_export_to(extension).not() {
+                return Err(Error::UnsupportedExportExtension(doc_type));
+            }
+            return Ok(ExportTarget {
+                path: path.to_path_buf(),
+                extension,
+                is_default: false,
+            });
+        }
+
+        let extension = doc_type.default_export_type();
+        let mut path = path.as_os_str().to_owned();
+        path.push(format!(".{extension}"));
+        Ok(ExportTarget {
+            path: PathBuf::from(path),
+            extension,
+            is_default: true,
+        })
+    }
+}
+
 pub async fn export_file(
     hub: &Hub,
     file_id: &str,
@@ -101,6 +183,12 @@ pub enum Error {
     UnsupportedDriveMime(String),
     UnsupportedExportExtension(DocType),
     SaveFile(files::download::errors::SaveBodyToFile),
+    NotASpreadsheet(DocType),
+    UnsupportedSheetExtension(FileExtension),
+    GetSheets(Box<google_drive3::Error>),
+    SheetNotFound(String),
+    MissingExportLink(FileExtension),
+    CreateDirectory(PathBuf, io::Error),
 }
 
 impl Display for Error {
@@ -141,6 +229,29 @@ impl Display for Error {
             Error::SaveFile(_) => {
                 write!(f, "failed to save file")
             }
+            Error::NotASpreadsheet(doc_type) => {
+                write!(f, "only spreadsheets have sheets, the file is a {doc_type}")
+            }
+            Error::UnsupportedSheetExtension(extension) => write!(
+                f,
+                "a single sheet cannot be exported to {extension}, only to csv and tsv"
+            ),
+            Error::GetSheets(_) => f.write_str(
+                "unable to get the sheets of the spreadsheet, the Sheets API must be enabled in \
+                the Google Cloud project of the account",
+            ),
+            Error::SheetNotFound(selector) => {
+                write!(
+                    f,
+                    "the spreadsheet has no sheet named or at index '{selector}'"
+                )
+            }
+            Error::MissingExportLink(extension) => {
+                write!(f, "drive offers no {extension} export of the spreadsheet")
+            }
+            Error::CreateDirectory(path, _) => {
+                write!(f, "unable to create directory '{}'", path.display())
+            }
         }
     }
 }
@@ -153,8 +264,44 @@ impl error::Error for Error {
             | Error::MissingDriveMime
             | Error::UnsupportedDriveMime(_)
             | Error::UnsupportedExportExtension(_)
-            | Error::SaveFile(_) => None,
-            Error::GetFile(source) | Error::ExportFile(source) => Some(source),
+            | Error::SaveFile(_)
+            | Error::NotASpreadsheet(_)
+            | Error::UnsupportedSheetExtension(_)
+            | Error::SheetNotFound(_)
+            | Error::MissingExportLink(_) => None,
+            Error::GetFile(source) | Error::ExportFile(source) | Error::GetSheets(source) => {
+                Some(source)
+            }
+            Error::CreateDirectory(_, source) => Some(source),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{Error, ExportTarget};
+    use crate::common::drive_file::{DocType, FileExtension};
+
+    #[test]
+    fn default_export_format() {
+        let target = ExportTarget::new(Path::new("out"), DocType::Document).unwrap();
+        assert_eq!(target.path, PathBuf::from("out.pdf"));
+        assert_eq!(target.extension, FileExtension::Pdf);
+        assert!(target.is_default);
+
+        let target = ExportTarget::new(Path::new("sales.v2"), DocType::Spreadsheet).unwrap();
+        assert_eq!(target.path, PathBuf::from("sales.v2.csv"));
+        assert!(target.is_default);
+
+        let target = ExportTarget::new(Path::new("sales.xlsx"), DocType::Spreadsheet).unwrap();
+        assert_eq!(target.path, PathBuf::from("sales.xlsx"));
+        assert!(!target.is_default);
+
+        assert!(matches!(
+            ExportTarget::new(Path::new("slides.docx"), DocType::Presentation),
+            Err(Error::UnsupportedExportExtension(DocType::Presentation))
+        ));
+    }
+}
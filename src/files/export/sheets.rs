@@ -0,0 +1,320 @@
+//! Export of single sheets of a spreadsheet. Drive exports only the first sheet to csv and tsv,
+//! the other ones are exported through the export link of the file with the `gid` of the sheet.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use google_drive3::hyper;
+use serde::Deserialize;
+
+use super::{Error, ExportTarget};
+use crate::{
+    common::{
+        drive_file::{DocType, FileExtension},
+        endpoints,
+        local_name::{self, SanitizeNames},
+        transfer::{ExistingFileAction, Resolution},
+    },
+    files::{self, download::collision},
+    hub::Hub,
+};
+
+/// A sheet of a spreadsheet, as described by the Sheets API.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct Sheet {
+    /// The `gid` of the sheet in its links, the first sheet usually has 0
+    #[serde(default, rename = "sheetId")]
+    gid: i64,
+    #[serde(default)]
+    title: String,
+    /// Position of the sheet in the spreadsheet, starting from 0
+    #[serde(default)]
+    index: usize,
+}
+
+#[derive(Deserialize)]
+struct Spreadsheet {
+    #[serde(default)]
+    sheets: Vec<SheetEntry>,
+}
+
+#[derive(Deserialize)]
+struct SheetEntry {
+    properties: Sheet,
+}
+
+/// Exports the sheet named `selector`, or at the `selector` index, to `file_path`, which must be
+/// a csv or tsv file.
+pub async fn export_sheet(
+    hub: &Hub,
+    file_id: &str,
+    selector: &str,
+    file_path: &Path,
+    existing_file_action: ExistingFileAction,
+) -> Result<(), Error> {
+    let target = ExportTarget::new(file_path, DocType::Spreadsheet)?;
+    if !matches!(target.extension, FileExtension::Csv | FileExtension::Tsv) {
+        return Err(Error::UnsupportedSheetExtension(target.extension));
+    }
+    if target.is_default {
+        println!(
+            "No known extension in '{}', exporting as {}",
+            file_path.display(),
+            target.extension
+        );
+    }
+
+    let sheets = get_sheets(hub, file_id).await?;
+    let sheet =
+        find_sheet(&sheets, selector).ok_or_else(|| Error::SheetNotFound(selector.to_owned()))?;
+    let export_link = get_export_link(hub, file_id, target.extension).await?;
+
+    save_sheet(hub, &export_link, sheet, &target.path, existing_file_action).await?;
+
+    Ok(())
+}
+
+/// Exports each sheet to a csv file named after it in `destination`, which is created if missing.
+pub async fn export_all_sheets(
+    hub: &Hub,
+    file_id: &str,
+    destination: &Path,
+    existing_file_action: ExistingFileAction,
+) -> Result<(), Error> {
+    let sheets = get_sheets(hub, file_id).await?;
+    let export_link = get_export_link(hub, file_id, FileExtension::Csv).await?;
+
+    tokio::fs::create_dir_all(destination)
+        .await
+        .map_err(|err| Error::CreateDirectory(destination.to_path_buf(), err))?;
+
+    let file_names = sheet_file_names(&sheets, FileExtension::Csv);
+    for (sheet, file_name) in sheets.iter().zip(file_names) {
+        let file_path = destination.join(file_name);
+        save_sheet(hub, &export_link, sheet, &file_path, existing_file_action).await?;
+    }
+
+    println!(
+        "Exported {} sheets to {}",
+        sheets.len(),
+        destination.display()
+    );
+
+    Ok(())
+}
+
+async fn save_sheet(
+    hub: &Hub,
+    export_link: &str,
+    sheet: &Sheet,
+    file_path: &Path,
+    existing_file_action: ExistingFileAction,
+) -> Result<(), Error> {
+    // Exported sheets have no checksum, an existing file is either kept or replaced
+    match existing_file_action.resolve(file_path, None).await {
+        Resolution::Write => {}
+        Resolution::Keep => {
+            println!("Skipping {}, it already exists", file_path.display());
+            return Ok(());
+        }
+        Resolution::Conflict => return Err(Error::FileExists(file_path.to_path_buf())),
+    }
+
+    println!(
+        "Exporting sheet '{}' to {}",
+        sheet.title,
+        file_path.display()
+    );
+
+    let request = hyper::Request::builder().uri(sheet_export_url(export_link, sheet.gid));
+    let response = hub
+        .authorized_get(request, google_drive3::api::Scope::Full)
+        .await
+        .map_err(|err| Error::ExportFile(Box::new(err)))?;
+    files::download::save_body_to_file(response.into_body(), file_path, None)
+        .await
+        .map_err(Error::SaveFile)?;
+
+    println!("Successfully exported {}", file_path.display());
+
+    Ok(())
+}
+
+/// The sheets of the spreadsheet, in the order they are shown.
+async fn get_sheets(hub: &Hub, file_id: &str) -> Result<Vec<Sheet>, Error> {
+    let url = format!(
+        "{}spreadsheets/{file_id}?fields=sheets.properties(sheetId,title,index)",
+        endpoints::sheets_api_url()
+    );
+    // The Sheets API requires the same authorization of the Drive API
+    let response = hub
+        .authorized_get(
+            hyper::Request::builder().uri(url),
+            google_drive3::api::Scope::Full,
+        )
+        .await
+        .map_err(|err| Error::GetSheets(Box::new(err)))?;
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|err| Error::GetSheets(Box::new(google_drive3::Error::HttpError(err))))?;
+    let spreadsheet: Spreadsheet = serde_json::from_slice(&body).map_err(|err| {
+        Error::GetSheets(Box::new(google_drive3::Error::JsonDecodeError(
+            String::from_utf8_lossy(&body).into_owned(),
+            err,
+        )))
+    })?;
+
+    let mut sheets: Vec<_> = spreadsheet
+        .sheets
+        .into_iter()
+        .map(|entry| entry.properties)
+        .collect();
+    sheets.sort_by_key(|sheet| sheet.index);
+    Ok(sheets)
+}
+
+async fn get_export_link(
+    hub: &Hub,
+    file_id: &str,
+    extension: FileExtension,
+) -> Result<String, Error> {
+    let (_, file) = hub
+        .files()
+        .get(file_id)
+        .param("fields", "id,exportLinks")
+        .supports_all_drives(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .doit()
+        .await
+        .map_err(|err| Error::GetFile(Box::new(err)))?;
+
+    file.export_links
+        .and_then(|mut links| links.remove(extension.get_export_mime().as_ref()))
+        .ok_or(Error::MissingExportLink(extension))
+}
+
+/// The sheet matching `selector` by title, or else by index.
+fn find_sheet<'a>(sheets: &'a [Sheet], selector: &str) -> Option<&'a Sheet> {
+    sheets
+        .iter()
+        .find(|sheet| sheet.title == selector)
+        .or_else(|| {
+            let index = selector.trim().parse::<usize>().ok()?;
+            sheets.iter().find(|sheet| sheet.index == index)
+        })
+}
+
+fn sheet_export_url(export_link: &str, gid: i64) -> String {
+    let separator = if export_link.contains('?') { '&' } else { '?' };
+    format!("{export_link}{separator}gid={gid}")
+}
+
+/// Sheet titles can contain slashes, they are always sanitized.
+fn sheet_file_name(title: &str, extension: FileExtension) -> PathBuf {
+    let name = local_name::sanitize(title, SanitizeNames::Always);
+    PathBuf::from(format!("{name}.{extension}"))
+}
+
+/// The file name of each sheet. Titles which are the same once sanitized, i.e. `Q1/Q2` and
+/// `Q1_Q2`, get " (1)", " (2)" and so on appended to the latter ones.
+fn sheet_file_names(sheets: &[Sheet], extension: FileExtension) -> Vec<PathBuf> {
+    let mut taken = HashSet::new();
+    sheets
+        .iter()
+        .map(|sheet| {
+            let name = sheet_file_name(&sheet.title, extension);
+            let name = if taken.contains(&collision::collision_key(&name)) {
+                // Fewer names than this are taken, one of the suffixes is always free
+                (1..=taken.len() + 1)
+                    .map(|index| collision::with_suffix(&name, &index.to_string()))
+                    .find(|candidate| !taken.contains(&collision::collision_key(candidate)))
+                    .unwrap_or(name)
+            } else {
+                name
+            };
+            taken.insert(collision::collision_key(&name));
+            name
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        find_sheet, sheet_export_url, sheet_file_name, sheet_file_names, Sheet, Spreadsheet,
+    };
+    use crate::common::drive_file::FileExtension;
+
+    #[test]
+    fn select_sheet_by_title_or_index() {
+        let spreadsheet: Spreadsheet = serde_json::from_str(
+            r#"{"sheets": [
+                {"properties": {"sheetId": 123, "title": "2", "index": 1}},
+                {"properties": {"title": "Sales"}},
+                {"properties": {"sheetId": 456, "title": "Q1/Q2", "index": 2}}
+            ]}"#,
+        )
+        .unwrap();
+        let sheets: Vec<Sheet> = spreadsheet
+            .sheets
+            .into_iter()
+            .map(|entry| entry.properties)
+            .collect();
+
+        assert_eq!(find_sheet(&sheets, "Sales").unwrap().gid, 0);
+        assert_eq!(find_sheet(&sheets, "0").unwrap().title, "Sales");
+        // Titles take precedence over indexes
+        assert_eq!(find_sheet(&sheets, "2").unwrap().gid, 123);
+        assert!(find_sheet(&sheets, "3").is_none());
+        assert!(find_sheet(&sheets, "Costs").is_none());
+
+        assert_eq!(
+            sheet_file_name(&sheets[2].title, FileExtension::Csv),
+            PathBuf::from("Q1_Q2.csv")
+        );
+    }
+
+    #[test]
+    fn sanitized_titles_collisions() {
+        let sheet = |title: &str| Sheet {
+            gid: 0,
+            title: title.to_owned(),
+            index: 0,
+        };
+        let sheets = [
+            sheet("Q1/Q2"),
+            sheet("Q1_Q2"),
+            sheet("Q1_Q2 (1)"),
+            sheet("Q3"),
+        ];
+
+        assert_eq!(
+            sheet_file_names(&sheets, FileExtension::Csv),
+            [
+                PathBuf::from("Q1_Q2.csv"),
+                PathBuf::from("Q1_Q2 (1).csv"),
+                PathBuf::from("Q1_Q2 (1) (1).csv"),
+                PathBuf::from("Q3.csv"),
+            ]
+        );
+    }
+
+    #[test]
+    fn export_url_with_gid() {
+        assert_eq!(
+            sheet_export_url(
+                "https://docs.google.com/spreadsheets/export?id=1a2B&exportFormat=csv",
+                456
+            ),
+            "https://docs.google.com/spreadsheets/export?id=1a2B&exportFormat=csv&gid=456"
+        );
+        assert_eq!(
+            sheet_export_url("https://example.com/export", 0),
+            "https://example.com/export?gid=0"
+        );
+    }
+}
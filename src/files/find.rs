@@ -0,0 +1,173 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    io,
+    path::PathBuf,
+};
+
+use crate::{
+    common::{
+        checksum::Algorithm,
+        drive_file::MIME_TYPE_DRIVE_FOLDER,
+        hub_helper::{get_hub, GetHubError},
+        size_format::SizeFormat,
+        table::{self, Table},
+        transfer,
+    },
+    files::{
+        self,
+        info::DateFormat,
+        list::{ListFilesConfig, ListQuery, ListSortOrder, QueryBuilder},
+    },
+};
+
+#[derive(Clone, Debug)]
+pub enum Md5 {
+    /// A checksum given as 32 hexadecimal digits
+    Hex(String),
+    /// The checksum of a local file
+    Of(PathBuf),
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub md5: Md5,
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub output: table::OutputFormat,
+    pub size_format: SizeFormat,
+}
+
+/// Finds the files whose content has the given md5. Drive cannot search by checksum, therefore
+/// every file of the account is listed and compared locally.
+pub async fn find(config: Config) -> Result<(), Error> {
+    let md5 = match &config.md5 {
+        Md5::Hex(hex) => parse_md5(hex).ok_or_else(|| Error::InvalidMd5(hex.clone()))?,
+        Md5::Of(path) => transfer::compute_checksum_from_path(path, Algorithm::Md5)
+            .await
+            .map(|checksum| checksum.to_string())
+            .map_err(|err| Error::Hash(path.clone(), err))?,
+    };
+
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let query = ListQuery::from(
+        QueryBuilder::new()
+            .not_mime_type(MIME_TYPE_DRIVE_FOLDER)
+            .trashed(false),
+    );
+    let files = files::list::list_files(
+        &hub,
+        ListFilesConfig {
+            query: &query,
+            order_by: &ListSortOrder::Custom(String::from("name")),
+            max_files: usize::MAX,
+            extra_fields: &[],
+            drive_id: None,
+            corpora: None,
+        },
+    )
+    .await
+    .map_err(|err| Error::ListFiles(Box::new(err)))?;
+
+    let searched = files.len();
+    let matches: Vec<_> = files
+        .into_iter()
+        .filter(|file| file.md5_checksum.as_deref() == Some(md5.as_str()))
+        .collect();
+
+    print_matches(&config, &matches);
+    eprintln!(
+        "Found {} files with md5 {md5} among {searched} files",
+        matches.len()
+    );
+
+    Ok(())
+}
+
+fn print_matches(config: &Config, files: &[google_drive3::api::File]) {
+    let values: Vec<[String; 4]> = files
+        .iter()
+        .map(|file| {
+            [
+                file.id.clone().unwrap_or_default(),
+                file.name.clone().unwrap_or_default(),
+                file.size
+                    .and_then(|size| u64::try_from(size).ok())
+                    .map(|size| config.size_format.display(size).to_string())
+                    .unwrap_or_default(),
+                file.created_time
+                    .map(|time| files::info::format_date_time(time, &DateFormat::Local))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let table = Table {
+        header: ["Id", "Name", "Size", "Created"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: &config.field_separator,
+            format: config.output,
+        },
+    );
+}
+
+/// Normalizes an md5 given in hexadecimal to the lowercase form reported by Drive.
+fn parse_md5(hex: &str) -> Option<String> {
+    let is_md5 = hex.len() == 32 && hex.bytes().all(|byte| byte.is_ascii_hexdigit());
+    is_md5.then(|| hex.to_ascii_lowercase())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    InvalidMd5(String),
+    Hash(PathBuf, io::Error),
+    ListFiles(Box<files::list::Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::InvalidMd5(hex) => {
+                write!(f, "'{hex}' is not an md5, expected 32 hexadecimal digits")
+            }
+            Error::Hash(path, _) => write!(f, "unable to compute the md5 of '{}'", path.display()),
+            Error::ListFiles(_) => f.write_str("unable to list files"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::InvalidMd5(_) => None,
+            Error::Hash(_, source) => Some(source),
+            Error::ListFiles(source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_md5;
+
+    #[test]
+    fn parse_md5_hex() {
+        assert_eq!(
+            parse_md5("D41D8CD98F00B204E9800998ECF8427E").as_deref(),
+            Some("d41d8cd98f00b204e9800998ecf8427e")
+        );
+        assert_eq!(parse_md5("d41d8cd98f00b204"), None);
+        assert_eq!(parse_md5("z41d8cd98f00b204e9800998ecf8427e"), None);
+    }
+}
@@ -2,51 +2,96 @@ use std::{
     error,
     fmt::{Display, Formatter},
     fs, io,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use crate::{
     common::{
         delegate::UploadDelegateConfig,
         drive_file::{self, DocType},
+        file_id::{self, FileId},
         file_info::{self, FileInfo},
+        file_tree::{self, FileTree},
         hub_helper::{get_hub, GetHubError},
+        id_gen::IdGen,
+        FileTreeLike, FolderLike,
     },
-    files::{self, info::DisplayConfig},
+    files::{self, info::DisplayConfig, mkdir},
+    hub::Hub,
 };
 
 #[derive(Clone, Debug)]
 pub struct Config {
     pub file_path: PathBuf,
-    pub parents: Option<Vec<String>>,
+    pub parents: Option<Vec<FileId>>,
     pub print_only_id: bool,
+    /// Import the supported files of a directory and its subdirectories, recreating the
+    /// directories on Drive
+    pub recursive: bool,
 }
 
 pub async fn import(config: Config) -> Result<(), Error> {
     let hub = get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
 
+    if config.file_path.is_dir() {
+        if !config.recursive {
+            return Err(Error::IsDirectory(config.file_path));
+        }
+        return import_directory(&hub, &config, &delegate_config).await;
+    }
+
+    let parents = config.parents.as_deref().map(file_id::to_strings);
     let doc_type =
         drive_file::DocType::from_file_path(&config.file_path).ok_or(Error::UnsupportedFileType)?;
-    let mime_type = doc_type.mime();
+    let file = import_file(
+        &hub,
+        &config.file_path,
+        doc_type,
+        parents,
+        config.print_only_id,
+        &delegate_config,
+    )
+    .await?;
 
-    let file = match fs::File::open(&config.file_path) {
+    if config.print_only_id {
+        print!("{}", file.id.unwrap_or_default());
+    } else {
+        println!("File successfully imported");
+        files::info::print_file_info(&file, &DisplayConfig::default());
+    }
+
+    Ok(())
+}
+
+async fn import_file(
+    hub: &Hub,
+    path: &Path,
+    doc_type: DocType,
+    parents: Option<Vec<String>>,
+    print_only_id: bool,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<google_drive3::api::File, Error> {
+    let file = match fs::File::open(path) {
         Ok(file) => file,
-        Err(err) => return Err(Error::OpenFile(config.file_path, err)),
+        Err(err) => return Err(Error::OpenFile(path.to_path_buf(), err)),
     };
 
     let file_info = match FileInfo::from_file(
         &file,
         file_info::Config {
-            file_path: &config.file_path,
-            mime_type: Some(mime_type),
-            parents: config.parents.clone(),
+            file_path: path,
+            mime_type: Some(doc_type.mime()),
+            parents,
+            app_properties: None,
+            description: None,
+            starred: false,
         },
     ) {
         Ok(file_info) => file_info,
         Err(source) => {
             return Err(Error::FileInfo {
-                path: config.file_path,
+                path: path.to_path_buf(),
                 source,
             })
         }
@@ -54,19 +99,87 @@ pub async fn import(config: Config) -> Result<(), Error> {
 
     let reader = std::io::BufReader::new(file);
 
-    if !config.print_only_id {
-        println!("Importing {} as a {}", config.file_path.display(), doc_type);
+    if !print_only_id {
+        println!("Importing {} as a {}", path.display(), doc_type);
     }
 
-    let file = files::upload::upload_file(&hub, reader, None, file_info, &delegate_config)
+    files::upload::upload_file(hub, reader, None, file_info, delegate_config)
         .await
-        .map_err(Error::UploadFile)?;
+        .map_err(Error::UploadFile)
+}
 
-    if config.print_only_id {
-        print!("{}", file.id.unwrap_or_default());
-    } else {
-        println!("File successfully imported");
-        files::info::print_file_info(&file, &DisplayConfig::default());
+/// Recreates the directory on Drive and imports each of its files with a supported type,
+/// skipping the others.
+async fn import_directory(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<(), Error> {
+    let mut ids = IdGen::new(hub, delegate_config);
+    let tree = FileTree::from_path(&config.file_path, &mut ids, &file_tree::Config::default())
+        .await
+        .map_err(Error::CreateFileTree)?;
+
+    let mut imported = 0;
+    let mut skipped = 0;
+    for folder in &tree.folders() {
+        let parents = match &folder.info.parent {
+            Some(parent) => Some(vec![FileId::from_drive(parent.drive_id.clone())]),
+            None => config.parents.clone(),
+        };
+        if !config.print_only_id {
+            println!(
+                "Creating directory '{}' with id: {}",
+                folder.relative_path().display(),
+                folder.info.drive_id
+            );
+        }
+        mkdir::create_directory(
+            hub,
+            &mkdir::Config {
+                id: Some(folder.info.drive_id.clone()),
+                name: folder.info.name.clone(),
+                parents,
+                ..mkdir::Config::default()
+            },
+            delegate_config,
+        )
+        .await
+        .map_err(|err| Error::Mkdir(folder.info.path.clone(), err))?;
+
+        for file in folder.files() {
+            let Some(doc_type) = DocType::from_file_path(&file.path) else {
+                if !config.print_only_id {
+                    println!("Skipping '{}', not a supported type", file.path.display());
+                }
+                skipped += 1;
+                continue;
+            };
+
+            let parents = Some(vec![folder.info.drive_id.clone()]);
+            let drive_file = import_file(
+                hub,
+                &file.path,
+                doc_type,
+                parents,
+                config.print_only_id,
+                delegate_config,
+            )
+            .await?;
+            imported += 1;
+
+            if config.print_only_id {
+                println!(
+                    "{}: {}",
+                    file.relative_path().display(),
+                    drive_file.id.unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    if !config.print_only_id {
+        println!("Imported {imported} files, skipped {skipped} files of unsupported types");
     }
 
     Ok(())
@@ -82,12 +195,17 @@ pub enum Error {
     },
     UploadFile(google_drive3::Error),
     UnsupportedFileType,
+    IsDirectory(PathBuf),
+    CreateFileTree(file_tree::errors::FileTree),
+    Mkdir(PathBuf, google_drive3::Error),
 }
 
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::FileInfo { source, .. } => Some(source),
+            Error::CreateFileTree(source) => Some(source),
+            Error::Mkdir(_, source) => Some(source),
             // FIXME: correctly impl std::error::Error
             _ => None,
         }
@@ -107,6 +225,15 @@ impl Display for Error {
             Error::UploadFile(err) => {
                 write!(f, "Failed to upload file: {err}")
             }
+            Error::IsDirectory(path) => write!(
+                f,
+                "'{}' is a directory, use --recursive to import its files",
+                path.display()
+            ),
+            Error::CreateFileTree(_) => f.write_str("unable to walk the directory"),
+            Error::Mkdir(path, _) => {
+                write!(f, "unable to create the directory '{}'", path.display())
+            }
             Error::UnsupportedFileType => {
                 const _: () = const {
                     assert!(
@@ -1,36 +1,93 @@
 use std::{
     error,
     fmt::{self, Display, Formatter},
+    str::FromStr,
 };
 
-use bytesize::ByteSize;
-use google_drive3::chrono::{
-    self,
-    format::{DelayedFormat, StrftimeItems},
-    DateTime,
+use google_drive3::{
+    chrono::{
+        self,
+        format::{Item, StrftimeItems},
+        DateTime,
+    },
+    client::url::Params,
+    hyper,
 };
 
 use crate::{
-    common::hub_helper::{get_hub, GetHubError},
+    common::{
+        endpoints,
+        file_id::FileId,
+        hub_helper::{get_metadata_hub, GetHubError},
+        size_format::SizeFormat,
+    },
     hub::Hub,
 };
 
 pub struct Config {
-    pub file_id: String,
-    pub size_in_bytes: bool,
+    pub file_id: FileId,
+    pub size_format: SizeFormat,
+    /// Request exactly these fields and print the response as JSON
+    pub fields: Option<String>,
+    /// Print only this field, failing when the file has none
+    pub only: Option<InfoField>,
+}
+
+/// A field printed alone by `info`, for scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoField {
+    Md5,
+    /// The size in bytes
+    Size,
+    /// The short lived link to the thumbnail, custom or generated by Drive
+    ThumbnailLink,
+}
+
+impl InfoField {
+    fn value(self, file: &google_drive3::api::File) -> Option<String> {
+        match self {
+            InfoField::Md5 => file.md5_checksum.clone(),
+            InfoField::Size => file.size.map(|size| size.to_string()),
+            InfoField::ThumbnailLink => file.thumbnail_link.clone(),
+        }
+    }
+}
+
+impl Display for InfoField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InfoField::Md5 => f.write_str("md5"),
+            InfoField::Size => f.write_str("size"),
+            InfoField::ThumbnailLink => f.write_str("thumbnail link"),
+        }
+    }
 }
 
 pub async fn info(config: Config) -> Result<(), Error> {
-    let hub = get_hub().await.map_err(Error::Hub)?;
+    let hub = get_metadata_hub().await.map_err(Error::Hub)?;
+
+    if let Some(fields) = &config.fields {
+        let file = get_file_json(&hub, &config.file_id, fields)
+            .await
+            .map_err(Error::GetFile)?;
+        println!("{file:#}");
+        return Ok(());
+    }
 
     let file = get_file(&hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
+    if let Some(field) = config.only {
+        let value = field.value(&file).ok_or(Error::MissingField(field))?;
+        println!("{value}");
+        return Ok(());
+    }
+
     print_file_info(
         &file,
         &DisplayConfig {
-            size_in_bytes: config.size_in_bytes,
+            size_format: config.size_format,
         },
     );
 
@@ -46,20 +103,50 @@ pub async fn get_file(
         .get(file_id)
         .param(
             "fields",
-            "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,\
-            description,webContentLink,webViewLink,shortcutDetails(targetId,targetMimeType)",
+            "id,name,size,createdTime,modifiedTime,md5Checksum,sha256Checksum,mimeType,parents,shared,\
+            description,webContentLink,webViewLink,shortcutDetails(targetId,targetMimeType),\
+            version,driveId,thumbnailLink",
         )
         .supports_all_drives(true)
-        .add_scope(google_drive3::api::Scope::Full)
+        .add_scope(hub.metadata_scope())
         .doit()
         .await?;
 
     Ok(file)
 }
 
+/// Gets the metadata of a file as raw JSON.
+///
+/// Unlike [`get_file`], fields unknown to the API bindings are kept, therefore any field
+/// supported by Drive can be requested.
+pub async fn get_file_json(
+    hub: &Hub,
+    file_id: &str,
+    fields: &str,
+) -> Result<serde_json::Value, google_drive3::Error> {
+    let mut params = Params::with_capacity(2);
+    params.push("fields", fields);
+    params.push("supportsAllDrives", "true");
+    let url = params.parse_with_url(&format!("{}files/{file_id}", endpoints::drive_api_url()));
+
+    let response = hub
+        .authorized_get(
+            hyper::Request::builder().uri(url.as_str()),
+            hub.metadata_scope(),
+        )
+        .await?;
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(google_drive3::Error::HttpError)?;
+
+    serde_json::from_slice(&body).map_err(|err| {
+        google_drive3::Error::JsonDecodeError(String::from_utf8_lossy(&body).into_owned(), err)
+    })
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct DisplayConfig {
-    pub size_in_bytes: bool,
+    pub size_format: SizeFormat,
 }
 
 pub(crate) fn print_file_info(file: &google_drive3::api::File, display_config: &DisplayConfig) {
@@ -71,8 +158,11 @@ pub(crate) fn print_file_info(file: &google_drive3::api::File, display_config: &
         modified_time,
         name,
         parents,
+        sha256_checksum,
         shared,
         size,
+        thumbnail_link,
+        version,
         web_view_link,
         ..
     } = file;
@@ -87,12 +177,21 @@ pub(crate) fn print_file_info(file: &google_drive3::api::File, display_config: &
             config: display_config,
         }),
     );
-    print_field("Created", created_time.map(format_date_time));
-    print_field("Modified", modified_time.map(format_date_time));
+    print_field(
+        "Created",
+        created_time.map(|time| format_date_time(time, &DateFormat::default())),
+    );
+    print_field(
+        "Modified",
+        modified_time.map(|time| format_date_time(time, &DateFormat::default())),
+    );
     print_field("MD5", md5_checksum.as_ref());
+    print_field("SHA256", sha256_checksum.as_ref());
     print_field("Shared", shared.map(format_bool));
     print_field("Parents", parents.as_deref().map(DisplayJoinedSlice));
     print_field("ViewUrl", web_view_link.as_ref());
+    print_field("ThumbnailUrl", thumbnail_link.as_ref());
+    print_field("Version", version.as_ref());
 }
 
 fn print_field(name: &str, value: Option<impl Display>) {
@@ -139,25 +238,88 @@ pub struct DisplayBytes<'a> {
 impl Display for DisplayBytes<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let &Self { bytes, config } = self;
+        write!(f, "{}", config.size_format.display(bytes))
+    }
+}
 
-        if config.size_in_bytes {
-            write!(f, "{bytes}")
+/// How dates are displayed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// Local time as `%Y-%m-%d %H:%M:%S`
+    #[default]
+    Local,
+    /// Local time formatted with a strftime pattern
+    Custom(String),
+    /// Time elapsed from now, like "2 days ago"
+    Relative,
+}
+
+impl FromStr for DateFormat {
+    type Err = InvalidDateFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if StrftimeItems::new(s).any(|item| item == Item::Error) {
+            Err(InvalidDateFormat)
         } else {
-            write!(f, "{}", ByteSize::b(bytes).display().si())
+            Ok(DateFormat::Custom(s.to_owned()))
         }
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidDateFormat;
+
+impl Display for InvalidDateFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("not a valid strftime format")
+    }
+}
+
+impl error::Error for InvalidDateFormat {}
+
 #[must_use]
-pub fn format_date_time(utc_time: DateTime<chrono::Utc>) -> DelayedFormat<StrftimeItems<'static>> {
+pub fn format_date_time(utc_time: DateTime<chrono::Utc>, format: &DateFormat) -> String {
     let local_time = DateTime::<chrono::Local>::from(utc_time);
-    local_time.format("%Y-%m-%d %H:%M:%S")
+
+    match format {
+        DateFormat::Local => local_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        DateFormat::Custom(pattern) => local_time.format(pattern).to_string(),
+        DateFormat::Relative => format_relative_time(utc_time, chrono::Utc::now()),
+    }
+}
+
+fn format_relative_time(time: DateTime<chrono::Utc>, now: DateTime<chrono::Utc>) -> String {
+    const UNITS: [(&str, i64); 6] = [
+        ("year", 365 * 24 * 60 * 60),
+        ("month", 30 * 24 * 60 * 60),
+        ("day", 24 * 60 * 60),
+        ("hour", 60 * 60),
+        ("minute", 60),
+        ("second", 1),
+    ];
+
+    let seconds = (now - time).num_seconds();
+    let Some((unit, count)) = UNITS
+        .iter()
+        .map(|&(unit, unit_seconds)| (unit, seconds.abs() / unit_seconds))
+        .find(|&(_, count)| count > 0)
+    else {
+        return String::from("just now");
+    };
+
+    let plural = if count == 1 { "" } else { "s" };
+    if seconds < 0 {
+        format!("in {count} {unit}{plural}")
+    } else {
+        format!("{count} {unit}{plural} ago")
+    }
 }
 
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
     GetFile(google_drive3::Error),
+    MissingField(InfoField),
 }
 
 impl Display for Error {
@@ -165,6 +327,13 @@ impl Display for Error {
         match self {
             Error::Hub(_) => f.write_str("unable to get drive hub"),
             Error::GetFile(_) => f.write_str("unable to get file"),
+            Error::MissingField(InfoField::ThumbnailLink) => f.write_str(
+                "the file has no thumbnail, set one with --thumbnail on upload or update",
+            ),
+            Error::MissingField(field) => write!(
+                f,
+                "the file has no {field}, folders and Google Docs files have neither md5 nor size"
+            ),
         }
     }
 }
@@ -174,6 +343,46 @@ impl error::Error for Error {
         match self {
             Error::Hub(source) => Some(source),
             Error::GetFile(source) => Some(source),
+            Error::MissingField(_) => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::chrono::{TimeDelta, Utc};
+
+    use super::{format_relative_time, DateFormat};
+
+    #[test]
+    fn relative_time() {
+        let now = Utc::now();
+
+        assert_eq!(format_relative_time(now, now), "just now");
+        assert_eq!(
+            format_relative_time(now - TimeDelta::seconds(1), now),
+            "1 second ago"
+        );
+        assert_eq!(
+            format_relative_time(now - TimeDelta::days(2), now),
+            "2 days ago"
+        );
+        assert_eq!(
+            format_relative_time(now + TimeDelta::hours(3), now),
+            "in 3 hours"
+        );
+        assert_eq!(
+            format_relative_time(now - TimeDelta::days(800), now),
+            "2 years ago"
+        );
+    }
+
+    #[test]
+    fn parse_date_format() {
+        assert_eq!(
+            "%d/%m/%Y".parse(),
+            Ok(DateFormat::Custom(String::from("%d/%m/%Y")))
+        );
+        assert!("%Q".parse::<DateFormat>().is_err());
+    }
+}
@@ -1,24 +1,43 @@
 use std::{
     borrow::Cow,
     cmp::min,
+    collections::{BTreeMap, HashMap},
     error,
     fmt::{self, Display, Formatter},
     io,
     str::FromStr,
 };
 
+use clap::ValueEnum;
+use futures::stream::{self, StreamExt};
+
 use crate::{
+    app_config,
     common::{
-        drive_file,
-        hub_helper::{get_hub, GetHubError},
+        drive_file, drive_path, error_report,
+        hub_helper::{get_metadata_hub, get_metadata_hub_by_account_name, GetHubError},
+        size_format::SizeFormat,
         table::{self, Table},
     },
-    files::{self, info::DisplayConfig},
+    drives,
+    files::{
+        self,
+        info::{DateFormat, DisplayConfig},
+    },
     hub::Hub,
 };
 
+pub mod query;
+mod tree;
+
+pub use query::QueryBuilder;
+
 const MAX_PAGE_SIZE: usize = 1000;
 
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "they are orthogonal one each other"
+)]
 pub struct Config {
     pub query: ListQuery,
     pub order_by: ListSortOrder,
@@ -26,25 +45,296 @@ pub struct Config {
     pub skip_header: bool,
     pub truncate_name: bool,
     pub field_separator: String,
+    pub output: table::OutputFormat,
+    pub all_accounts: bool,
+    pub parent_path: Option<ParentPath>,
+    pub links: bool,
+    pub date_format: DateFormat,
+    pub size_format: SizeFormat,
+    pub resolve_shortcuts: bool,
+    /// Print only the ids, one per record
+    pub flat: bool,
+    /// Terminate the records of the flat output with NUL instead of newline
+    pub null: bool,
+    /// List the shared drive with this name, or resolve `parent_path` against it
+    pub drive_name: Option<String>,
+    /// Print the folders and files below the listed folder as a tree
+    pub tree: bool,
+    /// How many levels of the tree to list, all of them when `None`
+    pub depth: Option<usize>,
+    /// Where to search, see [`Corpora`]. By default the drive of the listed folder, or the files
+    /// of the user
+    pub corpora: Option<Corpora>,
+    /// Shared drive searched with [`Corpora::Drive`]
+    pub drive_id: Option<String>,
 }
 
+/// The bodies of items the files are searched in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Corpora {
+    /// Files created by, opened by or shared directly with the user
+    User,
+    /// Files of a single shared drive, given with --drive or --drive-name
+    Drive,
+    /// Files shared with the domain of the user
+    Domain,
+    /// Files of My Drive and of all the shared drives the user is a member of. Slower and the
+    /// results may be incomplete
+    #[value(name = "allDrives")]
+    AllDrives,
+}
+
+impl Corpora {
+    fn as_str(self) -> &'static str {
+        match self {
+            Corpora::User => "user",
+            Corpora::Drive => "drive",
+            Corpora::Domain => "domain",
+            Corpora::AllDrives => "allDrives",
+        }
+    }
+}
+
+/// A folder given by a human readable path instead of its id.
+pub struct ParentPath {
+    pub path: String,
+    /// Resolve the path against this shared drive instead of My Drive
+    pub drive_id: Option<String>,
+}
+
+const HEADER: [&str; 5] = ["Id", "Name", "Type", "Size", "Created"];
+const LINKS_HEADER: [&str; 5] = ["Id", "Name", "ViewUrl", "DownloadUrl", "ExportUrls"];
+const LINK_FIELDS: [&str; 3] = ["webViewLink", "webContentLink", "exportLinks"];
+const SHORTCUTS_HEADER: [&str; 8] = [
+    "Id",
+    "Name",
+    "Type",
+    "Size",
+    "Created",
+    "TargetId",
+    "TargetType",
+    "TargetDrive",
+];
+const SHORTCUT_FIELDS: [&str; 1] = ["shortcutDetails(targetId,targetMimeType)"];
+const MAX_CONCURRENT_TARGET_REQUESTS: usize = 10;
+
 pub async fn list(config: Config) -> Result<(), Error> {
-    let hub = get_hub().await.map_err(Error::Hub)?;
-    let files = list_files(
-        &hub,
+    if config.all_accounts {
+        return list_all_accounts(&config).await;
+    }
+
+    let hub = get_metadata_hub().await.map_err(Error::Hub)?;
+    let config = resolve_drive_name(&hub, config).await?;
+    if config.corpora == Some(Corpora::Drive) && config.drive_id().is_none() {
+        return Err(Error::MissingDrive);
+    }
+    let query = config.resolve_query(&hub).await?;
+    if config.tree {
+        return tree::print(&hub, &config.tree_config(&query)).await;
+    }
+
+    let files = list_files(&hub, config.list_files_config(&query)).await?;
+
+    if config.flat {
+        let ids = files.into_iter().filter_map(|file| file.id);
+        let _ = table::write_records(io::stdout(), ids, config.null);
+        return Ok(());
+    }
+
+    if config.resolve_shortcuts {
+        let targets = get_shortcut_targets(&hub, &files).await?;
+        let table = Table {
+            header: SHORTCUTS_HEADER,
+            values: shortcut_rows(&config, files, &targets),
+        };
+        write_table(&config, table);
+        return Ok(());
+    }
+
+    let table = Table {
+        header: config.header(),
+        values: file_rows(&config, files),
+    };
+
+    write_table(&config, table);
+
+    Ok(())
+}
+
+/// Replaces the drive name by the id of the drive, in the query or in the parent path.
+async fn resolve_drive_name(hub: &Hub, mut config: Config) -> Result<Config, Error> {
+    let Some(name) = config.drive_name.take() else {
+        return Ok(config);
+    };
+
+    let drive_id = drives::list::find_drive_id(hub, &name)
+        .await
+        .map_err(Error::FindDrive)?;
+    match &mut config.parent_path {
+        Some(parent_path) => parent_path.drive_id = Some(drive_id),
+        // The whole drive is searched with the query as it is
+        None if config.corpora == Some(Corpora::Drive) => config.drive_id = Some(drive_id),
+        None => config.query = ListQuery::FilesOnDrive { drive_id },
+    }
+
+    Ok(config)
+}
+
+async fn list_all_accounts(config: &Config) -> Result<(), Error> {
+    let accounts = app_config::list_accounts().map_err(Error::ListAccounts)?;
+    let mut values: Vec<[String; 6]> = vec![];
+
+    for account in accounts {
+        let files = match list_account_files(&account, config).await {
+            Ok(files) => files,
+            Err(source) => {
+                return Err(Error::Account {
+                    name: account,
+                    source: Box::new(source),
+                })
+            }
+        };
+
+        values.extend(
+            file_rows(config, files)
+                .into_iter()
+                .map(|[col1, col2, col3, col4, col5]| {
+                    [account.clone(), col1, col2, col3, col4, col5]
+                }),
+        );
+    }
+
+    let [col1, col2, col3, col4, col5] = config.header();
+    let table = Table {
+        header: ["Account", col1, col2, col3, col4, col5],
+        values,
+    };
+
+    write_table(config, table);
+
+    Ok(())
+}
+
+async fn list_account_files(
+    account: &str,
+    config: &Config,
+) -> Result<Vec<google_drive3::api::File>, Error> {
+    let hub = get_metadata_hub_by_account_name(account)
+        .await
+        .map_err(Error::Hub)?;
+    let query = config.resolve_query(&hub).await?;
+    list_files(&hub, config.list_files_config(&query)).await
+}
+
+impl Config {
+    async fn resolve_query(&self, hub: &Hub) -> Result<Cow<'_, ListQuery>, Error> {
+        let Some(parent_path) = &self.parent_path else {
+            return Ok(Cow::Borrowed(&self.query));
+        };
+
+        let folder_id = drive_path::resolve_folder_path(
+            hub,
+            parent_path.drive_id.as_deref(),
+            &parent_path.path,
+        )
+        .await
+        .map_err(Error::ResolvePath)?;
+
+        Ok(Cow::Owned(ListQuery::FilesInFolder { folder_id }))
+    }
+
+    fn list_files_config<'a>(&'a self, query: &'a ListQuery) -> ListFilesConfig<'a> {
         ListFilesConfig {
-            query: &config.query,
-            order_by: &config.order_by,
-            max_files: config.max_files,
+            query,
+            order_by: &self.order_by,
+            max_files: self.max_files,
+            extra_fields: if self.links {
+                &LINK_FIELDS
+            } else if self.resolve_shortcuts {
+                &SHORTCUT_FIELDS
+            } else {
+                &[]
+            },
+            drive_id: self.drive_id(),
+            corpora: self.corpora,
+        }
+    }
+
+    fn tree_config<'a>(&'a self, query: &'a ListQuery) -> tree::Config<'a> {
+        let root_id = match query {
+            ListQuery::FilesInFolder { folder_id } => folder_id,
+            ListQuery::FilesOnDrive { drive_id } => drive_id,
+            _ => "root",
+        };
+
+        tree::Config {
+            root_id,
+            max_depth: self.depth,
+            order_by: &self.order_by,
+            drive_id: self.drive_id(),
+        }
+    }
+
+    fn drive_id(&self) -> Option<&str> {
+        match (&self.parent_path, &self.query) {
+            (Some(parent_path), _) => parent_path.drive_id.as_deref(),
+            (None, ListQuery::FilesOnDrive { drive_id }) => Some(drive_id),
+            (None, _) => self.drive_id.as_deref(),
+        }
+    }
+
+    fn header(&self) -> [&'static str; 5] {
+        if self.links {
+            LINKS_HEADER
+        } else {
+            HEADER
+        }
+    }
+}
+
+fn write_table<const COLUMNS: usize>(config: &Config, table: Table<&str, String, COLUMNS>) {
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: &config.field_separator,
+            format: config.output,
         },
-    )
-    .await?;
+    );
+}
+
+fn file_rows(config: &Config, files: Vec<google_drive3::api::File>) -> Vec<[String; 5]> {
+    if config.links {
+        return files
+            .into_iter()
+            .map(|file| {
+                let file_name = format_file_name(config, &file).into_owned();
+                let export_urls = file
+                    .export_links
+                    .map(|links| {
+                        // Sort by mime type to get a stable output
+                        let links: BTreeMap<_, _> = links.into_iter().collect();
+                        links.into_values().collect::<Vec<_>>().join(",")
+                    })
+                    .unwrap_or_default();
+
+                [
+                    file.id.unwrap_or_default(),
+                    file_name,
+                    file.web_view_link.unwrap_or_default(),
+                    file.web_content_link.unwrap_or_default(),
+                    export_urls,
+                ]
+            })
+            .collect();
+    }
 
     let mut values: Vec<[String; 5]> = vec![];
 
     for file in files {
         let file_type = simplified_file_type(&file);
-        let file_name = format_file_name(&config, &file).into_owned();
+        let file_name = format_file_name(config, &file).into_owned();
 
         values.push([
             file.id.unwrap_or_default(),
@@ -54,32 +344,121 @@ pub async fn list(config: Config) -> Result<(), Error> {
                 .map(|bytes| {
                     files::info::DisplayBytes {
                         bytes: bytes.try_into().unwrap_or(0),
-                        config: &DisplayConfig::default(),
+                        config: &DisplayConfig {
+                            size_format: config.size_format,
+                        },
                     }
                     .to_string()
                 })
                 .unwrap_or_default(),
             file.created_time
-                .map(|created_time| files::info::format_date_time(created_time).to_string())
+                .map(|created_time| {
+                    files::info::format_date_time(created_time, &config.date_format)
+                })
                 .unwrap_or_default(),
         ]);
     }
 
-    let table = Table {
-        header: ["Id", "Name", "Type", "Size", "Created"],
-        values,
-    };
+    values
+}
 
-    let _ = table::write(
-        io::stdout(),
-        table,
-        &table::DisplayConfig {
-            skip_header: config.skip_header,
-            separator: &config.field_separator,
-        },
-    );
+/// The target of a shortcut, as seen by the current account.
+enum ShortcutTarget {
+    Found {
+        file_type: &'static str,
+        drive_id: Option<String>,
+    },
+    Missing,
+    Inaccessible,
+}
 
-    Ok(())
+/// Fetches the targets of all the shortcuts in `files`, a few at a time.
+async fn get_shortcut_targets(
+    hub: &Hub,
+    files: &[google_drive3::api::File],
+) -> Result<HashMap<String, ShortcutTarget>, Error> {
+    let mut target_ids: Vec<_> = files.iter().filter_map(shortcut_target_id).collect();
+    target_ids.sort_unstable();
+    target_ids.dedup();
+
+    let mut results = stream::iter(target_ids)
+        .map(|target_id| async move {
+            let result = files::info::get_file(hub, target_id).await;
+            (target_id, result)
+        })
+        .buffer_unordered(MAX_CONCURRENT_TARGET_REQUESTS);
+
+    let mut targets = HashMap::new();
+    while let Some((target_id, result)) = results.next().await {
+        let target = match result {
+            Ok(file) => ShortcutTarget::Found {
+                file_type: simplified_file_type(&file),
+                drive_id: file.drive_id,
+            },
+            Err(err) => match error_report::http_status(&err) {
+                Some(404) => ShortcutTarget::Missing,
+                Some(403) => ShortcutTarget::Inaccessible,
+                _ => {
+                    return Err(Error::GetShortcutTarget {
+                        id: target_id.to_owned(),
+                        source: Box::new(err),
+                    })
+                }
+            },
+        };
+        targets.insert(target_id.to_owned(), target);
+    }
+
+    Ok(targets)
+}
+
+fn shortcut_target_id(file: &google_drive3::api::File) -> Option<&str> {
+    file.shortcut_details
+        .as_ref()
+        .and_then(|details| details.target_id.as_deref())
+}
+
+fn shortcut_rows(
+    config: &Config,
+    files: Vec<google_drive3::api::File>,
+    targets: &HashMap<String, ShortcutTarget>,
+) -> Vec<[String; 8]> {
+    let target_ids: Vec<_> = files
+        .iter()
+        .map(|file| shortcut_target_id(file).map(ToOwned::to_owned))
+        .collect();
+
+    file_rows(config, files)
+        .into_iter()
+        .zip(target_ids)
+        .map(|([col1, col2, col3, col4, col5], target_id)| {
+            let (target_type, target_drive) = match target_id.as_ref().map(|id| targets.get(id)) {
+                Some(Some(ShortcutTarget::Found {
+                    file_type,
+                    drive_id,
+                })) => (
+                    (*file_type).to_owned(),
+                    drive_id.clone().unwrap_or_default(),
+                ),
+                Some(Some(ShortcutTarget::Missing)) => (String::from("missing"), String::new()),
+                Some(Some(ShortcutTarget::Inaccessible)) => {
+                    (String::from("inaccessible"), String::new())
+                }
+                Some(None) | None => (String::new(), String::new()),
+            };
+
+            [
+                col1,
+                col2,
+                col3,
+                col4,
+                col5,
+                target_id.unwrap_or_default(),
+                target_type,
+                target_drive,
+            ]
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -87,6 +466,13 @@ pub struct ListFilesConfig<'a> {
     pub query: &'a ListQuery,
     pub order_by: &'a ListSortOrder,
     pub max_files: usize,
+    /// Additional file fields to request, on top of the ones always requested
+    pub extra_fields: &'a [&'a str],
+    /// Shared drive the listed files belong to. Restricting the search to the drive corpus is
+    /// required to get all the files of a shared drive, and it is faster.
+    pub drive_id: Option<&'a str>,
+    /// Searched corpora, the drive of `drive_id` or the files of the user when `None`
+    pub corpora: Option<Corpora>,
 }
 
 pub async fn list_files(
@@ -95,6 +481,13 @@ pub async fn list_files(
 ) -> Result<Vec<google_drive3::api::File>, Error> {
     let mut collected_files: Vec<google_drive3::api::File> = vec![];
     let mut next_page_token: Option<String> = None;
+    let mut file_fields =
+        String::from("id,name,md5Checksum,sha256Checksum,mimeType,size,createdTime,parents");
+    for field in config.extra_fields {
+        file_fields.push(',');
+        file_fields.push_str(field);
+    }
+    let fields = format!("files({file_fields}),nextPageToken");
 
     loop {
         let max_files = config.max_files - collected_files.len();
@@ -106,17 +499,22 @@ pub async fn list_files(
             req = req.page_token(&token);
         }
 
+        match (config.corpora, config.drive_id) {
+            (None | Some(Corpora::Drive), Some(drive_id)) => {
+                req = req.corpora(Corpora::Drive.as_str()).drive_id(drive_id);
+            }
+            (Some(corpora), _) => req = req.corpora(corpora.as_str()),
+            (None, None) => {}
+        }
+
         let (_, file_list) = req
             .page_size(page_size.try_into().unwrap_or(i32::MAX))
             .q(&config.query.to_string())
             .order_by(&config.order_by.to_string())
-            .add_scope(google_drive3::api::Scope::Full)
+            .add_scope(hub.metadata_scope())
             .supports_all_drives(true)
             .include_items_from_all_drives(true)
-            .param(
-                "fields",
-                "files(id,name,md5Checksum,mimeType,size,createdTime,parents),nextPageToken",
-            )
+            .param("fields", &fields)
             .doit()
             .await
             .map_err(|err| Error::ListFiles(Box::new(err)))?;
@@ -146,6 +544,14 @@ pub enum ListQuery {
     FilesInFolder {
         folder_id: String,
     },
+    /// Files owned by the given email address, `me` being the current account
+    OwnedBy {
+        email: String,
+    },
+    SharedWithMe,
+    /// Every file of the searched corpora which is not in the trash
+    NotTrashed,
+    /// A query used as it is, see [`QueryBuilder`] to build one with escaped values
     Custom(String),
     None,
 }
@@ -164,17 +570,27 @@ impl Display for ListQuery {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             ListQuery::RootNotTrashed => {
-                write!(f, "'root' in parents and trashed = false")
+                QueryBuilder::new().in_parent("root").trashed(false).fmt(f)
             }
 
-            ListQuery::FilesOnDrive { drive_id } => {
-                write!(f, "'{drive_id}' in parents and trashed = false")
-            }
+            ListQuery::FilesOnDrive { drive_id } => QueryBuilder::new()
+                .in_parent(drive_id)
+                .trashed(false)
+                .fmt(f),
 
-            ListQuery::FilesInFolder { folder_id } => {
-                write!(f, "'{folder_id}' in parents and trashed = false")
+            ListQuery::FilesInFolder { folder_id } => QueryBuilder::new()
+                .in_parent(folder_id)
+                .trashed(false)
+                .fmt(f),
+
+            ListQuery::OwnedBy { email } => {
+                QueryBuilder::new().owned_by(email).trashed(false).fmt(f)
             }
 
+            ListQuery::SharedWithMe => QueryBuilder::new().shared_with_me().trashed(false).fmt(f),
+
+            ListQuery::NotTrashed => QueryBuilder::new().trashed(false).fmt(f),
+
             ListQuery::Custom(query) => {
                 write!(f, "{query}")
             }
@@ -221,6 +637,19 @@ impl fmt::Display for ListSortOrder {
 pub enum Error {
     Hub(GetHubError),
     ListFiles(Box<google_drive3::Error>),
+    ListAccounts(app_config::errors::ListAccounts),
+    Account {
+        name: String,
+        source: Box<Error>,
+    },
+    ResolvePath(drive_path::Error),
+    FindDrive(drives::list::FindDriveError),
+    GetShortcutTarget {
+        id: String,
+        source: Box<google_drive3::Error>,
+    },
+    GetTreeRoot(Box<google_drive3::Error>),
+    MissingDrive,
 }
 
 impl Display for Error {
@@ -228,6 +657,19 @@ impl Display for Error {
         match self {
             Error::Hub(_) => f.write_str("unable to get drive hub"),
             Error::ListFiles(_) => f.write_str("unable to list files"),
+            Error::ListAccounts(_) => f.write_str("unable to list accounts"),
+            Error::Account { name, source: _ } => {
+                write!(f, "unable to list files for account '{name}'")
+            }
+            Error::ResolvePath(_) => f.write_str("unable to resolve parent path"),
+            Error::FindDrive(_) => f.write_str("unable to find the shared drive"),
+            Error::MissingDrive => {
+                f.write_str("searching the drive corpora needs --drive or --drive-name")
+            }
+            Error::GetShortcutTarget { id, source: _ } => {
+                write!(f, "unable to get shortcut target '{id}'")
+            }
+            Error::GetTreeRoot(_) => f.write_str("unable to get the folder to list as a tree"),
         }
     }
 }
@@ -236,7 +678,14 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::Hub(source) => Some(source),
-            Error::ListFiles(source) => Some(source),
+            Error::ListFiles(source)
+            | Error::GetShortcutTarget { source, .. }
+            | Error::GetTreeRoot(source) => Some(source),
+            Error::ListAccounts(source) => Some(source),
+            Error::Account { source, .. } => Some(source),
+            Error::ResolvePath(source) => Some(source),
+            Error::FindDrive(source) => Some(source),
+            Error::MissingDrive => None,
         }
     }
 }
@@ -304,7 +753,29 @@ fn truncate_middle(s: &str, max_length: usize) -> Cow<'_, str> {
 
 #[cfg(test)]
 mod tests {
-    use super::truncate_middle;
+    use super::{truncate_middle, ListQuery};
+
+    #[test]
+    fn owner_and_shared_queries() {
+        let query = ListQuery::OwnedBy {
+            email: String::from("o'brien@example.com"),
+        };
+        assert_eq!(
+            query.to_string(),
+            "'o\\'brien@example.com' in owners and trashed = false"
+        );
+        assert_eq!(
+            ListQuery::SharedWithMe.to_string(),
+            "sharedWithMe and trashed = false"
+        );
+        assert_eq!(
+            ListQuery::FilesInFolder {
+                folder_id: String::from("1a2B"),
+            }
+            .to_string(),
+            "'1a2B' in parents and trashed = false"
+        );
+    }
 
     #[test]
     fn truncate_middle_ascii() {
@@ -0,0 +1,173 @@
+use std::fmt::{self, Display, Formatter};
+
+use google_drive3::chrono::{DateTime, SecondsFormat, Utc};
+
+use super::ListQuery;
+
+/// Builds the `q` parameter of a files list request from typed conditions, all of which must
+/// hold. The values are escaped, therefore names with quotes or backslashes are matched as they
+/// are.
+///
+/// ```ignore
+/// let query = QueryBuilder::new()
+///     .in_parent("1a2B")
+///     .name_contains("John's")
+///     .trashed(false)
+///     .build();
+/// assert_eq!(query, "'1a2B' in parents and name contains 'John\\'s' and trashed = false");
+/// ```
+///
+/// See <https://developers.google.com/drive/api/guides/search-files>.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[must_use]
+pub struct QueryBuilder {
+    terms: Vec<String>,
+}
+
+impl QueryBuilder {
+    pub fn new() -> Self {
+        QueryBuilder::default()
+    }
+
+    pub fn name_equals(self, name: &str) -> Self {
+        self.term(format!("name = '{}'", escape_value(name)))
+    }
+
+    /// Names containing `name` at the start of a word, as Drive does not match substrings.
+    pub fn name_contains(self, name: &str) -> Self {
+        self.term(format!("name contains '{}'", escape_value(name)))
+    }
+
+    pub fn mime_type(self, mime_type: &str) -> Self {
+        self.term(format!("mimeType = '{}'", escape_value(mime_type)))
+    }
+
+    pub fn not_mime_type(self, mime_type: &str) -> Self {
+        self.term(format!("mimeType != '{}'", escape_value(mime_type)))
+    }
+
+    /// Files directly in the folder `parent_id`, which is the id of a shared drive for the files
+    /// at its root.
+    pub fn in_parent(self, parent_id: &str) -> Self {
+        self.term(format!("'{}' in parents", escape_value(parent_id)))
+    }
+
+    /// Files owned by the given email address, `me` being the current account.
+    pub fn owned_by(self, email: &str) -> Self {
+        self.term(format!("'{}' in owners", escape_value(email)))
+    }
+
+    pub fn shared_with_me(self) -> Self {
+        self.term(String::from("sharedWithMe"))
+    }
+
+    /// Shortcuts pointing to `target_id`.
+    pub fn shortcut_target(self, target_id: &str) -> Self {
+        self.term(format!(
+            "shortcutDetails.targetId = '{}'",
+            escape_value(target_id)
+        ))
+    }
+
+    pub fn trashed(self, trashed: bool) -> Self {
+        self.term(format!("trashed = {trashed}"))
+    }
+
+    /// Files modified after `time`, excluded.
+    pub fn modified_after(self, time: DateTime<Utc>) -> Self {
+        self.term(format!("modifiedTime > '{}'", format_time(time)))
+    }
+
+    /// Files modified before `time`, excluded.
+    pub fn modified_before(self, time: DateTime<Utc>) -> Self {
+        self.term(format!("modifiedTime < '{}'", format_time(time)))
+    }
+
+    /// A condition written in the query language, used as it is. It is enclosed in parentheses,
+    /// so that an `or` in it does not apply to the other conditions.
+    pub fn custom(self, fragment: &str) -> Self {
+        if fragment.trim().is_empty() {
+            return self;
+        }
+        self.term(format!("({fragment})"))
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    #[must_use]
+    pub fn build(&self) -> String {
+        self.terms.join(" and ")
+    }
+
+    fn term(mut self, term: String) -> Self {
+        self.terms.push(term);
+        self
+    }
+}
+
+impl Display for QueryBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.build())
+    }
+}
+
+impl From<QueryBuilder> for ListQuery {
+    fn from(builder: QueryBuilder) -> Self {
+        ListQuery::from(builder.build())
+    }
+}
+
+/// Escapes a value to be enclosed in single quotes in a query, i.e. a name containing quotes.
+#[must_use]
+pub fn escape_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::chrono::{TimeZone, Utc};
+
+    use super::{escape_value, QueryBuilder};
+    use crate::files::list::ListQuery;
+
+    #[test]
+    fn escape_quotes() {
+        assert_eq!(escape_value("John's"), "John\\'s");
+        assert_eq!(escape_value("a\\b"), "a\\\\b");
+        assert_eq!(escape_value("a\\'b"), "a\\\\\\'b");
+    }
+
+    #[test]
+    fn build_query() {
+        let query = QueryBuilder::new()
+            .in_parent("1a2B")
+            .name_contains("John's \\ notes")
+            .not_mime_type("application/vnd.google-apps.folder")
+            .modified_after(Utc.with_ymd_and_hms(2024, 5, 1, 2, 3, 4).unwrap())
+            .custom("starred or name = 'x'")
+            .trashed(false)
+            .build();
+
+        assert_eq!(
+            query,
+            "'1a2B' in parents and name contains 'John\\'s \\\\ notes' \
+            and mimeType != 'application/vnd.google-apps.folder' \
+            and modifiedTime > '2024-05-01T02:03:04Z' and (starred or name = 'x') \
+            and trashed = false"
+        );
+    }
+
+    #[test]
+    fn empty_query() {
+        let builder = QueryBuilder::new().custom(" ");
+        assert!(builder.is_empty());
+        assert!(matches!(ListQuery::from(builder), ListQuery::None));
+    }
+}
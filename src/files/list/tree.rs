@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use super::{
+    list_files, shortcut_target_id, Error, ListFilesConfig, ListQuery, ListSortOrder,
+    SHORTCUT_FIELDS,
+};
+use crate::{common::drive_file, files, hub::Hub};
+
+pub struct Config<'a> {
+    /// The folder at the top of the tree, `root` being My Drive
+    pub root_id: &'a str,
+    /// How many levels below the root to list, all of them when `None`
+    pub max_depth: Option<usize>,
+    pub order_by: &'a ListSortOrder,
+    pub drive_id: Option<&'a str>,
+}
+
+/// The children of a listed folder still to print, with the prefix drawing their ancestors.
+struct Level {
+    children: std::vec::IntoIter<google_drive3::api::File>,
+    prefix: String,
+}
+
+/// Prints the folders and files below the root, one folder listing at a time and without
+/// fetching anything but their metadata. Shortcuts are printed with their target and never
+/// followed, and a folder reached twice, i.e. through multiple parents, is listed only once.
+pub async fn print(hub: &Hub, config: &Config<'_>) -> Result<(), Error> {
+    let root = files::info::get_file(hub, config.root_id)
+        .await
+        .map_err(|err| Error::GetTreeRoot(Box::new(err)))?;
+    let root_id = root.id.clone().unwrap_or_else(|| config.root_id.to_owned());
+    println!("{}\t{root_id}", display_name(&root));
+
+    let mut listed = HashSet::from([root_id.clone()]);
+    let mut levels = vec![Level {
+        children: list_children(hub, config, &root_id).await?.into_iter(),
+        prefix: String::new(),
+    }];
+
+    while let Some(level) = levels.last_mut() {
+        let Some(file) = level.children.next() else {
+            levels.pop();
+            continue;
+        };
+        let is_last = level.children.len() == 0;
+        let prefix = level.prefix.clone();
+        let depth = levels.len();
+        let id = file.id.clone().unwrap_or_default();
+        let is_directory = drive_file::is_directory(&file);
+        let first_visit = !is_directory || listed.insert(id.clone());
+
+        let name = if first_visit {
+            display_name(&file)
+        } else {
+            format!("{} (already listed)", display_name(&file))
+        };
+        println!("{}\t{id}", tree_line(&prefix, is_last, &name));
+
+        let depth_reached = config.max_depth.is_some_and(|max_depth| depth >= max_depth);
+        if !is_directory || !first_visit || depth_reached {
+            continue;
+        }
+
+        levels.push(Level {
+            children: list_children(hub, config, &id).await?.into_iter(),
+            prefix: child_prefix(&prefix, is_last),
+        });
+    }
+
+    Ok(())
+}
+
+async fn list_children(
+    hub: &Hub,
+    config: &Config<'_>,
+    folder_id: &str,
+) -> Result<Vec<google_drive3::api::File>, Error> {
+    let query = ListQuery::FilesInFolder {
+        folder_id: folder_id.to_owned(),
+    };
+
+    list_files(
+        hub,
+        ListFilesConfig {
+            query: &query,
+            order_by: config.order_by,
+            max_files: usize::MAX,
+            extra_fields: &SHORTCUT_FIELDS,
+            drive_id: config.drive_id,
+            corpora: None,
+        },
+    )
+    .await
+}
+
+/// The name of the file, with a trailing slash for folders and the target for shortcuts.
+fn display_name(file: &google_drive3::api::File) -> String {
+    let name = file.name.as_deref().unwrap_or_default();
+    if drive_file::is_directory(file) {
+        format!("{name}/")
+    } else if let Some(target_id) = shortcut_target_id(file) {
+        format!("{name} -> {target_id}")
+    } else {
+        name.to_owned()
+    }
+}
+
+fn tree_line(prefix: &str, is_last: bool, name: &str) -> String {
+    let branch = if is_last { "└── " } else { "├── " };
+    format!("{prefix}{branch}{name}")
+}
+
+fn child_prefix(prefix: &str, is_last: bool) -> String {
+    let indent = if is_last { "    " } else { "│   " };
+    format!("{prefix}{indent}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{child_prefix, tree_line};
+
+    #[test]
+    fn draw_nested_lines() {
+        let prefix = child_prefix(&child_prefix("", false), true);
+        assert_eq!(prefix, "│       ");
+        assert_eq!(tree_line(&prefix, false, "a"), "│       ├── a");
+        assert_eq!(tree_line(&prefix, true, "b/"), "│       └── b/");
+    }
+}
@@ -1,47 +1,262 @@
 use std::{
+    collections::HashMap,
     error,
-    fmt::{Display, Formatter},
+    fmt::{self, Display, Formatter},
+    str::FromStr,
 };
 
 use crate::{
     common::{
         delegate::{UploadDelegate, UploadDelegateConfig},
         drive_file::MIME_TYPE_DRIVE_FOLDER,
+        drive_path::{self, ChildFolder, MY_DRIVE_ROOT_ID},
         empty_file::EmptyFile,
+        file_id::{self, FileId},
         hub_helper::{get_hub, GetHubError},
     },
     hub::Hub,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Config {
     pub id: Option<String>,
     pub name: String,
-    pub parents: Option<Vec<String>>,
+    pub parents: Option<Vec<FileId>>,
     pub print_only_id: bool,
+    /// Print id, name and link of the created directory as JSON
+    pub json: bool,
+    pub color: Option<FolderColor>,
+    /// Properties private to this application, set on the created directory
+    pub app_properties: Option<HashMap<String, String>>,
+    /// Take `name` as a slash separated path, reusing the folders that already exist and only
+    /// creating the missing ones
+    pub ensure: bool,
+    /// Create in the folder with this name below another one, instead of `parents`
+    pub parent_name: Option<ChildFolder>,
+    /// Create in the folder with this slash separated path from the root of My Drive, created if
+    /// missing, instead of `parents`
+    pub parent_path: Option<String>,
 }
 
 pub async fn mkdir(config: Config) -> Result<(), Error> {
     let hub = get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
 
+    let config = resolve_parent(&hub, config).await?;
+
+    if config.ensure {
+        let folders = ensure_path(&hub, &config, &delegate_config).await?;
+        print_ensured(&config, &folders);
+        return Ok(());
+    }
+
     let file = create_directory(&hub, &config, &delegate_config)
         .await
         .map_err(Error::CreateDirectory)?;
 
     if config.print_only_id {
         print!("{}", file.id.unwrap_or_default());
+    } else if config.json {
+        let json = serde_json::json!({
+            "id": file.id,
+            "name": file.name,
+            "webViewLink": file.web_view_link,
+        });
+        println!("{json:#}");
     } else {
         println!(
             "Created directory '{}' with id: {}",
             config.name,
             file.id.unwrap_or_default()
         );
+        if let Some(link) = file.web_view_link {
+            println!("View it at: {link}");
+        }
     }
 
     Ok(())
 }
 
+/// Replaces the parent given by name or by path with its id, creating the missing folders if
+/// requested.
+async fn resolve_parent(hub: &Hub, config: Config) -> Result<Config, Error> {
+    let quiet = config.print_only_id || config.json;
+    let parent_id = resolve_parent_id(
+        hub,
+        config.parent_name.as_ref(),
+        config.parent_path.as_deref(),
+        quiet,
+    )
+    .await?;
+    let Some(parent_id) = parent_id else {
+        return Ok(config);
+    };
+
+    Ok(Config {
+        parents: Some(vec![FileId::from_drive(parent_id)]),
+        parent_name: None,
+        parent_path: None,
+        ..config
+    })
+}
+
+/// Finds the id of the parent given by name below another folder, or by slash separated path from
+/// the root of My Drive, creating the missing folders if requested. `None` when neither is given.
+/// Unless `quiet`, the created folders are printed.
+pub async fn resolve_parent_id(
+    hub: &Hub,
+    parent_name: Option<&ChildFolder>,
+    parent_path: Option<&str>,
+    quiet: bool,
+) -> Result<Option<String>, Error> {
+    if let Some(parent_name) = parent_name {
+        let (parent_id, created) = parent_name
+            .resolve(hub)
+            .await
+            .map_err(Error::FindDirectory)?;
+        if created && !quiet {
+            println!(
+                "Created parent directory '{}' with id: {parent_id}",
+                parent_name.name
+            );
+        }
+        return Ok(Some(parent_id));
+    }
+
+    let Some(parent_path) = parent_path else {
+        return Ok(None);
+    };
+    let path_config = Config {
+        name: parent_path.to_owned(),
+        ..Config::default()
+    };
+    let folders = ensure_path(hub, &path_config, &UploadDelegateConfig::default()).await?;
+    if !quiet {
+        for folder in folders.iter().filter(|folder| folder.created) {
+            println!(
+                "Created parent directory '{}' with id: {}",
+                folder.path, folder.id
+            );
+        }
+    }
+
+    Ok(Some(
+        folders
+            .last()
+            .map(|folder| folder.id.clone())
+            .unwrap_or_default(),
+    ))
+}
+
+/// A folder of the path given to `mkdir --ensure`.
+#[derive(Debug, Clone)]
+pub struct EnsuredFolder {
+    pub path: String,
+    pub id: String,
+    pub created: bool,
+}
+
+/// Walks the path from the parent, or from the root of My Drive, creating the folders that do
+/// not exist yet. The color and the properties are only set on the last folder, when created.
+pub async fn ensure_path(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<Vec<EnsuredFolder>, Error> {
+    let mut parent_id = match config.parents.as_deref() {
+        None | Some([]) => MY_DRIVE_ROOT_ID.to_owned(),
+        Some([parent]) => parent.to_string(),
+        Some(_) => return Err(Error::MultipleParents),
+    };
+    let segments: Vec<&str> = drive_path::path_segments(&config.name).collect();
+    if segments.is_empty() {
+        return Err(Error::EmptyPath);
+    }
+    // The folders below the parent are in the same shared drive, if any
+    let drive_id = drive_path::shared_drive_id(hub, &parent_id)
+        .await
+        .map_err(Error::FindDirectory)?;
+
+    let mut folders: Vec<EnsuredFolder> = Vec::with_capacity(segments.len());
+    for (index, segment) in segments.iter().enumerate() {
+        let path = segments[..=index].join("/");
+
+        // Once a folder is created, the ones below it cannot exist
+        let already_created = folders.last().is_some_and(|folder| folder.created);
+        let existing = if already_created {
+            None
+        } else {
+            match drive_path::find_child_folder(hub, drive_id.as_deref(), &parent_id, segment).await
+            {
+                Ok(id) => Some(id),
+                Err(drive_path::Error::NotFound(_)) => None,
+                Err(drive_path::Error::Ambiguous(_)) => return Err(Error::Ambiguous(path)),
+                Err(err) => return Err(Error::FindDirectory(err)),
+            }
+        };
+
+        let folder = if let Some(id) = existing {
+            EnsuredFolder {
+                path,
+                id,
+                created: false,
+            }
+        } else {
+            let is_last = index + 1 == segments.len();
+            let file = create_directory(
+                hub,
+                &Config {
+                    name: (*segment).to_owned(),
+                    parents: Some(vec![FileId::from_drive(parent_id.clone())]),
+                    color: config.color.clone().filter(|_| is_last),
+                    app_properties: config.app_properties.clone().filter(|_| is_last),
+                    ..Config::default()
+                },
+                delegate_config,
+            )
+            .await
+            .map_err(Error::CreateDirectory)?;
+            EnsuredFolder {
+                path,
+                id: file.id.unwrap_or_default(),
+                created: true,
+            }
+        };
+        parent_id.clone_from(&folder.id);
+        folders.push(folder);
+    }
+
+    Ok(folders)
+}
+
+fn print_ensured(config: &Config, folders: &[EnsuredFolder]) {
+    if config.print_only_id {
+        if let Some(folder) = folders.last() {
+            print!("{}", folder.id);
+        }
+    } else if config.json {
+        let json: Vec<_> = folders
+            .iter()
+            .map(|folder| {
+                serde_json::json!({
+                    "path": folder.path,
+                    "id": folder.id,
+                    "created": folder.created,
+                })
+            })
+            .collect();
+        println!("{:#}", serde_json::Value::Array(json));
+    } else {
+        for folder in folders {
+            if folder.created {
+                println!("Created directory '{}' with id: {}", folder.path, folder.id);
+            } else {
+                println!("Found directory '{}' with id: {}", folder.path, folder.id);
+            }
+        }
+    }
+}
+
 pub async fn create_directory(
     hub: &Hub,
     config: &Config,
@@ -50,8 +265,10 @@ pub async fn create_directory(
     let dst_file = google_drive3::api::File {
         id: config.id.clone(),
         name: Some(config.name.clone()),
-        parents: config.parents.clone(),
+        parents: config.parents.as_deref().map(file_id::to_strings),
         mime_type: Some(MIME_TYPE_DRIVE_FOLDER.to_string()),
+        folder_color_rgb: config.color.as_ref().map(ToString::to_string),
+        app_properties: config.app_properties.clone(),
         ..google_drive3::api::File::default()
     };
 
@@ -60,7 +277,7 @@ pub async fn create_directory(
     let req = hub
         .files()
         .create(dst_file)
-        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
+        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,sha256Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true);
@@ -72,10 +289,49 @@ pub async fn create_directory(
     Ok(file)
 }
 
+/// The color of a folder, as a `#rrggbb` hex triplet. Drive replaces colors it does not offer in
+/// its palette with the closest one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderColor(String);
+
+impl FromStr for FolderColor {
+    type Err = InvalidFolderColor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(InvalidFolderColor);
+        }
+
+        Ok(FolderColor(format!("#{}", hex.to_ascii_lowercase())))
+    }
+}
+
+impl Display for FolderColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidFolderColor;
+
+impl Display for InvalidFolderColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("color must be a hex triplet like #4986e7")
+    }
+}
+
+impl error::Error for InvalidFolderColor {}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
     CreateDirectory(google_drive3::Error),
+    MultipleParents,
+    EmptyPath,
+    FindDirectory(drive_path::Error),
+    Ambiguous(String),
 }
 
 impl error::Error for Error {}
@@ -87,6 +343,38 @@ impl Display for Error {
             Error::CreateDirectory(err) => {
                 write!(f, "Failed to create directory on drive: {err}")
             }
+            Error::MultipleParents => {
+                f.write_str("A path can only be ensured in a single parent directory")
+            }
+            Error::EmptyPath => f.write_str("The path to ensure has no directory name"),
+            Error::FindDirectory(err) => write!(f, "Failed to look for directory: {err}"),
+            Error::Ambiguous(path) => write!(
+                f,
+                "Path '{path}' is ambiguous, multiple directories have this name"
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_folder_color() {
+        assert_eq!(
+            "#4986E7"
+                .parse::<FolderColor>()
+                .map(|color| color.to_string()),
+            Ok("#4986e7".to_string())
+        );
+        assert_eq!(
+            "16a765"
+                .parse::<FolderColor>()
+                .map(|color| color.to_string()),
+            Ok("#16a765".to_string())
+        );
+        assert_eq!("#fff".parse::<FolderColor>(), Err(InvalidFolderColor));
+        assert_eq!("#gggggg".parse::<FolderColor>(), Err(InvalidFolderColor));
+    }
+}
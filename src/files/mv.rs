@@ -7,16 +7,24 @@ use crate::{
     common::{
         delegate::{UploadDelegate, UploadDelegateConfig},
         drive_file,
+        file_id::FileId,
         hub_helper::{get_hub, GetHubError},
     },
-    files,
+    files::{self, copy::CopyConfig},
     hub::Hub,
 };
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_id: String,
-    pub to_folder_id: String,
+    pub file_id: FileId,
+    pub to_folder_id: FileId,
+    /// When moving between different drives fails, copy the file and trash the original instead
+    pub copy_fallback: bool,
+    /// Set the modified time of the moved file, or of its copy, back to the original one
+    pub keep_modified_time: bool,
+    /// Print only the id of the file after the move, which differs from the original one when
+    /// it was copied
+    pub print_only_id: bool,
 }
 
 pub async fn mv(config: Config) -> Result<(), Error> {
@@ -39,22 +47,97 @@ pub async fn mv(config: Config) -> Result<(), Error> {
 
     err_if_not_directory(&new_parent)?;
 
-    println!(
-        "Moving '{}' from '{}' to '{}'",
-        old_file.name.unwrap_or_default(),
-        old_parent.name.unwrap_or_default(),
-        new_parent.name.unwrap_or_default()
-    );
+    let crosses_drives = old_file.drive_id != new_parent.drive_id;
+    let is_directory = drive_file::is_directory(&old_file);
+
+    if !config.print_only_id {
+        println!(
+            "Moving '{}' from '{}' to '{}'",
+            old_file.name.as_deref().unwrap_or_default(),
+            old_parent.name.unwrap_or_default(),
+            new_parent.name.unwrap_or_default()
+        );
+    }
 
     let change_parent_config = ChangeParentConfig {
-        file_id: config.file_id,
+        file_id: config.file_id.to_string(),
         old_parent_id,
-        new_parent_id: config.to_folder_id,
+        new_parent_id: config.to_folder_id.to_string(),
+    };
+
+    let err = match change_parent(&hub, &delegate_config, &change_parent_config).await {
+        Ok(_) => {
+            if config.keep_modified_time {
+                files::update::restore_modified_time(
+                    &hub,
+                    &delegate_config,
+                    &old_file,
+                    &config.file_id,
+                )
+                .await
+                .map_err(|err| Error::RestoreModifiedTime(Box::new(err)))?;
+            }
+            if config.print_only_id {
+                print!("{}", config.file_id);
+            }
+            return Ok(());
+        }
+        Err(err) => Box::new(err),
     };
 
-    change_parent(&hub, &delegate_config, &change_parent_config)
+    if !crosses_drives {
+        return Err(Error::Move(err));
+    }
+    if !config.copy_fallback {
+        return Err(Error::MoveAcrossDrives(err));
+    }
+    if is_directory {
+        return Err(Error::CopyFallbackDirectory(err));
+    }
+
+    // The printed id differs from the given one, stderr still tells why
+    if config.print_only_id {
+        eprintln!("Moving across drives failed ({err}), copying the file instead");
+    } else {
+        println!("Moving across drives failed ({err}), copying the file instead");
+    }
+
+    let copy_config = CopyConfig {
+        file_id: config.file_id.to_string(),
+        to_folder_id: config.to_folder_id.to_string(),
+        name: None,
+    };
+    let new_file = files::copy::copy_file(&hub, &delegate_config, &copy_config)
         .await
-        .map_err(|err| Error::Move(Box::new(err)))?;
+        .map_err(|err| Error::Copy(Box::new(err)))?;
+
+    if config.keep_modified_time {
+        files::update::restore_modified_time(
+            &hub,
+            &delegate_config,
+            &old_file,
+            new_file.id.as_deref().unwrap_or_default(),
+        )
+        .await
+        .map_err(|err| Error::RestoreModifiedTime(Box::new(err)))?;
+    }
+
+    files::delete::trash_file(&hub, &config.file_id)
+        .await
+        .map_err(|err| Error::TrashOriginal(config.file_id.to_string(), Box::new(err)))?;
+
+    if config.print_only_id {
+        print!("{}", new_file.id.unwrap_or_default());
+        return Ok(());
+    }
+
+    println!(
+        "Copied '{}' to new id {}, the original {} has been moved to the trash",
+        old_file.name.unwrap_or_default(),
+        new_file.id.unwrap_or_default(),
+        config.file_id
+    );
+    println!("Note that links and permissions referring to the old id are not carried over");
 
     Ok(())
 }
@@ -79,7 +162,7 @@ pub async fn change_parent(
         .update(empty_file, &config.file_id)
         .remove_parents(&config.old_parent_id)
         .add_parents(&config.new_parent_id)
-        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
+        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,sha256Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true)
@@ -98,6 +181,11 @@ pub enum Error {
     MultipleParents,
     NotADirectory,
     Move(Box<google_drive3::Error>),
+    MoveAcrossDrives(Box<google_drive3::Error>),
+    CopyFallbackDirectory(Box<google_drive3::Error>),
+    Copy(Box<google_drive3::Error>),
+    TrashOriginal(String, Box<google_drive3::Error>),
+    RestoreModifiedTime(Box<google_drive3::Error>),
 }
 
 impl error::Error for Error {}
@@ -127,6 +215,36 @@ impl Display for Error {
             Error::Move(err) => {
                 write!(f, "Failed to move file: {err}")
             }
+            Error::MoveAcrossDrives(err) => {
+                write!(
+                    f,
+                    "Failed to move file to another drive: {err}. Use --copy-fallback to copy it \
+                    and trash the original instead, the copy will have a new id"
+                )
+            }
+            Error::CopyFallbackDirectory(err) => {
+                write!(
+                    f,
+                    "Failed to move directory to another drive: {err}. Directories can't be \
+                    copied as a fallback"
+                )
+            }
+            Error::Copy(err) => {
+                write!(f, "Failed to copy file to the other drive: {err}")
+            }
+            Error::TrashOriginal(id, err) => {
+                write!(
+                    f,
+                    "File copied to the other drive, but failed to trash the original '{id}': \
+                    {err}"
+                )
+            }
+            Error::RestoreModifiedTime(err) => {
+                write!(
+                    f,
+                    "File moved, but failed to restore its modified time: {err}"
+                )
+            }
         }
     }
 }
@@ -137,7 +255,7 @@ fn get_old_parent_id(file: &google_drive3::api::File) -> Result<String, Error> {
 
         Some(parents) => match &parents[..] {
             [] => Err(Error::NoParents),
-            [parent_id] => Ok(parent_id.to_string()),
+            [parent_id] => Ok(parent_id.clone()),
             _ => Err(Error::MultipleParents),
         },
     }
@@ -0,0 +1,100 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    io,
+    process::ExitStatus,
+};
+
+use tokio::process::Command;
+
+use crate::{
+    common::{
+        file_id::FileId,
+        hub_helper::{get_hub, GetHubError},
+    },
+    files,
+};
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub file_id: FileId,
+    /// Print the link instead of opening it
+    pub print: bool,
+}
+
+pub async fn open(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let file = files::info::get_file(&hub, &config.file_id)
+        .await
+        .map_err(|err| Error::GetFile(Box::new(err)))?;
+    let link = file
+        .web_view_link
+        .ok_or_else(|| Error::MissingLink(config.file_id.to_string()))?;
+
+    if config.print {
+        println!("{link}");
+        return Ok(());
+    }
+
+    let status = browser_command(&link)
+        .status()
+        .await
+        .map_err(Error::StartBrowser)?;
+    if !status.success() {
+        return Err(Error::Browser(status));
+    }
+
+    Ok(())
+}
+
+/// The command opening `url` with the default browser of the system.
+fn browser_command(url: &str) -> Command {
+    let mut command = if cfg!(target_os = "windows") {
+        // Unlike `start`, the url is not interpreted by the shell
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command.arg(url);
+    command
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    GetFile(Box<google_drive3::Error>),
+    MissingLink(String),
+    StartBrowser(io::Error),
+    Browser(ExitStatus),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFile(_) => f.write_str("unable to get file"),
+            Error::MissingLink(id) => write!(f, "file '{id}' has no web link"),
+            Error::StartBrowser(_) => {
+                f.write_str("unable to start the browser, use --print to get the link instead")
+            }
+            Error::Browser(status) => write!(f, "the browser exited with {status}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source) => Some(source),
+            Error::StartBrowser(source) => Some(source),
+            Error::MissingLink(_) | Error::Browser(_) => None,
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+};
+
+use crate::{
+    common::{
+        delegate::{UploadDelegate, UploadDelegateConfig},
+        drive_file::DocType,
+        file_id::FileId,
+        hub_helper::{get_hub, GetHubError},
+    },
+    files,
+    hub::Hub,
+};
+
+/// Number of revisions requested per page, the maximum allowed by the API.
+const PAGE_SIZE: i32 = 1000;
+
+const REVISION_FIELDS: &str = "id,published,publishAuto,publishedOutsideDomain,publishedLink";
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub file_id: FileId,
+    /// Stop publishing the document instead
+    pub unpublish: bool,
+    /// Keep publishing the new revisions of the document as it is edited
+    pub auto: bool,
+    /// Publish to everyone instead of only the users of the domain, for Workspace accounts
+    pub outside_domain: bool,
+}
+
+pub async fn publish(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let file = files::info::get_file(&hub, &config.file_id)
+        .await
+        .map_err(|err| Error::GetFile(Box::new(err)))?;
+    let mime_type = file.mime_type.unwrap_or_default();
+    if DocType::from_mime_type(&mime_type).is_none() {
+        return Err(Error::NotADocument {
+            id: config.file_id.into(),
+            mime_type,
+        });
+    }
+
+    // Publishing applies to a revision, the latest one is what the document currently shows
+    let revision_id = latest_revision_id(&hub, &config.file_id)
+        .await
+        .map_err(|err| Error::ListRevisions(Box::new(err)))?
+        .ok_or_else(|| Error::NoRevision(config.file_id.to_string()))?;
+
+    let revision = update_revision(&hub, &config, &revision_id)
+        .await
+        .map_err(|err| Error::UpdateRevision(Box::new(err)))?;
+
+    let name = file.name.unwrap_or_default();
+    if revision.published == Some(true) {
+        println!("Published revision {revision_id} of '{name}'");
+        if let Some(link) = revision.published_link {
+            println!("{link}");
+        }
+    } else {
+        println!("Unpublished revision {revision_id} of '{name}'");
+    }
+
+    Ok(())
+}
+
+async fn latest_revision_id(
+    hub: &Hub,
+    file_id: &str,
+) -> Result<Option<String>, google_drive3::Error> {
+    let delegate_config = UploadDelegateConfig::default();
+    let mut latest = None;
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut delegate = UploadDelegate::new(&delegate_config);
+        let mut req = hub
+            .revisions()
+            .list(file_id)
+            .page_size(PAGE_SIZE)
+            .param("fields", "nextPageToken,revisions(id)");
+        if let Some(token) = &next_page_token {
+            req = req.page_token(token);
+        }
+
+        let (_, revision_list) = req
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .doit()
+            .await?;
+
+        // Revisions are listed from the oldest to the newest
+        if let Some(id) = revision_list
+            .revisions
+            .and_then(|revisions| revisions.into_iter().last())
+            .and_then(|revision| revision.id)
+        {
+            latest = Some(id);
+        }
+
+        next_page_token = revision_list.next_page_token;
+        if next_page_token.is_none() {
+            return Ok(latest);
+        }
+    }
+}
+
+async fn update_revision(
+    hub: &Hub,
+    config: &Config,
+    revision_id: &str,
+) -> Result<google_drive3::api::Revision, google_drive3::Error> {
+    let revision = if config.unpublish {
+        google_drive3::api::Revision {
+            published: Some(false),
+            ..Default::default()
+        }
+    } else {
+        google_drive3::api::Revision {
+            published: Some(true),
+            publish_auto: Some(config.auto),
+            published_outside_domain: Some(config.outside_domain),
+            ..Default::default()
+        }
+    };
+
+    let delegate_config = UploadDelegateConfig::default();
+    let mut delegate = UploadDelegate::new(&delegate_config);
+    let (_, revision) = hub
+        .revisions()
+        .update(revision, &config.file_id, revision_id)
+        .param("fields", REVISION_FIELDS)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await?;
+
+    Ok(revision)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    GetFile(Box<google_drive3::Error>),
+    NotADocument { id: String, mime_type: String },
+    ListRevisions(Box<google_drive3::Error>),
+    NoRevision(String),
+    UpdateRevision(Box<google_drive3::Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFile(_) => f.write_str("unable to get file"),
+            Error::NotADocument { id, mime_type } => write!(
+                f,
+                "file '{id}' has type '{mime_type}', only Google Docs, Sheets and Slides can be published"
+            ),
+            Error::ListRevisions(_) => f.write_str("unable to list the revisions of the file"),
+            Error::NoRevision(id) => write!(f, "file '{id}' has no revision to publish"),
+            Error::UpdateRevision(_) => f.write_str("unable to update the revision"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source)
+            | Error::ListRevisions(source)
+            | Error::UpdateRevision(source) => Some(source),
+            Error::NotADocument { .. } | Error::NoRevision(_) => None,
+        }
+    }
+}
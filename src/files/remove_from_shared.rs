@@ -0,0 +1,250 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+};
+
+use crate::{
+    common::{
+        delegate::{UploadDelegate, UploadDelegateConfig},
+        drive_file::MIME_TYPE_DRIVE_SHORTCUT,
+        file_id::FileId,
+        hub_helper::{get_hub, GetHubError},
+        prompt::{self, ConfirmError},
+    },
+    files::list::{self, ListFilesConfig, ListQuery, ListSortOrder, QueryBuilder},
+    hub::Hub,
+    permissions,
+};
+
+pub struct Config {
+    pub file_id: FileId,
+    /// Give up the access to the item as well, which is the only way to hide it from "Shared
+    /// with me"
+    pub leave: bool,
+    /// Don't ask for confirmation before giving up the access
+    pub assume_yes: bool,
+}
+
+/// Tidies an item shared with the user: removes the shortcuts the user made to it and the
+/// folders of the user it was added to, and with [`Config::leave`] the permission of the user.
+///
+/// Drive has no request to hide an item from "Shared with me" and keep access to it, and deleting
+/// it is not an option since the user does not own it.
+pub async fn remove_from_shared(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let (_, file) = hub
+        .files()
+        .get(&config.file_id)
+        .param("fields", "id,name,ownedByMe,parents")
+        .supports_all_drives(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .doit()
+        .await
+        .map_err(|err| Error::GetFile(Box::new(err)))?;
+    let name = file.name.clone().unwrap_or_default();
+    if file.owned_by_me == Some(true) {
+        return Err(Error::OwnedByMe(name));
+    }
+
+    // Confirm before changing anything, declining must leave the item as it was
+    if config.leave {
+        prompt::confirm(
+            &format!("Give up your access to '{name}'? Only its owner can share it again"),
+            config.assume_yes,
+        )
+        .map_err(Error::Confirm)?;
+    }
+
+    let shortcuts = find_own_shortcuts(&hub, &config.file_id).await?;
+    for shortcut in &shortcuts {
+        let shortcut_id = shortcut.id.as_deref().unwrap_or_default();
+        hub.files()
+            .delete(shortcut_id)
+            .supports_all_drives(true)
+            .add_scope(google_drive3::api::Scope::Full)
+            .doit()
+            .await
+            .map_err(|err| Error::DeleteShortcut(shortcut_id.to_owned(), Box::new(err)))?;
+        println!(
+            "Deleted shortcut '{}' ({shortcut_id})",
+            shortcut.name.as_deref().unwrap_or_default()
+        );
+    }
+
+    let own_parents = find_own_parents(&hub, file.parents.as_deref().unwrap_or_default()).await?;
+    if !own_parents.is_empty() {
+        remove_parents(&hub, &delegate_config, &config.file_id, &own_parents)
+            .await
+            .map_err(|err| Error::RemoveParents(Box::new(err)))?;
+        println!(
+            "Removed '{name}' from {} of your folders: {}",
+            own_parents.len(),
+            own_parents.join(", ")
+        );
+    }
+
+    if config.leave {
+        leave(&hub, &delegate_config, &config.file_id).await?;
+        println!("Removed your access to '{name}'");
+    } else if shortcuts.is_empty() && own_parents.is_empty() {
+        println!(
+            "'{name}' has no shortcut nor folder of yours, Drive keeps it in \"Shared with me\" \
+            until you give up your access with --leave"
+        );
+    }
+
+    Ok(())
+}
+
+/// The shortcuts to `file_id` owned by the user, which show the item in their My Drive.
+async fn find_own_shortcuts(
+    hub: &Hub,
+    file_id: &str,
+) -> Result<Vec<google_drive3::api::File>, Error> {
+    let query = ListQuery::from(
+        QueryBuilder::new()
+            .owned_by("me")
+            .mime_type(MIME_TYPE_DRIVE_SHORTCUT)
+            .shortcut_target(file_id)
+            .trashed(false),
+    );
+    list::list_files(
+        hub,
+        ListFilesConfig {
+            query: &query,
+            order_by: &ListSortOrder::default(),
+            max_files: usize::MAX,
+            extra_fields: &[],
+            drive_id: None,
+            corpora: None,
+        },
+    )
+    .await
+    .map_err(|err| Error::FindShortcuts(Box::new(err)))
+}
+
+/// The parents the user owns, i.e. the folders the item was added to before shortcuts existed.
+async fn find_own_parents(hub: &Hub, parent_ids: &[String]) -> Result<Vec<String>, Error> {
+    let mut own_parents = Vec::new();
+    for parent_id in parent_ids {
+        let (_, parent) = hub
+            .files()
+            .get(parent_id)
+            .param("fields", "id,ownedByMe")
+            .supports_all_drives(true)
+            .add_scope(hub.metadata_scope())
+            .doit()
+            .await
+            .map_err(|err| Error::GetParent(parent_id.clone(), Box::new(err)))?;
+        if parent.owned_by_me == Some(true) {
+            own_parents.push(parent_id.clone());
+        }
+    }
+
+    Ok(own_parents)
+}
+
+async fn remove_parents(
+    hub: &Hub,
+    delegate_config: &UploadDelegateConfig,
+    file_id: &str,
+    parent_ids: &[String],
+) -> Result<google_drive3::api::File, google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let (_, file) = hub
+        .files()
+        .update(google_drive3::api::File::default(), file_id)
+        .remove_parents(&parent_ids.join(","))
+        .param("fields", "id,name,parents")
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .supports_all_drives(true)
+        .doit_without_upload()
+        .await?;
+
+    Ok(file)
+}
+
+/// Deletes the permission of the user, which only works when the item is shared with them
+/// directly, not through a group or their domain.
+async fn leave(
+    hub: &Hub,
+    delegate_config: &UploadDelegateConfig,
+    file_id: &str,
+) -> Result<(), Error> {
+    let (_, about) = hub
+        .about()
+        .get()
+        .param("fields", "user(permissionId)")
+        .add_scope(hub.metadata_scope())
+        .doit()
+        .await
+        .map_err(|err| Error::GetUser(Box::new(err)))?;
+    let permission_id = about
+        .user
+        .and_then(|user| user.permission_id)
+        .ok_or(Error::MissingPermissionId)?;
+
+    permissions::revoke::delete_permission(hub, delegate_config, file_id, &permission_id, false)
+        .await
+        .map_err(|err| Error::Leave(Box::new(err)))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    GetFile(Box<google_drive3::Error>),
+    OwnedByMe(String),
+    FindShortcuts(Box<list::Error>),
+    DeleteShortcut(String, Box<google_drive3::Error>),
+    GetParent(String, Box<google_drive3::Error>),
+    RemoveParents(Box<google_drive3::Error>),
+    Confirm(ConfirmError),
+    GetUser(Box<google_drive3::Error>),
+    MissingPermissionId,
+    Leave(Box<google_drive3::Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFile(_) => f.write_str("unable to get file"),
+            Error::OwnedByMe(name) => write!(
+                f,
+                "'{name}' is owned by you, it is not shared with you. Use delete to remove it"
+            ),
+            Error::FindShortcuts(_) => f.write_str("unable to find your shortcuts to the file"),
+            Error::DeleteShortcut(id, _) => write!(f, "unable to delete shortcut '{id}'"),
+            Error::GetParent(id, _) => write!(f, "unable to get parent folder '{id}'"),
+            Error::RemoveParents(_) => f.write_str("unable to remove the file from your folders"),
+            Error::Confirm(_) => f.write_str("access not given up"),
+            Error::GetUser(_) => f.write_str("unable to get the current user"),
+            Error::MissingPermissionId => f.write_str("drive did not report your permission id"),
+            Error::Leave(_) => f.write_str(
+                "unable to give up your access, which is not possible when the file is shared \
+                with a group or a domain you belong to",
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source)
+            | Error::DeleteShortcut(_, source)
+            | Error::GetParent(_, source)
+            | Error::RemoveParents(source)
+            | Error::GetUser(source)
+            | Error::Leave(source) => Some(source),
+            Error::FindShortcuts(source) => Some(source),
+            Error::Confirm(source) => Some(source),
+            Error::OwnedByMe(_) | Error::MissingPermissionId => None,
+        }
+    }
+}
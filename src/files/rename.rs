@@ -6,6 +6,8 @@ use std::{
 use crate::{
     common::{
         delegate::UploadDelegateConfig,
+        file_id::FileId,
+        file_version,
         hub_helper::{get_hub, GetHubError},
     },
     files::{self, update::PatchFile},
@@ -13,8 +15,9 @@ use crate::{
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_id: String,
+    pub file_id: FileId,
     pub name: String,
+    pub if_match: Option<i64>,
 }
 
 pub async fn rename(config: Config) -> Result<(), Error> {
@@ -25,13 +28,17 @@ pub async fn rename(config: Config) -> Result<(), Error> {
         .await
         .map_err(Error::GetFile)?;
 
+    if let Some(expected) = config.if_match {
+        file_version::check(&old_file, expected).map_err(Error::Version)?;
+    }
+
     println!(
         "Renaming {} to {}",
         old_file.name.unwrap_or_default(),
         config.name
     );
 
-    let patch_file = PatchFile::new(config.file_id).with_name(config.name);
+    let patch_file = PatchFile::new(config.file_id.into()).with_name(config.name);
 
     files::update::update_metadata(&hub, &delegate_config, patch_file)
         .await
@@ -45,6 +52,7 @@ pub enum Error {
     Hub(GetHubError),
     GetFile(google_drive3::Error),
     Rename(google_drive3::Error),
+    Version(file_version::Error),
 }
 
 impl error::Error for Error {}
@@ -59,6 +67,9 @@ impl Display for Error {
             Error::Rename(err) => {
                 write!(f, "Failed to rename file: {err}")
             }
+            Error::Version(err) => {
+                write!(f, "Refusing to rename file: {err}")
+            }
         }
     }
 }
@@ -0,0 +1,257 @@
+use std::{
+    collections::HashSet,
+    error,
+    fmt::{Display, Formatter},
+};
+
+use regex::Regex;
+
+use crate::{
+    common::{
+        bulk,
+        delegate::UploadDelegateConfig,
+        file_id::FileId,
+        hub_helper::{get_hub, GetHubError},
+    },
+    files::{
+        self,
+        list::{ListFilesConfig, ListQuery, ListSortOrder},
+        update::PatchFile,
+    },
+};
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub parent_id: FileId,
+    pub pattern: Regex,
+    /// Replacement for the first match of `pattern`, it can refer to capture groups with `$1`
+    /// or `${name}`
+    pub replacement: String,
+    pub dry_run: bool,
+    /// Stop renaming after the first failure
+    pub fail_fast: bool,
+}
+
+pub async fn rename_batch(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let children = files::list::list_files(
+        &hub,
+        ListFilesConfig {
+            query: &ListQuery::FilesInFolder {
+                folder_id: config.parent_id.to_string(),
+            },
+            order_by: &ListSortOrder::default(),
+            max_files: usize::MAX,
+            extra_fields: &[],
+            drive_id: None,
+            corpora: None,
+        },
+    )
+    .await
+    .map_err(|err| Error::ListFiles(Box::new(err)))?;
+
+    let children: Vec<_> = children
+        .into_iter()
+        .map(|file| Child {
+            id: file.id.unwrap_or_default(),
+            name: file.name.unwrap_or_default(),
+        })
+        .collect();
+
+    let renames = plan_renames(&children, &config.pattern, &config.replacement)?;
+
+    if config.dry_run {
+        for rename in &renames {
+            println!(
+                "Would rename '{}' to '{}'",
+                rename.old_name, rename.new_name
+            );
+        }
+        println!("{} files would be renamed", renames.len());
+        return Ok(());
+    }
+
+    let operations = renames.into_iter().map(|rename| bulk::Operation {
+        description: format!("rename '{}' to '{}'", rename.old_name, rename.new_name),
+        input: rename,
+    });
+    let bulk_config = bulk::Config {
+        fail_fast: config.fail_fast,
+        ..bulk::Config::default()
+    };
+    let summary = bulk::run(&bulk_config, operations, |rename| {
+        let patch_file = PatchFile::new(rename.id.clone()).with_name(rename.new_name.clone());
+        let (hub, delegate_config) = (&hub, &delegate_config);
+        async move {
+            files::update::update_metadata(hub, delegate_config, patch_file)
+                .await
+                .map(bulk::Outcome::Done)
+        }
+    })
+    .await;
+    summary.print();
+
+    if !summary.failed.is_empty() {
+        return Err(Error::FailedRenames(summary.failed.len()));
+    }
+
+    Ok(())
+}
+
+struct Child {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Rename {
+    id: String,
+    old_name: String,
+    new_name: String,
+}
+
+/// Computes the new names of the children matching `pattern`, refusing renames that would leave
+/// two children of the folder with the same name.
+fn plan_renames(
+    children: &[Child],
+    pattern: &Regex,
+    replacement: &str,
+) -> Result<Vec<Rename>, Error> {
+    let renames: Vec<_> = children
+        .iter()
+        .filter_map(|child| {
+            let new_name = pattern.replace(&child.name, replacement);
+            (new_name != child.name).then(|| Rename {
+                id: child.id.clone(),
+                old_name: child.name.clone(),
+                new_name: new_name.into_owned(),
+            })
+        })
+        .collect();
+
+    let renamed_ids: HashSet<_> = renames.iter().map(|rename| rename.id.as_str()).collect();
+    let mut names: HashSet<_> = children
+        .iter()
+        .filter(|child| !renamed_ids.contains(child.id.as_str()))
+        .map(|child| child.name.as_str())
+        .collect();
+
+    for rename in &renames {
+        if rename.new_name.is_empty() {
+            return Err(Error::EmptyName(rename.old_name.clone()));
+        }
+
+        if !names.insert(&rename.new_name) {
+            return Err(Error::NameCollision(rename.new_name.clone()));
+        }
+    }
+
+    Ok(renames)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    ListFiles(Box<files::list::Error>),
+    EmptyName(String),
+    NameCollision(String),
+    FailedRenames(usize),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::ListFiles(_) => f.write_str("unable to list files"),
+            Error::EmptyName(name) => write!(f, "'{name}' would be renamed to an empty name"),
+            Error::NameCollision(name) => write!(
+                f,
+                "more than one file would be named '{name}', no file has been renamed"
+            ),
+            Error::FailedRenames(count) => write!(f, "{count} files could not be renamed"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::ListFiles(source) => Some(source),
+            Error::EmptyName(_) | Error::NameCollision(_) | Error::FailedRenames(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::{plan_renames, Child, Error, Rename};
+
+    fn children(names: &[&str]) -> Vec<Child> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Child {
+                id: index.to_string(),
+                name: (*name).to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rename_matching_children() {
+        let pattern = Regex::new(r"^IMG_(\d+)\.jpg$").unwrap();
+        let renames = plan_renames(
+            &children(&["IMG_1.jpg", "notes.txt", "IMG_2.jpg"]),
+            &pattern,
+            "photo-$1.jpg",
+        )
+        .unwrap();
+
+        assert_eq!(
+            renames,
+            [
+                Rename {
+                    id: String::from("0"),
+                    old_name: String::from("IMG_1.jpg"),
+                    new_name: String::from("photo-1.jpg"),
+                },
+                Rename {
+                    id: String::from("2"),
+                    old_name: String::from("IMG_2.jpg"),
+                    new_name: String::from("photo-2.jpg"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn refuse_collisions() {
+        let pattern = Regex::new(r"\d+").unwrap();
+
+        assert!(matches!(
+            plan_renames(&children(&["a1", "a2"]), &pattern, "x"),
+            Err(Error::NameCollision(name)) if name == "ax"
+        ));
+        assert!(matches!(
+            plan_renames(&children(&["a1", "ax"]), &pattern, "x"),
+            Err(Error::NameCollision(name)) if name == "ax"
+        ));
+        // Swapping names is fine, Drive allows the duplicate name in between
+        let renames = plan_renames(
+            &children(&["a-b", "b-a"]),
+            &Regex::new(r"^(\w)-(\w)$").unwrap(),
+            "$2-$1",
+        )
+        .unwrap();
+        let new_names: Vec<_> = renames
+            .iter()
+            .map(|rename| (rename.old_name.as_str(), rename.new_name.as_str()))
+            .collect();
+        assert_eq!(new_names, [("a-b", "b-a"), ("b-a", "a-b")]);
+    }
+}
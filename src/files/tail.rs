@@ -0,0 +1,204 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    io,
+    str::FromStr,
+    time::Duration,
+};
+
+use futures::StreamExt;
+use google_drive3::hyper;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    common::{
+        file_filter,
+        file_id::FileId,
+        hub_helper::{get_hub, GetHubError},
+    },
+    files::{self, download::ranges},
+    hub::Hub,
+};
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub file_id: FileId,
+    /// Print this many bytes from the end of the file before following it
+    pub bytes: u64,
+    /// Keep printing the content appended to the file
+    pub follow: bool,
+    /// Time between two checks of the size of a followed file
+    pub interval: Interval,
+}
+
+pub async fn tail(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let size = file_size(&hub, &config.file_id).await?;
+    let mut offset = size.saturating_sub(config.bytes);
+    if size > offset {
+        offset = print_from(&hub, &config.file_id, offset).await?;
+    }
+
+    if !config.follow {
+        return Ok(());
+    }
+
+    loop {
+        tokio::time::sleep(config.interval.0).await;
+
+        let size = file_size(&hub, &config.file_id).await?;
+        if size < offset {
+            eprintln!("File truncated, following it from the start");
+            offset = 0;
+        }
+        if size > offset {
+            offset = print_from(&hub, &config.file_id, offset).await?;
+        }
+    }
+}
+
+async fn file_size(hub: &Hub, file_id: &str) -> Result<u64, Error> {
+    let file = files::info::get_file(hub, file_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    // Folders and Google Docs files have no content to follow
+    file.size
+        .and_then(|size| u64::try_from(size).ok())
+        .ok_or(Error::NoContent)
+}
+
+/// Prints the content of the file from `offset`, returning the offset following the printed
+/// content. The content appended meanwhile is printed as well.
+async fn print_from(hub: &Hub, file_id: &str, offset: u64) -> Result<u64, Error> {
+    let response = ranges::request_range(hub, file_id, offset, None)
+        .await
+        .map_err(Error::GetContent)?;
+
+    // The whole content comes back when the range is ignored
+    let mut to_skip = if response.status() == hyper::StatusCode::PARTIAL_CONTENT {
+        0
+    } else {
+        offset
+    };
+    let mut offset = offset;
+
+    let mut stdout = tokio::io::stdout();
+    let mut body = response.into_body();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(Error::ReadContent)?;
+        let skipped = usize::try_from(to_skip)
+            .unwrap_or(usize::MAX)
+            .min(chunk.len());
+        to_skip -= skipped as u64;
+
+        let new_content = &chunk[skipped..];
+        stdout.write_all(new_content).await.map_err(Error::Write)?;
+        offset += new_content.len() as u64;
+    }
+    stdout.flush().await.map_err(Error::Write)?;
+
+    Ok(offset)
+}
+
+/// Time between two checks of a followed file, given in seconds or with a unit: `30s`, `5m`,
+/// `1h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval(pub Duration);
+
+impl Default for Interval {
+    fn default() -> Self {
+        Interval(Duration::from_secs(10))
+    }
+}
+
+impl FromStr for Interval {
+    type Err = InvalidInterval;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let duration = match s.parse::<u64>() {
+            Ok(seconds) => Duration::from_secs(seconds),
+            Err(_) => file_filter::parse_age(s)
+                .and_then(|age| age.to_std().ok())
+                .ok_or(InvalidInterval)?,
+        };
+
+        if duration.is_zero() {
+            Err(InvalidInterval)
+        } else {
+            Ok(Interval(duration))
+        }
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}s", self.0.as_secs())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidInterval;
+
+impl Display for InvalidInterval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("not a valid interval, use a number of seconds or a duration like 30s or 5m")
+    }
+}
+
+impl error::Error for InvalidInterval {}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    GetFile(google_drive3::Error),
+    NoContent,
+    GetContent(google_drive3::Error),
+    ReadContent(hyper::Error),
+    Write(io::Error),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source) | Error::GetContent(source) => Some(source),
+            Error::NoContent => None,
+            Error::ReadContent(source) => Some(source),
+            Error::Write(source) => Some(source),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFile(_) => f.write_str("unable to get file"),
+            Error::NoContent => {
+                f.write_str("the file has no content to print, i.e. a folder or a Google Docs file")
+            }
+            Error::GetContent(_) => f.write_str("unable to get the content of the file"),
+            Error::ReadContent(_) => f.write_str("unable to read the content of the file"),
+            Error::Write(_) => f.write_str("unable to write to stdout"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Interval, InvalidInterval};
+
+    #[test]
+    fn parse_interval() {
+        assert_eq!("10".parse(), Ok(Interval(Duration::from_secs(10))));
+        assert_eq!("30s".parse(), Ok(Interval(Duration::from_secs(30))));
+        assert_eq!("5m".parse(), Ok(Interval(Duration::from_secs(300))));
+        assert_eq!("0s".parse::<Interval>(), Err(InvalidInterval));
+        assert_eq!("soon".parse::<Interval>(), Err(InvalidInterval));
+        assert_eq!(Interval::default().to_string(), "10s");
+    }
+}
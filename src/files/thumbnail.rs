@@ -0,0 +1,258 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use google_drive3::{
+    api::{FileContentHints, FileContentHintsThumbnail},
+    hyper,
+};
+
+use crate::{
+    common::{
+        file_id::FileId,
+        hub_helper::{get_hub, GetHubError},
+        transfer::{ExistingFileAction, Resolution},
+    },
+    files,
+    hub::Hub,
+};
+
+pub struct Config {
+    pub file_id: FileId,
+    /// Length in pixels of the longest side of the thumbnail, Drive default is used when missing
+    pub size: Option<u32>,
+    pub destination: PathBuf,
+    pub existing_file_action: ExistingFileAction,
+}
+
+pub async fn thumbnail(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    // Thumbnails have no checksum to compare, an existing file is either kept or replaced
+    match config
+        .existing_file_action
+        .resolve(&config.destination, None)
+        .await
+    {
+        Resolution::Write => {}
+        Resolution::Keep => {
+            println!(
+                "Skipping {}, it already exists",
+                config.destination.display()
+            );
+            return Ok(());
+        }
+        Resolution::Conflict => return Err(Error::FileExists(config.destination)),
+    }
+
+    let (_, file) = hub
+        .files()
+        .get(&config.file_id)
+        .param("fields", "id,name,thumbnailLink")
+        .supports_all_drives(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .doit()
+        .await
+        .map_err(|err| Error::GetFile(Box::new(err)))?;
+
+    let name = file.name.unwrap_or_default();
+    let Some(link) = file.thumbnail_link else {
+        return Err(Error::MissingThumbnail(name));
+    };
+    let link = match config.size {
+        Some(size) => sized_thumbnail_link(&link, size),
+        None => link,
+    };
+
+    println!(
+        "Downloading thumbnail of '{name}' to {}",
+        config.destination.display()
+    );
+
+    let body = download_thumbnail(&hub, &link).await?;
+    files::download::save_body_to_file(body, &config.destination, None)
+        .await
+        .map_err(Error::SaveFile)?;
+
+    println!("Successfully downloaded {}", config.destination.display());
+
+    Ok(())
+}
+
+/// Thumbnail links are short lived urls which require the same authorization of the API.
+async fn download_thumbnail(hub: &Hub, link: &str) -> Result<hyper::Body, Error> {
+    let response = hub
+        .authorized_get(
+            hyper::Request::builder().uri(link),
+            google_drive3::api::Scope::Full,
+        )
+        .await
+        .map_err(|err| Error::DownloadThumbnail(Box::new(err)))?;
+
+    Ok(response.into_body())
+}
+
+/// Largest custom thumbnail accepted by Drive.
+const MAX_CUSTOM_THUMBNAIL_SIZE: u64 = 2 * 1024 * 1024;
+
+/// Formats of the custom thumbnails accepted by Drive.
+const CUSTOM_THUMBNAIL_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/gif"];
+
+/// Reads a local image to be set as the thumbnail of a file, which Drive shows when it cannot
+/// generate one itself, i.e. for proprietary formats. The format is guessed from the extension.
+pub fn read_custom(path: &Path) -> Result<FileContentHints, CustomThumbnailError> {
+    let mime_type = mime_guess::from_path(path)
+        .first()
+        .map(|mime| mime.essence_str().to_owned())
+        .filter(|mime| CUSTOM_THUMBNAIL_TYPES.contains(&mime.as_str()))
+        .ok_or_else(|| CustomThumbnailError::UnsupportedFormat(path.to_owned()))?;
+
+    let size = fs::metadata(path)
+        .map_err(|err| CustomThumbnailError::Read(path.to_owned(), err))?
+        .len();
+    if size > MAX_CUSTOM_THUMBNAIL_SIZE {
+        return Err(CustomThumbnailError::TooLarge(path.to_owned(), size));
+    }
+    let image = fs::read(path).map_err(|err| CustomThumbnailError::Read(path.to_owned(), err))?;
+
+    Ok(FileContentHints {
+        thumbnail: Some(FileContentHintsThumbnail {
+            image: Some(image),
+            mime_type: Some(mime_type),
+        }),
+        ..FileContentHints::default()
+    })
+}
+
+/// Replaces the size suffix of a thumbnail link, i.e. `=s220`, with the requested size.
+fn sized_thumbnail_link(link: &str, size: u32) -> String {
+    let path_start = link.rfind('/').unwrap_or(0);
+    match link[path_start..].rfind('=') {
+        Some(index) => format!("{}=s{size}", &link[..path_start + index]),
+        None => format!("{link}=s{size}"),
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    FileExists(PathBuf),
+    GetFile(Box<google_drive3::Error>),
+    MissingThumbnail(String),
+    DownloadThumbnail(Box<google_drive3::Error>),
+    SaveFile(files::download::errors::SaveBodyToFile),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::FileExists(path) => {
+                write!(
+                    f,
+                    "file '{}' already exists, use --overwrite to overwrite it",
+                    path.display()
+                )
+            }
+            Error::GetFile(_) => f.write_str("unable to get file"),
+            Error::MissingThumbnail(name) => {
+                write!(f, "drive does not provide a thumbnail for '{name}'")
+            }
+            Error::DownloadThumbnail(_) => f.write_str("unable to download thumbnail"),
+            Error::SaveFile(_) => f.write_str("failed to save thumbnail"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source) | Error::DownloadThumbnail(source) => Some(source),
+            Error::SaveFile(source) => Some(source),
+            Error::FileExists(_) | Error::MissingThumbnail(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CustomThumbnailError {
+    Read(PathBuf, io::Error),
+    UnsupportedFormat(PathBuf),
+    TooLarge(PathBuf, u64),
+}
+
+impl Display for CustomThumbnailError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            CustomThumbnailError::Read(path, _) => {
+                write!(f, "unable to read thumbnail '{}'", path.display())
+            }
+            CustomThumbnailError::UnsupportedFormat(path) => write!(
+                f,
+                "thumbnail '{}' must be a png, jpeg or gif image",
+                path.display()
+            ),
+            CustomThumbnailError::TooLarge(path, size) => write!(
+                f,
+                "thumbnail '{}' is {size} bytes, the maximum is {MAX_CUSTOM_THUMBNAIL_SIZE}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl error::Error for CustomThumbnailError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CustomThumbnailError::Read(_, source) => Some(source),
+            CustomThumbnailError::UnsupportedFormat(_) | CustomThumbnailError::TooLarge(..) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{read_custom, sized_thumbnail_link, CustomThumbnailError};
+
+    #[test]
+    fn sized_thumbnail_link_replaces_size() {
+        assert_eq!(
+            sized_thumbnail_link(
+                "https://lh3.googleusercontent.com/drive-storage/abc=s220",
+                640
+            ),
+            "https://lh3.googleusercontent.com/drive-storage/abc=s640"
+        );
+        assert_eq!(
+            sized_thumbnail_link("https://lh3.googleusercontent.com/drive-storage/abc", 640),
+            "https://lh3.googleusercontent.com/drive-storage/abc=s640"
+        );
+    }
+
+    #[test]
+    fn read_custom_thumbnail() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("preview.png");
+        fs::write(&png, b"\x89PNG").unwrap();
+        let thumbnail = read_custom(&png).unwrap().thumbnail.unwrap();
+        assert_eq!(thumbnail.mime_type.as_deref(), Some("image/png"));
+        assert_eq!(thumbnail.image.as_deref(), Some(&b"\x89PNG"[..]));
+
+        let svg = dir.path().join("preview.svg");
+        fs::write(&svg, "<svg/>").unwrap();
+        assert!(matches!(
+            read_custom(&svg),
+            Err(CustomThumbnailError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            read_custom(&dir.path().join("missing.jpg")),
+            Err(CustomThumbnailError::Read(..))
+        ));
+    }
+}
@@ -1,38 +1,78 @@
+mod stream;
+
 use std::{
+    borrow::Cow,
     error,
     fmt::{Display, Formatter},
+    io,
     path::PathBuf,
     time::Duration,
 };
 
+use google_drive3::{
+    api::FileContentHints,
+    chrono::{DateTime, Utc},
+};
 use mime::Mime;
 
 use crate::{
     common::{
         delegate::{BackoffConfig, ChunkSize, UploadDelegate, UploadDelegateConfig},
         file_helper,
+        file_id::FileId,
         file_info::{self, FileInfo},
+        file_version,
         hub_helper::{get_hub, GetHubError},
     },
     files::{
         self,
         info::{self, DisplayConfig},
+        thumbnail,
     },
     hub::Hub,
 };
 
+const FILE_FIELDS: &str =
+    "id,name,size,createdTime,modifiedTime,md5Checksum,sha256Checksum,mimeType,parents,shared,\
+    description,webContentLink,webViewLink";
+
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "they are orthogonal one each other"
+)]
 pub struct Config {
-    pub file_id: String,
+    pub file_id: FileId,
+    /// File to upload, stdin is streamed if `None`
     pub file_path: Option<PathBuf>,
+    /// New name of the file, defaults to the name of the uploaded file or the current name when
+    /// reading from stdin
+    pub name: Option<String>,
+    /// Number of bytes that will be read from stdin, if known in advance
+    pub size: Option<u64>,
     pub mime_type: Option<Mime>,
     pub chunk_size: ChunkSize,
     pub print_chunk_errors: bool,
     pub print_chunk_info: bool,
+    pub if_match: Option<i64>,
+    pub protect: bool,
+    /// Set the modified time back to the one the file had before the update
+    pub keep_modified_time: bool,
+    /// Image set as the thumbnail of the file
+    pub thumbnail: Option<PathBuf>,
+    /// Print only the id of the updated file
+    pub print_only_id: bool,
 }
 
 pub async fn update(config: Config) -> Result<(), Error> {
     let hub = get_hub().await.map_err(Error::Hub)?;
 
+    let content_hints = config
+        .thumbnail
+        .as_deref()
+        .map(thumbnail::read_custom)
+        .transpose()
+        .map_err(Error::Thumbnail)?;
+
     let delegate_config = UploadDelegateConfig {
         chunk_size: config.chunk_size,
         backoff_config: BackoffConfig {
@@ -44,29 +84,48 @@ pub async fn update(config: Config) -> Result<(), Error> {
         print_chunk_info: config.print_chunk_info,
     };
 
-    let mut file_helper = match file_helper::open_file(&config.file_path) {
-        Ok(file_helper) => file_helper,
-        Err(err) => {
-            return Err(Error::OpenFile(
-                config.file_path.unwrap_or_else(|| PathBuf::from("<stdin>")),
-                err,
-            ))
-        }
-    };
-
     let drive_file = info::get_file(&hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
+    let expected_version = if config.protect {
+        drive_file.version
+    } else {
+        config.if_match
+    };
+    if let Some(expected) = expected_version {
+        file_version::check(&drive_file, expected).map_err(Error::Version)?;
+    }
+
+    let Some(path) = &config.file_path else {
+        return update_from_stdin(
+            &hub,
+            &config,
+            &drive_file,
+            expected_version,
+            content_hints.as_ref(),
+            &delegate_config,
+        )
+        .await;
+    };
+
+    let mut file_helper = match file_helper::open_file(&config.file_path) {
+        Ok(file_helper) => file_helper,
+        Err(err) => return Err(Error::OpenFile(path.clone(), err)),
+    };
+
     let (file, file_path) = file_helper.file_mut_and_path();
 
     let file_info_config = file_info::Config {
         file_path,
         mime_type: config.mime_type.as_ref(),
         parents: drive_file.parents.clone(),
+        app_properties: None,
+        description: None,
+        starred: false,
     };
 
-    let file_info = match FileInfo::from_file(file, file_info_config) {
+    let mut file_info = match FileInfo::from_file(file, file_info_config) {
         Ok(file_info) => file_info,
         Err(source) => {
             return Err(Error::FileInfo {
@@ -76,21 +135,96 @@ pub async fn update(config: Config) -> Result<(), Error> {
         }
     };
 
+    if let Some(name) = &config.name {
+        file_info.name = Cow::Borrowed(name);
+    }
+    file_info.content_hints = content_hints;
+
     let reader = std::io::BufReader::new(file);
 
-    println!("Updating {} with {}", config.file_id, file_path.display());
+    if !config.print_only_id {
+        println!("Updating {} with {}", config.file_id, file_path.display());
+    }
+
+    if let Some(expected) = expected_version {
+        file_version::ensure(&hub, &config.file_id, expected)
+            .await
+            .map_err(Error::Version)?;
+    }
 
-    let file = update_file(&hub, reader, &config.file_id, file_info, &delegate_config)
+    let mut file = update_file(&hub, reader, &config.file_id, file_info, &delegate_config)
         .await
         .map_err(Error::Update)?;
 
-    println!("File successfully updated");
+    if config.keep_modified_time {
+        file = restore_modified_time(&hub, &delegate_config, &drive_file, &config.file_id)
+            .await
+            .map_err(Error::RestoreModifiedTime)?;
+    }
 
-    files::info::print_file_info(&file, &DisplayConfig::default());
+    print_updated_file(&config, &file);
 
     Ok(())
 }
 
+async fn update_from_stdin(
+    hub: &Hub,
+    config: &Config,
+    drive_file: &google_drive3::api::File,
+    expected_version: Option<i64>,
+    content_hints: Option<&FileContentHints>,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<(), Error> {
+    let mime_type = config
+        .mime_type
+        .clone()
+        .or_else(|| drive_file.mime_type.as_deref()?.parse().ok())
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+    if !config.print_only_id {
+        println!("Updating {} with stdin", config.file_id);
+    }
+
+    if let Some(expected) = expected_version {
+        file_version::ensure(hub, &config.file_id, expected)
+            .await
+            .map_err(Error::Version)?;
+    }
+
+    let mut file = stream::update_from_reader(
+        hub,
+        &config.file_id,
+        tokio::io::stdin(),
+        &stream::Metadata {
+            name: config.name.as_deref(),
+            mime_type: &mime_type,
+            size: config.size,
+            content_hints,
+        },
+        delegate_config,
+    )
+    .await?;
+
+    if config.keep_modified_time {
+        file = restore_modified_time(hub, delegate_config, drive_file, &config.file_id)
+            .await
+            .map_err(Error::RestoreModifiedTime)?;
+    }
+
+    print_updated_file(config, &file);
+
+    Ok(())
+}
+
+fn print_updated_file(config: &Config, file: &google_drive3::api::File) {
+    if config.print_only_id {
+        print!("{}", file.id.as_deref().unwrap_or_default());
+    } else {
+        println!("File successfully updated");
+        files::info::print_file_info(file, &DisplayConfig::default());
+    }
+}
+
 pub async fn update_file<RS>(
     hub: &Hub,
     src_file: RS,
@@ -103,6 +237,7 @@ where
 {
     let dst_file = google_drive3::api::File {
         name: Some(file_info.name.into_owned()),
+        content_hints: file_info.content_hints,
         ..google_drive3::api::File::default()
     };
 
@@ -111,11 +246,7 @@ where
     let req = hub
         .files()
         .update(dst_file, file_id)
-        .param(
-            "fields",
-            "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,\
-            description,webContentLink,webViewLink",
-        )
+        .param("fields", FILE_FIELDS)
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true);
@@ -141,11 +272,7 @@ pub async fn update_metadata(
     let (_, file) = hub
         .files()
         .update(patch_file.file, &patch_file.id)
-        .param(
-            "fields",
-            "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,\
-            description,webContentLink,webViewLink",
-        )
+        .param("fields", FILE_FIELDS)
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true)
@@ -155,6 +282,22 @@ pub async fn update_metadata(
     Ok(file)
 }
 
+/// Sets the modified time of `file_id` to the one of `original`, which Drive resets whenever a
+/// file is copied or its content changes.
+pub async fn restore_modified_time(
+    hub: &Hub,
+    delegate_config: &UploadDelegateConfig,
+    original: &google_drive3::api::File,
+    file_id: &str,
+) -> Result<google_drive3::api::File, google_drive3::Error> {
+    let mut patch_file = PatchFile::new(file_id.to_string());
+    if let Some(modified_time) = original.modified_time {
+        patch_file = patch_file.with_modified_time(modified_time);
+    }
+
+    update_metadata(hub, delegate_config, patch_file).await
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
@@ -165,6 +308,17 @@ pub enum Error {
     OpenFile(PathBuf, file_helper::OpenFileError),
     GetFile(google_drive3::Error),
     Update(google_drive3::Error),
+    Version(file_version::Error),
+    StartUploadSession(google_drive3::Error),
+    ReadStdin(io::Error),
+    SizeMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    UploadChunk(google_drive3::Error),
+    ChunkNotPersisted,
+    RestoreModifiedTime(google_drive3::Error),
+    Thumbnail(thumbnail::CustomThumbnailError),
 }
 
 impl Display for Error {
@@ -179,6 +333,21 @@ impl Display for Error {
             }
             Error::GetFile(_) => f.write_str("unable to get file"),
             Error::Update(_) => f.write_str("unable to update file"),
+            Error::Version(_) => f.write_str("refusing to update file"),
+            Error::StartUploadSession(_) => f.write_str("unable to start the upload session"),
+            Error::ReadStdin(_) => f.write_str("unable to read stdin"),
+            Error::SizeMismatch { expected, actual } => write!(
+                f,
+                "expected {expected} bytes from stdin, but {actual} bytes were read"
+            ),
+            Error::UploadChunk(_) => f.write_str("unable to upload chunk"),
+            Error::ChunkNotPersisted => {
+                f.write_str("the server lost data that had already been uploaded")
+            }
+            Error::RestoreModifiedTime(_) => {
+                f.write_str("file updated, but unable to restore its modified time")
+            }
+            Error::Thumbnail(_) => f.write_str("unable to set the custom thumbnail"),
         }
     }
 }
@@ -189,7 +358,15 @@ impl error::Error for Error {
             Error::Hub(source) => Some(source),
             Error::FileInfo { source, .. } => Some(source),
             Error::OpenFile(_, source) => Some(source),
-            Error::GetFile(source) | Error::Update(source) => Some(source),
+            Error::GetFile(source)
+            | Error::Update(source)
+            | Error::StartUploadSession(source)
+            | Error::UploadChunk(source)
+            | Error::RestoreModifiedTime(source) => Some(source),
+            Error::Version(source) => Some(source),
+            Error::ReadStdin(source) => Some(source),
+            Error::Thumbnail(source) => Some(source),
+            Error::SizeMismatch { .. } | Error::ChunkNotPersisted => None,
         }
     }
 }
@@ -217,4 +394,13 @@ impl PatchFile {
         };
         self
     }
+
+    #[must_use]
+    pub fn with_modified_time(mut self, modified_time: DateTime<Utc>) -> Self {
+        self.file = google_drive3::api::File {
+            modified_time: Some(modified_time),
+            ..self.file
+        };
+        self
+    }
 }
@@ -0,0 +1,349 @@
+use std::ops::Range;
+
+use bytesize::ByteSize;
+use error_trace::ErrorTrace;
+use google_drive3::{api::FileContentHints, client::url::Params, hyper};
+use mime::Mime;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{Error, FILE_FIELDS};
+use crate::{
+    common::{
+        delegate::{Backoff, UploadDelegateConfig},
+        endpoints,
+    },
+    hub::{self, Hub},
+};
+
+pub(super) struct Metadata<'a> {
+    /// New name of the file, the current one is kept if `None`
+    pub name: Option<&'a str>,
+    pub mime_type: &'a Mime,
+    /// Number of bytes the reader yields, if known in advance
+    pub size: Option<u64>,
+    pub content_hints: Option<&'a FileContentHints>,
+}
+
+/// Replaces the content of a file with the bytes read from `reader`, sent in chunks through a
+/// resumable upload session. Only one chunk is kept in memory, therefore the data does not need
+/// to be staged on disk to learn its size first.
+pub(super) async fn update_from_reader<R>(
+    hub: &Hub,
+    file_id: &str,
+    mut reader: R,
+    metadata: &Metadata<'_>,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<google_drive3::api::File, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    let session_url = start_session(hub, file_id, metadata)
+        .await
+        .map_err(Error::StartUploadSession)?;
+
+    let chunk_size = usize::try_from(delegate_config.chunk_size.in_bytes()).unwrap_or(usize::MAX);
+    let mut chunk = Vec::with_capacity(chunk_size);
+    let mut offset = 0;
+
+    loop {
+        chunk.clear();
+        (&mut reader)
+            .take(chunk_size as u64)
+            .read_to_end(&mut chunk)
+            .await
+            .map_err(Error::ReadStdin)?;
+
+        let end = offset + chunk.len() as u64;
+        let is_last = chunk.len() < chunk_size || metadata.size == Some(end);
+        match metadata.size {
+            Some(expected) if end > expected || (is_last && end != expected) => {
+                return Err(Error::SizeMismatch {
+                    expected,
+                    actual: end,
+                });
+            }
+            _ => {}
+        }
+
+        let total = if is_last { Some(end) } else { metadata.size };
+        if let Some(file) = upload_chunk(
+            hub,
+            &session_url,
+            &chunk,
+            offset..end,
+            total,
+            delegate_config,
+        )
+        .await?
+        {
+            return Ok(file);
+        }
+
+        if is_last {
+            // Every byte has been acknowledged, but the session did not complete
+            return Err(Error::ChunkNotPersisted);
+        }
+        offset = end;
+    }
+}
+
+/// Sends the bytes of `range`, retrying with backoff and resending whatever the server did not
+/// persist. Returns the file once the upload is complete.
+async fn upload_chunk(
+    hub: &Hub,
+    session_url: &str,
+    chunk: &[u8],
+    range: Range<u64>,
+    total: Option<u64>,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<Option<google_drive3::api::File>, Error> {
+    let mut backoff = Backoff::new(&delegate_config.backoff_config);
+    let mut sent = range.start;
+    // After a failure the state of the session is unknown and must be queried first
+    let mut query_status = false;
+
+    loop {
+        let data = if query_status {
+            &[][..]
+        } else {
+            #[expect(
+                clippy::cast_possible_truncation,
+                reason = "the offset is within the chunk, which fits in memory"
+            )]
+            &chunk[(sent - range.start) as usize..]
+        };
+
+        if delegate_config.print_chunk_info && !data.is_empty() {
+            println!(
+                "Info: Uploading {} chunk ({}-{} of {})",
+                ByteSize::b(data.len() as u64).display().si(),
+                sent,
+                range.end - 1,
+                total.map_or_else(|| String::from("*"), |total| total.to_string()),
+            );
+        }
+
+        match put_chunk(hub, session_url, data, sent, total).await {
+            Ok(ChunkResponse::Complete(file)) => return Ok(Some(*file)),
+            Ok(ChunkResponse::Incomplete { received }) if received >= range.end => return Ok(None),
+            Ok(ChunkResponse::Incomplete { received }) if received >= range.start => {
+                tracing::debug!(received, end = range.end, "chunk partially persisted");
+                sent = received;
+                query_status = false;
+            }
+            Ok(ChunkResponse::Incomplete { .. }) => return Err(Error::ChunkNotPersisted),
+            Err(ChunkError {
+                source,
+                retryable: true,
+            }) => {
+                if delegate_config.print_chunk_errors {
+                    eprintln!(
+                        "Warning: Failed attempt to upload chunk: {}",
+                        source.trace()
+                    );
+                }
+                let Some(delay) = backoff.next_delay() else {
+                    return Err(Error::UploadChunk(source));
+                };
+                tracing::warn!(?delay, "chunk upload failed, retrying");
+                tokio::time::sleep(delay).await;
+                query_status = true;
+            }
+            Err(ChunkError { source, .. }) => return Err(Error::UploadChunk(source)),
+        }
+    }
+}
+
+async fn start_session(
+    hub: &Hub,
+    file_id: &str,
+    metadata: &Metadata<'_>,
+) -> Result<String, google_drive3::Error> {
+    let mut params = Params::with_capacity(3);
+    params.push("uploadType", "resumable");
+    params.push("supportsAllDrives", "true");
+    params.push("fields", FILE_FIELDS);
+    let url = params.parse_with_url(&format!("{}files/{file_id}", endpoints::upload_api_url()));
+
+    let mut body = serde_json::to_value(google_drive3::api::File {
+        name: metadata.name.map(str::to_owned),
+        content_hints: metadata.content_hints.cloned(),
+        ..google_drive3::api::File::default()
+    })
+    .expect("file metadata should be serializable");
+    google_drive3::client::remove_json_null_values(&mut body);
+
+    let mut request = hyper::Request::patch(url.as_str())
+        .header(
+            hyper::header::CONTENT_TYPE,
+            "application/json; charset=UTF-8",
+        )
+        .header("X-Upload-Content-Type", metadata.mime_type.as_ref());
+    if let Some(size) = metadata.size {
+        request = request.header("X-Upload-Content-Length", size);
+    }
+    if let Some(token) = get_token(hub).await? {
+        request = request.header(hyper::header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    let request = request
+        .body(hyper::Body::from(body.to_string()))
+        .expect("request should be valid");
+
+    let response = hub
+        .client
+        .request(request)
+        .await
+        .map_err(google_drive3::Error::HttpError)?;
+
+    if !response.status().is_success() {
+        return Err(hub::api_error(response).await);
+    }
+
+    let session_url = response
+        .headers()
+        .get(hyper::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .map(str::to_string);
+    session_url.ok_or(google_drive3::Error::Failure(response))
+}
+
+enum ChunkResponse {
+    Complete(Box<google_drive3::api::File>),
+    /// The server persisted the bytes before `received`
+    Incomplete {
+        received: u64,
+    },
+}
+
+struct ChunkError {
+    source: google_drive3::Error,
+    retryable: bool,
+}
+
+/// Sends `data` starting at `start`. An empty `data` only asks for the state of the session, or
+/// completes it when `total` is known.
+async fn put_chunk(
+    hub: &Hub,
+    session_url: &str,
+    data: &[u8],
+    start: u64,
+    total: Option<u64>,
+) -> Result<ChunkResponse, ChunkError> {
+    let fatal = |source| ChunkError {
+        source,
+        retryable: false,
+    };
+
+    let total = total.map_or_else(|| String::from("*"), |total| total.to_string());
+    let content_range = if data.is_empty() {
+        format!("bytes */{total}")
+    } else {
+        format!("bytes {start}-{}/{total}", start + data.len() as u64 - 1)
+    };
+
+    let mut request = hyper::Request::put(session_url)
+        .header(hyper::header::CONTENT_RANGE, content_range)
+        .header(hyper::header::CONTENT_LENGTH, data.len());
+    if let Some(token) = get_token(hub).await.map_err(fatal)? {
+        request = request.header(hyper::header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    let request = request
+        .body(hyper::Body::from(data.to_vec()))
+        .expect("request should be valid");
+
+    let response = match hub.client.request(request).await {
+        Ok(response) => response,
+        Err(err) => {
+            return Err(ChunkError {
+                source: google_drive3::Error::HttpError(err),
+                retryable: true,
+            })
+        }
+    };
+
+    let status = response.status();
+    if status == hyper::StatusCode::PERMANENT_REDIRECT {
+        let received = response
+            .headers()
+            .get(hyper::header::RANGE)
+            .and_then(|range| range.to_str().ok())
+            .map_or(Some(0), parse_received);
+        return received
+            .map(|received| ChunkResponse::Incomplete { received })
+            .ok_or_else(|| fatal(google_drive3::Error::Failure(response)));
+    }
+
+    if !status.is_success() {
+        if status == hyper::StatusCode::UNAUTHORIZED {
+            hub::reject_token();
+        }
+        return Err(ChunkError {
+            source: hub::api_error(response).await,
+            retryable: is_retryable(status),
+        });
+    }
+
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|err| ChunkError {
+            source: google_drive3::Error::HttpError(err),
+            retryable: true,
+        })?;
+    serde_json::from_slice(&body)
+        .map(|file| ChunkResponse::Complete(Box::new(file)))
+        .map_err(|err| {
+            fatal(google_drive3::Error::JsonDecodeError(
+                String::from_utf8_lossy(&body).into_owned(),
+                err,
+            ))
+        })
+}
+
+/// Failures worth retrying after querying the state of the session. A rejected token is refreshed
+/// before the next attempt, the session itself survives it.
+fn is_retryable(status: hyper::StatusCode) -> bool {
+    status.is_server_error()
+        || status == hyper::StatusCode::TOO_MANY_REQUESTS
+        || status == hyper::StatusCode::UNAUTHORIZED
+}
+
+/// Parses the `Range` header of an incomplete upload, i.e. `bytes=0-1048575`, into the number of
+/// persisted bytes.
+fn parse_received(range: &str) -> Option<u64> {
+    let (first, last) = range.strip_prefix("bytes=")?.split_once('-')?;
+    if first != "0" {
+        return None;
+    }
+    last.parse::<u64>().ok()?.checked_add(1)
+}
+
+async fn get_token(hub: &Hub) -> Result<Option<String>, google_drive3::Error> {
+    hub.auth
+        .get_token(&[google_drive3::api::Scope::Full.as_ref()])
+        .await
+        .map_err(google_drive3::Error::MissingToken)
+}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::hyper::StatusCode;
+
+    use super::{is_retryable, parse_received};
+
+    #[test]
+    fn parse_received_range() {
+        assert_eq!(parse_received("bytes=0-1048575"), Some(1_048_576));
+        assert_eq!(parse_received("bytes=0-0"), Some(1));
+        assert_eq!(parse_received("bytes=10-20"), None);
+        assert_eq!(parse_received("0-20"), None);
+        assert_eq!(parse_received("bytes=0-"), None);
+    }
+
+    #[test]
+    fn expired_token_is_retried() {
+        assert!(is_retryable(StatusCode::UNAUTHORIZED));
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable(StatusCode::FORBIDDEN));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+    }
+}
@@ -1,25 +1,42 @@
 use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     error,
     fmt::{Display, Formatter},
-    fs, io,
+    fs,
+    io::{self, Read, Seek},
     path::{Path, PathBuf},
     time::Duration,
 };
 
 use bytesize::ByteSize;
+use futures::stream::{self, StreamExt};
 use mime::Mime;
 
 use crate::{
+    about,
     common::{
+        archive::{self, Archive, FileSlice},
+        checksum::{self, Checksum},
+        compression::{self, GzipReader},
         delegate::{BackoffConfig, ChunkSize, UploadDelegate, UploadDelegateConfig},
+        drive_file,
+        drive_path::ChildFolder,
+        error_report,
+        failure_manifest::{self, FailureKind, FailureManifest},
+        file_filter::FileFilter,
         file_helper,
+        file_id::{self, FileId},
         file_info::{self, FileInfo},
         file_tree::{self, FileTree},
+        hook::{self, OnComplete},
         hub_helper::{get_hub, GetHubError},
         id_gen::IdGen,
-        FileTreeLike, FolderLike,
+        size_format::SizeFormat,
+        split_file, FileTreeLike, FolderLike, TreeInfo,
     },
-    files::{self, info::DisplayConfig, mkdir},
+    drives,
+    files::{self, info::DisplayConfig, mkdir, thumbnail},
     hub::Hub,
 };
 
@@ -27,22 +44,85 @@ use crate::{
     clippy::struct_excessive_bools,
     reason = "they are orthogonal one each other"
 )]
+#[derive(Clone)]
 pub struct Config {
     pub file_path: Option<PathBuf>,
     pub mime_type: Option<Mime>,
-    pub parents: Option<Vec<String>>,
+    pub parents: Option<Vec<FileId>>,
     pub chunk_size: ChunkSize,
     pub print_chunk_errors: bool,
     pub print_chunk_info: bool,
     pub upload_directories: bool,
     pub print_only_id: bool,
+    pub gzip: bool,
+    pub continue_on_error: bool,
+    pub failure_manifest: Option<PathBuf>,
+    pub retry_failed: Option<PathBuf>,
+    pub from_archive: Option<PathBuf>,
+    pub verify: bool,
+    pub check_quota: bool,
+    pub filter: FileFilter,
+    /// How many levels of directories below the uploaded one to walk, all of them when `None`
+    pub max_depth: Option<usize>,
+    /// Skip the directories on another file system than the uploaded one
+    pub one_file_system: bool,
+    /// Upload the file as parts of this many bytes, stored in a new folder
+    pub split: Option<u64>,
+    pub size_format: SizeFormat,
+    /// Properties private to this application, set on every uploaded file and created folder
+    pub app_properties: Option<HashMap<String, String>>,
+    /// Name of the uploaded file instead of the local one, only for single files
+    pub name: Option<String>,
+    /// Description of the uploaded file, only for single files
+    pub description: Option<String>,
+    /// Star the uploaded file, only for single files
+    pub starred: bool,
+    /// Image set as the thumbnail of the uploaded file, only for single files
+    pub thumbnail: Option<PathBuf>,
+    /// Upload to the root of the shared drive with this name, instead of `parents`
+    pub drive_name: Option<String>,
+    /// Upload to the folder with this name below another one, instead of `parents`
+    pub parent_name: Option<ChildFolder>,
+    /// Upload to the folder with this slash separated path from the root of My Drive, created if
+    /// missing, instead of `parents`
+    pub parent_path: Option<String>,
+    /// Run after each uploaded file
+    pub on_complete: Option<OnComplete>,
+    /// Where the content of `file_path` was copied from, when it is a temporary file
+    pub staged: Option<Staged>,
+}
+
+/// The origin of content staged in a temporary file, given to the `--on-complete` command instead
+/// of the temporary file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Staged {
+    Path(PathBuf),
+    Stdin,
 }
 
 pub async fn upload(config: Config) -> Result<(), Error> {
     let hub = get_hub().await.map_err(Error::Hub)?;
+    upload_with_hub(&hub, config).await
+}
+
+/// Like [`upload`], with a hub shared by the uploads of a run, so that they reuse its connections.
+pub async fn upload_with_hub(hub: &Hub, config: Config) -> Result<(), Error> {
+    let config = if let Some(name) = &config.drive_name {
+        // The root folder of a shared drive has the id of the drive
+        let drive_id = drives::list::find_drive_id(hub, name)
+            .await
+            .map_err(Error::FindDrive)?;
+        Config {
+            parents: Some(vec![FileId::from_drive(drive_id)]),
+            ..config
+        }
+    } else {
+        config
+    };
+    let config = resolve_parent(hub, config).await?;
 
     let delegate_config = UploadDelegateConfig {
-        chunk_size: config.chunk_size.clone(),
+        chunk_size: config.chunk_size,
         backoff_config: BackoffConfig {
             max_retries: 100_000,
             min_sleep: Duration::from_secs(1),
@@ -52,31 +132,161 @@ pub async fn upload(config: Config) -> Result<(), Error> {
         print_chunk_info: config.print_chunk_info,
     };
 
-    if let Some(path) = &config.file_path {
-        err_if_directory(path, &config)?;
+    if let Some(manifest_path) = &config.retry_failed {
+        retry_failed_uploads(hub, &config, &delegate_config, manifest_path).await?;
+    } else if let Some(archive_path) = &config.from_archive {
+        upload_archive(hub, &config, &delegate_config, archive_path).await?;
+    } else if let Some(path) = config.file_path.clone() {
+        err_if_directory(&path, &config)?;
 
+        let is_stream =
+            file_helper::is_stream(&path).map_err(|err| Error::OpenFile(path.clone(), err))?;
         if path.is_dir() {
-            upload_directory(&hub, &config, &delegate_config).await?;
+            upload_directory(hub, &config, &delegate_config).await?;
+        } else if is_stream {
+            Box::pin(upload_staged(hub, config, &delegate_config, Some(&path))).await?;
         } else {
-            upload_regular(&hub, &config, &delegate_config).await?;
+            upload_path(hub, &config, &delegate_config, &path).await?;
         }
     } else {
-        let tmp_file = file_helper::stdin_to_file().map_err(Error::StdinToFile)?;
-
-        upload_regular(
-            &hub,
-            &Config {
-                file_path: Some(tmp_file.as_ref().to_path_buf()),
-                ..config
-            },
-            &delegate_config,
-        )
-        .await?;
+        Box::pin(upload_staged(hub, config, &delegate_config, None)).await?;
     }
 
     Ok(())
 }
 
+/// Uploads the content of the stream at `path`, or of stdin when `None`, once staged in a
+/// temporary file.
+async fn upload_staged(
+    hub: &Hub,
+    config: Config,
+    delegate_config: &UploadDelegateConfig,
+    path: Option<&Path>,
+) -> Result<(), Error> {
+    if let Some(path) = path {
+        if !config.print_only_id {
+            println!("Staging {} in a temporary file", path.display());
+        }
+    }
+    let tmp_file =
+        file_helper::stage(path).map_err(|err| Error::Stage(path.map(Path::to_path_buf), err))?;
+
+    // The temporary file has neither the name nor the extension of the original path
+    let config = Config {
+        file_path: Some(tmp_file.as_ref().to_path_buf()),
+        staged: Some(path.map_or(Staged::Stdin, |path| Staged::Path(path.to_path_buf()))),
+        mime_type: config
+            .mime_type
+            .or_else(|| path.and_then(|path| mime_guess::from_path(path).first())),
+        name: config.name.or_else(|| {
+            path.and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+        }),
+        ..config
+    };
+    upload_path(hub, &config, delegate_config, tmp_file.as_ref()).await
+}
+
+/// Uploads each of the paths with the same options, up to `jobs` of them at the same time. A path
+/// failing to upload does not stop the others, the failures are counted in the returned error.
+pub async fn upload_paths(config: Config, paths: Vec<PathBuf>, jobs: usize) -> Result<(), Error> {
+    if paths.len() <= 1 {
+        let file_path = paths.into_iter().next();
+        return Box::pin(upload(Config {
+            file_path,
+            ..config
+        }))
+        .await;
+    }
+    if config.name.is_some() {
+        return Err(Error::NameWithMultiplePaths);
+    }
+
+    // A single hub keeps the connections open from one upload to the next
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    // Resolved once, the uploads running at the same time would each create the folder
+    let config = if config.parent_name.is_some() || config.parent_path.is_some() {
+        resolve_parent(&hub, config).await?
+    } else {
+        config
+    };
+
+    let mut results = stream::iter(paths)
+        .map(|path| {
+            let config = Config {
+                file_path: Some(path.clone()),
+                ..config.clone()
+            };
+            let hub = &hub;
+            async move {
+                let result = Box::pin(upload_with_hub(hub, config)).await;
+                (path, result)
+            }
+        })
+        .buffer_unordered(jobs.max(1));
+
+    let mut failed = 0;
+    while let Some((path, result)) = results.next().await {
+        match result {
+            // Single files only print their id, without a newline
+            Ok(()) if config.print_only_id && !path.is_dir() => println!(),
+            Ok(()) => {}
+            Err(err) => {
+                eprintln!(
+                    "Failed to upload '{}': {}",
+                    path.display(),
+                    error_report::trace(&err)
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        Err(Error::FailedUploads(failed))
+    } else {
+        Ok(())
+    }
+}
+
+/// Replaces the parent given by name or by path with its id, creating the missing folders if
+/// requested.
+async fn resolve_parent(hub: &Hub, config: Config) -> Result<Config, Error> {
+    let parent_id = mkdir::resolve_parent_id(
+        hub,
+        config.parent_name.as_ref(),
+        config.parent_path.as_deref(),
+        config.print_only_id,
+    )
+    .await
+    .map_err(|err| Error::Parent(Box::new(err)))?;
+    let Some(parent_id) = parent_id else {
+        return Ok(config);
+    };
+
+    Ok(Config {
+        parents: Some(vec![FileId::from_drive(parent_id)]),
+        parent_name: None,
+        parent_path: None,
+        ..config
+    })
+}
+
+/// Uploads a regular file or a block device, split in parts if requested.
+async fn upload_path(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+    path: &Path,
+) -> Result<(), Error> {
+    if let Some(part_size) = config.split {
+        upload_split(hub, config, delegate_config, path, part_size).await
+    } else {
+        upload_regular(hub, config, delegate_config).await
+    }
+}
+
 pub async fn upload_regular(
     hub: &Hub,
     config: &Config,
@@ -85,12 +295,15 @@ pub async fn upload_regular(
     let file_path = config.file_path.as_ref().unwrap();
     let file = fs::File::open(file_path).map_err(|err| Error::OpenFile(file_path.clone(), err))?;
 
-    let file_info = match FileInfo::from_file(
+    let mut file_info = match FileInfo::from_file(
         &file,
         file_info::Config {
             file_path,
             mime_type: config.mime_type.as_ref(),
-            parents: config.parents.clone(),
+            parents: config.parents.as_deref().map(file_id::to_strings),
+            app_properties: config.app_properties.clone(),
+            description: config.description.clone(),
+            starred: config.starred,
         },
     ) {
         Ok(file_info) => file_info,
@@ -101,6 +314,17 @@ pub async fn upload_regular(
             })
         }
     };
+    if let Some(name) = &config.name {
+        file_info.name = Cow::Owned(name.clone());
+    }
+    if let Some(thumbnail_path) = &config.thumbnail {
+        let content_hints = thumbnail::read_custom(thumbnail_path).map_err(Error::Thumbnail)?;
+        file_info.content_hints = Some(content_hints);
+    }
+
+    if config.check_quota {
+        check_quota(hub, file_info.size).await?;
+    }
 
     let reader = std::io::BufReader::new(file);
 
@@ -108,15 +332,153 @@ pub async fn upload_regular(
         println!("Uploading {}", file_path.display());
     }
 
-    let file = upload_file(hub, reader, None, file_info, delegate_config)
-        .await
-        .map_err(|err| Error::Upload(Box::new(err)))?;
+    let file = if config.gzip {
+        let reader =
+            GzipReader::new(reader).map_err(|err| Error::Compress(file_path.clone(), err))?;
+        let file_info = gzip_file_info(file_info, &reader);
+        upload_file(hub, reader, None, file_info, delegate_config).await
+    } else {
+        upload_file(hub, reader, None, file_info, delegate_config).await
+    }
+    .map_err(|err| Error::Upload(Box::new(err)))?;
+
+    if config.verify {
+        let content =
+            fs::File::open(file_path).map_err(|err| Error::OpenFile(file_path.clone(), err))?;
+        verify_upload(&file, file_path, content, config.gzip)?;
+    }
+
+    run_on_complete(config, &file, file_path).await;
 
     if config.print_only_id {
         print!("{}", file.id.unwrap_or_default());
     } else {
         println!("File successfully uploaded");
-        files::info::print_file_info(&file, &DisplayConfig::default());
+        files::info::print_file_info(
+            &file,
+            &DisplayConfig {
+                size_format: config.size_format,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Uploads a file as consecutive parts of `part_size` bytes, named `<name>.partNN`, in a new
+/// folder named `<name>.parts`.
+async fn upload_split(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+    path: &Path,
+    part_size: u64,
+) -> Result<(), Error> {
+    if part_size == 0 {
+        return Err(Error::EmptySplitSize);
+    }
+
+    let file = fs::File::open(path).map_err(|err| Error::OpenFile(path.to_owned(), err))?;
+
+    let mut file_info = match FileInfo::from_file(
+        &file,
+        file_info::Config {
+            file_path: path,
+            mime_type: config.mime_type.as_ref(),
+            parents: config.parents.as_deref().map(file_id::to_strings),
+            app_properties: config.app_properties.clone(),
+            description: config.description.clone(),
+            starred: config.starred,
+        },
+    ) {
+        Ok(file_info) => file_info,
+        Err(source) => {
+            return Err(Error::FileInfo {
+                path: path.to_owned(),
+                source,
+            })
+        }
+    };
+    if let Some(name) = &config.name {
+        file_info.name = Cow::Owned(name.clone());
+    }
+
+    if config.check_quota {
+        check_quota(hub, file_info.size).await?;
+    }
+
+    let folder_name = format!("{}{}", file_info.name, split_file::PARTS_FOLDER_SUFFIX);
+    let folder = mkdir::create_directory(
+        hub,
+        &mkdir::Config {
+            name: folder_name.clone(),
+            parents: file_info.parents.clone().map(file_id::from_strings),
+            app_properties: config.app_properties.clone(),
+            ..mkdir::Config::default()
+        },
+        delegate_config,
+    )
+    .await
+    .map_err(|err| Error::Mkdir(Box::new(err)))?;
+    let folder_id = folder.id.ok_or(Error::DriveFolderMissingId)?;
+    let folder_link = folder.web_view_link;
+
+    let part_count = file_info.size.div_ceil(part_size).max(1);
+    if !config.print_only_id {
+        println!(
+            "Uploading {} in {part_count} parts of {}",
+            path.display(),
+            config.size_format.display(part_size)
+        );
+    }
+
+    for index in 0..part_count {
+        let start = index * part_size;
+        let len = part_size.min(file_info.size - start);
+        let name = split_file::part_name(&file_info.name, index, part_count);
+
+        let open_part = || {
+            file.try_clone()
+                .and_then(|file| FileSlice::new(file, start, len))
+                .map_err(|err| Error::OpenFile(path.to_owned(), err))
+        };
+
+        if !config.print_only_id {
+            println!("Uploading part '{name}'");
+        }
+
+        let part_info = FileInfo {
+            name: Cow::Owned(name),
+            mime_type: Cow::Borrowed(&mime::APPLICATION_OCTET_STREAM),
+            parents: Some(vec![folder_id.clone()]),
+            size: len,
+            app_properties: config.app_properties.clone(),
+            description: config.description.clone(),
+            starred: config.starred,
+            content_hints: None,
+        };
+        let part = upload_file(
+            hub,
+            io::BufReader::new(open_part()?),
+            None,
+            part_info,
+            delegate_config,
+        )
+        .await
+        .map_err(|err| Error::Upload(Box::new(err)))?;
+
+        if config.verify {
+            verify_upload(&part, path, open_part()?, false)?;
+        }
+
+        run_on_complete(config, &part, path).await;
+    }
+
+    if config.print_only_id {
+        print!("{folder_id}");
+    } else {
+        println!("File successfully uploaded to folder '{folder_name}' ({folder_id})");
+        print_folder_link(folder_link.as_deref());
     }
 
     Ok(())
@@ -127,29 +489,74 @@ pub async fn upload_directory(
     config: &Config,
     delegate_config: &UploadDelegateConfig,
 ) -> Result<(), Error> {
+    let mut manifest = FailureManifest::default();
+    upload_tree(hub, config, delegate_config, &mut manifest).await?;
+    finish_failure_manifest(config, &manifest)
+}
+
+async fn build_file_tree(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<FileTree, Error> {
     let mut ids = IdGen::new(hub, delegate_config);
-    let tree = FileTree::from_path(config.file_path.as_ref().unwrap(), &mut ids)
+    let tree_config = file_tree::Config {
+        filter: config.filter,
+        max_depth: config.max_depth,
+        one_file_system: config.one_file_system,
+    };
+
+    FileTree::from_path(config.file_path.as_ref().unwrap(), &mut ids, &tree_config)
         .await
-        .map_err(Error::CreateFileTree)?;
+        .map_err(Error::CreateFileTree)
+}
+
+async fn upload_tree(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+    manifest: &mut FailureManifest,
+) -> Result<(), Error> {
+    let tree = build_file_tree(hub, config, delegate_config).await?;
 
     let tree_info = tree.info();
+    let previous_failures = manifest.failures.len();
+
+    if config.check_quota {
+        check_quota(hub, tree_info.total_file_size).await?;
+    }
 
     if !config.print_only_id {
         println!(
             "Found {} files in {} directories with a total size of {}",
             tree_info.file_count,
             tree_info.folder_count,
-            ByteSize::b(tree_info.total_file_size).display().si(),
+            config.size_format.display(tree_info.total_file_size),
         );
     }
 
+    // Folders are sorted parents first, therefore descendants of a failed folder are always
+    // skipped: they are already covered by the failure of their ancestor.
+    let mut failed_folders = HashSet::new();
+    let mut root_link = None;
+
     for folder in &tree.folders() {
+        if folder
+            .info
+            .parent
+            .as_ref()
+            .is_some_and(|parent| failed_folders.contains(&parent.drive_id))
+        {
+            failed_folders.insert(folder.info.drive_id.clone());
+            continue;
+        }
+
         let folder_parents = folder
             .info
             .parent
             .as_ref()
             .map(|p| vec![p.drive_id.clone()])
-            .or_else(|| config.parents.clone());
+            .or_else(|| config.parents.as_deref().map(file_id::to_strings));
 
         if !config.print_only_id {
             println!(
@@ -159,18 +566,25 @@ pub async fn upload_directory(
             );
         }
 
-        let drive_folder = mkdir::create_directory(
-            hub,
-            &mkdir::Config {
-                id: Some(folder.info.drive_id.clone()),
-                name: folder.info.name.clone(),
-                parents: folder_parents,
-                print_only_id: false,
-            },
-            delegate_config,
-        )
-        .await
-        .map_err(|err| Error::Mkdir(Box::new(err)))?;
+        let folder_id =
+            match create_tree_folder(hub, config, folder, folder_parents.clone(), delegate_config)
+                .await
+            {
+                Ok(drive_folder) => {
+                    if folder.info.parent.is_none() {
+                        root_link = drive_folder.web_view_link;
+                    }
+                    drive_folder.id.ok_or(Error::DriveFolderMissingId)?
+                }
+                Err(err) if config.continue_on_error => {
+                    manifest
+                        .add(FailureKind::Directory, folder.info.path.clone(), &err)
+                        .parents = folder_parents;
+                    failed_folders.insert(folder.info.drive_id.clone());
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
 
         if config.print_only_id {
             println!(
@@ -180,49 +594,343 @@ pub async fn upload_directory(
             );
         }
 
-        let folder_id = drive_folder.id.ok_or(Error::DriveFolderMissingId)?;
-        let parents = Some(vec![folder_id.clone()]);
+        let parents = Some(vec![folder_id]);
 
         for file in folder.files() {
-            let os_file = fs::File::open(&file.path)
-                .map_err(|err| Error::OpenFile(config.file_path.as_ref().unwrap().clone(), err))?;
+            match upload_tree_file(hub, config, &file, parents.clone(), delegate_config).await {
+                Ok(()) => {}
+                Err(err) if config.continue_on_error => {
+                    manifest
+                        .add(FailureKind::File, file.path.clone(), &err)
+                        .parents
+                        .clone_from(&parents);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    if !config.print_only_id {
+        let failures = manifest.failures.len() - previous_failures;
+        print_tree_summary(
+            &tree_info,
+            failures,
+            root_link.as_deref(),
+            config.size_format,
+        );
+    }
 
-            let file_info = file.info(parents.clone());
+    Ok(())
+}
 
-            if !config.print_only_id {
-                println!(
-                    "Uploading file '{}' with id: {}",
-                    file.relative_path().display(),
-                    file.drive_id
-                );
-            }
+fn print_tree_summary(
+    tree_info: &TreeInfo,
+    failures: usize,
+    root_link: Option<&str>,
+    size_format: SizeFormat,
+) {
+    println!(
+        "Uploaded {} files in {} directories with a total size of {}",
+        tree_info.file_count,
+        tree_info.folder_count,
+        size_format.display(tree_info.total_file_size),
+    );
+
+    if failures > 0 {
+        println!("{failures} files or directories failed to upload");
+    }
+
+    print_folder_link(root_link);
+}
+
+async fn create_tree_folder(
+    hub: &Hub,
+    config: &Config,
+    folder: &file_tree::Folder,
+    parents: Option<Vec<String>>,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<google_drive3::api::File, Error> {
+    mkdir::create_directory(
+        hub,
+        &mkdir::Config {
+            id: Some(folder.info.drive_id.clone()),
+            name: folder.info.name.clone(),
+            parents: parents.map(file_id::from_strings),
+            app_properties: config.app_properties.clone(),
+            ..mkdir::Config::default()
+        },
+        delegate_config,
+    )
+    .await
+    .map_err(|err| Error::Mkdir(Box::new(err)))
+}
+
+fn print_folder_link(link: Option<&str>) {
+    if let Some(link) = link {
+        println!("View the uploaded directory at: {link}");
+    }
+}
+
+async fn upload_tree_file(
+    hub: &Hub,
+    config: &Config,
+    file: &file_tree::File,
+    parents: Option<Vec<String>>,
+    delegate_config: &UploadDelegateConfig,
+) -> Result<(), Error> {
+    let os_file =
+        fs::File::open(&file.path).map_err(|err| Error::OpenFile(file.path.clone(), err))?;
+
+    let file_info = FileInfo {
+        app_properties: config.app_properties.clone(),
+        ..file.info(parents)
+    };
+
+    if !config.print_only_id {
+        println!(
+            "Uploading file '{}' with id: {}",
+            file.relative_path().display(),
+            file.drive_id
+        );
+    }
+
+    let drive_id = Some(file.drive_id.clone());
+    let drive_file = if config.gzip {
+        let reader =
+            GzipReader::new(os_file).map_err(|err| Error::Compress(file.path.clone(), err))?;
+        let file_info = gzip_file_info(file_info, &reader);
+        upload_file(hub, reader, drive_id, file_info, delegate_config).await
+    } else {
+        upload_file(hub, os_file, drive_id, file_info, delegate_config).await
+    }
+    .map_err(|err| Error::Upload(Box::new(err)))?;
+
+    if config.verify {
+        let content =
+            fs::File::open(&file.path).map_err(|err| Error::OpenFile(file.path.clone(), err))?;
+        verify_upload(&drive_file, &file.path, content, config.gzip)?;
+    }
+
+    run_on_complete(config, &drive_file, &file.path).await;
+
+    if config.print_only_id {
+        println!("{}: {}", file.relative_path().display(), file.drive_id);
+    }
+
+    Ok(())
+}
+
+/// Uploads the entries of a tar or zip archive, recreating its directory structure under the
+/// configured parents.
+async fn upload_archive(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+    archive_path: &Path,
+) -> Result<(), Error> {
+    let mut archive = Archive::open(archive_path).map_err(Error::Archive)?;
+    let entries = archive.entries().to_vec();
+
+    if config.check_quota {
+        check_quota(hub, entries.iter().map(|entry| entry.size).sum()).await?;
+    }
+
+    let mut folder_ids = HashMap::new();
+    let mut file_count: u64 = 0;
+    let mut total_size = 0;
+
+    for entry in &entries {
+        if entry.is_dir {
+            ensure_archive_folder(hub, config, delegate_config, &mut folder_ids, &entry.path)
+                .await?;
+            continue;
+        }
+
+        let parents = match entry.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => Some(vec![
+                ensure_archive_folder(hub, config, delegate_config, &mut folder_ids, parent)
+                    .await?,
+            ]),
+            _ => config.parents.as_deref().map(file_id::to_strings),
+        };
+
+        let file_info = FileInfo {
+            name: entry
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default(),
+            mime_type: config.mime_type.as_ref().map_or_else(
+                || {
+                    mime_guess::from_path(&entry.path)
+                        .first()
+                        .map_or(Cow::Borrowed(&mime::APPLICATION_OCTET_STREAM), Cow::Owned)
+                },
+                Cow::Borrowed,
+            ),
+            parents,
+            size: entry.size,
+            app_properties: config.app_properties.clone(),
+            description: None,
+            starred: false,
+            content_hints: None,
+        };
+
+        if !config.print_only_id {
+            println!("Uploading file '{}'", entry.path.display());
+        }
+
+        let reader = archive.reader(entry).map_err(Error::Archive)?;
+        let file = if config.gzip {
+            let reader =
+                GzipReader::new(reader).map_err(|err| Error::Compress(entry.path.clone(), err))?;
+            let file_info = gzip_file_info(file_info, &reader);
+            upload_file(hub, reader, None, file_info, delegate_config).await
+        } else {
+            upload_file(hub, reader, None, file_info, delegate_config).await
+        }
+        .map_err(|err| Error::Upload(Box::new(err)))?;
 
-            upload_file(
+        if config.verify {
+            let content = archive.reader(entry).map_err(Error::Archive)?;
+            verify_upload(&file, &entry.path, content, config.gzip)?;
+        }
+
+        run_on_complete(config, &file, &entry.path).await;
+
+        if config.print_only_id {
+            println!("{}: {}", entry.path.display(), file.id.unwrap_or_default());
+        }
+
+        file_count += 1;
+        total_size += entry.size;
+    }
+
+    if !config.print_only_id {
+        println!(
+            "Uploaded {} files in {} directories with a total size of {}",
+            file_count,
+            folder_ids.len(),
+            config.size_format.display(total_size),
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the drive id of an archive folder, creating it and its missing ancestors first.
+async fn ensure_archive_folder(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+    folder_ids: &mut HashMap<PathBuf, String>,
+    path: &Path,
+) -> Result<String, Error> {
+    let mut parents = config.parents.clone();
+    let mut folder_path = PathBuf::new();
+
+    for component in path {
+        folder_path.push(component);
+
+        let folder_id = if let Some(folder_id) = folder_ids.get(&folder_path) {
+            folder_id.clone()
+        } else {
+            let drive_folder = mkdir::create_directory(
                 hub,
-                os_file,
-                Some(file.drive_id.clone()),
-                file_info,
+                &mkdir::Config {
+                    name: component.to_string_lossy().into_owned(),
+                    parents,
+                    app_properties: config.app_properties.clone(),
+                    ..mkdir::Config::default()
+                },
                 delegate_config,
             )
             .await
-            .map_err(|err| Error::Upload(Box::new(err)))?;
+            .map_err(|err| Error::Mkdir(Box::new(err)))?;
+            let folder_id = drive_folder.id.ok_or(Error::DriveFolderMissingId)?;
 
             if config.print_only_id {
-                println!("{}: {}", file.relative_path().display(), file.drive_id);
+                println!("{}: {}", folder_path.display(), folder_id);
+            } else {
+                println!("Created folder '{}'", folder_path.display());
             }
+
+            folder_ids.insert(folder_path.clone(), folder_id.clone());
+            folder_id
+        };
+
+        parents = Some(vec![FileId::from_drive(folder_id)]);
+    }
+
+    Ok(parents
+        .and_then(|mut parents| parents.pop())
+        .map(String::from)
+        .unwrap_or_default())
+}
+
+/// Uploads again the files and directories listed in a failure manifest, collecting the ones
+/// failing again into a new manifest.
+async fn retry_failed_uploads(
+    hub: &Hub,
+    config: &Config,
+    delegate_config: &UploadDelegateConfig,
+    manifest_path: &Path,
+) -> Result<(), Error> {
+    let previous = FailureManifest::read(manifest_path).map_err(Error::FailureManifest)?;
+
+    let mut manifest = FailureManifest::default();
+    for failed in previous.failures {
+        let retry_config = Config {
+            file_path: Some(failed.path.clone()),
+            parents: failed.parents.clone().map(file_id::from_strings),
+            upload_directories: true,
+            continue_on_error: true,
+            ..config.clone()
+        };
+
+        let result = match failed.kind {
+            FailureKind::File => upload_regular(hub, &retry_config, delegate_config).await,
+            FailureKind::Directory => {
+                upload_tree(hub, &retry_config, delegate_config, &mut manifest).await
+            }
+        };
+
+        if let Err(err) = result {
+            manifest.add(failed.kind, failed.path, &err).parents = failed.parents;
         }
     }
 
-    if !config.print_only_id {
-        println!(
-            "Uploaded {} files in {} directories with a total size of {}",
-            tree_info.file_count,
-            tree_info.folder_count,
-            ByteSize::b(tree_info.total_file_size).display().si(),
-        );
+    finish_failure_manifest(config, &manifest)
+}
+
+fn finish_failure_manifest(config: &Config, manifest: &FailureManifest) -> Result<(), Error> {
+    match manifest
+        .finish(config.failure_manifest.as_deref())
+        .map_err(Error::FailureManifest)?
+    {
+        0 => Ok(()),
+        failures => Err(Error::FailedUploads(failures)),
     }
+}
 
-    Ok(())
+/// Runs the `--on-complete` command for an uploaded file, read from `path`, or from where it was
+/// staged from.
+async fn run_on_complete(config: &Config, file: &google_drive3::api::File, path: &Path) {
+    if let Some(on_complete) = &config.on_complete {
+        let path = match &config.staged {
+            None => Some(path),
+            Some(Staged::Path(original)) => Some(original.as_path()),
+            Some(Staged::Stdin) => None,
+        };
+        let event = hook::Event {
+            transfer: hook::Transfer::Upload,
+            file_id: file.id.as_deref().unwrap_or_default(),
+            path,
+            md5: file.md5_checksum.as_deref(),
+            size: file.size.and_then(|size| u64::try_from(size).ok()),
+        };
+        on_complete.run(&event).await;
+    }
 }
 
 pub async fn upload_file<RS>(
@@ -232,6 +940,22 @@ pub async fn upload_file<RS>(
     file_info: FileInfo<'_>,
     delegate_config: &UploadDelegateConfig,
 ) -> Result<google_drive3::api::File, google_drive3::Error>
+where
+    RS: google_drive3::client::ReadSeek,
+{
+    let mut delegate = UploadDelegate::new(delegate_config);
+    upload_file_with_delegate(hub, src_file, file_id, file_info, &mut delegate).await
+}
+
+/// Like [`upload_file`], but with a delegate owned by the caller, which can inspect it afterwards.
+#[tracing::instrument(level = "info", skip_all, fields(name = %file_info.name, size = file_info.size))]
+pub async fn upload_file_with_delegate<RS>(
+    hub: &Hub,
+    src_file: RS,
+    file_id: Option<String>,
+    file_info: FileInfo<'_>,
+    delegate: &mut UploadDelegate<'_>,
+) -> Result<google_drive3::api::File, google_drive3::Error>
 where
     RS: google_drive3::client::ReadSeek,
 {
@@ -240,18 +964,21 @@ where
         name: Some(file_info.name.into_owned()),
         mime_type: Some(file_info.mime_type.to_string()),
         parents: file_info.parents,
+        app_properties: file_info.app_properties,
+        description: file_info.description,
+        starred: file_info.starred.then_some(true),
+        content_hints: file_info.content_hints,
         ..google_drive3::api::File::default()
     };
 
-    let chunk_size_bytes = delegate_config.chunk_size.in_bytes();
-    let mut delegate = UploadDelegate::new(delegate_config);
+    let chunk_size_bytes = google_drive3::client::Delegate::chunk_size(delegate);
 
     let req = hub
         .files()
         .create(dst_file)
-        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
+        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,sha256Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
         .add_scope(google_drive3::api::Scope::Full)
-        .delegate(&mut delegate)
+        .delegate(delegate)
         .supports_all_drives(true);
 
     let (_, file) = if file_info.size > chunk_size_bytes {
@@ -265,6 +992,19 @@ where
     Ok(file)
 }
 
+fn gzip_file_info<'a, R>(file_info: FileInfo<'_>, reader: &GzipReader<R>) -> FileInfo<'a> {
+    FileInfo {
+        name: Cow::Owned(compression::gzip_file_name(&file_info.name)),
+        mime_type: Cow::Borrowed(&drive_file::MIME_TYPE_GZIP_MIME),
+        parents: file_info.parents,
+        size: reader.compressed_len(),
+        app_properties: file_info.app_properties,
+        description: file_info.description,
+        starred: file_info.starred,
+        content_hints: file_info.content_hints,
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
@@ -273,19 +1013,52 @@ pub enum Error {
         source: file_info::FromFileError,
     },
     OpenFile(PathBuf, io::Error),
-    StdinToFile(file_helper::StdinToFileError),
+    /// Staging of the stream at the path, or of stdin when `None`
+    Stage(Option<PathBuf>, file_helper::StageError),
+    FindDrive(drives::list::FindDriveError),
+    Parent(Box<mkdir::Error>),
     Upload(Box<google_drive3::Error>),
     IsDirectory(PathBuf),
     DriveFolderMissingId,
     CreateFileTree(file_tree::errors::FileTree),
     Mkdir(Box<google_drive3::Error>),
+    Compress(PathBuf, io::Error),
+    FailureManifest(failure_manifest::Error),
+    FailedUploads(usize),
+    NameWithMultiplePaths,
+    Thumbnail(thumbnail::CustomThumbnailError),
+    EmptySplitSize,
+    Archive(archive::Error),
+    GetStorageQuota(Box<google_drive3::Error>),
+    InsufficientQuota {
+        required: u64,
+        available: u64,
+    },
+    Verify(PathBuf, io::Error),
+    ChecksumMismatch {
+        path: PathBuf,
+        expected: Checksum,
+        actual: Checksum,
+    },
+    SizeMismatch {
+        path: PathBuf,
+        expected: u64,
+        actual: u64,
+    },
 }
 
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Error::FileInfo { source, .. } => Some(source),
-            Error::StdinToFile(source) => Some(source),
+            Error::Stage(_, source) => Some(source),
+            Error::FindDrive(source) => Some(source),
+            Error::Parent(source) => Some(source),
+            Error::Thumbnail(source) => Some(source),
+            Error::Archive(source) => Some(source),
+            Error::GetStorageQuota(source) => Some(source),
+            Error::Compress(_, source) | Error::Verify(_, source) => Some(source),
+            Error::FailureManifest(source) => Some(source),
             // FIXME: correctly impl std::error::Error
             _ => None,
         }
@@ -302,7 +1075,16 @@ impl Display for Error {
             Error::OpenFile(path, err) => {
                 write!(f, "Failed to open file '{}': {}", path.display(), err)
             }
-            Error::StdinToFile(_) => f.write_str("unable to write stdin to file"),
+            Error::Stage(None, _) => f.write_str("unable to write stdin to file"),
+            Error::Stage(Some(path), _) => {
+                write!(
+                    f,
+                    "unable to stage '{}' in a temporary file",
+                    path.display()
+                )
+            }
+            Error::FindDrive(_) => f.write_str("unable to find the shared drive"),
+            Error::Parent(_) => f.write_str("unable to resolve the parent directory"),
             Error::Upload(err) => write!(f, "Failed to upload file: {err}"),
             Error::IsDirectory(path) => write!(
                 f,
@@ -312,6 +1094,142 @@ impl Display for Error {
             Error::DriveFolderMissingId => write!(f, "Folder created on drive does not have an id"),
             Error::CreateFileTree(err) => write!(f, "Failed to create file tree: {err}"),
             Error::Mkdir(err) => write!(f, "Failed to create directory: {err}"),
+            Error::Compress(path, _) => {
+                write!(f, "unable to compress file '{}'", path.display())
+            }
+            Error::FailureManifest(err) => write!(f, "{err}"),
+            Error::FailedUploads(count) => {
+                write!(f, "{count} files or directories failed to upload")
+            }
+            Error::Thumbnail(_) => f.write_str("unable to set the custom thumbnail"),
+            Error::NameWithMultiplePaths => {
+                f.write_str("--name can only be given when uploading a single path")
+            }
+            Error::EmptySplitSize => f.write_str("the size of the parts must be greater than zero"),
+            Error::Archive(_) => f.write_str("unable to read archive"),
+            Error::GetStorageQuota(_) => f.write_str("unable to get storage quota"),
+            Error::InsufficientQuota {
+                required,
+                available,
+            } => write!(
+                f,
+                "not enough storage quota: the upload needs {}, but only {} are available",
+                ByteSize::b(*required).display().si(),
+                ByteSize::b(*available).display().si(),
+            ),
+            Error::Verify(path, _) => {
+                write!(f, "unable to verify upload of '{}'", path.display())
+            }
+            Error::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} of uploaded file '{}' mismatches (expected {expected}, drive has {actual})",
+                expected.algorithm(),
+                path.display()
+            ),
+            Error::SizeMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "size of uploaded file '{}' mismatches (expected {expected} bytes, drive has {actual})",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Fails early when `upload_size` bytes do not fit in the remaining storage quota of the account.
+async fn check_quota(hub: &Hub, upload_size: u64) -> Result<(), Error> {
+    let quota = about::get_storage_quota(hub)
+        .await
+        .map_err(|err| Error::GetStorageQuota(Box::new(err)))?;
+
+    // Accounts without a limit have unlimited storage
+    let Some(limit) = quota.limit else {
+        return Ok(());
+    };
+    let available = u64::try_from(limit.saturating_sub(quota.usage.unwrap_or(0))).unwrap_or(0);
+
+    if upload_size > available {
+        Err(Error::InsufficientQuota {
+            required: upload_size,
+            available,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Compares the size and the checksum reported by drive for an uploaded file with the ones of the
+/// local content, read once. The checksum is sha256 when available and md5 otherwise; drive
+/// reports neither for some files, i.e. the ones converted to Google Docs formats, which are
+/// verified by size alone, or not at all when drive does not report a size either.
+fn verify_upload<R>(
+    file: &google_drive3::api::File,
+    path: &Path,
+    content: R,
+    gzip: bool,
+) -> Result<(), Error>
+where
+    R: Read + Seek,
+{
+    // Converted files have the size of the native document, not the one of the uploaded content
+    if drive_file::is_google_apps(file) {
+        eprintln!(
+            "Warning: '{}' was converted to a Google format, the upload could not be verified",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let actual_checksum = Checksum::from_drive_file(file);
+    let actual_size = file.size.and_then(|size| u64::try_from(size).ok());
+    if actual_checksum.is_none() && actual_size.is_none() {
+        eprintln!(
+            "Warning: drive reported neither a checksum nor a size for '{}', the upload could not be verified",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let algorithm = actual_checksum.map(|checksum| checksum.algorithm());
+    let (expected_checksum, expected_size) = if gzip {
+        let reader =
+            GzipReader::new(content).map_err(|err| Error::Compress(path.to_owned(), err))?;
+        checksum::compute_with_size(algorithm, reader)
+    } else {
+        checksum::compute_with_size(algorithm, io::BufReader::new(content))
+    }
+    .map_err(|err| Error::Verify(path.to_owned(), err))?;
+
+    if let Some(actual) = actual_size {
+        if actual != expected_size {
+            return Err(Error::SizeMismatch {
+                path: path.to_owned(),
+                expected: expected_size,
+                actual,
+            });
+        }
+    }
+
+    match (expected_checksum, actual_checksum) {
+        (Some(expected), Some(actual)) if expected != actual => Err(Error::ChecksumMismatch {
+            path: path.to_owned(),
+            expected,
+            actual,
+        }),
+        (Some(_), Some(_)) => Ok(()),
+        _ => {
+            eprintln!(
+                "Warning: drive did not report a checksum for '{}', only its size was verified",
+                path.display()
+            );
+            Ok(())
         }
     }
 }
@@ -323,3 +1241,64 @@ fn err_if_directory(path: &Path, config: &Config) -> Result<(), Error> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::Path};
+
+    use super::{verify_upload, Error};
+
+    fn uploaded(size: i64, md5: Option<&str>, mime: &str) -> google_drive3::api::File {
+        google_drive3::api::File {
+            size: Some(size),
+            md5_checksum: md5.map(String::from),
+            mime_type: Some(String::from(mime)),
+            ..google_drive3::api::File::default()
+        }
+    }
+
+    fn verify(file: &google_drive3::api::File) -> Result<(), Error> {
+        verify_upload(file, Path::new("hello.txt"), Cursor::new(b"hello"), false)
+    }
+
+    #[test]
+    fn verify_checksum_and_size() {
+        const MD5: &str = "5d41402abc4b2a76b9719d911017c592";
+
+        assert!(verify(&uploaded(5, Some(MD5), "text/plain")).is_ok());
+        assert!(matches!(
+            verify(&uploaded(
+                5,
+                Some("00000000000000000000000000000000"),
+                "text/plain"
+            )),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            verify(&uploaded(6, Some(MD5), "text/plain")),
+            Err(Error::SizeMismatch {
+                expected: 5,
+                actual: 6,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn verify_without_checksum() {
+        // Only the size is verified
+        assert!(verify(&uploaded(5, None, "text/plain")).is_ok());
+        assert!(matches!(
+            verify(&uploaded(4, None, "text/plain")),
+            Err(Error::SizeMismatch { .. })
+        ));
+
+        // The size of converted files is the one of the native document
+        assert!(verify(&uploaded(
+            1024,
+            None,
+            "application/vnd.google-apps.document"
+        ))
+        .is_ok());
+    }
+}
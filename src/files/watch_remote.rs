@@ -0,0 +1,73 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+};
+
+use crate::common::{
+    channel::{self, Action},
+    file_id::FileId,
+    hub_helper::{get_hub, GetHubError},
+};
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub file_id: FileId,
+    pub action: Action,
+}
+
+/// Opens a channel notified of the changes of a file, or closes it.
+pub async fn watch_remote(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    match &config.action {
+        Action::Start(channel_config) => {
+            let (_, channel) = hub
+                .files()
+                .watch(channel_config.channel(), &config.file_id)
+                .supports_all_drives(true)
+                .doit()
+                .await
+                .map_err(Error::Watch)?;
+
+            println!("Watching file {}", config.file_id);
+            channel::print_channel(&channel);
+        }
+        Action::Stop {
+            channel_id,
+            resource_id,
+        } => {
+            channel::stop(&hub, channel_id, resource_id)
+                .await
+                .map_err(Error::Stop)?;
+            println!("Stopped channel {channel_id}");
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    Watch(google_drive3::Error),
+    Stop(google_drive3::Error),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::Watch(source) | Error::Stop(source) => Some(source),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::Watch(_) => f.write_str("unable to watch the file"),
+            Error::Stop(_) => f.write_str("unable to stop the channel"),
+        }
+    }
+}
@@ -1,48 +1,216 @@
 use std::{
+    env,
+    error::Error,
+    fs,
     future::Future,
     io,
     ops::Deref,
     path::{Path, PathBuf},
     pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use google_drive3::{
+    api::Scope,
+    client::GetToken,
     hyper::{self, client::HttpConnector},
     hyper_rustls::{HttpsConnector, HttpsConnectorBuilder},
-    oauth2::{self, authenticator::Authenticator, authenticator_delegate::InstalledFlowDelegate},
+    oauth2::{
+        self,
+        authenticator::{ApplicationDefaultCredentialsTypes, Authenticator},
+        authenticator_delegate::InstalledFlowDelegate,
+        authorized_user::AuthorizedUserSecret,
+    },
     DriveHub,
 };
+use serde::Deserialize;
 
-use crate::app_config;
+use crate::{
+    app_config,
+    common::{endpoints, http_debug::DebugConnector, http_pool::PoolConfig},
+};
+
+/// Tokens expiring within this delay are refreshed before being used, so that the requests made
+/// during long jobs are not sent with a token about to expire. The chunks of resumable uploads are
+/// sent to the session url, which needs no token.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Set when the API rejected the current access token, the next request gets a new one.
+static TOKEN_REJECTED: AtomicBool = AtomicBool::new(false);
 
 pub struct HubConfig {
     pub secret: oauth2::ApplicationSecret,
     pub tokens_path: PathBuf,
 }
 
-pub struct Hub(DriveHub<HttpsConnector<HttpConnector>>);
+pub type Connector = DebugConnector<HttpsConnector<HttpConnector>>;
+
+pub struct Hub {
+    drive: DriveHub<Connector>,
+    metadata_scope: Scope,
+}
 
 impl Deref for Hub {
-    type Target = DriveHub<HttpsConnector<HttpConnector>>;
+    type Target = DriveHub<Connector>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.drive
     }
 }
 
 impl Hub {
     pub fn new(auth: Auth) -> io::Result<Hub> {
-        let connector = HttpsConnectorBuilder::new()
-            .with_native_roots()?
-            .https_or_http()
-            .enable_http1()
-            .enable_http2()
-            .build();
+        let mut drive = google_drive3::DriveHub::new(http_client()?, RefreshingAuth(auth.0));
+        drive.root_url(endpoints::api_root_url());
+        drive.base_url(endpoints::drive_api_url());
+
+        Ok(Hub {
+            drive,
+            metadata_scope: Scope::Full,
+        })
+    }
+
+    /// Makes the calls that only read metadata request `scope` instead of full access. The token
+    /// is picked by the authenticator among the stored ones covering `scope`, which can be a token
+    /// with full access as well.
+    #[must_use]
+    pub fn with_metadata_scope(self, scope: Scope) -> Hub {
+        Hub {
+            metadata_scope: scope,
+            ..self
+        }
+    }
+
+    /// The scope requested by the calls that only read metadata, i.e. listing files.
+    #[must_use]
+    pub fn metadata_scope(&self) -> Scope {
+        self.metadata_scope
+    }
+
+    /// Sends a GET request authorized for `scope`, for the requests the API bindings don't cover,
+    /// i.e. export links. `request` is given its method and body. Failures are the ones the
+    /// bindings would return.
+    pub async fn authorized_get(
+        &self,
+        mut request: hyper::http::request::Builder,
+        scope: Scope,
+    ) -> Result<hyper::Response<hyper::Body>, google_drive3::Error> {
+        let token = self
+            .auth
+            .get_token(&[scope.as_ref()])
+            .await
+            .map_err(google_drive3::Error::MissingToken)?;
+
+        if let Some(token) = token {
+            request = request.header(hyper::header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let request = request
+            .method(hyper::Method::GET)
+            .body(hyper::Body::empty())
+            .map_err(|err| {
+                google_drive3::Error::Io(io::Error::new(io::ErrorKind::InvalidInput, err))
+            })?;
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(google_drive3::Error::HttpError)?;
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(api_error(response).await)
+        }
+    }
+}
+
+/// Mimics the errors returned by the API bindings for a failed response: the JSON error the API
+/// answered with, or the response itself.
+pub async fn api_error(response: hyper::Response<hyper::Body>) -> google_drive3::Error {
+    let (parts, body) = response.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(err) => return google_drive3::Error::HttpError(err),
+    };
+
+    match serde_json::from_slice::<serde_json::Value>(&body) {
+        Ok(value) => google_drive3::Error::BadRequest(value),
+        Err(_) => google_drive3::Error::Failure(hyper::Response::from_parts(
+            parts,
+            hyper::Body::from(body),
+        )),
+    }
+}
+
+/// Makes the next request refresh the access token, after the API answered with 401.
+pub fn reject_token() {
+    TOKEN_REJECTED.store(true, Ordering::Relaxed);
+}
+
+/// Hands out the cached access token, refreshing it beforehand when it is about to expire or was
+/// rejected. Refreshing does not need any interaction as long as a refresh token is available.
+#[derive(Clone)]
+struct RefreshingAuth(Authenticator<HttpsConnector<HttpConnector>>);
+
+type TokenResult = Result<Option<String>, Box<dyn Error + Send + Sync>>;
+
+impl GetToken for RefreshingAuth {
+    fn get_token<'a>(
+        &'a self,
+        scopes: &'a [&str],
+    ) -> Pin<Box<dyn Future<Output = TokenResult> + Send + 'a>> {
+        Box::pin(async move {
+            let rejected = TOKEN_REJECTED.swap(false, Ordering::Relaxed);
+            let mut token = self.0.token(scopes).await?;
+
+            let expires_soon = token
+                .expiration_time()
+                .is_some_and(|time| expires_within(time.unix_timestamp(), TOKEN_REFRESH_MARGIN));
+            if rejected || expires_soon {
+                tracing::debug!(rejected, expires_soon, "refreshing access token");
+                token = self.0.force_refreshed_token(scopes).await?;
+            }
+
+            Ok(token.token().map(str::to_owned))
+        })
+    }
+}
+
+/// Whether a token expiring at the `expiration` unix timestamp expires within `margin`.
+fn expires_within(expiration: i64, margin: Duration) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    let deadline = now.saturating_add(margin.as_secs());
+    u64::try_from(expiration).map_or(true, |expiration| expiration <= deadline)
+}
 
-        let http_client = hyper::Client::builder().build(connector);
+/// Creates an HTTP client trusting the native root certificates, inspecting the exchanges when
+/// HTTP debugging or the usage statistics are enabled. The connection pool is tuned through the
+/// environment, see [`PoolConfig::from_env`].
+pub fn http_client() -> io::Result<hyper::Client<Connector>> {
+    let pool_config =
+        PoolConfig::from_env().map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
 
-        Ok(Hub(google_drive3::DriveHub::new(http_client, auth.0)))
+    let builder = HttpsConnectorBuilder::new()
+        .with_native_roots()?
+        .https_or_http()
+        .enable_http1();
+    let connector = builder.enable_http2().build();
+
+    tracing::debug!(?pool_config, "creating HTTP client");
+    let mut builder = hyper::Client::builder();
+    builder
+        .pool_max_idle_per_host(pool_config.max_idle_per_host)
+        .pool_idle_timeout(pool_config.idle_timeout);
+    if let Some(interval) = pool_config.http2_keep_alive {
+        builder
+            .http2_keep_alive_interval(interval)
+            .http2_keep_alive_while_idle(true);
     }
+
+    Ok(builder.build(DebugConnector::new(connector)))
 }
 
 pub struct Auth(pub Authenticator<HttpsConnector<HttpConnector>>);
@@ -71,14 +239,142 @@ impl Auth {
 
         Ok(Auth(auth))
     }
+
+    /// Authenticates with credentials from the environment, keeping the tokens in memory only.
+    pub async fn from_env_credentials(
+        credentials: &app_config::EnvCredentials,
+    ) -> Result<Auth, io::Error> {
+        let secret = &credentials.secret;
+
+        let auth = match &credentials.refresh_token {
+            Some(refresh_token) => {
+                oauth2::AuthorizedUserAuthenticator::builder(AuthorizedUserSecret {
+                    client_id: secret.client_id.clone(),
+                    client_secret: secret.client_secret.clone(),
+                    refresh_token: refresh_token.clone(),
+                    key_type: String::from("authorized_user"),
+                })
+                .build()
+                .await?
+            }
+            None => {
+                oauth2::InstalledFlowAuthenticator::builder(
+                    oauth2_secret(secret),
+                    oauth2::InstalledFlowReturnMethod::HTTPPortRedirect(8085),
+                )
+                .flow_delegate(Box::new(AuthDelegate))
+                .build()
+                .await?
+            }
+        };
+
+        Ok(Auth(auth))
+    }
+
+    /// Authenticates with Application Default Credentials.
+    ///
+    /// The credentials are looked up in the same order as the Google Cloud client libraries:
+    /// the file pointed by `GOOGLE_APPLICATION_CREDENTIALS`, the file created by
+    /// `gcloud auth application-default login` and finally the GCE metadata server.
+    pub async fn application_default(tokens_path: &Path) -> Result<Auth, io::Error> {
+        let Some(credentials_path) = adc_credentials_path() else {
+            let auth = match oauth2::ApplicationDefaultCredentialsAuthenticator::builder(
+                oauth2::ApplicationDefaultCredentialsFlowOpts::default(),
+            )
+            .await
+            {
+                ApplicationDefaultCredentialsTypes::ServiceAccount(builder) => {
+                    builder.persist_tokens_to_disk(tokens_path).build().await?
+                }
+                ApplicationDefaultCredentialsTypes::InstanceMetadata(builder) => {
+                    builder.persist_tokens_to_disk(tokens_path).build().await?
+                }
+            };
+
+            return Ok(Auth(auth));
+        };
+
+        let content = std::fs::read(&credentials_path)?;
+        let credentials: AdcCredentials =
+            serde_json::from_slice(&content).map_err(io::Error::other)?;
+
+        let auth = match credentials.kind.as_str() {
+            "service_account" => {
+                let key = oauth2::parse_service_account_key(&content)?;
+                oauth2::ServiceAccountAuthenticator::builder(key)
+                    .persist_tokens_to_disk(tokens_path)
+                    .build()
+                    .await?
+            }
+            "authorized_user" => {
+                let secret: AuthorizedUserSecret =
+                    serde_json::from_slice(&content).map_err(io::Error::other)?;
+                oauth2::AuthorizedUserAuthenticator::builder(secret)
+                    .persist_tokens_to_disk(tokens_path)
+                    .build()
+                    .await?
+            }
+            kind => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "unsupported credentials type '{kind}' in '{}'",
+                        credentials_path.display()
+                    ),
+                ))
+            }
+        };
+
+        Ok(Auth(auth))
+    }
+}
+
+/// A token as stored on disk by the authenticator, only the scopes are of interest.
+#[derive(Deserialize)]
+struct StoredToken {
+    scopes: Vec<String>,
+}
+
+/// Returns the scopes of the tokens stored at `path`, each one once.
+pub fn stored_token_scopes(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let content = fs::read(path)?;
+    let tokens: Vec<StoredToken> = serde_json::from_slice(&content)?;
+
+    let mut scopes: Vec<_> = tokens.into_iter().flat_map(|token| token.scopes).collect();
+    scopes.sort();
+    scopes.dedup();
+    Ok(scopes)
+}
+
+#[derive(Deserialize)]
+struct AdcCredentials {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+fn adc_credentials_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("GOOGLE_APPLICATION_CREDENTIALS") {
+        return Some(PathBuf::from(path));
+    }
+
+    let gcloud_config_path = if cfg!(windows) {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else {
+        home::home_dir()?.join(".config")
+    };
+
+    let path = gcloud_config_path
+        .join("gcloud")
+        .join("application_default_credentials.json");
+    path.exists().then_some(path)
 }
 
 fn oauth2_secret(config: &app_config::Secret) -> oauth2::ApplicationSecret {
     oauth2::ApplicationSecret {
         client_id: config.client_id.clone(),
         client_secret: config.client_secret.clone(),
-        token_uri: String::from("https://oauth2.googleapis.com/token"),
-        auth_uri: String::from("https://accounts.google.com/o/oauth2/auth"),
+        token_uri: endpoints::token_uri(),
+        auth_uri: endpoints::auth_uri(),
         redirect_uris: vec![String::from("urn:ietf:wg:oauth:2.0:oob")],
         project_id: None,
         client_email: None,
@@ -107,3 +403,48 @@ impl InstalledFlowDelegate for AuthDelegate {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use super::{expires_within, stored_token_scopes};
+
+    #[test]
+    fn token_expiration_margin() {
+        let now = i64::try_from(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        )
+        .unwrap();
+        let margin = Duration::from_secs(300);
+
+        assert!(expires_within(now - 10, margin));
+        assert!(expires_within(now + 120, margin));
+        assert!(!expires_within(now + 3600, margin));
+    }
+
+    #[test]
+    fn token_scopes_are_deduplicated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokens.json");
+        fs::write(
+            &path,
+            r#"[
+                {"scopes": ["https://www.googleapis.com/auth/drive"], "token": {}},
+                {"scopes": ["https://www.googleapis.com/auth/drive", "openid"], "token": {}}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            stored_token_scopes(&path).unwrap(),
+            ["https://www.googleapis.com/auth/drive", "openid"]
+        );
+    }
+}
@@ -1,32 +1,101 @@
 pub mod about;
 pub mod account;
 pub mod app_config;
+pub mod benchmark;
+pub mod browse;
+pub mod changes;
 pub mod common;
 pub mod drives;
 pub mod files;
 pub mod hub;
 pub mod permissions;
+pub mod queue;
+pub mod schedule;
+pub mod stats;
 pub mod version;
 
-use std::{path::PathBuf, process::ExitCode};
-
-use clap::{Parser, Subcommand};
-use common::{delegate::ChunkSize, permission};
-use error_trace::ErrorTrace;
-use files::list::{ListQuery, ListSortOrder};
+use std::{env, path::PathBuf, process::ExitCode, time::Duration};
+
+use bytesize::ByteSize;
+use clap::{Args, Parser, Subcommand};
+use common::{
+    app_property::{self, AppProperty},
+    channel,
+    delegate::ChunkSize,
+    drive_file::FileExtension,
+    drive_path::ChildFolder,
+    error_report::{self, ErrorFormat},
+    file_filter::{FileFilter, TimeBound},
+    file_id::FileId,
+    hook::OnComplete,
+    http_debug,
+    local_name::SanitizeNames,
+    logging::{self, LogLevel},
+    name_template::{self, InvalidTemplate},
+    permission,
+    size_format::SizeFormat,
+    table::OutputFormat,
+    tempfiles,
+    transfer::{Destination, ExistingFileAction},
+    usage_stats,
+};
+use files::{
+    download::OnCollision,
+    info::DateFormat,
+    list::{Corpora, ListQuery, ListSortOrder},
+    mkdir::FolderColor,
+};
 use mime::Mime;
+use regex::Regex;
+use schedule::cron::CronSchedule;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, disable_version_flag = true)]
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    /// Format of the errors printed on failure
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    errors: ErrorFormat,
+
+    /// Don't ask for confirmation before destructive operations
+    #[arg(long, short, global = true)]
+    yes: bool,
+
+    /// How sizes are displayed in listings, file info, storage quota and transfer summaries
+    #[arg(long, global = true, value_enum, default_value_t = SizeFormat::default())]
+    size_format: SizeFormat,
+
+    /// Log requests, chunk uploads, retries and directory walks at this level (default: off, or info with --log-file)
+    #[arg(long, global = true, value_enum)]
+    log_level: Option<LogLevel>,
+
+    /// Append the logs to this file as JSON lines instead of printing them to stderr
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Log the method, URL and response status of every request to the Drive API, as well as retries. Tokens and session ids are never logged. Meant to be attached to bug reports
+    #[arg(long, global = true)]
+    debug_http: bool,
 }
 
 #[derive(Subcommand)]
 enum Command {
     /// Print information about gdrive
-    About,
+    About {
+        /// Print storage quota of the current account
+        #[arg(long)]
+        storage: bool,
+
+        /// Print storage quota of every configured account
+        #[arg(long, requires = "storage")]
+        all_accounts: bool,
+
+        /// Print the build, the credential files of the current account and the scopes of its tokens, useful when asking for support
+        #[arg(long, conflicts_with = "storage")]
+        diagnostics: bool,
+    },
 
     /// Commands for managing accounts
     Account {
@@ -34,6 +103,17 @@ enum Command {
         command: AccountCommand,
     },
 
+    /// Browse files interactively in the terminal
+    Browse {
+        /// Folder to start browsing from, defaults to the root of My Drive
+        #[arg(long, value_name = "DIRECTORY_ID")]
+        parent: Option<FileId>,
+
+        /// Max files to list per folder
+        #[arg(long, default_value_t = 1000)]
+        max: usize,
+    },
+
     /// Commands for managing drives
     Drives {
         #[command(subcommand)]
@@ -43,7 +123,7 @@ enum Command {
     /// Commands for managing files
     Files {
         #[command(subcommand)]
-        command: FileCommand,
+        command: Box<FileCommand>,
     },
 
     /// Commands for managing file permissions
@@ -54,12 +134,283 @@ enum Command {
 
     /// Print version information
     Version,
+
+    /// Measure upload and download throughput with a temporary random file
+    Benchmark {
+        /// Size of the temporary file
+        #[arg(long, default_value = "100MB")]
+        size: ByteSize,
+
+        /// Set chunk size, a multiple of 256K up to 8192M, i.e. 48M or 768K. A number without unit is in MiB
+        #[arg(long, value_name = "SIZE", default_value_t = ChunkSize::default())]
+        chunk_size: ChunkSize,
+    },
+
+    /// Show the requests sent to the Drive API over the last days, to find out which commands
+    /// consume the quota. The statistics are only recorded once enabled, and never leave the
+    /// machine
+    Stats(StatsArgs),
+
+    /// Commands for running gdrive periodically
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+
+    /// Commands for the uploads, deletions and moves queued with --queue
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommand,
+    },
+
+    /// Commands for following the changes of the files of the account
+    Changes {
+        #[command(subcommand)]
+        command: ChangeCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ChangeCommand {
+    /// Register a webhook notified by Drive whenever a file of the account, or of a shared drive, changes
+    Watch {
+        /// Watch the changes of this shared drive
+        #[arg(long, value_name = "DRIVE_ID")]
+        drive: Option<FileId>,
+
+        #[command(flatten)]
+        channel: ChannelArgs,
+    },
+}
+
+/// Options of the commands opening or closing a notification channel.
+#[derive(Args)]
+struct ChannelArgs {
+    /// HTTPS URL receiving the notifications
+    #[arg(long, value_name = "URL", required_unless_present = "stop")]
+    webhook: Option<String>,
+
+    /// Id of the channel, generated when opening one if not given
+    #[arg(long, value_name = "ID")]
+    channel_id: Option<String>,
+
+    /// Token sent back with every notification, i.e. to check where it comes from
+    #[arg(long, conflicts_with = "stop")]
+    token: Option<String>,
+
+    /// Close the channel after this many seconds, Drive caps it to a day for files and a week for changes
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "stop")]
+    ttl: Option<u64>,
+
+    /// Close the channel given by --channel-id and --resource-id instead of opening one
+    #[arg(long, conflicts_with = "webhook", requires_all = ["channel_id", "resource_id"])]
+    stop: bool,
+
+    /// Resource id printed when the channel was opened
+    #[arg(long, value_name = "ID", requires = "stop")]
+    resource_id: Option<String>,
+}
+
+impl ChannelArgs {
+    fn into_action(self) -> channel::Action {
+        if self.stop {
+            channel::Action::Stop {
+                channel_id: self.channel_id.unwrap_or_default(),
+                resource_id: self.resource_id.unwrap_or_default(),
+            }
+        } else {
+            channel::Action::Start(channel::Config {
+                webhook: self.webhook.unwrap_or_default(),
+                channel_id: self.channel_id,
+                token: self.token,
+                ttl: self.ttl.map(Duration::from_secs),
+            })
+        }
+    }
+}
+
+/// Options selecting the parent folder by its name below another folder or by its path, instead
+/// of by its id.
+#[derive(Args)]
+struct ParentArgs {
+    /// Use the folder with this name in the --within folder as parent. The tokens {date}, {time} and {datetime} are replaced with the current local time, a strftime format can follow a colon, i.e. {date:%Y-%m}
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "within",
+        conflicts_with = "parent"
+    )]
+    parent_name: Option<String>,
+
+    /// Folder to look for --parent-name in
+    #[arg(long, value_name = "DIRECTORY_ID", requires = "parent_name")]
+    within: Option<FileId>,
+
+    /// Create the --parent-name folder when it does not exist
+    #[arg(long, requires = "parent_name")]
+    create_missing: bool,
+
+    /// Use the directory with this path from the root of My Drive as parent, i.e. backups/{date}, creating the missing directories. Tokens are replaced as in --parent-name
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["parent", "parent_name"])]
+    parent_path: Option<String>,
+}
+
+impl ParentArgs {
+    fn expanded_path(&self) -> Result<Option<String>, InvalidTemplate> {
+        self.parent_path
+            .as_deref()
+            .map(name_template::expand)
+            .transpose()
+    }
+
+    fn into_child_folder(self) -> Result<Option<ChildFolder>, InvalidTemplate> {
+        let (Some(name), Some(within)) = (self.parent_name, self.within) else {
+            return Ok(None);
+        };
+        Ok(Some(ChildFolder {
+            name: name_template::expand(&name)?,
+            within,
+            create_missing: self.create_missing,
+        }))
+    }
+}
+
+/// Metadata of a single uploaded file.
+#[derive(Args)]
+struct UploadedFileArgs {
+    /// Name of the file on Drive instead of the local name. With --gzip the .gz extension is still appended. The tokens {date}, {time} and {datetime} are replaced with the current local time, a strftime format can follow a colon, i.e. dump-{date:%Y%m%d}
+    #[arg(long, conflicts_with_all = ["recursive", "from_archive", "retry_failed"])]
+    name: Option<String>,
+
+    /// Description of the uploaded file, set when it is created
+    #[arg(long, conflicts_with_all = ["recursive", "from_archive", "retry_failed"])]
+    description: Option<String>,
+
+    /// Star the uploaded file
+    #[arg(long, conflicts_with_all = ["recursive", "from_archive", "retry_failed"])]
+    starred: bool,
+
+    /// Png, jpeg or gif image of at most 2MB shown as the thumbnail of the uploaded file, for formats Drive cannot preview
+    #[arg(long, value_name = "IMAGE", conflicts_with_all = ["recursive", "from_archive", "retry_failed", "split"])]
+    thumbnail: Option<PathBuf>,
+}
+
+/// Options of the commands walking a Drive folder, download only with --recursive.
+#[derive(Args)]
+struct CacheArgs {
+    /// Reuse the folder listings of a previous download, diff or diff-local of the same folder made less than this many seconds ago, as long as nothing changed on the drive since, deleted files included
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    cache_ttl: Option<u64>,
+}
+
+impl CacheArgs {
+    fn ttl(&self) -> Option<Duration> {
+        self.cache_ttl.map(Duration::from_secs)
+    }
+}
+
+/// Options of the stats command, which shows the statistics unless told otherwise.
+#[derive(Args)]
+struct StatsArgs {
+    /// Number of days to show, today included
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    days: u32,
+
+    /// Record the method and the path of the requests of the next runs, ids left out
+    #[arg(long, conflicts_with_all = ["disable", "reset"])]
+    enable: bool,
+
+    /// Stop recording, the recorded statistics are kept
+    #[arg(long, conflicts_with = "reset")]
+    disable: bool,
+
+    /// Remove the recorded statistics
+    #[arg(long)]
+    reset: bool,
+}
+
+impl StatsArgs {
+    fn into_action(self) -> stats::Action {
+        if self.enable {
+            stats::Action::Enable
+        } else if self.disable {
+            stats::Action::Disable
+        } else if self.reset {
+            stats::Action::Reset
+        } else {
+            stats::Action::Show { days: self.days }
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum QueueCommand {
+    /// List the queued commands, oldest first
+    List {
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+    },
+
+    /// Run the queued commands in order, keeping the failed ones in the queue
+    Flush {
+        /// Run the following commands when one fails instead of stopping. Only for independent
+        /// commands, a move queued after an upload would run even if the upload failed
+        #[arg(long)]
+        keep_going: bool,
+    },
+
+    /// Drop the queued commands without running them
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommand {
+    /// Print a crontab line or systemd units running a gdrive command on a schedule
+    Generate {
+        /// When to run, in crontab syntax, i.e. "0 2 * * *" or "@daily"
+        #[arg(long)]
+        cron: CronSchedule,
+
+        /// The gdrive command to run, i.e. "files upload --recursive /home/me/backups"
+        #[arg(long)]
+        command: String,
+
+        /// What to generate
+        #[arg(long, value_enum, default_value_t = schedule::generate::Format::default())]
+        format: schedule::generate::Format,
+
+        /// Name of the job, used for the systemd units and the log file
+        #[arg(long, default_value = "backup")]
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
 enum AccountCommand {
     /// Add an account
-    Add,
+    Add {
+        /// Use Application Default Credentials (`GOOGLE_APPLICATION_CREDENTIALS`, gcloud or the GCE metadata server) instead of a client secret
+        #[arg(long)]
+        adc: bool,
+
+        /// Seconds to wait for each attempt of getting the account info after signing in. Failed attempts are retried a few times
+        #[arg(long, value_name = "SECONDS", default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        timeout: u64,
+
+        /// Finish adding an account whose info could not be fetched after signing in, without signing in again
+        #[arg(long, conflicts_with = "adc")]
+        resume: bool,
+    },
 
     /// List all accounts
     List,
@@ -69,8 +420,12 @@ enum AccountCommand {
 
     /// Switch to a different account
     Switch {
-        /// Account name
-        account_name: String,
+        /// Account name. Without it, the accounts are listed to pick one
+        account_name: Option<String>,
+
+        /// Pick the account from a numbered list, the current one is marked with '*', even when stdin is not a terminal, i.e. `echo 2 | gdrive account switch -i`
+        #[arg(long, short, conflicts_with = "account_name")]
+        interactive: bool,
     },
 
     /// Remove an account
@@ -83,12 +438,25 @@ enum AccountCommand {
     Export {
         /// Account name
         account_name: String,
+
+        /// Directory or file path to write the archive to, or `-` to write it to stdout
+        #[arg(long, value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the archive if it already exists
+        #[arg(long)]
+        overwrite: bool,
     },
 
     /// Import account that was created with the export command
     Import {
-        /// Path to archive
-        file_path: PathBuf,
+        /// Path to archive, or `-` to read it from stdin
+        #[arg(required_unless_present = "url", conflicts_with = "url")]
+        file_path: Option<PathBuf>,
+
+        /// Download the archive from this https URL
+        #[arg(long)]
+        url: Option<String>,
     },
 }
 
@@ -103,6 +471,87 @@ enum DriveCommand {
         /// Field separator
         #[arg(long, default_value_t = String::from("\t"))]
         field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+
+        /// Print the drives as a JSON array of objects with their id and name
+        #[arg(long, conflicts_with_all = ["skip_header", "field_separator", "output"])]
+        json: bool,
+    },
+
+    /// Commands for managing the members of a shared drive
+    Members {
+        #[command(subcommand)]
+        command: MemberCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum MemberCommand {
+    /// List the members of a shared drive
+    List {
+        /// Drive id
+        drive_id: String,
+
+        /// Act as a domain administrator, managing a shared drive of the domain without being one of its members
+        #[arg(long)]
+        domain_admin: bool,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+    },
+
+    /// Add a member to a shared drive
+    Add {
+        /// Drive id
+        drive_id: String,
+
+        /// Email address of the user or group to add
+        #[arg(long)]
+        email: String,
+
+        /// The role granted to the member. Allowed values are: organizer, fileOrganizer, writer, commenter, reader
+        #[arg(long, default_value_t = permission::Role::default())]
+        role: permission::Role,
+
+        /// The email address belongs to a group
+        #[arg(long)]
+        group: bool,
+
+        /// Act as a domain administrator, managing a shared drive of the domain without being one of its members
+        #[arg(long)]
+        domain_admin: bool,
+    },
+
+    /// Remove a member from a shared drive
+    Remove {
+        /// Drive id
+        drive_id: String,
+
+        /// Email address of the member to remove
+        #[arg(long, required_unless_present = "id", conflicts_with = "id")]
+        email: Option<String>,
+
+        /// Permission id of the member to remove
+        #[arg(long, value_name = "PERMISSION_ID")]
+        id: Option<String>,
+
+        /// Act as a domain administrator, managing a shared drive of the domain without being one of its members
+        #[arg(long)]
+        domain_admin: bool,
     },
 }
 
@@ -111,11 +560,83 @@ enum FileCommand {
     /// Print file info
     Info {
         /// File id
-        file_id: String,
+        file_id: FileId,
 
-        /// Display size in bytes
+        /// Display size in bytes, same as --size-format bytes
         #[arg(long, default_value_t = false)]
         size_in_bytes: bool,
+
+        /// Request exactly these fields, i.e. "id,name,imageMediaMetadata", and print the
+        /// response as JSON. See <https://developers.google.com/drive/api/guides/fields-parameter>
+        #[arg(long, conflicts_with = "size_in_bytes")]
+        fields: Option<String>,
+
+        /// Print only the md5 of the file, exit with an error if it has none
+        #[arg(long, conflicts_with_all = ["size_in_bytes", "fields", "size_only"])]
+        md5_only: bool,
+
+        /// Print only the size of the file in bytes, exit with an error if it has none
+        #[arg(long, conflicts_with_all = ["size_in_bytes", "fields"])]
+        size_only: bool,
+
+        /// Print only the short lived link to the thumbnail of the file, custom or generated by Drive, exit with an error if it has none
+        #[arg(long, conflicts_with_all = ["size_in_bytes", "fields", "md5_only", "size_only"])]
+        thumbnail_link_only: bool,
+    },
+
+    /// Register a webhook notified by Drive whenever the file changes
+    WatchRemote {
+        /// File id
+        file_id: FileId,
+
+        #[command(flatten)]
+        channel: ChannelArgs,
+    },
+
+    /// Print the end of a file, i.e. a log that another process appends to on Drive
+    Tail {
+        /// File id
+        file_id: FileId,
+
+        /// Number of bytes to print from the end of the file
+        #[arg(long, short = 'c', value_name = "SIZE", default_value = "1KiB")]
+        bytes: ByteSize,
+
+        /// Keep checking the size of the file and print the content appended to it, until interrupted
+        #[arg(long, short)]
+        follow: bool,
+
+        /// Time between two checks with --follow, in seconds or with a unit like 30s or 5m
+        #[arg(long, value_name = "DURATION", default_value_t = files::tail::Interval::default(), requires = "follow")]
+        interval: files::tail::Interval,
+    },
+
+    /// Open a file in the web browser
+    Open {
+        /// File id
+        file_id: FileId,
+
+        /// Print the link instead of opening it
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Publish a Google Doc, Sheet or Slides presentation to the web
+    Publish {
+        /// File id
+        file_id: FileId,
+
+        /// Stop publishing the file
+        #[arg(long, conflicts_with_all = ["auto", "outside_domain"])]
+        unpublish: bool,
+
+        /// Republish the file automatically when it is edited
+        #[arg(long)]
+        auto: bool,
+
+        /// Publish to everyone, not only to the users of the domain of a Workspace account
+        #[arg(long)]
+        outside_domain: bool,
     },
 
     /// List files
@@ -124,9 +645,9 @@ enum FileCommand {
         #[arg(long, default_value_t = 30)]
         max: usize,
 
-        /// Query. See <https://developers.google.com/drive/search-parameters>
-        #[arg(long, default_value_t = ListQuery::default())]
-        query: ListQuery,
+        /// Query. See <https://developers.google.com/drive/search-parameters> [default: 'root' in parents and trashed = false, or trashed = false with --corpora]
+        #[arg(long)]
+        query: Option<ListQuery>,
 
         /// Order by. See <https://developers.google.com/drive/api/v3/reference/files/list>
         #[arg(long, default_value_t = ListSortOrder::default())]
@@ -134,12 +655,26 @@ enum FileCommand {
 
         /// List files in a specific folder
         #[arg(long, value_name = "DIRECTORY_ID")]
-        parent: Option<String>,
+        parent: Option<FileId>,
 
         /// List files on a shared drive
         #[arg(long, value_name = "DRIVE_ID")]
         drive: Option<String>,
 
+        /// List files on the shared drive with this name, as printed by `drives list`. With
+        /// --parent-path the path is resolved against it
+        #[arg(
+            long,
+            value_name = "NAME",
+            conflicts_with_all = ["drive", "parent", "owner", "shared_with_me", "all_accounts"]
+        )]
+        drive_name: Option<String>,
+
+        /// List files in a folder given by its path, i.e. "projects/2024". The path is resolved
+        /// against My Drive, or against the shared drive given with --drive
+        #[arg(long, value_name = "PATH", conflicts_with = "parent")]
+        parent_path: Option<String>,
+
         /// Don't print header
         #[arg(long)]
         skip_header: bool,
@@ -151,17 +686,82 @@ enum FileCommand {
         /// Field separator
         #[arg(long, default_value_t = String::from("\t"))]
         field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+
+        /// List files of every configured account, prefixing rows with the account name
+        #[arg(long)]
+        all_accounts: bool,
+
+        /// Print view, download and export links instead of file type, size and creation time
+        #[arg(long)]
+        links: bool,
+
+        /// Format dates with a strftime pattern, i.e. "%d/%m/%Y %H:%M"
+        #[arg(long, value_name = "FORMAT")]
+        date_format: Option<DateFormat>,
+
+        /// Print dates relative to now, i.e. "2 days ago"
+        #[arg(long, conflicts_with = "date_format")]
+        relative_time: bool,
+
+        /// Add the id, type and shared drive of the target of each shortcut. Missing targets
+        /// are reported as "missing", the ones the account cannot access as "inaccessible"
+        #[arg(long, conflicts_with_all = ["links", "all_accounts"])]
+        resolve_shortcuts: bool,
+
+        /// List files owned by the given email address. Use "me" for the current account
+        #[arg(long, value_name = "EMAIL", conflicts_with_all = ["query", "parent", "drive", "parent_path"])]
+        owner: Option<String>,
+
+        /// List files shared with the current account
+        #[arg(long, conflicts_with_all = ["query", "parent", "drive", "parent_path", "owner"])]
+        shared_with_me: bool,
+
+        /// Print only the file ids, one per line, without header
+        #[arg(long, conflicts_with_all = ["all_accounts", "links", "resolve_shortcuts", "output"])]
+        flat: bool,
+
+        /// Separate the ids printed by --flat with NUL bytes instead of newlines, for `xargs -0`
+        #[arg(short = '0', long, requires = "flat")]
+        null: bool,
+
+        /// Print the folders and files below the folder as a tree, My Drive by default. Only
+        /// names and ids are fetched, shortcuts are not followed
+        #[arg(
+            long,
+            conflicts_with_all = ["max", "query", "owner", "shared_with_me", "all_accounts", "links", "resolve_shortcuts", "flat"]
+        )]
+        tree: bool,
+
+        /// How many levels of the tree to list, 1 being the content of the folder. All levels
+        /// are listed by default
+        #[arg(long, value_name = "N", requires = "tree", value_parser = clap::value_parser!(u64).range(1..))]
+        depth: Option<u64>,
+
+        /// Bodies of items to search. With drive, --drive or --drive-name selects the shared drive
+        /// and the whole drive is searched instead of its root folder
+        #[arg(long, value_enum, conflicts_with_all = ["tree", "all_accounts"])]
+        corpora: Option<Corpora>,
     },
 
     /// Download file
+    #[command(mut_arg("cache_ttl", |arg| arg.requires("recursive")))]
     Download {
         /// File id
-        file_id: String,
+        file_id: FileId,
 
-        /// Overwrite existing files and folders
+        /// Overwrite existing files and folders, same as --if-exists overwrite
         #[arg(long)]
         overwrite: bool,
 
+        /// What to do with local files that already exist
+        #[arg(long, value_enum, value_name = "ACTION", default_value_t, conflicts_with_all = ["overwrite", "stdout"])]
+        if_exists: ExistingFileAction,
+
         /// Follow shortcut and download target file (does not work with recursive download)
         #[arg(long)]
         follow_shortcuts: bool,
@@ -177,12 +777,90 @@ enum FileCommand {
         /// Write file to stdout
         #[arg(long)]
         stdout: bool,
+
+        /// Decompress gzip files while downloading, removing the .gz extension (does not work with recursive download)
+        #[arg(long)]
+        auto_decompress: bool,
+
+        /// Only download files of at least this size, i.e. 10MB (only with --recursive)
+        #[arg(long, value_name = "SIZE", requires = "recursive")]
+        min_size: Option<ByteSize>,
+
+        /// Only download files of at most this size, i.e. 1GiB (only with --recursive)
+        #[arg(long, value_name = "SIZE", requires = "recursive")]
+        max_size: Option<ByteSize>,
+
+        /// Only download files modified after this time: an age like 12h, 7d or 2w, a date like 2024-01-31 or an RFC 3339 timestamp (only with --recursive)
+        #[arg(long, value_name = "TIME", requires = "recursive")]
+        newer_than: Option<TimeBound>,
+
+        /// Only download files modified before this time, in the same formats as --newer-than (only with --recursive)
+        #[arg(long, value_name = "TIME", requires = "recursive")]
+        older_than: Option<TimeBound>,
+
+        /// Count and list Google Docs files too, so that the number of files found matches the web interface. They are skipped since they can only be exported (only with --recursive)
+        #[arg(long, requires = "recursive")]
+        include_docs: bool,
+
+        /// Write a JSON manifest mapping the relative path of each downloaded file to its id, md5 and modification time (only with --recursive)
+        #[arg(long, value_name = "PATH", requires = "recursive")]
+        manifest: Option<PathBuf>,
+
+        /// Give up on a file that receives no data for this many seconds, list it in the failure manifest and go on with the next one (only with --recursive)
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..), requires = "recursive")]
+        file_timeout: Option<u64>,
+
+        /// Write the files given up because of --file-timeout to this file instead of printing them
+        #[arg(long, value_name = "PATH", requires = "file_timeout")]
+        failure_manifest: Option<PathBuf>,
+
+        #[command(flatten)]
+        cache_args: CacheArgs,
+
+        /// Don't verify the downloaded files against the md5/sha256 checksum provided by Drive
+        #[arg(long)]
+        no_verify_md5: bool,
+
+        /// The file is a directory created by `upload --split`: download its parts and join them back into the original file
+        #[arg(long, conflicts_with_all = ["recursive", "stdout"])]
+        join: bool,
+
+        /// Download the file as this many byte ranges fetched concurrently, which can be faster for large files
+        #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..=32), conflicts_with_all = ["recursive", "stdout", "join", "auto_decompress"])]
+        ranges: u16,
+
+        /// Export Google Docs files to this format, i.e. docx or xlsx. By default documents and presentations are exported to pdf, spreadsheets to csv
+        #[arg(long, value_name = "EXTENSION", conflicts_with_all = ["recursive", "join"])]
+        export_format: Option<FileExtension>,
+
+        /// Save the file under this name instead of its Drive name. No extension is added for exported documents or removed for decompressed files
+        #[arg(long, conflicts_with_all = ["recursive", "stdout"])]
+        name: Option<String>,
+
+        /// Shell command run after each downloaded file, given its id, local path, md5 and size in the `GDRIVE_FILE_ID`, `GDRIVE_FILE_PATH`, `GDRIVE_FILE_MD5` and `GDRIVE_FILE_SIZE` environment variables. Defaults to the `GDRIVE_ON_COMPLETE` environment variable
+        #[arg(long, value_name = "COMMAND", conflicts_with = "stdout")]
+        on_complete: Option<String>,
+
+        /// When to change the Drive names into names Windows accepts: forbidden characters and trailing dots or spaces are replaced by '_' and reserved names like CON or NUL get a '_' appended
+        #[arg(long, value_enum, value_name = "POLICY", default_value_t)]
+        sanitize_names: SanitizeNames,
+
+        /// What to do with files of the same folder saved under the same name, which Drive allows (only with --recursive)
+        #[arg(
+            long,
+            value_enum,
+            value_name = "POLICY",
+            default_value_t,
+            requires = "recursive"
+        )]
+        on_collision: OnCollision,
     },
 
     /// Upload file
+    #[command(group = clap::ArgGroup::new("queued_paths").args(["file_paths", "retry_failed", "from_archive"]).multiple(true))]
     Upload {
-        /// Path of file to upload
-        file_path: Option<PathBuf>,
+        /// Paths of the files to upload, all of them into the same parent. Stdin is uploaded when none is given. Stdin, pipes and devices are staged in a temporary file first, up to 16GiB unless `GDRIVE_MAX_STAGED_SIZE` gives another size in bytes (0 for no limit)
+        file_paths: Vec<PathBuf>,
 
         /// Force mime type [default: auto-detect]
         #[arg(long, value_name = "MIME_TYPE")]
@@ -190,14 +868,14 @@ enum FileCommand {
 
         /// Upload to an existing directory
         #[arg(long, value_name = "DIRECTORY_ID")]
-        parent: Option<Vec<String>>,
+        parent: Option<Vec<FileId>>,
 
-        /// Upload directories. Note that this will always create a new directory on drive and will not update existing directories with the same name
+        /// Upload directories. Note that this will always create a new directory on drive and will not update existing directories with the same name. Paths matched by the .gdriveignore files (gitignore syntax) of the directory and its subdirectories are skipped, as well as the .gdriveignore files themselves unless they contain `!/.gdriveignore`
         #[arg(long)]
         recursive: bool,
 
-        /// Set chunk size in MB, must be a power of two.
-        #[arg(long, value_name = "1|2|4|8|16|32|64|128|256|512|1024|4096|8192", default_value_t = ChunkSize::default())]
+        /// Set chunk size, a multiple of 256K up to 8192M, i.e. 48M or 768K. A number without unit is in MiB
+        #[arg(long, value_name = "SIZE", default_value_t = ChunkSize::default())]
         chunk_size: ChunkSize,
 
         /// Print errors occuring during chunk upload
@@ -211,22 +889,113 @@ enum FileCommand {
         /// Print only id of file/folder
         #[arg(long, default_value_t = false)]
         print_only_id: bool,
+
+        /// Compress files with gzip while uploading. Files are stored as <name>.gz
+        #[arg(long)]
+        gzip: bool,
+
+        /// Keep uploading the other files of a directory when some of them fail, then print a
+        /// JSON manifest of the failures
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Write the failure manifest to this file instead of printing it
+        #[arg(long, value_name = "PATH")]
+        failure_manifest: Option<PathBuf>,
+
+        /// Retry only the files and directories listed in a failure manifest
+        #[arg(long, value_name = "MANIFEST", conflicts_with_all = ["file_paths", "parent"])]
+        retry_failed: Option<PathBuf>,
+
+        /// Upload the files and directories of a .tar or .zip archive, preserving its structure
+        #[arg(long, value_name = "ARCHIVE", conflicts_with_all = ["file_paths", "recursive", "retry_failed"])]
+        from_archive: Option<PathBuf>,
+
+        /// Verify the uploaded files against the size and the checksum reported by drive, using sha256 when available and md5 otherwise. Files without a checksum are verified by size only, converted ones are not verified, with a warning
+        #[arg(long)]
+        verify: bool,
+
+        /// Check that the files fit in the remaining storage quota before starting the upload
+        #[arg(long)]
+        check_quota: bool,
+
+        /// Only upload files of at least this size, i.e. 10MB (only with --recursive)
+        #[arg(long, value_name = "SIZE", requires = "recursive")]
+        min_size: Option<ByteSize>,
+
+        /// Only upload files of at most this size, i.e. 1GiB (only with --recursive)
+        #[arg(long, value_name = "SIZE", requires = "recursive")]
+        max_size: Option<ByteSize>,
+
+        /// Only upload files modified after this time: an age like 12h, 7d or 2w, a date like 2024-01-31 or an RFC 3339 timestamp (only with --recursive)
+        #[arg(long, value_name = "TIME", requires = "recursive")]
+        newer_than: Option<TimeBound>,
+
+        /// Only upload files modified before this time, in the same formats as --newer-than (only with --recursive)
+        #[arg(long, value_name = "TIME", requires = "recursive")]
+        older_than: Option<TimeBound>,
+
+        /// Only walk this many levels of directories below the uploaded one, 0 uploading only the files directly in it (only with --recursive)
+        #[arg(long, value_name = "N", requires = "recursive")]
+        max_depth: Option<usize>,
+
+        /// Skip directories on another file system than the uploaded one, i.e. mounted network shares. Only supported on Unix (only with --recursive)
+        #[arg(long, requires = "recursive")]
+        one_file_system: bool,
+
+        /// Upload the file as parts of this size, i.e. 4GB, named <name>.partNN in a new <name>.parts directory. Use `download --join` to reassemble it
+        #[arg(long, value_name = "SIZE", conflicts_with_all = ["recursive", "gzip", "from_archive", "retry_failed"])]
+        split: Option<ByteSize>,
+
+        /// Set a property private to gdrive on every uploaded file and created folder, as key=value. Can be repeated
+        #[arg(long = "property", value_name = "KEY=VALUE")]
+        properties: Vec<AppProperty>,
+
+        #[command(flatten)]
+        file_args: Box<UploadedFileArgs>,
+
+        /// Upload to the root of the shared drive with this name, as printed by `drives list`
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["parent", "parent_name", "parent_path"])]
+        drive_name: Option<String>,
+
+        #[command(flatten)]
+        parent_args: Box<ParentArgs>,
+
+        /// Shell command run after each uploaded file, given its id, local path, md5 and size in the `GDRIVE_FILE_ID`, `GDRIVE_FILE_PATH`, `GDRIVE_FILE_MD5` and `GDRIVE_FILE_SIZE` environment variables. The path is empty for stdin. Defaults to the `GDRIVE_ON_COMPLETE` environment variable
+        #[arg(long, value_name = "COMMAND")]
+        on_complete: Option<String>,
+
+        /// Upload this many of the given paths at the same time
+        #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..=16))]
+        jobs: u16,
+
+        /// Don't run the command now, append it to the queue run by `gdrive queue flush`. Stdin cannot be queued, the files to upload must be given
+        #[arg(long, requires = "queued_paths")]
+        queue: bool,
     },
 
     /// Update file. This will create a new version of the file. The older versions will typically be kept for 30 days.
     Update {
         /// File id of the file you want ot update
-        file_id: String,
+        file_id: FileId,
 
-        /// Path of file to upload
+        /// Path of file to upload. If omitted, stdin is uploaded as it is read, one chunk at a time
         file_path: Option<PathBuf>,
 
+        /// New name of the file [default: name of the uploaded file, or the current name when reading from stdin]
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Number of bytes that will be read from stdin, i.e. 1.5GB. If given, a stream of a different size is rejected
+        #[arg(long, value_name = "SIZE", conflicts_with = "file_path")]
+        size: Option<ByteSize>,
+
         /// Force mime type [default: auto-detect]
         #[arg(long, value_name = "MIME_TYPE")]
         mime: Option<Mime>,
 
-        /// Set chunk size in MB, must be a power of two.
-        #[arg(long, value_name = "1|2|4|8|16|32|64|128|256|512|1024|4096|8192", default_value_t = ChunkSize::default())]
+        /// Set chunk size, a multiple of 256K up to 8192M, i.e. 48M or 768K. A number without unit is in MiB
+        #[arg(long, value_name = "SIZE", default_value_t = ChunkSize::default())]
         chunk_size: ChunkSize,
 
         /// Print errors occuring during chunk upload
@@ -236,57 +1005,254 @@ enum FileCommand {
         /// Print details about each chunk
         #[arg(long, value_name = "", default_value_t = false)]
         print_chunk_info: bool,
+
+        /// Only update the file if its current version, as printed by `files info`, matches
+        #[arg(long, value_name = "VERSION")]
+        if_match: Option<i64>,
+
+        /// Abort if the file is modified by someone else while the update is being prepared
+        #[arg(long, conflicts_with = "if_match")]
+        protect: bool,
+
+        /// Keep the modified time the file had before the update, which Drive resets by default
+        #[arg(long)]
+        keep_modified_time: bool,
+
+        /// Png, jpeg or gif image of at most 2MB shown as the thumbnail of the file, for formats Drive cannot preview
+        #[arg(long, value_name = "IMAGE")]
+        thumbnail: Option<PathBuf>,
+
+        /// Print only the id of the updated file
+        #[arg(long, conflicts_with = "print_chunk_info")]
+        print_only_id: bool,
     },
 
     /// Delete file
     Delete {
         /// File id
-        file_id: String,
+        file_id: FileId,
 
         /// Delete directory and all it's content
         #[arg(long)]
         recursive: bool,
+
+        /// Move the file to the trash instead of deleting it permanently. Members of a shared drive can only trash its files, unless they are organizers
+        #[arg(long)]
+        trash: bool,
+
+        /// Don't run the command now, append it to the queue run by `gdrive queue flush`
+        #[arg(long)]
+        queue: bool,
+    },
+
+    /// Remove a file shared with you from your Drive: delete your shortcuts to it and remove it from your folders
+    RemoveFromShared {
+        /// File id
+        file_id: FileId,
+
+        /// Give up your access to the file as well, which is the only way to hide it from "Shared with me"
+        #[arg(long)]
+        leave: bool,
     },
 
     /// Create directory
     Mkdir {
-        /// Name
+        /// Name. The tokens {date}, {time} and {datetime} are replaced with the current local time, a strftime format can follow a colon, i.e. {date:%Y-%m}
         name: String,
 
         /// Create in an existing directory
         #[arg(long, value_name = "DIRECTORY_ID")]
-        parent: Option<Vec<String>>,
+        parent: Option<Vec<FileId>>,
 
         /// Print only id of folder
         #[arg(long, default_value_t = false)]
         print_only_id: bool,
+
+        /// Print id, name and web link of the folder as JSON
+        #[arg(long, conflicts_with = "print_only_id")]
+        json: bool,
+
+        /// Folder color as a hex triplet, i.e. #4986e7. Drive picks the closest color of its palette
+        #[arg(long, value_name = "HEX")]
+        color: Option<FolderColor>,
+
+        /// Set a property private to gdrive on the folder, as key=value. Can be repeated
+        #[arg(long = "property", value_name = "KEY=VALUE")]
+        properties: Vec<AppProperty>,
+
+        /// Take the name as a path like a/b/c, reusing the directories that exist and only
+        /// creating the missing ones. Color and properties are set on the last directory when it
+        /// is created
+        #[arg(long)]
+        ensure: bool,
+
+        #[command(flatten)]
+        parent_args: ParentArgs,
     },
 
     /// Rename file/directory
     Rename {
         /// Id of file or directory
-        file_id: String,
+        file_id: FileId,
 
         /// New name
         name: String,
+
+        /// Only rename the file if its current version, as printed by `files info`, matches
+        #[arg(long, value_name = "VERSION")]
+        if_match: Option<i64>,
+    },
+
+    /// Rename the files of a folder whose name matches a regular expression
+    RenameBatch {
+        /// Id of the folder containing the files to rename
+        #[arg(long, value_name = "DIRECTORY_ID")]
+        parent: FileId,
+
+        /// Regular expression matched against the file names
+        #[arg(long = "match", value_name = "REGEX")]
+        pattern: Regex,
+
+        /// Replacement for the first match, capture groups can be referenced as $1 or ${name}
+        #[arg(long = "replace", value_name = "TEMPLATE")]
+        replacement: String,
+
+        /// Print the new names without renaming anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Stop renaming after the first failure, the files not renamed yet are reported as skipped
+        #[arg(long, conflicts_with = "dry_run")]
+        fail_fast: bool,
     },
 
     /// Move file/directory
     Move {
         /// Id of file or directory to move
-        file_id: String,
+        file_id: FileId,
 
         /// Id of folder to move to
-        folder_id: String,
+        folder_id: FileId,
+
+        /// When moving to another drive fails, copy the file and trash the original instead. The copy gets a new id
+        #[arg(long)]
+        copy_fallback: bool,
+
+        /// Keep the modified time the file had before the move
+        #[arg(long)]
+        keep_modified_time: bool,
+
+        /// Print only the id of the moved file, which is a new one when --copy-fallback copied it
+        #[arg(long)]
+        print_only_id: bool,
+
+        /// Don't run the command now, append it to the queue run by `gdrive queue flush`
+        #[arg(long)]
+        queue: bool,
     },
 
     /// Copy file
     Copy {
         /// Id of file or directory to move
-        file_id: String,
+        file_id: FileId,
 
         /// Id of folder to copy to
-        folder_id: String,
+        folder_id: FileId,
+
+        /// Name of the copy [default: name of the source file]
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Give the copy the modified time of the source file, instead of the time of the copy
+        #[arg(long)]
+        keep_modified_time: bool,
+
+        /// Print only the id of the copy
+        #[arg(long)]
+        print_only_id: bool,
+    },
+
+    /// Compare the files of two folders recursively, reporting the ones missing on either side or differing in size or checksum. Google documents are not compared
+    Diff {
+        /// Id of the first folder
+        folder_id_a: FileId,
+
+        /// Id of the second folder
+        folder_id_b: FileId,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+
+        /// Print the differences as a JSON array
+        #[arg(long, conflicts_with_all = ["skip_header", "field_separator", "output"])]
+        json: bool,
+
+        #[command(flatten)]
+        cache_args: CacheArgs,
+    },
+
+    /// Compare a local directory with a Drive folder by relative path, size and md5 without transferring anything. Files only present locally are reported as missing, files only present on Drive as extra. Paths matched by .gdriveignore files are skipped, like for `upload --recursive`
+    DiffLocal {
+        /// Path of the local directory
+        local_path: PathBuf,
+
+        /// Id of the Drive folder
+        folder_id: FileId,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+
+        /// Print the differences as a JSON array
+        #[arg(long, conflicts_with_all = ["skip_header", "field_separator", "output"])]
+        json: bool,
+
+        #[command(flatten)]
+        cache_args: CacheArgs,
+    },
+
+    /// Find the files with the given md5, i.e. to check whether a local file is already uploaded.
+    /// Every file of the account is listed, this can take a while on large drives
+    Find {
+        /// The md5 to look for, as 32 hexadecimal digits
+        #[arg(long, value_name = "HEX", required_unless_present = "md5_of")]
+        md5: Option<String>,
+
+        /// Look for the md5 of this local file
+        #[arg(long, value_name = "PATH", conflicts_with = "md5")]
+        md5_of: Option<PathBuf>,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
     },
 
     /// Import file as a google document/spreadsheet/presentation.
@@ -297,20 +1263,66 @@ enum FileCommand {
 
         /// Upload to an existing directory
         #[arg(long, value_name = "DIRECTORY_ID")]
-        parent: Option<Vec<String>>,
+        parent: Option<Vec<FileId>>,
 
         /// Print only id of file
         #[arg(long, default_value_t = false)]
         print_only_id: bool,
+
+        /// Import every file of a supported type in the directory and its subdirectories, recreating the directories on Drive. Other files are skipped
+        #[arg(long)]
+        recursive: bool,
     },
 
     /// Export google document to file
     Export {
         /// File id
-        file_id: String,
+        file_id: FileId,
 
-        /// File path to export to. The file extension will determine the export format
-        file_path: PathBuf,
+        /// File path to export to. The file extension will determine the export format. Without a
+        /// known extension, the default format of the document is used and its extension appended
+        #[arg(required_unless_present = "all_sheets", conflicts_with = "all_sheets")]
+        file_path: Option<PathBuf>,
+
+        /// Export only this sheet of a spreadsheet, given its name or its index starting from 0, to a csv or tsv file. Without it, csv and tsv exports only get the first sheet
+        #[arg(long, value_name = "NAME|INDEX")]
+        sheet: Option<String>,
+
+        /// Export each sheet of a spreadsheet to a csv file named after the sheet
+        #[arg(long, conflicts_with = "sheet")]
+        all_sheets: bool,
+
+        /// Directory to write the csv files of --all-sheets to, created if missing [default: current directory]
+        #[arg(long, value_name = "DIR", conflicts_with = "file_path")]
+        destination: Option<PathBuf>,
+
+        /// Overwrite existing files, same as --if-exists overwrite
+        #[arg(long)]
+        overwrite: bool,
+
+        /// What to do when the file already exists. Documents have no checksum, overwrite-if-different always overwrites
+        #[arg(
+            long,
+            value_enum,
+            value_name = "ACTION",
+            default_value_t,
+            conflicts_with = "overwrite"
+        )]
+        if_exists: ExistingFileAction,
+    },
+
+    /// Download the thumbnail of a file
+    Thumbnail {
+        /// File id
+        file_id: FileId,
+
+        /// Length in pixels of the longest side of the thumbnail [default: drive default]
+        #[arg(long, value_name = "PIXELS")]
+        size: Option<u32>,
+
+        /// File path to write the thumbnail to
+        #[arg(long, value_name = "PATH")]
+        destination: PathBuf,
 
         /// Overwrite existing files
         #[arg(long)]
@@ -323,7 +1335,8 @@ enum PermissionCommand {
     /// Grant permission to file
     Share {
         /// File id
-        file_id: String,
+        #[arg(required_unless_present = "from_csv", conflicts_with = "from_csv")]
+        file_id: Option<FileId>,
 
         /// The role granted by this permission. Allowed values are: owner, organizer, fileOrganizer, writer, commenter, reader
         #[arg(long, default_value_t = permission::Role::default())]
@@ -334,22 +1347,58 @@ enum PermissionCommand {
         type_: permission::Type,
 
         /// Email address. Required for user and group type
-        #[arg(long)]
+        #[arg(long, conflicts_with = "from_csv")]
         email: Option<String>,
 
         /// Domain. Required for domain type
-        #[arg(long)]
+        #[arg(long, conflicts_with = "from_csv")]
         domain: Option<String>,
 
         /// Whether the permission allows the file to be discovered through search. This is only applicable for permissions of type domain or anyone
         #[arg(long)]
         discoverable: bool,
+
+        /// Grant the permissions listed in a csv file with the columns `file_id`, `email_or_domain`, `role`, `type` and `expiration` (RFC 3339), an optional header included. Empty roles and types default to --role and --type
+        #[arg(long, value_name = "PATH")]
+        from_csv: Option<PathBuf>,
+
+        /// Stop granting the permissions of the csv file after the first failure
+        #[arg(long, requires = "from_csv")]
+        fail_fast: bool,
+
+        /// Act as a domain administrator, sharing the files of a shared drive of the domain without being one of its members
+        #[arg(long)]
+        domain_admin: bool,
     },
 
     /// List permissions for a file
     List {
         /// File id
-        file_id: String,
+        file_id: FileId,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+
+        /// List the permissions of every file and folder below the given folder as well, one row
+        /// per file and permission. Combine with `--output csv` for an export
+        #[arg(long)]
+        recursive: bool,
+    },
+
+    /// Report all non-owner permissions of a folder and everything below it
+    Audit {
+        /// Folder id
+        folder_id: FileId,
 
         /// Don't print header
         #[arg(long)]
@@ -358,12 +1407,21 @@ enum PermissionCommand {
         /// Field separator
         #[arg(long, default_value_t = String::from("\t"))]
         field_separator: String,
+
+        /// Output format: table, csv or tsv. csv and tsv escape separators and newlines in values,
+        /// ignoring --field-separator
+        #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
+        output: OutputFormat,
+
+        /// Print the findings as a JSON array
+        #[arg(long, conflicts_with_all = ["skip_header", "field_separator", "output"])]
+        json: bool,
     },
 
     /// Revoke permissions for a file. If no other options are specified, the 'anyone' permission will be revoked
     Revoke {
         /// File id
-        file_id: String,
+        file_id: FileId,
 
         /// Revoke all permissions (except owner)
         #[arg(long)]
@@ -372,55 +1430,249 @@ enum PermissionCommand {
         /// Revoke specific permission
         #[arg(long, value_name = "PERMISSION_ID")]
         id: Option<String>,
+
+        /// Revoke permissions granted to this email address
+        #[arg(long, conflicts_with_all = ["all", "id"])]
+        email: Option<String>,
+
+        /// Revoke permissions granted to this domain, or to any email address in it
+        #[arg(long, conflicts_with_all = ["all", "id", "email"])]
+        domain: Option<String>,
+
+        /// Stop revoking after the first failure, the permissions not revoked yet are reported as skipped
+        #[arg(long)]
+        fail_fast: bool,
+
+        /// Act as a domain administrator, revoking the permissions of the files of a shared drive of the domain without being one of its members
+        #[arg(long)]
+        domain_admin: bool,
     },
 }
 
-#[tokio::main]
-async fn main() -> ExitCode {
-    if let Err(err) = run().await {
-        eprintln!("{}", err.trace());
+impl FileCommand {
+    /// Whether the command must be queued instead of run.
+    fn is_queued(&self) -> bool {
+        matches!(
+            self,
+            FileCommand::Upload { queue: true, .. }
+                | FileCommand::Delete { queue: true, .. }
+                | FileCommand::Move { queue: true, .. }
+        )
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let error_format = cli.errors;
+
+    if let Err(err) = logging::init(cli.log_level, cli.log_file.as_deref(), cli.debug_http) {
+        error_report::print(&err, error_format);
+        return ExitCode::FAILURE;
+    }
+    if cli.debug_http {
+        http_debug::enable();
+    }
+    tempfiles::remove_on_signal();
+    if let Err(err) = usage_stats::init() {
+        eprintln!(
+            "Warning: usage statistics are not recorded: {}",
+            error_report::trace(&err)
+        );
+    }
+
+    let result = Box::pin(run(cli)).await;
+    // Waiting for the lock of the statistics blocks the thread
+    let saved: Result<(), Box<dyn std::error::Error + 'static>> =
+        match tokio::task::spawn_blocking(usage_stats::save_run).await {
+            Ok(saved) => saved.map_err(Into::into),
+            Err(err) => Err(err.into()),
+        };
+    if let Err(err) = saved {
+        tracing::warn!(error = %error_report::trace(err.as_ref()), "unable to save usage statistics");
+        eprintln!(
+            "Warning: unable to save the usage statistics: {}",
+            error_report::trace(err.as_ref())
+        );
+    }
+
+    if let Err(err) = result {
+        tracing::error!(error = %error_report::trace(err.as_ref()), "command failed");
+        error_report::print(err.as_ref(), error_format);
         ExitCode::FAILURE
     } else {
         ExitCode::SUCCESS
     }
 }
 
-async fn run() -> Result<(), Box<dyn std::error::Error + 'static>> {
-    let cli = Cli::parse();
-
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error + 'static>> {
     match cli.command {
-        Command::About => {
-            about::about();
+        Command::About {
+            storage,
+            all_accounts,
+            diagnostics,
+        } => {
+            about::about(about::Config {
+                storage,
+                all_accounts,
+                diagnostics,
+                size_format: cli.size_format,
+            })
+            .await?;
         }
 
         Command::Account { command } => {
             handle_account_command(command).await?;
         }
 
+        Command::Browse { parent, max } => {
+            Box::pin(browse::browse(browse::Config {
+                folder_id: parent,
+                max_files: max,
+            }))
+            .await?;
+        }
+
         Command::Drives { command } => match command {
             DriveCommand::List {
                 skip_header,
                 field_separator,
+                output,
+                json,
             } => {
                 drives::list(drives::list::Config {
                     skip_header,
                     field_separator,
+                    output,
+                    json,
                 })
                 .await?;
             }
+
+            DriveCommand::Members { command } => {
+                handle_members_command(command).await?;
+            }
         },
 
         Command::Files { command } => {
-            handle_files_command(command).await?;
+            handle_files_command(*command, cli.yes, cli.size_format).await?;
         }
 
         Command::Permissions { command } => {
-            handle_permissions_command(command).await?;
+            handle_permissions_command(command, cli.yes).await?;
         }
 
         Command::Version => {
             version::version();
         }
+
+        Command::Benchmark { size, chunk_size } => {
+            benchmark::benchmark(benchmark::Config {
+                size: size.as_u64(),
+                chunk_size,
+            })
+            .await?;
+        }
+
+        Command::Stats(args) => {
+            let config = stats::Config {
+                action: args.into_action(),
+                size_format: cli.size_format,
+            };
+            // Waiting for the lock of the statistics blocks the thread
+            tokio::task::spawn_blocking(move || stats::stats(&config)).await??;
+        }
+
+        Command::Schedule { command } => match command {
+            ScheduleCommand::Generate {
+                cron,
+                command,
+                format,
+                name,
+            } => {
+                schedule::generate(&schedule::generate::Config {
+                    schedule: cron,
+                    command,
+                    format,
+                    name,
+                })?;
+            }
+        },
+
+        Command::Queue { command } => {
+            handle_queue_command(command, cli.yes).await?;
+        }
+
+        Command::Changes { command } => match command {
+            ChangeCommand::Watch { drive, channel } => {
+                changes::watch(changes::watch::Config {
+                    drive_id: drive,
+                    action: channel.into_action(),
+                })
+                .await?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+async fn handle_members_command(
+    command: MemberCommand,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    match command {
+        MemberCommand::List {
+            drive_id,
+            domain_admin,
+            skip_header,
+            field_separator,
+            output,
+        } => {
+            drives::members::list(drives::members::list::Config {
+                drive_id,
+                domain_admin,
+                skip_header,
+                field_separator,
+                output,
+            })
+            .await?;
+        }
+
+        MemberCommand::Add {
+            drive_id,
+            email,
+            role,
+            group,
+            domain_admin,
+        } => {
+            drives::members::add(drives::members::add::Config {
+                drive_id,
+                email,
+                role,
+                group,
+                domain_admin,
+            })
+            .await?;
+        }
+
+        MemberCommand::Remove {
+            drive_id,
+            email,
+            id,
+            domain_admin,
+        } => {
+            let member = match (email, id) {
+                (Some(email), _) => drives::members::remove::Member::Email(email),
+                (None, id) => drives::members::remove::Member::PermissionId(id.unwrap_or_default()),
+            };
+
+            drives::members::remove(drives::members::remove::Config {
+                drive_id,
+                member,
+                domain_admin,
+            })
+            .await?;
+        }
     }
 
     Ok(())
@@ -428,6 +1680,7 @@ async fn run() -> Result<(), Box<dyn std::error::Error + 'static>> {
 
 async fn handle_permissions_command(
     command: PermissionCommand,
+    assume_yes: bool,
 ) -> Result<(), Box<dyn std::error::Error + 'static>> {
     match command {
         PermissionCommand::Share {
@@ -437,41 +1690,128 @@ async fn handle_permissions_command(
             discoverable,
             email,
             domain,
+            from_csv,
+            fail_fast,
+            domain_admin,
         } => {
-            permissions::share(permissions::share::Config {
-                file_id,
-                role,
-                type_,
-                discoverable,
-                email,
-                domain,
-            })
-            .await?;
+            if let Some(path) = from_csv {
+                permissions::share::share_from_csv(&permissions::share::CsvConfig {
+                    path,
+                    default_role: role,
+                    default_type: type_,
+                    discoverable,
+                    domain_admin,
+                    fail_fast,
+                })
+                .await?;
+            } else {
+                permissions::share(permissions::share::Config {
+                    file_id: file_id.expect("clap requires either a file id or a csv file"),
+                    role,
+                    type_,
+                    discoverable,
+                    email,
+                    domain,
+                    domain_admin,
+                    expiration: None,
+                })
+                .await?;
+            }
         }
 
         PermissionCommand::List {
             file_id,
             skip_header,
             field_separator,
+            output,
+            recursive,
         } => {
             permissions::list(permissions::list::Config {
                 file_id,
                 skip_header,
                 field_separator,
+                output,
+                recursive,
             })
             .await?;
         }
 
-        PermissionCommand::Revoke { file_id, all, id } => {
-            let action = if all {
-                permissions::revoke::RevokeAction::AllExceptOwner
-            } else if id.is_some() {
-                permissions::revoke::RevokeAction::Id(id.unwrap_or_default())
-            } else {
-                permissions::revoke::RevokeAction::Anyone
+        PermissionCommand::Audit {
+            folder_id,
+            skip_header,
+            field_separator,
+            output,
+            json,
+        } => {
+            permissions::audit(permissions::audit::Config {
+                folder_id,
+                skip_header,
+                field_separator,
+                output,
+                json,
+            })
+            .await?;
+        }
+
+        PermissionCommand::Revoke {
+            file_id,
+            all,
+            id,
+            email,
+            domain,
+            fail_fast,
+            domain_admin,
+        } => {
+            use permissions::revoke::RevokeAction;
+            let action = match (all, id, email, domain) {
+                (true, ..) => RevokeAction::AllExceptOwner,
+                (_, Some(id), ..) => RevokeAction::Id(id),
+                (_, _, Some(email), _) => RevokeAction::Email(email),
+                (_, _, _, Some(domain)) => RevokeAction::Domain(domain),
+                _ => RevokeAction::Anyone,
             };
 
-            permissions::revoke(permissions::revoke::Config { file_id, action }).await?;
+            permissions::revoke(permissions::revoke::Config {
+                file_id,
+                action,
+                assume_yes,
+                fail_fast,
+                domain_admin,
+            })
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_queue_command(
+    command: QueueCommand,
+    assume_yes: bool,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    match command {
+        QueueCommand::List {
+            skip_header,
+            field_separator,
+            output,
+        } => {
+            queue::list(&queue::list::Config {
+                skip_header,
+                field_separator,
+                output,
+            })?;
+        }
+
+        QueueCommand::Flush { keep_going } => {
+            queue::flush(queue::flush::Config {
+                keep_going,
+                assume_yes,
+            })
+            .await?;
+        }
+
+        QueueCommand::Clear => {
+            queue::clear(&queue::clear::Config { assume_yes })?;
         }
     }
 
@@ -484,15 +1824,65 @@ async fn handle_permissions_command(
 )]
 async fn handle_files_command(
     command: FileCommand,
+    assume_yes: bool,
+    size_format: SizeFormat,
 ) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    if command.is_queued() {
+        queue::enqueue(env::args_os())?;
+        return Ok(());
+    }
+
     match command {
         FileCommand::Info {
             file_id,
             size_in_bytes,
+            fields,
+            md5_only,
+            size_only,
+            thumbnail_link_only,
         } => {
+            let only = if md5_only {
+                Some(files::info::InfoField::Md5)
+            } else if size_only {
+                Some(files::info::InfoField::Size)
+            } else if thumbnail_link_only {
+                Some(files::info::InfoField::ThumbnailLink)
+            } else {
+                None
+            };
+
             files::info(files::info::Config {
                 file_id,
-                size_in_bytes,
+                size_format: if size_in_bytes {
+                    SizeFormat::Bytes
+                } else {
+                    size_format
+                },
+                fields,
+                only,
+            })
+            .await?;
+        }
+
+        FileCommand::WatchRemote { file_id, channel } => {
+            files::watch_remote(files::watch_remote::Config {
+                file_id,
+                action: channel.into_action(),
+            })
+            .await?;
+        }
+
+        FileCommand::Tail {
+            file_id,
+            bytes,
+            follow,
+            interval,
+        } => {
+            files::tail(files::tail::Config {
+                file_id,
+                bytes: bytes.as_u64(),
+                follow,
+                interval,
             })
             .await?;
         }
@@ -506,10 +1896,56 @@ async fn handle_files_command(
             skip_header,
             full_name,
             field_separator,
+            output,
+            all_accounts,
+            parent_path,
+            links,
+            date_format,
+            relative_time,
+            resolve_shortcuts,
+            owner,
+            shared_with_me,
+            flat,
+            null,
+            drive_name,
+            tree,
+            depth,
+            corpora,
         } => {
-            let parent_query = parent.map(|folder_id| ListQuery::FilesInFolder { folder_id });
-            let drive_query = drive.map(|drive_id| ListQuery::FilesOnDrive { drive_id });
-            let q = parent_query.or(drive_query).unwrap_or(query);
+            let date_format = if relative_time {
+                DateFormat::Relative
+            } else {
+                date_format.unwrap_or_default()
+            };
+            let parent_path = parent_path.map(|path| files::list::ParentPath {
+                path,
+                drive_id: drive.clone(),
+            });
+            let parent_query = parent.map(|folder_id| ListQuery::FilesInFolder {
+                folder_id: folder_id.into(),
+            });
+            // Searching the drive corpora covers the whole drive, not only its root folder
+            let (drive_query, drive_id) = if corpora == Some(Corpora::Drive) {
+                (None, drive)
+            } else {
+                (
+                    drive.map(|drive_id| ListQuery::FilesOnDrive { drive_id }),
+                    None,
+                )
+            };
+            let owner_query = owner.map(|email| ListQuery::OwnedBy { email });
+            let shared_query = shared_with_me.then_some(ListQuery::SharedWithMe);
+            let default_query = if corpora.is_some() {
+                ListQuery::NotTrashed
+            } else {
+                ListQuery::default()
+            };
+            let q = parent_query
+                .or(drive_query)
+                .or(owner_query)
+                .or(shared_query)
+                .or(query)
+                .unwrap_or(default_query);
 
             files::list(files::list::Config {
                 query: q,
@@ -518,6 +1954,20 @@ async fn handle_files_command(
                 skip_header,
                 truncate_name: !full_name,
                 field_separator,
+                output,
+                all_accounts,
+                parent_path,
+                links,
+                date_format,
+                size_format,
+                resolve_shortcuts,
+                flat,
+                null,
+                drive_name,
+                tree,
+                depth: depth.and_then(|depth| usize::try_from(depth).ok()),
+                corpora,
+                drive_id,
             })
             .await?;
         }
@@ -525,23 +1975,42 @@ async fn handle_files_command(
         FileCommand::Download {
             file_id,
             overwrite,
+            if_exists,
             follow_shortcuts,
             recursive,
             destination,
             stdout,
+            auto_decompress,
+            min_size,
+            max_size,
+            newer_than,
+            older_than,
+            include_docs,
+            manifest,
+            file_timeout,
+            failure_manifest,
+            cache_args,
+            no_verify_md5,
+            join,
+            ranges,
+            export_format,
+            name,
+            on_complete,
+            sanitize_names,
+            on_collision,
         } => {
             let existing_file_action = if overwrite {
-                files::download::ExistingFileAction::Overwrite
+                ExistingFileAction::Overwrite
             } else {
-                files::download::ExistingFileAction::Abort
+                if_exists
             };
 
             let dst = if stdout {
-                files::download::Destination::Stdout
+                Destination::Stdout
             } else if let Some(path) = destination {
-                files::download::Destination::Path(path)
+                Destination::Path(path)
             } else {
-                files::download::Destination::CurrentDir
+                Destination::CurrentDir
             };
 
             files::download(files::download::Config {
@@ -550,12 +2019,34 @@ async fn handle_files_command(
                 follow_shortcuts,
                 download_directories: recursive,
                 destination: dst,
+                auto_decompress,
+                filter: FileFilter {
+                    min_size: min_size.map(|size| size.as_u64()),
+                    max_size: max_size.map(|size| size.as_u64()),
+                    newer_than,
+                    older_than,
+                },
+                include_docs,
+                manifest,
+                file_timeout: file_timeout.map(Duration::from_secs),
+                failure_manifest,
+                cache_ttl: cache_args.ttl(),
+                verify_checksum: !no_verify_md5,
+                join,
+                size_format,
+                ranges: usize::from(ranges),
+                export_format,
+                name,
+                on_complete: OnComplete::resolve(on_complete),
+                sanitize_names,
+                on_collision,
             })
             .await?;
         }
 
         FileCommand::Upload {
-            file_path,
+            file_paths,
+            jobs,
             mime,
             parent,
             recursive,
@@ -563,9 +2054,29 @@ async fn handle_files_command(
             print_chunk_errors,
             print_chunk_info,
             print_only_id,
+            gzip,
+            continue_on_error,
+            failure_manifest,
+            retry_failed,
+            from_archive,
+            verify,
+            check_quota,
+            min_size,
+            max_size,
+            newer_than,
+            older_than,
+            max_depth,
+            one_file_system,
+            split,
+            properties,
+            file_args,
+            drive_name,
+            parent_args,
+            on_complete,
+            queue: _,
         } => {
-            files::upload(files::upload::Config {
-                file_path,
+            let config = files::upload::Config {
+                file_path: None,
                 mime_type: mime,
                 parents: parent,
                 chunk_size,
@@ -573,33 +2084,94 @@ async fn handle_files_command(
                 print_chunk_info,
                 upload_directories: recursive,
                 print_only_id,
-            })
-            .await?;
+                gzip,
+                continue_on_error,
+                failure_manifest,
+                retry_failed,
+                from_archive,
+                verify,
+                check_quota,
+                filter: FileFilter {
+                    min_size: min_size.map(|size| size.as_u64()),
+                    max_size: max_size.map(|size| size.as_u64()),
+                    newer_than,
+                    older_than,
+                },
+                max_depth,
+                one_file_system,
+                split: split.map(|size| size.as_u64()),
+                size_format,
+                app_properties: app_property::to_map(&properties),
+                name: file_args
+                    .name
+                    .as_deref()
+                    .map(name_template::expand)
+                    .transpose()?,
+                description: file_args.description,
+                starred: file_args.starred,
+                thumbnail: file_args.thumbnail,
+                drive_name,
+                parent_path: parent_args.expanded_path()?,
+                parent_name: parent_args.into_child_folder()?,
+                on_complete: OnComplete::resolve(on_complete),
+                staged: None,
+            };
+            files::upload::upload_paths(config, file_paths, usize::from(jobs)).await?;
         }
 
         FileCommand::Update {
             file_id,
             file_path,
+            name,
+            size,
             mime,
             chunk_size,
             print_chunk_errors,
             print_chunk_info,
+            if_match,
+            protect,
+            keep_modified_time,
+            thumbnail,
+            print_only_id,
         } => {
             files::update(files::update::Config {
                 file_id,
                 file_path,
+                name,
+                size: size.map(|size| size.as_u64()),
                 mime_type: mime,
                 chunk_size,
                 print_chunk_errors,
                 print_chunk_info,
+                if_match,
+                protect,
+                keep_modified_time,
+                thumbnail,
+                print_only_id,
             })
             .await?;
         }
 
-        FileCommand::Delete { file_id, recursive } => {
+        FileCommand::Delete {
+            file_id,
+            recursive,
+            trash,
+            queue: _,
+        } => {
             files::delete(files::delete::Config {
                 file_id,
                 delete_directories: recursive,
+                assume_yes,
+                trash,
+            })
+            .await?;
+        }
+
+        FileCommand::RemoveFromShared { file_id, leave } => {
+            files::remove_from_shared(files::remove_from_shared::Config {
+                file_id,
+                leave,
+                assume_yes,
             })
             .await?;
         }
@@ -608,32 +2180,174 @@ async fn handle_files_command(
             name,
             parent,
             print_only_id,
+            json,
+            color,
+            properties,
+            ensure,
+            parent_args,
         } => {
             files::mkdir(files::mkdir::Config {
                 id: None,
-                name,
+                name: name_template::expand(&name)?,
                 parents: parent,
                 print_only_id,
+                json,
+                color,
+                app_properties: app_property::to_map(&properties),
+                ensure,
+                parent_path: parent_args.expanded_path()?,
+                parent_name: parent_args.into_child_folder()?,
+            })
+            .await?;
+        }
+
+        FileCommand::RenameBatch {
+            parent,
+            pattern,
+            replacement,
+            dry_run,
+            fail_fast,
+        } => {
+            files::rename_batch(files::rename_batch::Config {
+                parent_id: parent,
+                pattern,
+                replacement,
+                dry_run,
+                fail_fast,
             })
             .await?;
         }
 
-        FileCommand::Rename { file_id, name } => {
-            files::rename(files::rename::Config { file_id, name }).await?;
+        FileCommand::Open { file_id, print } => {
+            files::open(files::open::Config { file_id, print }).await?;
         }
 
-        FileCommand::Move { file_id, folder_id } => {
-            files::mv(files::mv::Config {
+        FileCommand::Publish {
+            file_id,
+            unpublish,
+            auto,
+            outside_domain,
+        } => {
+            files::publish(files::publish::Config {
                 file_id,
-                to_folder_id: folder_id,
+                unpublish,
+                auto,
+                outside_domain,
+            })
+            .await?;
+        }
+
+        FileCommand::Rename {
+            file_id,
+            name,
+            if_match,
+        } => {
+            files::rename(files::rename::Config {
+                file_id,
+                name,
+                if_match,
             })
             .await?;
         }
 
-        FileCommand::Copy { file_id, folder_id } => {
-            files::copy(files::copy::Config {
+        FileCommand::Move {
+            file_id,
+            folder_id,
+            copy_fallback,
+            keep_modified_time,
+            print_only_id,
+            queue: _,
+        } => {
+            Box::pin(files::mv(files::mv::Config {
+                file_id,
+                to_folder_id: folder_id,
+                copy_fallback,
+                keep_modified_time,
+                print_only_id,
+            }))
+            .await?;
+        }
+
+        FileCommand::Copy {
+            file_id,
+            folder_id,
+            name,
+            keep_modified_time,
+            print_only_id,
+        } => {
+            Box::pin(files::copy(files::copy::Config {
                 file_id,
                 to_folder_id: folder_id,
+                name,
+                keep_modified_time,
+                print_only_id,
+            }))
+            .await?;
+        }
+
+        FileCommand::Diff {
+            folder_id_a,
+            folder_id_b,
+            skip_header,
+            field_separator,
+            output,
+            json,
+            cache_args,
+        } => {
+            files::diff(files::diff::Config {
+                folder_a: folder_id_a,
+                folder_b: folder_id_b,
+                skip_header,
+                field_separator,
+                output,
+                json,
+                size_format,
+                cache_ttl: cache_args.ttl(),
+            })
+            .await?;
+        }
+
+        FileCommand::DiffLocal {
+            local_path,
+            folder_id,
+            skip_header,
+            field_separator,
+            output,
+            json,
+            cache_args,
+        } => {
+            files::diff_local(files::diff_local::Config {
+                local_path,
+                folder_id,
+                skip_header,
+                field_separator,
+                output,
+                json,
+                size_format,
+                cache_ttl: cache_args.ttl(),
+            })
+            .await?;
+        }
+
+        FileCommand::Find {
+            md5,
+            md5_of,
+            skip_header,
+            field_separator,
+            output,
+        } => {
+            let md5 = match (md5, md5_of) {
+                (_, Some(path)) => files::find::Md5::Of(path),
+                (Some(hex), None) => files::find::Md5::Hex(hex),
+                (None, None) => unreachable!("clap requires --md5 or --md5-of"),
+            };
+
+            files::find(files::find::Config {
+                md5,
+                skip_header,
+                field_separator,
+                output,
+                size_format,
             })
             .await?;
         }
@@ -642,11 +2356,13 @@ async fn handle_files_command(
             file_path,
             parent,
             print_only_id,
+            recursive,
         } => {
             files::import(files::import::Config {
                 file_path,
                 parents: parent,
                 print_only_id,
+                recursive,
             })
             .await?;
         }
@@ -654,17 +2370,52 @@ async fn handle_files_command(
         FileCommand::Export {
             file_id,
             file_path,
+            sheet,
+            all_sheets,
+            destination,
             overwrite,
+            if_exists,
         } => {
             let existing_file_action = if overwrite {
-                files::export::ExistingFileAction::Overwrite
+                ExistingFileAction::Overwrite
             } else {
-                files::export::ExistingFileAction::Abort
+                if_exists
+            };
+
+            let output = match (file_path, sheet) {
+                _ if all_sheets => files::export::Output::AllSheets {
+                    destination: destination.unwrap_or_else(|| PathBuf::from(".")),
+                },
+                (Some(file_path), Some(sheet)) => files::export::Output::Sheet { sheet, file_path },
+                (file_path, _) => files::export::Output::Document(
+                    file_path.expect("file_path is required without --all-sheets"),
+                ),
             };
 
             files::export(files::export::Config {
                 file_id,
-                file_path,
+                output,
+                existing_file_action,
+            })
+            .await?;
+        }
+
+        FileCommand::Thumbnail {
+            file_id,
+            size,
+            destination,
+            overwrite,
+        } => {
+            let existing_file_action = if overwrite {
+                ExistingFileAction::Overwrite
+            } else {
+                ExistingFileAction::Abort
+            };
+
+            files::thumbnail(files::thumbnail::Config {
+                file_id,
+                size,
+                destination,
                 existing_file_action,
             })
             .await?;
@@ -678,8 +2429,17 @@ async fn handle_account_command(
     command: AccountCommand,
 ) -> Result<(), Box<dyn std::error::Error + 'static>> {
     match command {
-        AccountCommand::Add => {
-            account::add().await?;
+        AccountCommand::Add {
+            adc,
+            timeout,
+            resume,
+        } => {
+            account::add(account::add::Config {
+                application_default_credentials: adc,
+                timeout: Duration::from_secs(timeout),
+                resume,
+            })
+            .await?;
         }
 
         AccountCommand::List => {
@@ -690,22 +2450,49 @@ async fn handle_account_command(
             account::current()?;
         }
 
-        AccountCommand::Switch { account_name } => {
-            account::switch(&account::switch::Config { account_name })?;
+        AccountCommand::Switch {
+            account_name,
+            interactive,
+        } => {
+            account::switch(&account::switch::Config {
+                account_name,
+                interactive,
+            })?;
         }
 
         AccountCommand::Remove { account_name } => {
             account::remove(&account::remove::Config { account_name })?;
         }
 
-        AccountCommand::Export { account_name } => {
-            account::export(&account::export::Config { account_name })?;
+        AccountCommand::Export {
+            account_name,
+            output,
+            overwrite,
+        } => {
+            let existing_file_action = if overwrite {
+                ExistingFileAction::Overwrite
+            } else {
+                ExistingFileAction::Abort
+            };
+
+            let destination = match output {
+                Some(path) if path.as_os_str() == "-" => Destination::Stdout,
+                Some(path) => Destination::Path(path),
+                None => Destination::CurrentDir,
+            };
+
+            account::export(&account::export::Config {
+                account_name,
+                destination,
+                existing_file_action,
+            })
+            .await?;
         }
 
-        AccountCommand::Import { file_path } => {
-            account::import(&account::import::Config {
-                archive_path: file_path,
-            })?;
+        AccountCommand::Import { file_path, url } => {
+            let source = account::import::Source::new(file_path, url)?;
+
+            account::import(&account::import::Config { source }).await?;
         }
     }
 
@@ -1,7 +1,9 @@
+pub mod audit;
 pub mod list;
 pub mod revoke;
 pub mod share;
 
+pub use audit::audit;
 pub use list::list;
 pub use revoke::revoke;
 pub use share::share;
@@ -0,0 +1,296 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    io,
+};
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+use google_drive3::api::Permission;
+
+use crate::{
+    common::{
+        delegate::UploadDelegateConfig,
+        drive_file,
+        file_id::FileId,
+        file_tree_drive::{self, FileTreeDrive},
+        hub_helper::{get_hub, GetHubError},
+        permission,
+        table::{self, Table},
+        FileTreeLike, FolderLike,
+    },
+    files,
+    hub::Hub,
+    permissions::list::list_permissions,
+};
+
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+const HEADER: [&str; 7] = [
+    "Path",
+    "FileId",
+    "Type",
+    "Role",
+    "Grantee",
+    "Discoverable",
+    "Expiration",
+];
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub folder_id: FileId,
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub output: table::OutputFormat,
+    /// Print the findings as a JSON array instead of a table
+    pub json: bool,
+}
+
+pub async fn audit(config: Config) -> Result<(), Error> {
+    let hub = get_hub().await.map_err(Error::Hub)?;
+
+    let root = files::info::get_file(&hub, &config.folder_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    let items = collect_items(&hub, root)
+        .await
+        .map_err(|err| Error::ListFiles(Box::new(err)))?;
+    let item_count = items.len();
+
+    let findings: Vec<Finding> = list_items_permissions(&hub, items, |path, source| {
+        Error::ListPermissions { path, source }
+    })
+    .await?
+    .into_iter()
+    .flat_map(|(item, permissions)| {
+        permissions
+            .into_iter()
+            .filter(|permission| !permission::is_owner(permission))
+            .map(move |permission| Finding::new(&item, permission))
+    })
+    .collect();
+
+    if config.json {
+        print_json(&findings);
+    } else {
+        print_table(&config, findings.iter());
+    }
+
+    eprintln!(
+        "Audited {item_count} files, found {} non-owner permissions",
+        findings.len()
+    );
+
+    Ok(())
+}
+
+pub(crate) struct Item {
+    pub id: String,
+    /// Path from the root of the walk, which is named after the root itself
+    pub path: String,
+}
+
+/// Walks the hierarchy below `root`, returning every file and folder including `root` itself.
+/// Unlike the file tree used for downloads, documents are kept: they can be shared as well.
+pub(crate) async fn collect_items(
+    hub: &Hub,
+    root: google_drive3::api::File,
+) -> Result<Vec<Item>, file_tree_drive::errors::FileTreeDrive> {
+    if !drive_file::is_directory(&root) {
+        return Ok(vec![Item {
+            id: root.id.unwrap_or_default(),
+            path: root.name.unwrap_or_default(),
+        }]);
+    }
+
+    let tree_config = file_tree_drive::Config {
+        include_docs: true,
+        ..file_tree_drive::Config::default()
+    };
+    let tree = Box::pin(FileTreeDrive::from_file(hub, root, &tree_config)).await?;
+
+    let mut items = vec![];
+    for folder in tree.folders() {
+        items.push(Item {
+            id: folder.info.drive_id.clone(),
+            path: folder.info.relative_path().display().to_string(),
+        });
+        items.extend(folder.files().into_iter().map(|file| Item {
+            path: file.relative_path().display().to_string(),
+            id: file.drive_id,
+        }));
+    }
+
+    Ok(items)
+}
+
+/// Lists the permissions of every item, a few at a time, keeping the order of the items. A failure
+/// is turned into an error by `map_err`, given the path of the item.
+pub(crate) async fn list_items_permissions<E>(
+    hub: &Hub,
+    items: Vec<Item>,
+    map_err: impl Fn(String, google_drive3::Error) -> E,
+) -> Result<Vec<(Item, Vec<Permission>)>, E> {
+    let delegate_config = UploadDelegateConfig::default();
+    let map_err = &map_err;
+
+    stream::iter(items)
+        .map(|item| {
+            let delegate_config = &delegate_config;
+            async move {
+                match list_permissions(hub, delegate_config, &item.id).await {
+                    Ok(permissions) => Ok((item, permissions)),
+                    Err(source) => Err(map_err(item.path, source)),
+                }
+            }
+        })
+        .buffered(MAX_CONCURRENT_REQUESTS)
+        .try_collect()
+        .await
+}
+
+/// Who a permission is granted to: an email address, a domain or anyone with the link.
+fn grantee(permission: &Permission) -> String {
+    permission
+        .email_address
+        .clone()
+        .or_else(|| permission.domain.clone())
+        .or_else(|| permission.type_.clone())
+        .unwrap_or_default()
+}
+
+struct Finding {
+    path: String,
+    file_id: String,
+    type_: String,
+    role: String,
+    grantee: String,
+    discoverable: bool,
+    expiration: Option<String>,
+}
+
+impl Finding {
+    fn new(item: &Item, permission: Permission) -> Self {
+        Finding {
+            path: item.path.clone(),
+            file_id: item.id.clone(),
+            grantee: grantee(&permission),
+            type_: permission.type_.unwrap_or_default(),
+            role: permission.role.unwrap_or_default(),
+            discoverable: permission.allow_file_discovery.unwrap_or_default(),
+            expiration: permission.expiration_time.map(|time| time.to_rfc3339()),
+        }
+    }
+}
+
+fn print_table<'a>(config: &Config, findings: impl Iterator<Item = &'a Finding>) {
+    let values: Vec<[String; 7]> = findings
+        .map(|finding| {
+            [
+                finding.path.clone(),
+                finding.file_id.clone(),
+                finding.type_.clone(),
+                finding.role.clone(),
+                finding.grantee.clone(),
+                files::info::format_bool(finding.discoverable).to_string(),
+                finding.expiration.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let table = Table {
+        header: HEADER,
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: &config.field_separator,
+            format: config.output,
+        },
+    );
+}
+
+fn print_json(findings: &[Finding]) {
+    let json: Vec<_> = findings
+        .iter()
+        .map(|finding| {
+            serde_json::json!({
+                "path": finding.path,
+                "fileId": finding.file_id,
+                "type": finding.type_,
+                "role": finding.role,
+                "grantee": finding.grantee,
+                "discoverable": finding.discoverable,
+                "expirationTime": finding.expiration,
+            })
+        })
+        .collect();
+    println!("{:#}", serde_json::Value::Array(json));
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(GetHubError),
+    GetFile(google_drive3::Error),
+    ListFiles(Box<file_tree_drive::errors::FileTreeDrive>),
+    ListPermissions {
+        path: String,
+        source: google_drive3::Error,
+    },
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source) | Error::ListPermissions { source, .. } => Some(source),
+            Error::ListFiles(source) => Some(source),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFile(_) => f.write_str("unable to get folder"),
+            Error::ListFiles(_) => f.write_str("unable to list files"),
+            Error::ListPermissions { path, .. } => {
+                write!(f, "unable to list permissions of '{path}'")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use google_drive3::api::Permission;
+
+    use super::grantee;
+
+    #[test]
+    fn grantee_prefers_email_then_domain_then_type() {
+        let user = Permission {
+            type_: Some("user".to_string()),
+            email_address: Some("alice@example.com".to_string()),
+            ..Permission::default()
+        };
+        let domain = Permission {
+            type_: Some("domain".to_string()),
+            domain: Some("example.com".to_string()),
+            ..Permission::default()
+        };
+        let anyone = Permission {
+            type_: Some("anyone".to_string()),
+            ..Permission::default()
+        };
+
+        assert_eq!(grantee(&user), "alice@example.com");
+        assert_eq!(grantee(&domain), "example.com");
+        assert_eq!(grantee(&anyone), "anyone");
+    }
+}
@@ -7,28 +7,61 @@ use std::{
 use crate::{
     common::{
         delegate::{UploadDelegate, UploadDelegateConfig},
-        hub_helper::{get_hub, GetHubError},
+        file_id::FileId,
+        file_tree_drive,
+        hub_helper::{get_metadata_hub, GetHubError},
+        permission,
         table::{self, Table},
     },
     files,
     hub::Hub,
+    permissions::audit::{self, Item},
 };
 
+const RECURSIVE_HEADER: [&str; 9] = [
+    "Path",
+    "FileId",
+    "Id",
+    "Type",
+    "Role",
+    "Email",
+    "Domain",
+    "Discoverable",
+    "Expiration",
+];
+
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_id: String,
+    pub file_id: FileId,
     pub skip_header: bool,
     pub field_separator: String,
+    pub output: table::OutputFormat,
+    /// List the permissions of every file and folder below `file_id` as well, one row per
+    /// permission
+    pub recursive: bool,
 }
 
 pub async fn list(config: Config) -> Result<(), Error> {
-    let hub = get_hub().await.map_err(Error::Hub)?;
+    let hub = get_metadata_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
 
-    files::info::get_file(&hub, &config.file_id)
+    let file = files::info::get_file(&hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
+    if config.recursive {
+        let items = audit::collect_items(&hub, file)
+            .await
+            .map_err(|err| Error::ListFiles(Box::new(err)))?;
+        let items_permissions = audit::list_items_permissions(&hub, items, |path, source| {
+            Error::ListPermissionsOf { path, source }
+        })
+        .await?;
+
+        print_items_permissions_table(&config, items_permissions);
+        return Ok(());
+    }
+
     let permissions = list_permissions(&hub, &delegate_config, &config.file_id)
         .await
         .map_err(Error::ListPermissions)?;
@@ -64,6 +97,49 @@ fn print_permissions_table(config: &Config, permissions: Vec<google_drive3::api:
         &table::DisplayConfig {
             skip_header: config.skip_header,
             separator: &config.field_separator,
+            format: config.output,
+        },
+    );
+}
+
+fn print_items_permissions_table(
+    config: &Config,
+    items_permissions: Vec<(Item, Vec<google_drive3::api::Permission>)>,
+) {
+    let mut values: Vec<[String; 9]> = vec![];
+
+    for (item, permissions) in items_permissions {
+        for permission in permissions {
+            values.push([
+                item.path.clone(),
+                item.id.clone(),
+                permission.id.unwrap_or_default(),
+                permission.type_.unwrap_or_default(),
+                permission.role.unwrap_or_default(),
+                permission.email_address.unwrap_or_default(),
+                permission.domain.unwrap_or_default(),
+                files::info::format_bool(permission.allow_file_discovery.unwrap_or_default())
+                    .to_string(),
+                permission
+                    .expiration_time
+                    .map(|time| time.to_rfc3339())
+                    .unwrap_or_default(),
+            ]);
+        }
+    }
+
+    let table = Table {
+        header: RECURSIVE_HEADER,
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: &config.field_separator,
+            format: config.output,
         },
     );
 }
@@ -72,6 +148,16 @@ pub async fn list_permissions(
     hub: &Hub,
     delegate_config: &UploadDelegateConfig,
     file_id: &str,
+) -> Result<Vec<google_drive3::api::Permission>, google_drive3::Error> {
+    list_permissions_as(hub, delegate_config, file_id, false).await
+}
+
+/// Like [`list_permissions`], as a domain administrator when `domain_admin` is set.
+pub async fn list_permissions_as(
+    hub: &Hub,
+    delegate_config: &UploadDelegateConfig,
+    file_id: &str,
+    domain_admin: bool,
 ) -> Result<Vec<google_drive3::api::Permission>, google_drive3::Error> {
     let mut delegate = UploadDelegate::new(delegate_config);
 
@@ -80,22 +166,48 @@ pub async fn list_permissions(
         .list(file_id)
         .param(
             "fields",
-            "permissions(id,role,type,domain,emailAddress,allowFileDiscovery)",
+            "permissions(id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime)",
         )
-        .add_scope(google_drive3::api::Scope::Full)
+        .add_scope(hub.metadata_scope())
         .delegate(&mut delegate)
         .supports_all_drives(true)
+        .use_domain_admin_access(domain_admin)
         .doit()
         .await?;
 
     Ok(permission_list.permissions.unwrap_or_default())
 }
 
+/// Gets the file whose permissions are changed. Domain administrators may be unable to read the
+/// files of the shared drives they manage, for them a file with only the id is returned instead
+/// when the file is denied. Any other error, i.e. a network failure, is returned.
+pub async fn get_file(
+    hub: &Hub,
+    file_id: &FileId,
+    domain_admin: bool,
+) -> Result<google_drive3::api::File, google_drive3::Error> {
+    match files::info::get_file(hub, file_id).await {
+        Err(err) if domain_admin && permission::denied_to_domain_admin(&err) => {
+            Ok(google_drive3::api::File {
+                id: Some(file_id.to_string()),
+                name: Some(file_id.to_string()),
+                ..google_drive3::api::File::default()
+            })
+        }
+        result => result,
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
     GetFile(google_drive3::Error),
     ListPermissions(google_drive3::Error),
+    ListFiles(Box<file_tree_drive::errors::FileTreeDrive>),
+    ListPermissionsOf {
+        path: String,
+        source: google_drive3::Error,
+    },
 }
 
 impl error::Error for Error {}
@@ -110,6 +222,12 @@ impl Display for Error {
             Error::ListPermissions(err) => {
                 write!(f, "Failed to list permissions: {err}")
             }
+            Error::ListFiles(err) => {
+                write!(f, "Failed to list files: {err}")
+            }
+            Error::ListPermissionsOf { path, source } => {
+                write!(f, "Failed to list permissions of '{path}': {source}")
+            }
         }
     }
 }
@@ -5,51 +5,96 @@ use std::{
 
 use crate::{
     common::{
+        bulk,
         delegate::{UploadDelegate, UploadDelegateConfig},
+        file_id::FileId,
         hub_helper::{get_hub, GetHubError},
         permission,
+        prompt::{self, ConfirmError},
     },
-    files,
     hub::Hub,
     permissions,
 };
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_id: String,
+    pub file_id: FileId,
     pub action: RevokeAction,
+    /// Don't ask for confirmation before revoking the permissions matched by email, domain or
+    /// `--all`
+    pub assume_yes: bool,
+    /// Stop revoking after the first failure, the remaining permissions are reported as skipped
+    pub fail_fast: bool,
+    /// Revoke as a domain administrator, who can manage the shared drives of the domain without
+    /// being a member
+    pub domain_admin: bool,
 }
 
 pub async fn revoke(config: Config) -> Result<(), Error> {
     let hub = get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
 
-    let file = files::info::get_file(&hub, &config.file_id)
+    let file = permissions::list::get_file(&hub, &config.file_id, config.domain_admin)
         .await
         .map_err(|err| Error::GetFile(Box::new(err)))?;
 
-    let permissions = permissions::list::list_permissions(&hub, &delegate_config, &config.file_id)
-        .await
-        .map_err(|err| Error::ListPermissions(Box::new(err)))?;
+    let permissions = permissions::list::list_permissions_as(
+        &hub,
+        &delegate_config,
+        &config.file_id,
+        config.domain_admin,
+    )
+    .await
+    .map_err(|err| Error::ListPermissions(Box::new(err)))?;
 
     let delete_list = config.action.get_matching_permissions(permissions)?;
 
-    for permission in delete_list {
-        if print_revoke_details(&file, &permission).is_err() {
-            println!(
-                "Revoking permission with id: '{}'",
-                permission.id.clone().unwrap_or_default()
-            );
+    if config.action.is_bulk() && !delete_list.is_empty() {
+        prompt::confirm(
+            &format!(
+                "Revoke {} permissions of '{}'?",
+                delete_list.len(),
+                file.name.as_deref().unwrap_or_default()
+            ),
+            config.assume_yes,
+        )
+        .map_err(Error::Confirm)?;
+    }
+
+    let operations = delete_list.into_iter().map(|permission| bulk::Operation {
+        description: revoke_description(&permission),
+        input: permission.id.unwrap_or_default(),
+    });
+    let bulk_config = bulk::Config {
+        fail_fast: config.fail_fast,
+        ..bulk::Config::default()
+    };
+    let summary = bulk::run(&bulk_config, operations, |permission_id| {
+        let (hub, delegate_config, file_id) = (&hub, &delegate_config, &config.file_id);
+        let permission_id = permission_id.clone();
+        async move {
+            delete_permission(
+                hub,
+                delegate_config,
+                file_id,
+                &permission_id,
+                config.domain_admin,
+            )
+            .await
+            .map(bulk::Outcome::Done)
         }
+    })
+    .await;
+    summary.print();
 
-        delete_permission(
-            &hub,
-            &delegate_config,
-            &config.file_id,
-            &permission.id.clone().unwrap_or_default(),
-        )
-        .await
-        .map_err(|err| Error::DeletePermission(Box::new((permission.clone(), err))))?;
+    let denied = summary.failed.iter().any(|failure| {
+        permission::denied_on_shared_drive(&failure.error, &file, config.domain_admin)
+    });
+    if denied {
+        return Err(Error::SharedDriveDenied(summary.failed.len()));
+    }
+    if !summary.failed.is_empty() {
+        return Err(Error::FailedRevokes(summary.failed.len()));
     }
 
     Ok(())
@@ -60,6 +105,7 @@ pub async fn delete_permission(
     delegate_config: &UploadDelegateConfig,
     file_id: &str,
     permission_id: &str,
+    domain_admin: bool,
 ) -> Result<(), google_drive3::Error> {
     let mut delegate = UploadDelegate::new(delegate_config);
 
@@ -72,6 +118,7 @@ pub async fn delete_permission(
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true)
+        .use_domain_admin_access(domain_admin)
         .doit()
         .await?;
 
@@ -83,10 +130,12 @@ pub enum Error {
     Hub(GetHubError),
     GetFile(Box<google_drive3::Error>),
     ListPermissions(Box<google_drive3::Error>),
-    DeletePermission(Box<(google_drive3::api::Permission, google_drive3::Error)>),
     PermissionNotFound(String),
-    UnknownPermissionType(String),
-    UnknownPermissionRole(String),
+    NoPermissionForEmail(String),
+    NoPermissionForDomain(String),
+    Confirm(ConfirmError),
+    FailedRevokes(usize),
+    SharedDriveDenied(usize),
 }
 
 impl error::Error for Error {}
@@ -101,22 +150,28 @@ impl Display for Error {
             Error::ListPermissions(err) => {
                 write!(f, "Failed to list permissions: {err}")
             }
-            Error::DeletePermission(data) => {
-                let (permission, err) = data.as_ref();
+            Error::PermissionNotFound(id) => {
+                write!(f, "Permission '{id}' not found")
+            }
+            Error::NoPermissionForEmail(email) => {
                 write!(
                     f,
-                    "Failed to delete permission '{}': {}",
-                    permission.id.as_deref().unwrap_or_default(),
-                    err
+                    "No permission other than the owner's found for email '{email}'"
                 )
             }
-            Error::PermissionNotFound(id) => {
-                write!(f, "Permission '{id}' not found")
-            }
-            Error::UnknownPermissionType(type_) => {
-                write!(f, "Unknown permission type: '{type_}'")
+            Error::NoPermissionForDomain(domain) => {
+                write!(
+                    f,
+                    "No permission other than the owner's found for domain '{domain}'"
+                )
             }
-            Error::UnknownPermissionRole(role) => write!(f, "Unknown permission role: '{role}'"),
+            Error::Confirm(err) => write!(f, "{err}"),
+            Error::FailedRevokes(count) => write!(f, "Failed to revoke {count} permissions"),
+            Error::SharedDriveDenied(count) => write!(
+                f,
+                "Failed to revoke {count} permissions. Only the organizers of a shared drive can \
+                manage the permissions of its files, domain administrators can use --domain-admin"
+            ),
         }
     }
 }
@@ -127,9 +182,19 @@ pub enum RevokeAction {
     Anyone,
     AllExceptOwner,
     Id(String),
+    Email(String),
+    Domain(String),
 }
 
 impl RevokeAction {
+    /// Whether the action can match several permissions, which are revoked after a confirmation.
+    fn is_bulk(&self) -> bool {
+        matches!(
+            self,
+            RevokeAction::AllExceptOwner | RevokeAction::Email(_) | RevokeAction::Domain(_)
+        )
+    }
+
     fn get_matching_permissions(
         &self,
         permissions: Vec<google_drive3::api::Permission>,
@@ -147,10 +212,67 @@ impl RevokeAction {
 
             RevokeAction::Id(id) => Self::find_permission_by_id(permissions, id)
                 .map(|p| vec![p])
-                .ok_or_else(|| Error::PermissionNotFound(id.to_string())),
+                .ok_or_else(|| Error::PermissionNotFound(id.clone())),
+
+            RevokeAction::Email(email) => {
+                let matching = Self::get_permissions_by_email(permissions, email);
+                if matching.is_empty() {
+                    Err(Error::NoPermissionForEmail(email.clone()))
+                } else {
+                    Ok(matching)
+                }
+            }
+
+            RevokeAction::Domain(domain) => {
+                let matching = Self::get_permissions_by_domain(permissions, domain);
+                if matching.is_empty() {
+                    Err(Error::NoPermissionForDomain(domain.clone()))
+                } else {
+                    Ok(matching)
+                }
+            }
         }
     }
 
+    // The owner cannot be revoked, it is left out before anything is deleted
+    fn get_permissions_by_email(
+        permissions: Vec<google_drive3::api::Permission>,
+        email: &str,
+    ) -> Vec<google_drive3::api::Permission> {
+        permissions
+            .into_iter()
+            .filter(|p| !permission::is_owner(p))
+            .filter(|p| {
+                p.email_address
+                    .as_deref()
+                    .is_some_and(|address| address.eq_ignore_ascii_case(email))
+            })
+            .collect()
+    }
+
+    // Matches both the permissions granted to the whole domain and the ones granted to users
+    // and groups with an email address in the domain, except the owner
+    fn get_permissions_by_domain(
+        permissions: Vec<google_drive3::api::Permission>,
+        domain: &str,
+    ) -> Vec<google_drive3::api::Permission> {
+        permissions
+            .into_iter()
+            .filter(|p| !permission::is_owner(p))
+            .filter(|p| {
+                let domain_matches = p.domain.as_deref().is_some_and(|permission_domain| {
+                    permission_domain.eq_ignore_ascii_case(domain)
+                });
+                let email_matches = p
+                    .email_address
+                    .as_deref()
+                    .and_then(|address| address.rsplit_once('@'))
+                    .is_some_and(|(_, email_domain)| email_domain.eq_ignore_ascii_case(domain));
+                domain_matches || email_matches
+            })
+            .collect()
+    }
+
     fn get_permissions_by_type(
         permissions: Vec<google_drive3::api::Permission>,
         type_: permission::Type,
@@ -181,48 +303,73 @@ impl RevokeAction {
     }
 }
 
-fn print_revoke_details(
-    file: &google_drive3::api::File,
-    permission: &google_drive3::api::Permission,
-) -> Result<(), Error> {
-    let type_: permission::Type = permission
-        .type_
-        .clone()
-        .unwrap_or_default()
-        .parse()
-        .map_err(|_| Error::UnknownPermissionType(permission.type_.clone().unwrap_or_default()))?;
-
-    let role: permission::Role = permission
-        .role
-        .clone()
-        .unwrap_or_default()
-        .parse()
-        .map_err(|_| Error::UnknownPermissionRole(permission.role.clone().unwrap_or_default()))?;
-
-    if type_.requires_domain() {
-        println!(
-            "Revoking '{}' permission to {} '{}' for '{}'",
-            role,
-            type_,
-            permission.domain.clone().unwrap_or_default(),
-            file.name.clone().unwrap_or_default()
-        );
-    } else if type_.requires_email() {
-        println!(
-            "Revoking '{}' permission to '{}' with email '{}' for '{}'",
-            role,
-            type_,
-            permission.email_address.clone().unwrap_or_default(),
-            file.name.clone().unwrap_or_default()
-        );
-    } else {
-        println!(
-            "Revoking '{}' permission to '{}' for '{}'",
-            role,
-            type_,
-            file.name.clone().unwrap_or_default()
+/// Describes a permission by its role and grantee, i.e. "revoke writer of 'a@example.com'".
+fn revoke_description(permission: &google_drive3::api::Permission) -> String {
+    let grantee = permission
+        .email_address
+        .as_deref()
+        .or(permission.domain.as_deref())
+        .map_or_else(
+            || permission.type_.clone().unwrap_or_default(),
+            |grantee| format!("'{grantee}'"),
         );
+    format!(
+        "revoke {} of {grantee} ({})",
+        permission.role.as_deref().unwrap_or_default(),
+        permission.id.as_deref().unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RevokeAction;
+
+    fn permission(
+        id: &str,
+        email: Option<&str>,
+        domain: Option<&str>,
+    ) -> google_drive3::api::Permission {
+        google_drive3::api::Permission {
+            id: Some(id.to_owned()),
+            email_address: email.map(str::to_owned),
+            domain: domain.map(str::to_owned),
+            ..Default::default()
+        }
     }
 
-    Ok(())
+    fn ids(permissions: &[google_drive3::api::Permission]) -> Vec<&str> {
+        permissions.iter().filter_map(|p| p.id.as_deref()).collect()
+    }
+
+    #[test]
+    fn matching_permissions_by_email_and_domain() {
+        let owner = google_drive3::api::Permission {
+            role: Some(String::from("owner")),
+            ..permission("5", Some("owner@example.com"), None)
+        };
+        let permissions = vec![
+            permission("1", Some("Alice@Example.com"), None),
+            permission("2", Some("bob@other.com"), None),
+            permission("3", None, Some("example.com")),
+            permission("4", None, None),
+            owner,
+        ];
+
+        let by_email = RevokeAction::Email("alice@example.com".to_owned())
+            .get_matching_permissions(permissions.clone())
+            .unwrap();
+        assert_eq!(ids(&by_email), ["1"]);
+
+        let by_domain = RevokeAction::Domain("example.com".to_owned())
+            .get_matching_permissions(permissions.clone())
+            .unwrap();
+        assert_eq!(ids(&by_domain), ["1", "3"]);
+
+        assert!(RevokeAction::Email("carol@example.com".to_owned())
+            .get_matching_permissions(permissions.clone())
+            .is_err());
+        assert!(RevokeAction::Email("owner@example.com".to_owned())
+            .get_matching_permissions(permissions)
+            .is_err());
+    }
 }
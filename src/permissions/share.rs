@@ -1,26 +1,56 @@
 use std::{
     error,
     fmt::{Display, Formatter},
+    fs, io,
+    path::PathBuf,
 };
 
+use google_drive3::chrono::{DateTime, Utc};
+
 use crate::{
     common::{
+        bulk, csv,
         delegate::{UploadDelegate, UploadDelegateConfig},
+        file_id::FileId,
         hub_helper::{get_hub, GetHubError},
         permission,
     },
-    files,
     hub::Hub,
+    permissions,
 };
 
+/// Columns of the csv file of the grants, in this order. Only the file id is required, the role
+/// and the type default to the ones given on the command line.
+const CSV_COLUMNS: [&str; 5] = ["file_id", "email_or_domain", "role", "type", "expiration"];
+
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_id: String,
+    pub file_id: FileId,
     pub role: permission::Role,
     pub type_: permission::Type,
     pub discoverable: bool,
     pub email: Option<String>,
     pub domain: Option<String>,
+    /// Grant the permission as a domain administrator, who can manage the shared drives of the
+    /// domain without being a member
+    pub domain_admin: bool,
+    /// Time the permission is removed at
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+/// Grants the permissions listed in a csv file, see [`CSV_COLUMNS`].
+#[derive(Clone, Debug)]
+pub struct CsvConfig {
+    pub path: PathBuf,
+    /// Used for the rows without a role
+    pub default_role: permission::Role,
+    /// Used for the rows without a type
+    pub default_type: permission::Type,
+    pub discoverable: bool,
+    /// Grant the permissions as a domain administrator
+    pub domain_admin: bool,
+    /// Stop granting after the first failure
+    pub fail_fast: bool,
 }
 
 impl Config {
@@ -44,7 +74,7 @@ pub async fn share(config: Config) -> Result<(), Error> {
     let hub = get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
 
-    let file = files::info::get_file(&hub, &config.file_id)
+    let file = permissions::list::get_file(&hub, &config.file_id, config.domain_admin)
         .await
         .map_err(|err| Error::GetFile(Box::new(err)))?;
 
@@ -52,11 +82,23 @@ pub async fn share(config: Config) -> Result<(), Error> {
 
     create_permission(&hub, &delegate_config, &config)
         .await
-        .map_err(|err| Error::CreatePermission(Box::new(err)))?;
+        .map_err(|err| create_error(err, &file, config.domain_admin))?;
 
     Ok(())
 }
 
+fn create_error(
+    err: google_drive3::Error,
+    file: &google_drive3::api::File,
+    domain_admin: bool,
+) -> Error {
+    if permission::denied_on_shared_drive(&err, file, domain_admin) {
+        Error::SharedDriveDenied(Box::new(err))
+    } else {
+        Error::CreatePermission(Box::new(err))
+    }
+}
+
 pub async fn create_permission(
     hub: &Hub,
     delegate_config: &UploadDelegateConfig,
@@ -70,6 +112,7 @@ pub async fn create_permission(
         allow_file_discovery: config.allow_file_discovery(),
         email_address: config.email.clone(),
         domain: config.domain.clone(),
+        expiration_time: config.expiration,
         ..google_drive3::api::Permission::default()
     };
 
@@ -84,33 +127,187 @@ pub async fn create_permission(
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true)
+        .use_domain_admin_access(config.domain_admin)
         .doit()
         .await?;
 
     Ok(permission)
 }
 
+/// Grants every permission of the csv file, with a shared rate limit, then prints a summary.
+/// The whole file is validated before the first grant.
+pub async fn share_from_csv(config: &CsvConfig) -> Result<(), Error> {
+    let content =
+        fs::read_to_string(&config.path).map_err(|err| Error::ReadCsv(config.path.clone(), err))?;
+    let grants = parse_grants(&content, config)?;
+    if grants.is_empty() {
+        println!("No permissions to grant in {}", config.path.display());
+        return Ok(());
+    }
+
+    let hub = get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let operations = grants.into_iter().map(|grant| bulk::Operation {
+        description: grant_description(&grant),
+        input: grant,
+    });
+    let bulk_config = bulk::Config {
+        fail_fast: config.fail_fast,
+        ..bulk::Config::default()
+    };
+    let summary = bulk::run(&bulk_config, operations, |grant| {
+        let (hub, delegate_config, grant) = (&hub, &delegate_config, grant.clone());
+        async move {
+            create_permission(hub, delegate_config, &grant)
+                .await
+                .map(bulk::Outcome::Done)
+        }
+    })
+    .await;
+    summary.print();
+
+    if !summary.failed.is_empty() {
+        return Err(Error::FailedGrants(summary.failed.len()));
+    }
+
+    Ok(())
+}
+
+/// Reads the grants of the csv file, skipping a header starting with `file_id`.
+fn parse_grants(content: &str, config: &CsvConfig) -> Result<Vec<Config>, Error> {
+    let records = csv::parse(content).map_err(Error::ParseCsv)?;
+    let records = match records.split_first() {
+        Some((header, rest)) if header.fields[0].trim() == CSV_COLUMNS[0] => rest,
+        _ => &records[..],
+    };
+
+    records
+        .iter()
+        .map(|record| {
+            parse_grant(record, config).map_err(|reason| Error::InvalidRow {
+                line: record.line,
+                reason,
+            })
+        })
+        .collect()
+}
+
+fn parse_grant(record: &csv::Record, config: &CsvConfig) -> Result<Config, String> {
+    if record.fields.len() > CSV_COLUMNS.len() {
+        return Err(format!(
+            "expected at most {} columns: {}",
+            CSV_COLUMNS.len(),
+            CSV_COLUMNS.join(", ")
+        ));
+    }
+    let field = |index: usize| {
+        record
+            .fields
+            .get(index)
+            .map(|field| field.trim())
+            .filter(|field| !field.is_empty())
+    };
+
+    let file_id = field(0)
+        .ok_or("the file id is missing")?
+        .parse::<FileId>()
+        .map_err(|err| err.to_string())?;
+    let role = field(2)
+        .map(str::parse::<permission::Role>)
+        .transpose()
+        .map_err(|err| err.to_string())?
+        .unwrap_or(config.default_role);
+    let type_ = field(3)
+        .map(str::parse::<permission::Type>)
+        .transpose()
+        .map_err(|err| err.to_string())?
+        .unwrap_or(config.default_type);
+    let expiration = field(4)
+        .map(|expiration| {
+            DateTime::parse_from_rfc3339(expiration)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| format!("'{expiration}' is not an RFC 3339 time"))
+        })
+        .transpose()?;
+
+    let grantee = field(1).map(str::to_owned);
+    let (email, domain) = if type_.requires_email() {
+        (
+            Some(grantee.ok_or(format!("an email is required for type '{type_}'"))?),
+            None,
+        )
+    } else if type_.requires_domain() {
+        (
+            None,
+            Some(grantee.ok_or(format!("a domain is required for type '{type_}'"))?),
+        )
+    } else {
+        (None, None)
+    };
+
+    Ok(Config {
+        file_id,
+        role,
+        type_,
+        discoverable: config.discoverable,
+        email,
+        domain,
+        domain_admin: config.domain_admin,
+        expiration,
+    })
+}
+
+fn grant_description(grant: &Config) -> String {
+    let grantee = grant
+        .email
+        .as_deref()
+        .or(grant.domain.as_deref())
+        .map_or_else(|| grant.type_.to_string(), |grantee| format!("'{grantee}'"));
+    format!("grant {} to {grantee} on {}", grant.role, grant.file_id)
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(GetHubError),
     GetFile(Box<google_drive3::Error>),
     CreatePermission(Box<google_drive3::Error>),
+    SharedDriveDenied(Box<google_drive3::Error>),
     MissingEmail(permission::Type),
     MissingDomain(permission::Type),
+    ReadCsv(PathBuf, io::Error),
+    ParseCsv(csv::UnterminatedQuote),
+    InvalidRow { line: usize, reason: String },
+    FailedGrants(usize),
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Hub(source) => Some(source),
+            Error::GetFile(source)
+            | Error::CreatePermission(source)
+            | Error::SharedDriveDenied(source) => Some(source.as_ref()),
+            Error::ReadCsv(_, source) => Some(source),
+            Error::ParseCsv(source) => Some(source),
+            Error::MissingEmail(_)
+            | Error::MissingDomain(_)
+            | Error::InvalidRow { .. }
+            | Error::FailedGrants(_) => None,
+        }
+    }
+}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{err}"),
-            Error::GetFile(err) => {
-                write!(f, "Failed to get file: {err}")
-            }
-            Error::CreatePermission(err) => {
-                write!(f, "Failed to share file: {err}")
-            }
+            Error::Hub(_) => f.write_str("unable to get drive hub"),
+            Error::GetFile(_) => f.write_str("unable to get file"),
+            Error::CreatePermission(_) => f.write_str("unable to share file"),
+            Error::SharedDriveDenied(_) => f.write_str(
+                "unable to share file, only the organizers of a shared drive can share its files, \
+                domain administrators can use --domain-admin",
+            ),
             Error::MissingEmail(type_) => {
                 write!(
                     f,
@@ -123,6 +320,10 @@ impl Display for Error {
                     "Domain is required for permission type '{type_}'. Use the --domain option"
                 )
             }
+            Error::ReadCsv(path, _) => write!(f, "unable to read '{}'", path.display()),
+            Error::ParseCsv(_) => f.write_str("unable to parse the csv file"),
+            Error::InvalidRow { line, reason } => write!(f, "invalid row on line {line}: {reason}"),
+            Error::FailedGrants(count) => write!(f, "{count} permissions could not be granted"),
         }
     }
 }
@@ -169,3 +370,78 @@ fn print_grant_details(file: &google_drive3::api::File, config: &Config) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{create_error, parse_grants, CsvConfig, Error};
+    use crate::common::permission::{Role, Type};
+
+    fn csv_config() -> CsvConfig {
+        CsvConfig {
+            path: PathBuf::from("grants.csv"),
+            default_role: Role::Reader,
+            default_type: Type::User,
+            discoverable: false,
+            domain_admin: false,
+            fail_fast: false,
+        }
+    }
+
+    #[test]
+    fn shared_drive_share_denied() {
+        let forbidden =
+            || google_drive3::Error::BadRequest(serde_json::json!({ "error": { "code": 403 } }));
+        let shared = google_drive3::api::File {
+            drive_id: Some(String::from("0AB")),
+            ..google_drive3::api::File::default()
+        };
+
+        assert!(matches!(
+            create_error(forbidden(), &shared, false),
+            Error::SharedDriveDenied(_)
+        ));
+        assert!(matches!(
+            create_error(forbidden(), &shared, true),
+            Error::CreatePermission(_)
+        ));
+    }
+
+    #[test]
+    fn parse_csv_grants() {
+        let grants = parse_grants(
+            "file_id,email_or_domain,role,type,expiration\n\
+            1a,student@example.com,,,\n\
+            2b,example.com,writer,domain,2024-06-30T18:00:00Z\n\
+            3c,,commenter,anyone\n",
+            &csv_config(),
+        )
+        .unwrap();
+
+        assert_eq!(grants.len(), 3);
+        assert_eq!(grants[0].email.as_deref(), Some("student@example.com"));
+        assert_eq!(
+            (grants[0].role, grants[0].type_),
+            (Role::Reader, Type::User)
+        );
+        assert_eq!(grants[1].domain.as_deref(), Some("example.com"));
+        assert_eq!(
+            grants[1].expiration.unwrap().to_rfc3339(),
+            "2024-06-30T18:00:00+00:00"
+        );
+        assert_eq!(
+            (grants[2].email.as_ref(), grants[2].domain.as_ref()),
+            (None, None)
+        );
+
+        assert!(matches!(
+            parse_grants("1a,,writer\n", &csv_config()),
+            Err(Error::InvalidRow { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse_grants("1a,a@example.com\n2b,b@example.com,boss\n", &csv_config()),
+            Err(Error::InvalidRow { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,10 @@
+pub mod clear;
+pub mod enqueue;
+pub mod flush;
+pub mod journal;
+pub mod list;
+
+pub use clear::clear;
+pub use enqueue::enqueue;
+pub use flush::flush;
+pub use list::list;
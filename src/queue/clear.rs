@@ -0,0 +1,57 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+};
+
+use super::journal;
+use crate::common::prompt::{self, ConfirmError};
+
+pub struct Config {
+    /// Don't ask for confirmation before dropping the queued commands
+    pub assume_yes: bool,
+}
+
+/// Drops every queued command without running it.
+pub fn clear(config: &Config) -> Result<(), Error> {
+    let path = journal::current_account_path().map_err(Error::Journal)?;
+    let entries = journal::load(&path).map_err(Error::Journal)?;
+    if entries.is_empty() {
+        println!("The queue is empty");
+        return Ok(());
+    }
+
+    prompt::confirm(
+        &format!("Drop {} queued commands?", entries.len()),
+        config.assume_yes,
+    )
+    .map_err(Error::Confirm)?;
+
+    journal::save(&path, &[]).map_err(Error::Journal)?;
+    println!("Dropped {} queued commands", entries.len());
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Journal(journal::Error),
+    Confirm(ConfirmError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Journal(_) => f.write_str("unable to access the queue"),
+            Error::Confirm(_) => f.write_str("queue not cleared"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Journal(source) => Some(source),
+            Error::Confirm(source) => Some(source),
+        }
+    }
+}
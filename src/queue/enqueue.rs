@@ -0,0 +1,111 @@
+use std::{
+    env, error,
+    ffi::OsString,
+    fmt::{Display, Formatter},
+    io,
+};
+
+use google_drive3::chrono::Utc;
+
+use super::journal::{self, Entry};
+
+/// The flag queuing a command instead of running it, removed from the queued arguments.
+const QUEUE_FLAG: &str = "--queue";
+
+/// Appends the command gdrive was started with to the journal of the current account, to be run
+/// later by `queue flush`.
+pub fn enqueue(args: impl IntoIterator<Item = OsString>) -> Result<(), Error> {
+    let args = queued_args(args)?;
+    let working_dir = env::current_dir().map_err(Error::WorkingDir)?;
+
+    let path = journal::current_account_path().map_err(Error::Journal)?;
+    let entry = Entry {
+        args,
+        working_dir,
+        queued_at: Utc::now(),
+    };
+    journal::append(&path, &entry).map_err(Error::Journal)?;
+
+    println!("Queued 'gdrive {}'", entry.args.join(" "));
+    println!("Run 'gdrive queue flush' once online to run it");
+
+    Ok(())
+}
+
+/// Drops the executable and the queue flag from the arguments of the process. Arguments after `--`
+/// are file names, even when spelled like the flag, and are kept.
+fn queued_args(args: impl IntoIterator<Item = OsString>) -> Result<Vec<String>, Error> {
+    let mut args: Vec<String> = args
+        .into_iter()
+        .skip(1)
+        .map(|arg| arg.into_string().map_err(Error::NonUnicodeArg))
+        .collect::<Result<_, _>>()?;
+
+    let flag = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == QUEUE_FLAG);
+    if let Some(index) = flag {
+        args.remove(index);
+    }
+
+    Ok(args)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NonUnicodeArg(OsString),
+    WorkingDir(io::Error),
+    Journal(journal::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::NonUnicodeArg(arg) => write!(
+                f,
+                "argument {} is not valid unicode and cannot be queued",
+                arg.to_string_lossy()
+            ),
+            Error::WorkingDir(_) => f.write_str("unable to get the current directory"),
+            Error::Journal(_) => f.write_str("unable to queue the command"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::NonUnicodeArg(_) => None,
+            Error::WorkingDir(source) => Some(source),
+            Error::Journal(source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::queued_args;
+
+    #[test]
+    fn queue_flag_is_dropped() {
+        let args = ["gdrive", "files", "upload", "--queue", "report.pdf"].map(OsString::from);
+
+        assert_eq!(
+            queued_args(args).unwrap(),
+            ["files", "upload", "report.pdf"]
+        );
+    }
+
+    #[test]
+    fn file_named_like_the_flag_is_kept() {
+        let args = ["gdrive", "files", "upload", "--queue", "--", "--queue"].map(OsString::from);
+
+        assert_eq!(
+            queued_args(args).unwrap(),
+            ["files", "upload", "--", "--queue"]
+        );
+    }
+}
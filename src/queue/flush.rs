@@ -0,0 +1,105 @@
+use std::{
+    env, error,
+    fmt::{Display, Formatter},
+    io,
+};
+
+use tokio::process::Command;
+
+use super::journal::{self, Entry};
+
+pub struct Config {
+    /// Run the following commands when one fails, instead of stopping at the first failure
+    pub keep_going: bool,
+    /// Pass `--yes` to the queued commands
+    pub assume_yes: bool,
+}
+
+/// Runs the queued commands in the order they were queued. A command leaves the journal once it
+/// succeeds, failed ones are kept to be retried by the next flush.
+pub async fn flush(config: Config) -> Result<(), Error> {
+    let path = journal::current_account_path().map_err(Error::Journal)?;
+    let mut pending = journal::load(&path).map_err(Error::Journal)?;
+    if pending.is_empty() {
+        println!("The queue is empty");
+        return Ok(());
+    }
+
+    let executable = env::current_exe().map_err(Error::CurrentExe)?;
+    let mut failed: Vec<Entry> = vec![];
+    let mut succeeded = 0;
+
+    while !pending.is_empty() {
+        let entry = pending.remove(0);
+        let command_line = format!("gdrive {}", entry.args.join(" "));
+        println!("Running '{command_line}'");
+
+        let mut command = Command::new(&executable);
+        if config.assume_yes {
+            // Global flags are accepted before the subcommand, where they cannot follow a `--`
+            command.arg("--yes");
+        }
+        command.args(&entry.args).current_dir(&entry.working_dir);
+
+        match command.status().await {
+            Ok(status) if status.success() => succeeded += 1,
+            Ok(status) => {
+                eprintln!("Warning: '{command_line}' exited with {status}");
+                failed.push(entry);
+            }
+            Err(err) => {
+                eprintln!("Warning: unable to run '{command_line}': {err}");
+                failed.push(entry);
+            }
+        }
+
+        let stop = !failed.is_empty() && !config.keep_going;
+        let remaining: Vec<Entry> = failed.iter().chain(&pending).cloned().collect();
+        journal::save(&path, &remaining).map_err(Error::Journal)?;
+        if stop {
+            break;
+        }
+    }
+
+    println!(
+        "Ran {succeeded} queued commands, {} left in the queue",
+        failed.len() + pending.len()
+    );
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Failed(failed.len()))
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Journal(journal::Error),
+    CurrentExe(io::Error),
+    Failed(usize),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Journal(_) => f.write_str("unable to access the queue"),
+            Error::CurrentExe(_) => f.write_str("unable to find the path of the gdrive executable"),
+            Error::Failed(count) => write!(
+                f,
+                "{count} queued commands failed and were kept in the queue, \
+                run 'gdrive queue flush' again to retry them"
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Journal(source) => Some(source),
+            Error::CurrentExe(source) => Some(source),
+            Error::Failed(_) => None,
+        }
+    }
+}
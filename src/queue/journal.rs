@@ -0,0 +1,173 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use google_drive3::chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::{self, AppConfig};
+
+const JOURNAL_NAME: &str = "queue.jsonl";
+
+/// A gdrive command waiting to be run, stored as one JSON line of the journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// The arguments of the command, without the executable and `--queue`
+    pub args: Vec<String>,
+    /// Relative paths in the arguments are resolved against it
+    pub working_dir: PathBuf,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// The journal of the current account, queued commands run as the account they were queued for.
+pub fn current_account_path() -> Result<PathBuf, Error> {
+    let app_cfg = AppConfig::load_current_account().map_err(Error::LoadCurrentAccount)?;
+    Ok(app_cfg.account_base_path().join(JOURNAL_NAME))
+}
+
+/// Reads the entries of the journal, in the order they were queued. A missing journal is empty.
+pub fn load(path: &Path) -> Result<Vec<Entry>, Error> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(Error::Read(path.to_path_buf(), err)),
+    };
+
+    parse(&content).map_err(|(line, err)| Error::Parse {
+        path: path.to_path_buf(),
+        line,
+        source: err,
+    })
+}
+
+/// Appends an entry, keeping the ones already queued untouched.
+pub fn append(path: &Path, entry: &Entry) -> Result<(), Error> {
+    let line = serde_json::to_string(entry).map_err(Error::Serialize)?;
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{line}"))
+        .map_err(|err| Error::Write(path.to_path_buf(), err))
+}
+
+/// Replaces the content of the journal with `entries`, atomically so that an interrupted flush
+/// never loses the entries still pending. The journal is removed when nothing is left.
+pub fn save(path: &Path, entries: &[Entry]) -> Result<(), Error> {
+    if entries.is_empty() {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                Err(Error::Write(path.to_path_buf(), err))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    let mut content = String::new();
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry).map_err(Error::Serialize)?);
+        content.push('\n');
+    }
+
+    let tmp_path = path.with_extension("jsonl.tmp");
+    fs::write(&tmp_path, content)
+        .and_then(|()| fs::rename(&tmp_path, path))
+        .map_err(|err| Error::Write(path.to_path_buf(), err))
+}
+
+/// Parses the JSON lines of a journal, returning the 1-based number of the first invalid line on
+/// failure. Blank lines are skipped.
+fn parse(content: &str) -> Result<Vec<Entry>, (usize, serde_json::Error)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| serde_json::from_str(line).map_err(|err| (index + 1, err)))
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    LoadCurrentAccount(app_config::errors::LoadCurrentAccount),
+    Read(PathBuf, io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+    Serialize(serde_json::Error),
+    Write(PathBuf, io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::LoadCurrentAccount(_) => f.write_str("unable to load the current account"),
+            Error::Read(path, _) => write!(f, "unable to read the queue '{}'", path.display()),
+            Error::Parse { path, line, .. } => write!(
+                f,
+                "unable to parse line {line} of the queue '{}'",
+                path.display()
+            ),
+            Error::Serialize(_) => f.write_str("unable to serialize the queued command"),
+            Error::Write(path, _) => write!(f, "unable to write the queue '{}'", path.display()),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::LoadCurrentAccount(source) => Some(source),
+            Error::Read(_, source) | Error::Write(_, source) => Some(source),
+            Error::Parse { source, .. } | Error::Serialize(source) => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(args: &[&str]) -> Entry {
+        Entry {
+            args: args.iter().map(ToString::to_string).collect(),
+            working_dir: PathBuf::from("/home/me"),
+            queued_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn append_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_NAME);
+        assert_eq!(load(&path).unwrap(), []);
+
+        let upload = entry(&["files", "upload", "report.pdf"]);
+        let delete = entry(&["files", "delete", "abc"]);
+        append(&path, &upload).unwrap();
+        append(&path, &delete).unwrap();
+        assert_eq!(load(&path).unwrap(), [upload, delete.clone()]);
+
+        save(&path, std::slice::from_ref(&delete)).unwrap();
+        assert_eq!(load(&path).unwrap(), [delete]);
+
+        save(&path, &[]).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn parse_reports_invalid_line() {
+        let valid = serde_json::to_string(&entry(&["files", "delete", "abc"])).unwrap();
+        let content = format!("{valid}\n\nnot json\n");
+
+        let (line, _) = parse(&content).unwrap_err();
+        assert_eq!(line, 3);
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    io,
+};
+
+use google_drive3::chrono::SecondsFormat;
+
+use super::journal;
+use crate::common::table::{self, Table};
+
+pub struct Config {
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub output: table::OutputFormat,
+}
+
+pub fn list(config: &Config) -> Result<(), Error> {
+    let path = journal::current_account_path().map_err(Error::Journal)?;
+    let entries = journal::load(&path).map_err(Error::Journal)?;
+
+    let values: Vec<[String; 3]> = entries
+        .into_iter()
+        .map(|entry| {
+            [
+                entry.queued_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+                entry.working_dir.display().to_string(),
+                entry.args.join(" "),
+            ]
+        })
+        .collect();
+
+    let table = Table {
+        header: ["Queued", "Directory", "Command"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: &config.field_separator,
+            format: config.output,
+        },
+    );
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Journal(journal::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Journal(_) => f.write_str("unable to read the queue"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Journal(source) => Some(source),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod cron;
+pub mod generate;
+
+pub use generate::generate;
@@ -0,0 +1,288 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+const DAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// A crontab schedule, either five fields (`minute hour day-of-month month day-of-week`) or a
+/// macro like `@daily`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CronSchedule {
+    Macro(CronMacro),
+    Fields(CronFields),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CronMacro {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronFields {
+    pub minute: String,
+    pub hour: String,
+    pub day_of_month: String,
+    pub month: String,
+    pub day_of_week: String,
+}
+
+impl CronSchedule {
+    /// Converts the schedule to a systemd `OnCalendar` expression.
+    pub fn to_on_calendar(&self) -> Result<String, InvalidCronSchedule> {
+        let fields = match self {
+            CronSchedule::Macro(cron_macro) => return Ok(cron_macro.on_calendar().to_owned()),
+            CronSchedule::Fields(fields) => fields,
+        };
+
+        // Cron runs when either of the days matches, systemd when both do
+        if fields.day_of_month != "*" && fields.day_of_week != "*" {
+            return Err(InvalidCronSchedule::DayOfMonthAndWeek);
+        }
+
+        let minute = calendar_field(&fields.minute, 0, 59, "0")?;
+        let hour = calendar_field(&fields.hour, 0, 23, "0")?;
+        let day_of_month = calendar_field(&fields.day_of_month, 1, 31, "1")?;
+        let month = calendar_field(&month_numbers(&fields.month), 1, 12, "1")?;
+        let day_of_week = day_of_week_names(&fields.day_of_week)?;
+
+        let date = format!("*-{month}-{day_of_month} {hour}:{minute}:00");
+        Ok(match day_of_week {
+            Some(days) => format!("{days} {date}"),
+            None => date,
+        })
+    }
+}
+
+impl Display for CronSchedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CronSchedule::Macro(cron_macro) => write!(f, "@{}", cron_macro.on_calendar()),
+            CronSchedule::Fields(fields) => write!(
+                f,
+                "{} {} {} {} {}",
+                fields.minute, fields.hour, fields.day_of_month, fields.month, fields.day_of_week
+            ),
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = InvalidCronSchedule;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = s.trim().strip_prefix('@') {
+            let cron_macro = match name {
+                "hourly" => CronMacro::Hourly,
+                "daily" | "midnight" => CronMacro::Daily,
+                "weekly" => CronMacro::Weekly,
+                "monthly" => CronMacro::Monthly,
+                "yearly" | "annually" => CronMacro::Yearly,
+                _ => return Err(InvalidCronSchedule::UnknownMacro(s.to_owned())),
+            };
+            return Ok(CronSchedule::Macro(cron_macro));
+        }
+
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(InvalidCronSchedule::FieldCount(fields.len()));
+        };
+
+        let is_valid = |field: &str| {
+            !field.is_empty()
+                && field
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '*' | ',' | '-' | '/'))
+        };
+        if let Some(field) = fields.iter().find(|field| !is_valid(field)) {
+            return Err(InvalidCronSchedule::InvalidField((*field).to_owned()));
+        }
+
+        Ok(CronSchedule::Fields(CronFields {
+            minute: minute.to_owned(),
+            hour: hour.to_owned(),
+            day_of_month: day_of_month.to_owned(),
+            month: month.to_owned(),
+            day_of_week: day_of_week.to_owned(),
+        }))
+    }
+}
+
+impl CronMacro {
+    fn on_calendar(self) -> &'static str {
+        match self {
+            CronMacro::Hourly => "hourly",
+            CronMacro::Daily => "daily",
+            CronMacro::Weekly => "weekly",
+            CronMacro::Monthly => "monthly",
+            CronMacro::Yearly => "yearly",
+        }
+    }
+}
+
+/// Converts a numeric cron field to the systemd syntax, where ranges are written `a..b` and
+/// steps start from the first allowed value.
+fn calendar_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    first: &str,
+) -> Result<String, InvalidCronSchedule> {
+    let invalid = || InvalidCronSchedule::InvalidField(field.to_owned());
+
+    field
+        .split(',')
+        .map(|part| {
+            let (values, step) = match part.split_once('/') {
+                Some((values, step)) => (values, Some(step)),
+                None => (part, None),
+            };
+
+            let values = match values.split_once('-') {
+                _ if values == "*" => match step {
+                    Some(_) => first.to_owned(),
+                    None => String::from("*"),
+                },
+                Some((start, end)) => {
+                    check_value(start, min, max).ok_or_else(invalid)?;
+                    check_value(end, min, max).ok_or_else(invalid)?;
+                    format!("{start}..{end}")
+                }
+                None => {
+                    check_value(values, min, max).ok_or_else(invalid)?;
+                    values.to_owned()
+                }
+            };
+
+            match step {
+                Some(step) => {
+                    check_value(step, 1, max).ok_or_else(invalid)?;
+                    if values.contains("..") {
+                        // systemd has no steps over a range
+                        return Err(invalid());
+                    }
+                    Ok(format!("{values}/{step}"))
+                }
+                None => Ok(values),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|parts| parts.join(","))
+}
+
+fn check_value(value: &str, min: u32, max: u32) -> Option<u32> {
+    value
+        .parse()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+}
+
+/// Replaces month names, i.e. `jan`, with their number.
+fn month_numbers(field: &str) -> String {
+    let mut field = field.to_ascii_lowercase();
+    for (index, name) in MONTH_NAMES.iter().enumerate() {
+        field = field.replace(name, &(index + 1).to_string());
+    }
+    field
+}
+
+/// Converts a day of week field to the systemd names, `None` when any day matches.
+fn day_of_week_names(field: &str) -> Result<Option<String>, InvalidCronSchedule> {
+    if field == "*" {
+        return Ok(None);
+    }
+
+    let invalid = || InvalidCronSchedule::InvalidField(field.to_owned());
+    let day_name = |day: &str| -> Result<&'static str, InvalidCronSchedule> {
+        if let Ok(number) = day.parse::<usize>() {
+            // Both 0 and 7 are Sunday
+            return DAY_NAMES
+                .get(number % 7)
+                .copied()
+                .filter(|_| number <= 7)
+                .ok_or_else(invalid);
+        }
+        DAY_NAMES
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(day))
+            .copied()
+            .ok_or_else(invalid)
+    };
+
+    field
+        .split(',')
+        .map(|part| match part.split_once('-') {
+            Some((start, end)) => Ok(format!("{}..{}", day_name(start)?, day_name(end)?)),
+            None => day_name(part).map(str::to_owned),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|days| Some(days.join(",")))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidCronSchedule {
+    FieldCount(usize),
+    InvalidField(String),
+    UnknownMacro(String),
+    DayOfMonthAndWeek,
+}
+
+impl Display for InvalidCronSchedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidCronSchedule::FieldCount(count) => write!(
+                f,
+                "expected 5 fields (minute hour day-of-month month day-of-week), found {count}"
+            ),
+            InvalidCronSchedule::InvalidField(field) => {
+                write!(f, "'{field}' is not a supported cron field")
+            }
+            InvalidCronSchedule::UnknownMacro(name) => write!(f, "unknown cron macro '{name}'"),
+            InvalidCronSchedule::DayOfMonthAndWeek => f.write_str(
+                "both day of month and day of week are restricted, which systemd does not support",
+            ),
+        }
+    }
+}
+
+impl error::Error for InvalidCronSchedule {}
+
+#[cfg(test)]
+mod tests {
+    use super::{CronSchedule, InvalidCronSchedule};
+
+    fn on_calendar(s: &str) -> Result<String, InvalidCronSchedule> {
+        s.parse::<CronSchedule>()?.to_on_calendar()
+    }
+
+    #[test]
+    fn cron_to_on_calendar() {
+        assert_eq!(on_calendar("0 2 * * *").unwrap(), "*-*-* 2:0:00");
+        assert_eq!(on_calendar("*/15 * * * *").unwrap(), "*-*-* *:0/15:00");
+        assert_eq!(on_calendar("30 1 1,15 * *").unwrap(), "*-*-1,15 1:30:00");
+        assert_eq!(on_calendar("0 3 * jan-mar *").unwrap(), "*-1..3-* 3:0:00");
+        assert_eq!(
+            on_calendar("0 22 * * 1-5").unwrap(),
+            "Mon..Fri *-*-* 22:0:00"
+        );
+        assert_eq!(
+            on_calendar("0 0 * * 0,sat").unwrap(),
+            "Sun,Sat *-*-* 0:0:00"
+        );
+        assert_eq!(on_calendar("@weekly").unwrap(), "weekly");
+
+        assert!(on_calendar("0 2 * *").is_err());
+        assert!(on_calendar("60 2 * * *").is_err());
+        assert!(on_calendar("0 2 1 * 1").is_err());
+        assert!(on_calendar("0 1-5/2 * * *").is_err());
+        assert!(on_calendar("@reboot").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    env, error,
+    fmt::{Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+
+use super::cron::{CronSchedule, InvalidCronSchedule};
+use crate::app_config::{self, AppConfig};
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub schedule: CronSchedule,
+    /// The gdrive arguments to run, i.e. `files upload --recursive backups`
+    pub command: String,
+    pub format: Format,
+    /// Used to name the systemd units and the log file
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A crontab line
+    #[default]
+    Crontab,
+    /// A systemd user service and timer
+    Systemd,
+}
+
+pub fn generate(config: &Config) -> Result<(), Error> {
+    let is_valid_name = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_');
+    if config.name.is_empty() || !config.name.chars().all(is_valid_name) {
+        return Err(Error::InvalidName(config.name.clone()));
+    }
+
+    let app_cfg = AppConfig::load_current_account().map_err(Error::LoadCurrentAccount)?;
+    let home_path = home::home_dir().ok_or(Error::HomeDir)?;
+    let executable = env::current_exe().map_err(Error::CurrentExe)?;
+
+    let command = config.command.trim();
+    let command = command.strip_prefix("gdrive ").unwrap_or(command);
+
+    let job = Job {
+        name: &config.name,
+        account_name: &app_cfg.account.name,
+        home_path: &home_path,
+        executable: &executable,
+        command,
+        log_path: app_cfg.base_path.join(format!("{}.log", config.name)),
+    };
+
+    match config.format {
+        Format::Crontab => print_crontab(&job, &config.schedule),
+        Format::Systemd => {
+            let on_calendar = config
+                .schedule
+                .to_on_calendar()
+                .map_err(Error::ConvertSchedule)?;
+            print_systemd_units(&job, &on_calendar);
+        }
+    }
+
+    Ok(())
+}
+
+struct Job<'a> {
+    name: &'a str,
+    account_name: &'a str,
+    home_path: &'a Path,
+    executable: &'a Path,
+    command: &'a str,
+    log_path: PathBuf,
+}
+
+fn print_crontab(job: &Job, schedule: &CronSchedule) {
+    // An unescaped % is a newline for cron
+    let escape = |s: &str| s.replace('%', "\\%");
+
+    println!("# Add this line with `crontab -e`.");
+    println!(
+        "# gdrive runs as the current account when the job starts, now '{}'",
+        job.account_name
+    );
+    println!(
+        "{schedule} HOME={} {} {} >> {} 2>&1",
+        shell_quote(&job.home_path.to_string_lossy()),
+        shell_quote(&job.executable.to_string_lossy()),
+        escape(job.command),
+        shell_quote(&escape(&job.log_path.to_string_lossy())),
+    );
+}
+
+fn print_systemd_units(job: &Job, on_calendar: &str) {
+    // An unescaped % starts a specifier for systemd
+    let escape = |s: &str| s.replace('%', "%%");
+    let unit_name = format!("gdrive-{}", job.name);
+
+    println!("# ~/.config/systemd/user/{unit_name}.service");
+    println!("[Unit]");
+    println!("Description=gdrive {}", escape(job.name));
+    println!();
+    println!("[Service]");
+    println!("Type=oneshot");
+    println!(
+        "# gdrive runs as the current account when the job starts, now '{}'",
+        job.account_name
+    );
+    println!(
+        "Environment={}",
+        shell_quote(&format!(
+            "HOME={}",
+            escape(&job.home_path.to_string_lossy())
+        ))
+    );
+    println!(
+        "ExecStart={} {}",
+        shell_quote(&escape(&job.executable.to_string_lossy())),
+        escape(job.command)
+    );
+    println!();
+    println!("# ~/.config/systemd/user/{unit_name}.timer");
+    println!("[Unit]");
+    println!("Description=Run gdrive {} on schedule", escape(job.name));
+    println!();
+    println!("[Timer]");
+    println!("OnCalendar={on_calendar}");
+    println!("Persistent=true");
+    println!();
+    println!("[Install]");
+    println!("WantedBy=timers.target");
+    println!();
+    println!(
+        "# Enable with: systemctl --user daemon-reload && systemctl --user enable --now \
+        {unit_name}.timer"
+    );
+    println!("# Check the output with: journalctl --user -u {unit_name}.service");
+}
+
+/// Quotes `s` for a POSIX shell, and for systemd which accepts the same syntax.
+fn shell_quote(s: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '=');
+    if !s.is_empty() && s.chars().all(is_safe) {
+        s.to_owned()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    LoadCurrentAccount(app_config::errors::LoadCurrentAccount),
+    InvalidName(String),
+    HomeDir,
+    CurrentExe(io::Error),
+    ConvertSchedule(InvalidCronSchedule),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::LoadCurrentAccount(source) => Some(source),
+            Error::InvalidName(_) | Error::HomeDir => None,
+            Error::CurrentExe(source) => Some(source),
+            Error::ConvertSchedule(source) => Some(source),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Error::InvalidName(name) => {
+                return write!(
+                    f,
+                    "'{name}' is not a valid name, use only letters, digits, '-' and '_'"
+                )
+            }
+            Error::LoadCurrentAccount(_) => "unable to load the current account",
+            Error::HomeDir => "unable to find the home directory",
+            Error::CurrentExe(_) => "unable to find the path of the gdrive executable",
+            Error::ConvertSchedule(_) => "unable to convert the schedule to a systemd timer",
+        };
+
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shell_quote;
+
+    #[test]
+    fn quote_for_shell() {
+        assert_eq!(shell_quote("/usr/bin/gdrive"), "/usr/bin/gdrive");
+        assert_eq!(shell_quote("/home/me/my files"), "'/home/me/my files'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    error,
+    fmt::{Display, Formatter},
+    io,
+};
+
+use google_drive3::chrono::{Days, Local};
+
+use crate::common::{
+    size_format::SizeFormat,
+    table::{self, Table},
+    usage_stats::{self, DayStats, UsageStats},
+};
+
+pub struct Config {
+    pub action: Action,
+    pub size_format: SizeFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Print the statistics of the last days, today included
+    Show {
+        days: u32,
+    },
+    Enable,
+    Disable,
+    /// Remove the recorded statistics, keeping them enabled or disabled
+    Reset,
+}
+
+pub fn stats(config: &Config) -> Result<(), Error> {
+    match config.action {
+        Action::Show { days } => {
+            let stats = UsageStats::load().map_err(Error::Load)?;
+            show(&stats, days, config.size_format);
+        }
+        Action::Enable => {
+            UsageStats::update(|stats| stats.enabled = true).map_err(Error::Save)?;
+            println!(
+                "Usage statistics enabled, the requests of the next runs are recorded in {}",
+                usage_path()
+            );
+        }
+        Action::Disable => {
+            UsageStats::update(|stats| stats.enabled = false).map_err(Error::Save)?;
+            println!("Usage statistics disabled, the recorded ones are kept");
+        }
+        Action::Reset => {
+            UsageStats::update(|stats| stats.days.clear()).map_err(Error::Save)?;
+            println!("Usage statistics removed");
+        }
+    }
+
+    Ok(())
+}
+
+fn show(stats: &UsageStats, days: u32, size_format: SizeFormat) {
+    if !stats.enabled {
+        println!("Usage statistics are disabled, enable them with 'gdrive stats --enable'");
+        if stats.days.is_empty() {
+            return;
+        }
+        println!();
+    }
+
+    let today = Local::now().date_naive();
+    let first_day = today
+        .checked_sub_days(Days::new(u64::from(days.saturating_sub(1))))
+        .unwrap_or(today);
+    let total = stats.total_since(first_day);
+    println!(
+        "Since {first_day}: {} requests in {} runs, {} sent, {} received",
+        total.request_count(),
+        total.runs,
+        size_format.display(total.bytes_sent),
+        size_format.display(total.bytes_received)
+    );
+    if total.requests.is_empty() {
+        return;
+    }
+
+    println!();
+    let mut endpoints: Vec<_> = total.requests.iter().collect();
+    endpoints.sort_by(|(a_name, a_count), (b_name, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+    });
+    print_table(Table {
+        header: ["Endpoint", "Requests"],
+        values: endpoints
+            .into_iter()
+            .map(|(endpoint, count)| [endpoint.clone(), count.to_string()])
+            .collect(),
+    });
+
+    println!();
+    print_table(Table {
+        header: ["Day", "Runs", "Requests", "Sent", "Received"],
+        values: stats
+            .days
+            .range(first_day..)
+            .map(|(day, stats)| day_row(&day.to_string(), stats, size_format))
+            .collect(),
+    });
+}
+
+fn day_row(day: &str, stats: &DayStats, size_format: SizeFormat) -> [String; 5] {
+    [
+        day.to_string(),
+        stats.runs.to_string(),
+        stats.request_count().to_string(),
+        size_format.display(stats.bytes_sent).to_string(),
+        size_format.display(stats.bytes_received).to_string(),
+    ]
+}
+
+fn print_table<const COLUMNS: usize>(table: Table<&str, String, COLUMNS>) {
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: false,
+            separator: "\t",
+            format: table::OutputFormat::Table,
+        },
+    );
+}
+
+fn usage_path() -> String {
+    UsageStats::path().map_or_else(
+        |_| "the gdrive directory".to_string(),
+        |path| path.display().to_string(),
+    )
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Load(usage_stats::Error),
+    Save(usage_stats::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Load(_) => f.write_str("unable to load the usage statistics"),
+            Error::Save(_) => f.write_str("unable to save the usage statistics"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Load(source) | Error::Save(source) => Some(source),
+        }
+    }
+}